@@ -0,0 +1,73 @@
+// Android entry point. The library is also built as a shared object (crate-type in
+// cargo.toml), cargo-apk packs it into an APK together with assets/ and the
+// NativeActivity calls android_main once the app starts.
+use crate::{
+    crash,
+    display::DisplaySettings,
+    game::{Game, LaunchOptions, FIXED_TIMESTEP},
+    localization::{self, DEFAULT_LANG_DIR},
+    logging::{self, LogConfig},
+    paths,
+};
+use fyrox::{
+    core::io::ANDROID_APP,
+    engine::{executor::Executor, GraphicsContextParams},
+    event_loop::EventLoopBuilder,
+    platform::android::{activity::AndroidApp, EventLoopBuilderExtAndroid},
+    window::WindowAttributes,
+};
+use std::path::Path;
+
+#[no_mangle]
+fn android_main(app: AndroidApp) {
+    // The resource manager reads models and textures out of the APK through it
+    let _ = ANDROID_APP.set(app.clone());
+    // Before anything asks for the data directory, the log file included
+    if let Some(dir) = app.internal_data_path() {
+        paths::set_data_dir(dir);
+    }
+
+    // No command line here either: a single player game with the defaults, online
+    // sessions can still be hosted and joined from the menu
+    let mut options = LaunchOptions {
+        mods_dir: None,
+        tuning_path: None,
+        leaderboard_url: None,
+        ..LaunchOptions::default()
+    };
+    // stderr goes nowhere on Android, the log file in the app's directory is all there is
+    logging::init(LogConfig {
+        filter: options.log_filter.clone(),
+        dir: options.log_dir.clone(),
+        console: false,
+    });
+    crash::install();
+    // Only the built-in English table is there, the others are packed in the APK
+    localization::init(Path::new(DEFAULT_LANG_DIR), &options.language);
+    options.display = DisplaySettings::load();
+
+    let event_loop = match EventLoopBuilder::new().with_android_app(app).build() {
+        Ok(event_loop) => event_loop,
+        Err(err) => {
+            log::error!("Game failed to start: {err}");
+            return;
+        }
+    };
+    // The window is always the whole screen, only its title is up to us
+    let mut window_attributes = WindowAttributes::default();
+    window_attributes.title = "Simple Fyrox Game".to_string();
+
+    // The graphics context is made when the activity resumes and dropped whenever it
+    // goes to the background, the game hears about both from the executor
+    let mut executor = Executor::from_params(
+        event_loop,
+        GraphicsContextParams {
+            window_attributes,
+            vsync: true,
+            msaa_sample_count: None,
+        },
+    );
+    executor.set_desired_update_rate(1.0 / FIXED_TIMESTEP);
+    executor.add_plugin(Game::new(options));
+    executor.run();
+}
@@ -0,0 +1,196 @@
+use crate::level::find_level;
+use fyrox::asset::{manager::ResourceManager, untyped::UntypedResource};
+use serde::Deserialize;
+use std::{
+    fmt,
+    path::{Path, PathBuf},
+};
+
+// Looked up relative to the working directory unless --assets points elsewhere
+pub const DEFAULT_MANIFEST_PATH: &str = "assets/manifest.json";
+
+// Every asset the game uses, loaded before the main menu opens so nothing is
+// loaded on first use in the middle of a level
+#[derive(Clone, Debug, Default, Deserialize)]
+#[serde(default)]
+pub struct AssetManifest {
+    pub textures: Vec<PathBuf>,
+    pub models: Vec<PathBuf>,
+    pub sounds: Vec<PathBuf>,
+    // Ids of the levels the game ships with
+    pub levels: Vec<String>,
+}
+
+// Browsers have no files to read and Android keeps them packed in the APK, so those
+// builds carry the manifest inside. The assets it lists are still separate, the resource
+// manager fetches them from the page's server or the APK.
+#[cfg(any(target_arch = "wasm32", target_os = "android"))]
+const BUILT_IN_MANIFEST: &str = include_str!("../assets/manifest.json");
+
+impl AssetManifest {
+    #[cfg(not(any(target_arch = "wasm32", target_os = "android")))]
+    pub fn load(path: &Path) -> Result<Self, String> {
+        std::fs::read_to_string(path)
+            .map_err(|err| err.to_string())
+            .and_then(|text| serde_json::from_str(&text).map_err(|err| err.to_string()))
+            .map_err(|err| format!("{}: {err}", path.display()))
+    }
+
+    #[cfg(any(target_arch = "wasm32", target_os = "android"))]
+    pub fn load(_path: &Path) -> Result<Self, String> {
+        serde_json::from_str(BUILT_IN_MANIFEST).map_err(|err| format!("built-in manifest: {err}"))
+    }
+}
+
+#[cfg(not(any(target_arch = "wasm32", target_os = "android")))]
+fn file_exists(path: &Path) -> bool {
+    path.exists()
+}
+
+// Nothing to look at there, a missing asset shows up as the loader's error instead
+#[cfg(any(target_arch = "wasm32", target_os = "android"))]
+fn file_exists(_path: &Path) -> bool {
+    true
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum AssetKind {
+    Texture,
+    Model,
+    Sound,
+    Level,
+}
+
+impl fmt::Display for AssetKind {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str(match self {
+            AssetKind::Texture => "texture",
+            AssetKind::Model => "model",
+            AssetKind::Sound => "sound",
+            AssetKind::Level => "level",
+        })
+    }
+}
+
+// An asset that is listed in the manifest but can't be used
+#[derive(Clone, Debug)]
+pub struct MissingAsset {
+    pub kind: AssetKind,
+    pub name: String,
+    pub reason: String,
+}
+
+impl fmt::Display for MissingAsset {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{} {}: {}", self.kind, self.name, self.reason)
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct PreloadProgress {
+    pub done: usize,
+    pub total: usize,
+}
+
+impl PreloadProgress {
+    pub fn is_finished(&self) -> bool {
+        self.done >= self.total
+    }
+}
+
+struct PendingAsset {
+    kind: AssetKind,
+    path: PathBuf,
+    resource: UntypedResource,
+}
+
+// Requests everything in the manifest at once and tracks it until the resource
+// manager has finished with all of it. Loaded resources are kept alive here so
+// they stay cached for the rest of the session.
+#[derive(Default)]
+pub struct Preloader {
+    pending: Vec<PendingAsset>,
+    loaded: Vec<UntypedResource>,
+    missing: Vec<MissingAsset>,
+    total: usize,
+}
+
+impl Preloader {
+    pub fn start(manifest: &AssetManifest, resource_manager: &ResourceManager) -> Self {
+        let mut preloader = Self::default();
+
+        for (kind, paths) in [
+            (AssetKind::Texture, &manifest.textures),
+            (AssetKind::Model, &manifest.models),
+            (AssetKind::Sound, &manifest.sounds),
+        ] {
+            for path in paths {
+                preloader.total += 1;
+                // Files that aren't there are reported right away instead of waiting for the loader
+                if !file_exists(path) {
+                    preloader.missing.push(MissingAsset {
+                        kind,
+                        name: path.display().to_string(),
+                        reason: "file not found".to_string(),
+                    });
+                    continue;
+                }
+                preloader.pending.push(PendingAsset {
+                    kind,
+                    path: path.clone(),
+                    resource: resource_manager.request_untyped(path),
+                });
+            }
+        }
+
+        // Levels are built in code, so all there is to check is that they exist
+        for level in &manifest.levels {
+            preloader.total += 1;
+            if find_level(level).is_none() {
+                preloader.missing.push(MissingAsset {
+                    kind: AssetKind::Level,
+                    name: level.clone(),
+                    reason: "unknown level".to_string(),
+                });
+            }
+        }
+
+        preloader
+    }
+
+    // Moves finished loads out of the pending list, call once per tick
+    pub fn update(&mut self) -> PreloadProgress {
+        let mut index = 0;
+        while index < self.pending.len() {
+            let asset = &self.pending[index];
+            if asset.resource.is_loading() {
+                index += 1;
+                continue;
+            }
+
+            let asset = self.pending.swap_remove(index);
+            if asset.resource.is_ok() {
+                self.loaded.push(asset.resource);
+            } else {
+                self.missing.push(MissingAsset {
+                    kind: asset.kind,
+                    name: asset.path.display().to_string(),
+                    reason: "failed to load".to_string(),
+                });
+            }
+        }
+
+        self.progress()
+    }
+
+    pub fn progress(&self) -> PreloadProgress {
+        PreloadProgress {
+            done: self.total - self.pending.len(),
+            total: self.total,
+        }
+    }
+
+    pub fn missing(&self) -> &[MissingAsset] {
+        &self.missing
+    }
+}
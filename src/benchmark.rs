@@ -0,0 +1,199 @@
+use crate::{
+    input_script::{seconds_to_ticks, InputScript, InputStep, ScriptPlayer},
+    profiler::{Phase, Profiler},
+};
+use fyrox::{core::instant::Instant, keyboard::KeyCode};
+use serde::Serialize;
+use std::{
+    collections::BTreeMap,
+    fs, io,
+    path::{Path, PathBuf},
+    time::Duration,
+};
+
+pub const DEFAULT_BENCHMARK_SECONDS: f32 = 30.0;
+pub const DEFAULT_REPORT_PATH: &str = "benchmark.json";
+
+// The first frames include shader compilation and texture uploads, they would only
+// skew the numbers
+const WARMUP_FRAMES: usize = 30;
+
+pub struct BenchmarkConfig {
+    pub seconds: f32,
+    pub report_path: PathBuf,
+}
+
+impl Default for BenchmarkConfig {
+    fn default() -> Self {
+        Self {
+            seconds: DEFAULT_BENCHMARK_SECONDS,
+            report_path: PathBuf::from(DEFAULT_REPORT_PATH),
+        }
+    }
+}
+
+// The path every benchmark plays: a lap around the middle of the level with some
+// diagonal runs past the obstacles, repeated until `seconds` are filled. It only
+// changes together with the numbers it produces.
+pub fn standard_path(seconds: f32) -> InputScript {
+    let lap = [
+        (&[KeyCode::KeyW][..], 2.0),
+        (&[KeyCode::KeyD][..], 1.5),
+        (&[KeyCode::KeyS][..], 2.0),
+        (&[KeyCode::KeyA][..], 1.5),
+        (&[KeyCode::KeyW, KeyCode::KeyD][..], 1.0),
+        (&[KeyCode::KeyS, KeyCode::KeyA][..], 1.0),
+    ];
+    let mut script = InputScript::new();
+    let mut total = 0.0;
+    while total < seconds {
+        for (keys, duration) in lap {
+            script = keys.iter().fold(script, |script, key| script.press(*key));
+            script = script.wait(duration);
+            script = keys.iter().fold(script, |script, key| script.release(*key));
+            total += duration;
+        }
+    }
+    script
+}
+
+// Plays the standard path into the running game one tick at a time and keeps the
+// frame and phase timings of the run
+pub struct Benchmark {
+    seconds: f32,
+    report_path: PathBuf,
+    path: ScriptPlayer,
+    ticks_left: u64,
+    ticks: u64,
+    last_frame: Option<Instant>,
+    frame_times: Vec<Duration>,
+    phase_times: Vec<[f32; Phase::ALL.len()]>,
+}
+
+impl Benchmark {
+    pub fn new(config: &BenchmarkConfig) -> Self {
+        Self {
+            seconds: config.seconds,
+            report_path: config.report_path.clone(),
+            path: ScriptPlayer::new(&standard_path(config.seconds)),
+            ticks_left: seconds_to_ticks(config.seconds),
+            ticks: 0,
+            last_frame: None,
+            frame_times: Vec::new(),
+            phase_times: Vec::new(),
+        }
+    }
+
+    pub fn report_path(&self) -> &Path {
+        &self.report_path
+    }
+
+    // Key presses and device events to feed the game before the coming tick, None
+    // once the run is over
+    pub fn next_tick(&mut self) -> Option<Vec<InputStep>> {
+        if self.ticks_left == 0 {
+            return None;
+        }
+        self.ticks_left -= 1;
+        self.ticks += 1;
+        Some(self.path.next_tick())
+    }
+
+    // Called for every frame drawn
+    pub fn record_frame(&mut self) {
+        let now = Instant::now();
+        if let Some(last) = self.last_frame.replace(now) {
+            self.frame_times.push(now - last);
+        }
+    }
+
+    // Called once per tick, after the profiler closed the previous frame
+    pub fn record_phases(&mut self, profiler: &Profiler) {
+        if self.ticks > 0 {
+            self.phase_times
+                .push(Phase::ALL.map(|phase| profiler.last_ms(phase)));
+        }
+    }
+
+    pub fn report(&self, level: &str) -> BenchmarkReport {
+        let frame_ms: Vec<_> = self
+            .frame_times
+            .iter()
+            .skip(WARMUP_FRAMES)
+            .map(|time| time.as_secs_f32() * 1000.0)
+            .collect();
+        let phases_ms = Phase::ALL
+            .iter()
+            .enumerate()
+            .map(|(index, phase)| {
+                let times: Vec<_> = self.phase_times.iter().map(|frame| frame[index]).collect();
+                (phase.name().to_string(), Timings::of(times))
+            })
+            .collect();
+
+        BenchmarkReport {
+            version: env!("CARGO_PKG_VERSION"),
+            level: level.to_string(),
+            seconds: self.seconds,
+            ticks: self.ticks,
+            frames: frame_ms.len(),
+            frame_ms: Timings::of(frame_ms),
+            phases_ms,
+        }
+    }
+}
+
+// What `--benchmark` writes, runs of different commits can be compared field by field
+#[derive(Debug, Serialize)]
+pub struct BenchmarkReport {
+    pub version: &'static str,
+    pub level: String,
+    pub seconds: f32,
+    pub ticks: u64,
+    // Frames drawn after the warm-up
+    pub frames: usize,
+    pub frame_ms: Timings,
+    // Per tick, keyed by Phase::name
+    pub phases_ms: BTreeMap<String, Timings>,
+}
+
+impl BenchmarkReport {
+    pub fn write(&self, path: &Path) -> io::Result<()> {
+        fs::write(path, serde_json::to_vec_pretty(self)?)
+    }
+}
+
+// Spread of a series of times, in milliseconds
+#[derive(Clone, Copy, Debug, Default, Serialize)]
+pub struct Timings {
+    pub average: f32,
+    pub min: f32,
+    pub p50: f32,
+    pub p90: f32,
+    pub p95: f32,
+    pub p99: f32,
+    pub max: f32,
+}
+
+impl Timings {
+    pub fn of(mut times: Vec<f32>) -> Self {
+        if times.is_empty() {
+            return Self::default();
+        }
+        times.sort_by(f32::total_cmp);
+        // Nearest rank, so every percentile is a time that was actually measured
+        let percentile = |p: f32| {
+            let rank = (p / 100.0 * times.len() as f32).ceil() as usize;
+            times[rank.clamp(1, times.len()) - 1]
+        };
+        Self {
+            average: times.iter().sum::<f32>() / times.len() as f32,
+            min: times[0],
+            p50: percentile(50.0),
+            p90: percentile(90.0),
+            p95: percentile(95.0),
+            p99: percentile(99.0),
+            max: times[times.len() - 1],
+        }
+    }
+}
@@ -0,0 +1,131 @@
+use crate::{
+    components::SimTransform,
+    game::Game,
+    handles::{CameraHandle, PlayerHandle},
+    profiler::Phase,
+};
+use fyrox::{
+    core::{
+        algebra::{UnitQuaternion, Vector3},
+        reflect::prelude::*,
+        type_traits::prelude::*,
+        visitor::prelude::*,
+    },
+    scene::{
+        base::BaseBuilder,
+        camera::{CameraBuilder, SkyBoxBuilder},
+        transform::TransformBuilder,
+        Scene,
+    },
+    script::{Script, ScriptContext, ScriptTrait},
+};
+
+// Third-person camera that trails its target and keeps looking at it
+#[derive(Visit, Reflect, Debug, Clone, TypeUuidProvider, ComponentProvider)]
+#[type_uuid(id = "c2d7e6a1-8b0f-4f3e-a5c9-1e64b9d03f72")]
+#[visit(optional)]
+pub struct CameraController {
+    pub target: PlayerHandle,
+    // Where the camera wants to be relative to the target (behind and above)
+    pub offset: Vector3<f32>,
+    // How quickly the camera catches up, higher is snappier
+    pub smoothing: f32,
+}
+
+impl Default for CameraController {
+    fn default() -> Self {
+        Self {
+            target: PlayerHandle::NONE,
+            offset: Vector3::new(0.0, 3.0, 5.0),
+            smoothing: 2.0,
+        }
+    }
+}
+
+impl ScriptTrait for CameraController {
+    fn on_update(&mut self, ctx: &mut ScriptContext) {
+        // The game flies the camera itself while frame-stepping
+        if ctx.plugins.get_mut::<Game>().frame_step().is_frozen() {
+            return;
+        }
+        let scope = ctx
+            .plugins
+            .get_mut::<Game>()
+            .profiler_mut()
+            .begin(Phase::Camera);
+        self.follow(ctx);
+        ctx.plugins.get_mut::<Game>().profiler_mut().end(scope);
+    }
+}
+
+impl CameraController {
+    fn follow(&mut self, ctx: &mut ScriptContext) {
+        // Follow the simulated transforms where there are any, not the interpolated nodes
+        let components = ctx.plugins.get_mut::<Game>().components_mut();
+
+        let Some(target_position) = components
+            .get::<SimTransform>(self.target.handle())
+            .map(|sim| sim.position)
+            .or_else(|| {
+                self.target
+                    .get(&ctx.scene.graph)
+                    .map(|target| **target.local_transform().position())
+            })
+        else {
+            return;
+        };
+
+        let Some(camera_node) = ctx.scene.graph.try_get_mut(ctx.handle) else {
+            return;
+        };
+        let current_position = components
+            .get::<SimTransform>(ctx.handle)
+            .map_or(**camera_node.local_transform().position(), |sim| {
+                sim.position
+            });
+
+        // Smoothly move camera to target position
+        let new_position =
+            current_position.lerp(&(target_position + self.offset), ctx.dt * self.smoothing);
+
+        // Look at target
+        let look_direction = (target_position - new_position).normalize();
+        let rotation = UnitQuaternion::look_at_rh(&look_direction, &Vector3::y());
+
+        match components.get_mut::<SimTransform>(ctx.handle) {
+            Some(sim) => {
+                sim.position = new_position;
+                sim.rotation = rotation;
+            }
+            None => {
+                camera_node
+                    .local_transform_mut()
+                    .set_position(new_position)
+                    .set_rotation(rotation);
+            }
+        }
+    }
+}
+
+// Camera that follows `target` through its script, or a bare one a spectator steers
+pub fn create_camera(scene: &mut Scene, target: Option<PlayerHandle>) -> CameraHandle {
+    // Create a skybox for better visual appeal
+    let skybox = SkyBoxBuilder::new(BaseBuilder::new()).build(&mut scene.graph);
+
+    let mut base = BaseBuilder::new().with_local_transform(
+        TransformBuilder::new()
+            .with_local_position(Vector3::new(0.0, 3.0, 5.0))
+            .build(),
+    );
+    if let Some(target) = target {
+        base = base.with_script(Script::new(CameraController {
+            target,
+            ..Default::default()
+        }));
+    }
+
+    let camera = CameraBuilder::new(base)
+        .with_skybox(skybox)
+        .build(&mut scene.graph);
+    CameraHandle::new(&scene.graph, camera)
+}
@@ -0,0 +1,165 @@
+use crate::{
+    console::CommandRegistry,
+    game::Game,
+    movement::{simulate_movement_at, MoveInput},
+};
+use fyrox::{core::algebra::Vector3, plugin::PluginContext};
+
+// Noclip flies this much faster than the player walks
+const NOCLIP_SPEED_FACTOR: f32 = 2.0;
+
+// Developer cheats. Each one is a switch the system it affects checks and steps
+// around, the game state itself is never bent to fake it: god mode makes the damage
+// handler skip the local player, noclip swaps the player's movement for free flight,
+// infinite stamina lets dashes skip paying for themselves.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Cheat {
+    GodMode,
+    Noclip,
+    InfiniteStamina,
+}
+
+impl Cheat {
+    pub const ALL: [Cheat; 3] = [Cheat::GodMode, Cheat::Noclip, Cheat::InfiniteStamina];
+
+    pub fn name(self) -> &'static str {
+        match self {
+            Cheat::GodMode => "god",
+            Cheat::Noclip => "noclip",
+            Cheat::InfiniteStamina => "stamina",
+        }
+    }
+
+    pub fn from_name(name: &str) -> Option<Self> {
+        Self::ALL.into_iter().find(|cheat| cheat.name() == name)
+    }
+}
+
+// Which cheats are on. Only the console and --dev turn them on, and the game ignores
+// them in online sessions.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct Cheats {
+    god_mode: bool,
+    noclip: bool,
+    infinite_stamina: bool,
+}
+
+impl Cheats {
+    // Parses --dev's list, e.g. "god,noclip"
+    pub fn parse(list: &str) -> Result<Self, String> {
+        let mut cheats = Self::default();
+        for name in list
+            .split(',')
+            .map(str::trim)
+            .filter(|name| !name.is_empty())
+        {
+            let cheat = Cheat::from_name(name).ok_or_else(|| format!("no cheat {name:?}"))?;
+            cheats.set(cheat, true);
+        }
+        Ok(cheats)
+    }
+
+    pub fn is_on(&self, cheat: Cheat) -> bool {
+        match cheat {
+            Cheat::GodMode => self.god_mode,
+            Cheat::Noclip => self.noclip,
+            Cheat::InfiniteStamina => self.infinite_stamina,
+        }
+    }
+
+    pub fn set(&mut self, cheat: Cheat, on: bool) {
+        match cheat {
+            Cheat::GodMode => self.god_mode = on,
+            Cheat::Noclip => self.noclip = on,
+            Cheat::InfiniteStamina => self.infinite_stamina = on,
+        }
+    }
+}
+
+pub fn register_commands(commands: &mut CommandRegistry) {
+    commands.register("god", "[on|off]", "take no damage", command_god);
+    commands.register(
+        "noclip",
+        "[on|off]",
+        "fly freely, E and Q go up and down",
+        command_noclip,
+    );
+    commands.register(
+        "stamina",
+        "[on|off]",
+        "dash without using up stamina",
+        command_stamina,
+    );
+    commands.register(
+        "giveall",
+        "",
+        "collect one of every pickup item",
+        command_give_all,
+    );
+}
+
+// Walking on the ground plane as usual plus going up and down, at a higher speed
+pub fn noclip_movement(
+    position: Vector3<f32>,
+    input: MoveInput,
+    up: bool,
+    down: bool,
+    dt: f32,
+    speed: f32,
+) -> Vector3<f32> {
+    let speed = speed * NOCLIP_SPEED_FACTOR;
+    let mut position = simulate_movement_at(position, input, dt, speed);
+    position.y += (up as i32 - down as i32) as f32 * speed * dt;
+    position
+}
+
+fn command_god(
+    game: &mut Game,
+    _context: &mut PluginContext,
+    args: &[&str],
+) -> Result<String, String> {
+    toggle(game, Cheat::GodMode, args)
+}
+
+fn command_noclip(
+    game: &mut Game,
+    _context: &mut PluginContext,
+    args: &[&str],
+) -> Result<String, String> {
+    toggle(game, Cheat::Noclip, args)
+}
+
+fn command_stamina(
+    game: &mut Game,
+    _context: &mut PluginContext,
+    args: &[&str],
+) -> Result<String, String> {
+    toggle(game, Cheat::InfiniteStamina, args)
+}
+
+fn toggle(game: &mut Game, cheat: Cheat, args: &[&str]) -> Result<String, String> {
+    let on = match args {
+        [] => !game.cheats().is_on(cheat),
+        ["on"] => true,
+        ["off"] => false,
+        _ => return Err(format!("usage: {} [on|off]", cheat.name())),
+    };
+    game.set_cheat(cheat, on)?;
+    Ok(format!(
+        "{} {}",
+        cheat.name(),
+        if on { "on" } else { "off" }
+    ))
+}
+
+fn command_give_all(
+    game: &mut Game,
+    _context: &mut PluginContext,
+    args: &[&str],
+) -> Result<String, String> {
+    if !args.is_empty() {
+        return Err("usage: giveall".to_string());
+    }
+    let count = game.give_all_items()?;
+    Ok(format!("collected {count} items"))
+}
@@ -0,0 +1,326 @@
+use crate::{console::CommandRegistry, game::Game, paths::data_dir};
+use fyrox::{
+    core::{instant::Instant, math::Rect},
+    plugin::PluginContext,
+    renderer::{
+        framework::{error::FrameworkError, state::PipelineState},
+        RenderPassStatistics, Renderer, SceneRenderPass, SceneRenderPassContext,
+    },
+};
+use glow::HasContext;
+use std::{
+    any::TypeId,
+    cell::RefCell,
+    collections::VecDeque,
+    io::Write,
+    path::{Path, PathBuf},
+    process::{Command, Stdio},
+    rc::Rc,
+    sync::{
+        mpsc::{self, Receiver, Sender},
+        Arc, Mutex,
+    },
+    thread,
+    time::{Duration, SystemTime, UNIX_EPOCH},
+};
+
+// How much of the past a clip holds
+pub const CLIP_SECONDS: u32 = 30;
+// Frames kept per second, a clip is for showing what happened, not for smooth motion
+const CLIP_FPS: u32 = 15;
+// Frames are shrunk to at most this wide, 30 seconds of them take about 300 MB
+const MAX_CLIP_WIDTH: u32 = 640;
+
+// One shrunk frame, RGB rows from the top
+struct Frame {
+    width: u32,
+    height: u32,
+    rgb: Vec<u8>,
+}
+
+// A full size frame read back by the render pass, RGBA rows from the bottom
+struct Capture {
+    width: u32,
+    height: u32,
+    rgba: Vec<u8>,
+}
+
+// Handed between the recorder, which asks for frames, and the render pass reading them
+#[derive(Default)]
+struct CaptureRequest {
+    wanted: bool,
+    captured: Option<Capture>,
+}
+
+// Keeps the last 30 seconds of the level as it was rendered and turns them into a
+// video when asked. Frames are read back by a render pass from the level's own image,
+// which is complete and defined at that point, unlike the window's buffers around a
+// swap. The HUD and menus go on top later, so clips show the game without them.
+// Reading costs a little each time, so recording is off until switched on. Encoding is
+// left to an ffmpeg on the PATH, on a worker thread so the game doesn't stop for it.
+pub struct ClipRecorder {
+    recording: bool,
+    frames: VecDeque<Frame>,
+    last_capture: Option<Instant>,
+    request: Arc<Mutex<CaptureRequest>>,
+    results: Receiver<Result<PathBuf, String>>,
+    result_sender: Sender<Result<PathBuf, String>>,
+}
+
+impl Default for ClipRecorder {
+    fn default() -> Self {
+        let (result_sender, results) = mpsc::channel();
+        Self {
+            recording: false,
+            frames: VecDeque::new(),
+            last_capture: None,
+            request: Arc::default(),
+            results,
+            result_sender,
+        }
+    }
+}
+
+impl ClipRecorder {
+    // Adds the pass that reads frames back to a new renderer
+    pub fn install(&self, renderer: &mut Renderer) {
+        renderer.add_render_pass(Rc::new(RefCell::new(ClipPass {
+            request: self.request.clone(),
+        })));
+    }
+
+    pub fn is_recording(&self) -> bool {
+        self.recording
+    }
+
+    // Browsers and phones can't read the shown frame back or run ffmpeg
+    pub fn set_recording(&mut self, recording: bool) -> Result<(), String> {
+        if recording && cfg!(any(target_arch = "wasm32", target_os = "android")) {
+            return Err("clips can't be recorded on this platform".to_string());
+        }
+        self.recording = recording;
+        if !recording {
+            self.frames.clear();
+            *self.lock_request() = CaptureRequest::default();
+        }
+        Ok(())
+    }
+
+    fn lock_request(&self) -> std::sync::MutexGuard<'_, CaptureRequest> {
+        self.request.lock().unwrap_or_else(|err| err.into_inner())
+    }
+
+    // Seconds of the past a clip saved now would hold
+    pub fn buffered_seconds(&self) -> f32 {
+        self.frames.len() as f32 / CLIP_FPS as f32
+    }
+
+    // Called every frame: keeps what the render pass read since, and asks for the next
+    // frame when it is time
+    pub fn update(&mut self) {
+        if !self.recording {
+            return;
+        }
+        let captured = {
+            let mut request = self.lock_request();
+            let interval = Duration::from_secs(1) / CLIP_FPS;
+            if !self
+                .last_capture
+                .is_some_and(|last| last.elapsed() < interval)
+            {
+                request.wanted = true;
+                self.last_capture = Some(Instant::now());
+            }
+            request.captured.take()
+        };
+        let Some(capture) = captured.filter(|capture| capture.width > 0 && capture.height > 0)
+        else {
+            return;
+        };
+
+        let frame = shrink(&capture);
+        // A video has one size throughout, a resized window starts the buffer over
+        if self
+            .frames
+            .back()
+            .is_some_and(|last| (last.width, last.height) != (frame.width, frame.height))
+        {
+            self.frames.clear();
+        }
+        self.frames.push_back(frame);
+        while self.frames.len() > (CLIP_SECONDS * CLIP_FPS) as usize {
+            self.frames.pop_front();
+        }
+    }
+
+    // Starts encoding what is buffered, returns the file it goes to. Recording goes on
+    // meanwhile, the outcome comes from poll().
+    pub fn save(&mut self) -> Result<PathBuf, String> {
+        let Some(first) = self.frames.front() else {
+            return Err("nothing recorded yet".to_string());
+        };
+        let (width, height) = (first.width, first.height);
+        let mut video = Vec::with_capacity(self.frames.len() * first.rgb.len());
+        for frame in &self.frames {
+            video.extend_from_slice(&frame.rgb);
+        }
+
+        let stamp = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map_or(0, |time| time.as_secs());
+        let path = data_dir().join("clips").join(format!("clip-{stamp}.mp4"));
+        let target = path.clone();
+        let results = self.result_sender.clone();
+        thread::spawn(move || {
+            let _ = results.send(encode(&video, width, height, &target).map(|()| target));
+        });
+        Ok(path)
+    }
+
+    // Outcome of the last save that has finished encoding
+    pub fn poll(&self) -> Option<Result<PathBuf, String>> {
+        self.results.try_recv().ok()
+    }
+}
+
+// Reads the level's image once the scene is drawn into it, whenever the recorder asks
+struct ClipPass {
+    request: Arc<Mutex<CaptureRequest>>,
+}
+
+impl SceneRenderPass for ClipPass {
+    fn on_ldr_render(
+        &mut self,
+        ctx: SceneRenderPassContext,
+    ) -> Result<RenderPassStatistics, FrameworkError> {
+        let mut request = self.request.lock().unwrap_or_else(|err| err.into_inner());
+        if request.wanted {
+            request.wanted = false;
+            ctx.pipeline_state.set_framebuffer(ctx.framebuffer.id());
+            request.captured = Some(read_frame(ctx.pipeline_state, ctx.viewport));
+        }
+        Ok(RenderPassStatistics::default())
+    }
+
+    fn source_type_id(&self) -> TypeId {
+        TypeId::of::<Game>()
+    }
+}
+
+// The viewport of the bound framebuffer's first color attachment, RGBA rows from the
+// bottom
+fn read_frame(state: &PipelineState, viewport: Rect<i32>) -> Capture {
+    let (width, height) = (viewport.w().max(0) as u32, viewport.h().max(0) as u32);
+    let mut rgba = vec![0; (width * height * 4) as usize];
+    let gl = &state.gl;
+    // Safety: plain reads from the framebuffer the pass was given, bound through the
+    // pipeline state so its idea of the binding stays right. Whatever read buffer was
+    // selected before is selected again afterwards.
+    unsafe {
+        let previous = gl.get_parameter_i32(glow::READ_BUFFER) as u32;
+        gl.read_buffer(glow::COLOR_ATTACHMENT0);
+        gl.read_pixels(
+            viewport.x(),
+            viewport.y(),
+            width as i32,
+            height as i32,
+            glow::RGBA,
+            glow::UNSIGNED_BYTE,
+            glow::PixelPackData::Slice(&mut rgba),
+        );
+        gl.read_buffer(previous);
+    }
+    Capture {
+        width,
+        height,
+        rgba,
+    }
+}
+
+// Every n-th pixel of every n-th row until the frame fits MAX_CLIP_WIDTH, flipped to
+// rows from the top. Sizes are kept even, video encoders want that.
+fn shrink(capture: &Capture) -> Frame {
+    let step = capture.width.div_ceil(MAX_CLIP_WIDTH).max(1);
+    let width = (capture.width / step) & !1;
+    let height = (capture.height / step) & !1;
+    let mut rgb = Vec::with_capacity((width * height * 3) as usize);
+    for y in 0..height {
+        let row = (capture.height - 1 - y * step) * capture.width;
+        for x in 0..width {
+            let pixel = ((row + x * step) * 4) as usize;
+            rgb.extend_from_slice(&capture.rgba[pixel..pixel + 3]);
+        }
+    }
+    Frame { width, height, rgb }
+}
+
+fn encode(video: &[u8], width: u32, height: u32, path: &Path) -> Result<(), String> {
+    if let Some(dir) = path.parent() {
+        std::fs::create_dir_all(dir).map_err(|err| err.to_string())?;
+    }
+    let mut ffmpeg = Command::new("ffmpeg")
+        .args([
+            "-y",
+            "-loglevel",
+            "error",
+            "-f",
+            "rawvideo",
+            "-pix_fmt",
+            "rgb24",
+        ])
+        .args(["-s", &format!("{width}x{height}")])
+        .args(["-r", &CLIP_FPS.to_string(), "-i", "-"])
+        .args(["-c:v", "libx264", "-pix_fmt", "yuv420p"])
+        .arg(path)
+        .stdin(Stdio::piped())
+        .stdout(Stdio::null())
+        .stderr(Stdio::piped())
+        .spawn()
+        .map_err(|err| format!("couldn't run ffmpeg: {err}"))?;
+    if let Some(mut stdin) = ffmpeg.stdin.take() {
+        stdin
+            .write_all(video)
+            .map_err(|err| format!("ffmpeg stopped reading: {err}"))?;
+    }
+    let output = ffmpeg.wait_with_output().map_err(|err| err.to_string())?;
+    if output.status.success() {
+        Ok(())
+    } else {
+        Err(format!(
+            "ffmpeg failed: {}",
+            String::from_utf8_lossy(&output.stderr).trim()
+        ))
+    }
+}
+
+pub fn register_commands(commands: &mut CommandRegistry) {
+    commands.register(
+        "clip",
+        "[on|off|save]",
+        "record the last 30 seconds, save writes them to a video",
+        command_clip,
+    );
+    commands.complete_with("clip", || {
+        vec!["on".to_string(), "off".to_string(), "save".to_string()]
+    });
+}
+
+fn command_clip(
+    game: &mut Game,
+    _context: &mut PluginContext,
+    args: &[&str],
+) -> Result<String, String> {
+    match args {
+        [] => {}
+        ["on"] => game.clips_mut().set_recording(true)?,
+        ["off"] => game.clips_mut().set_recording(false)?,
+        ["save"] => return game.save_clip(),
+        _ => return Err("usage: clip [on|off|save]".to_string()),
+    }
+    let clips = game.clips();
+    Ok(if clips.is_recording() {
+        format!("recording clips, {:.0}s buffered", clips.buffered_seconds())
+    } else {
+        "not recording clips".to_string()
+    })
+}
@@ -0,0 +1,170 @@
+use crate::{console::CommandRegistry, game::Game};
+use fyrox::plugin::PluginContext;
+use std::sync::RwLock;
+
+// How strongly one of the jarring screen effects plays
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum EffectLevel {
+    #[default]
+    Full,
+    Reduced,
+    Off,
+}
+
+impl EffectLevel {
+    pub const ALL: [EffectLevel; 3] = [EffectLevel::Full, EffectLevel::Reduced, EffectLevel::Off];
+
+    pub fn name(self) -> &'static str {
+        match self {
+            EffectLevel::Full => "full",
+            EffectLevel::Reduced => "reduced",
+            EffectLevel::Off => "off",
+        }
+    }
+
+    pub fn from_name(name: &str) -> Option<Self> {
+        Self::ALL.into_iter().find(|level| level.name() == name)
+    }
+
+    pub fn next(self) -> Self {
+        Self::ALL[(self as usize + 1) % Self::ALL.len()]
+    }
+
+    // What the effect's strength is multiplied with
+    pub fn scale(self) -> f32 {
+        match self {
+            EffectLevel::Full => 1.0,
+            EffectLevel::Reduced => 0.3,
+            EffectLevel::Off => 0.0,
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Effect {
+    CameraShake,
+    ScreenFlash,
+    Hitstop,
+}
+
+impl Effect {
+    pub const ALL: [Effect; 3] = [Effect::CameraShake, Effect::ScreenFlash, Effect::Hitstop];
+
+    pub fn name(self) -> &'static str {
+        match self {
+            Effect::CameraShake => "shake",
+            Effect::ScreenFlash => "flashes",
+            Effect::Hitstop => "hitstop",
+        }
+    }
+
+    pub fn from_name(name: &str) -> Option<Self> {
+        Self::ALL.into_iter().find(|effect| effect.name() == name)
+    }
+}
+
+// Settings for motion-sensitive players, every effect is checked by what plays it
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct ComfortSettings {
+    pub shake: EffectLevel,
+    pub flashes: EffectLevel,
+    pub hitstop: EffectLevel,
+}
+
+impl ComfortSettings {
+    pub fn all(level: EffectLevel) -> Self {
+        Self {
+            shake: level,
+            flashes: level,
+            hitstop: level,
+        }
+    }
+
+    pub fn level(&self, effect: Effect) -> EffectLevel {
+        match effect {
+            Effect::CameraShake => self.shake,
+            Effect::ScreenFlash => self.flashes,
+            Effect::Hitstop => self.hitstop,
+        }
+    }
+
+    pub fn set_level(&mut self, effect: Effect, level: EffectLevel) {
+        match effect {
+            Effect::CameraShake => self.shake = level,
+            Effect::ScreenFlash => self.flashes = level,
+            Effect::Hitstop => self.hitstop = level,
+        }
+    }
+
+    // The level all effects share, None once they were set one by one
+    pub fn common_level(&self) -> Option<EffectLevel> {
+        (self.shake == self.flashes && self.flashes == self.hitstop).then_some(self.shake)
+    }
+}
+
+static SETTINGS: RwLock<ComfortSettings> = RwLock::new(ComfortSettings {
+    shake: EffectLevel::Full,
+    flashes: EffectLevel::Full,
+    hitstop: EffectLevel::Full,
+});
+
+pub fn settings() -> ComfortSettings {
+    *SETTINGS.read().unwrap()
+}
+
+pub fn set_settings(settings: ComfortSettings) {
+    *SETTINGS.write().unwrap() = settings;
+}
+
+// Strength multiplier of `effect` with the current settings
+pub fn scale(effect: Effect) -> f32 {
+    settings().level(effect).scale()
+}
+
+pub fn register_commands(commands: &mut CommandRegistry) {
+    commands.register(
+        "comfort",
+        "[shake|flashes|hitstop|all] [full|reduced|off]",
+        "show or tone down screen effects",
+        command_comfort,
+    );
+    commands.complete_with("comfort", || {
+        Effect::ALL
+            .iter()
+            .map(|effect| effect.name().to_string())
+            .chain(["all".to_string()])
+            .collect()
+    });
+}
+
+fn command_comfort(
+    game: &mut Game,
+    context: &mut PluginContext,
+    args: &[&str],
+) -> Result<String, String> {
+    let mut settings = settings();
+    match args {
+        [] => return Ok(describe(settings)),
+        ["all", level] => settings = ComfortSettings::all(parse_level(level)?),
+        [effect, level] => {
+            let effect =
+                Effect::from_name(effect).ok_or_else(|| format!("no effect {effect:?}"))?;
+            settings.set_level(effect, parse_level(level)?);
+        }
+        _ => return Err("usage: comfort [EFFECT|all] [full|reduced|off]".to_string()),
+    }
+    game.set_comfort(context, settings);
+    Ok(describe(settings))
+}
+
+fn describe(settings: ComfortSettings) -> String {
+    Effect::ALL
+        .iter()
+        .map(|effect| format!("{} {}", effect.name(), settings.level(*effect).name()))
+        .collect::<Vec<_>>()
+        .join(", ")
+}
+
+fn parse_level(name: &str) -> Result<EffectLevel, String> {
+    EffectLevel::from_name(name).ok_or_else(|| format!("no level {name:?}"))
+}
@@ -0,0 +1,384 @@
+use fyrox::{
+    core::{
+        algebra::{UnitQuaternion, Vector3},
+        pool::Handle,
+    },
+    scene::node::Node,
+};
+use std::{
+    any::{Any, TypeId},
+    collections::{BTreeMap, BTreeSet, HashMap},
+};
+
+// Gameplay data attached to scene nodes. The scene graph keeps transforms and
+// visuals, everything the game rules care about lives here.
+// Components are plain data, shareable with the systems that read them on worker threads.
+pub trait Component: Send + Sync + 'static {}
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct Health {
+    pub current: f32,
+    pub max: f32,
+}
+
+impl Health {
+    pub fn new(max: f32) -> Self {
+        Self { current: max, max }
+    }
+
+    pub fn is_dead(&self) -> bool {
+        self.current <= 0.0
+    }
+
+    // Returns the damage actually taken
+    pub fn damage(&mut self, amount: f32) -> f32 {
+        let taken = amount.clamp(0.0, self.current);
+        self.current -= taken;
+        taken
+    }
+
+    pub fn heal(&mut self, amount: f32) {
+        self.current = (self.current + amount.max(0.0)).min(self.max);
+    }
+}
+
+impl Component for Health {}
+
+// Spent on dashes and recovered over time
+#[derive(Clone, Debug, PartialEq)]
+pub struct Stamina {
+    pub current: f32,
+    pub max: f32,
+    // Seconds left of the dash in progress
+    pub dash_left: f32,
+}
+
+impl Stamina {
+    pub fn new(max: f32) -> Self {
+        Self {
+            current: max,
+            max,
+            dash_left: 0.0,
+        }
+    }
+
+    // Takes `cost` if there is that much left, returns whether it did
+    pub fn spend(&mut self, cost: f32) -> bool {
+        if self.current < cost {
+            return false;
+        }
+        self.current -= cost;
+        true
+    }
+
+    pub fn recover(&mut self, amount: f32) {
+        self.current = (self.current + amount.max(0.0)).min(self.max);
+    }
+}
+
+impl Component for Stamina {}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum PickupKind {
+    Coin,
+    Health,
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct Pickup {
+    pub kind: PickupKind,
+    pub value: u32,
+    // Collected when the player gets this close
+    pub radius: f32,
+}
+
+impl Component for Pickup {}
+
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum EnemyState {
+    #[default]
+    Idle,
+    Chasing,
+    Returning,
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct EnemyAi {
+    pub speed: f32,
+    // Starts chasing players that come closer than this
+    pub aggro_radius: f32,
+    pub home: Vector3<f32>,
+    pub state: EnemyState,
+}
+
+impl Component for EnemyAi {}
+
+// Something the player can use by walking up to it and pressing F
+#[derive(Clone, Debug, PartialEq)]
+pub struct Interactable {
+    // String table key of what is shown to the player while in range, e.g. "Open crate"
+    pub prompt: String,
+    pub radius: f32,
+    // Collected by whoever opens it, which uses it up. Things without stay around.
+    pub contents: Option<(PickupKind, u32)>,
+}
+
+impl Component for Interactable {}
+
+// Hurts the player on touch, at most once per interval
+#[derive(Clone, Debug, PartialEq)]
+pub struct ContactDamage {
+    pub amount: f32,
+    // Horizontal distance at which the player counts as touched
+    pub radius: f32,
+    // Seconds between two hits
+    pub interval: f32,
+    // Seconds until the next hit can land
+    pub cooldown: f32,
+}
+
+impl ContactDamage {
+    pub fn new(amount: f32, radius: f32, interval: f32) -> Self {
+        Self {
+            amount,
+            radius,
+            interval,
+            cooldown: 0.0,
+        }
+    }
+}
+
+impl Component for ContactDamage {}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum TriggerKind {
+    // Finishes the level
+    Goal,
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct Trigger {
+    pub kind: TriggerKind,
+    // Horizontal distance at which something counts as inside
+    pub radius: f32,
+    // Fires only the first time something enters
+    pub once: bool,
+    pub fired: bool,
+}
+
+impl Trigger {
+    pub fn new(kind: TriggerKind, radius: f32) -> Self {
+        Self {
+            kind,
+            radius,
+            once: true,
+            fired: false,
+        }
+    }
+
+    pub fn contains(&self, center: Vector3<f32>, position: Vector3<f32>) -> bool {
+        let offset = Vector3::new(position.x - center.x, 0.0, position.z - center.z);
+        offset.norm() <= self.radius
+    }
+}
+
+impl Component for Trigger {}
+
+// Simulated transform of a node over the last two fixed ticks. Simulation reads and
+// writes `position`/`rotation`, the node itself only gets the blend of both that
+// matches the moment a frame is rendered.
+#[derive(Clone, Debug, PartialEq)]
+pub struct SimTransform {
+    pub previous_position: Vector3<f32>,
+    pub previous_rotation: UnitQuaternion<f32>,
+    pub position: Vector3<f32>,
+    pub rotation: UnitQuaternion<f32>,
+}
+
+impl SimTransform {
+    pub fn new(position: Vector3<f32>, rotation: UnitQuaternion<f32>) -> Self {
+        Self {
+            previous_position: position,
+            previous_rotation: rotation,
+            position,
+            rotation,
+        }
+    }
+
+    // Called at the start of every tick, before anything moves
+    pub fn begin_tick(&mut self) {
+        self.previous_position = self.position;
+        self.previous_rotation = self.rotation;
+    }
+
+    // Transform `alpha` of the way from the previous tick to the current one
+    pub fn interpolate(&self, alpha: f32) -> (Vector3<f32>, UnitQuaternion<f32>) {
+        (
+            self.previous_position.lerp(&self.position, alpha),
+            self.previous_rotation.nlerp(&self.rotation, alpha),
+        )
+    }
+}
+
+impl Component for SimTransform {}
+
+// Type-erased view of one component map, so a whole entity can be dropped at once
+trait Storage: Send + Sync {
+    fn remove_entity(&mut self, entity: Handle<Node>);
+    fn entities(&self) -> Vec<Handle<Node>>;
+    fn as_any(&self) -> &dyn Any;
+    fn as_any_mut(&mut self) -> &mut dyn Any;
+}
+
+impl<T: Component> Storage for BTreeMap<Handle<Node>, T> {
+    fn remove_entity(&mut self, entity: Handle<Node>) {
+        self.remove(&entity);
+    }
+
+    fn entities(&self) -> Vec<Handle<Node>> {
+        self.keys().copied().collect()
+    }
+
+    fn as_any(&self) -> &dyn Any {
+        self
+    }
+
+    fn as_any_mut(&mut self) -> &mut dyn Any {
+        self
+    }
+}
+
+// Components of every entity keyed by the node they belong to, one map per type.
+// Maps are ordered by handle so systems visit entities in the same order every run.
+#[derive(Default)]
+pub struct ComponentStore {
+    storages: HashMap<TypeId, Box<dyn Storage>>,
+}
+
+impl ComponentStore {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    // Attaches a component, returning the one of the same type it replaced
+    pub fn insert<T: Component>(&mut self, entity: Handle<Node>, component: T) -> Option<T> {
+        self.storage_mut::<T>().insert(entity, component)
+    }
+
+    pub fn remove<T: Component>(&mut self, entity: Handle<Node>) -> Option<T> {
+        self.storage_mut::<T>().remove(&entity)
+    }
+
+    pub fn get<T: Component>(&self, entity: Handle<Node>) -> Option<&T> {
+        self.storage::<T>()?.get(&entity)
+    }
+
+    pub fn get_mut<T: Component>(&mut self, entity: Handle<Node>) -> Option<&mut T> {
+        self.storage_mut::<T>().get_mut(&entity)
+    }
+
+    pub fn has<T: Component>(&self, entity: Handle<Node>) -> bool {
+        self.get::<T>(entity).is_some()
+    }
+
+    // Every entity with a component of this type
+    pub fn iter<T: Component>(&self) -> impl Iterator<Item = (Handle<Node>, &T)> {
+        self.storage::<T>().into_iter().flat_map(|storage| {
+            storage
+                .iter()
+                .map(|(entity, component)| (*entity, component))
+        })
+    }
+
+    pub fn iter_mut<T: Component>(&mut self) -> impl Iterator<Item = (Handle<Node>, &mut T)> {
+        self.storage_mut::<T>()
+            .iter_mut()
+            .map(|(entity, component)| (*entity, component))
+    }
+
+    // Drops all components of a node, e.g. when it is removed from the scene
+    pub fn remove_entity(&mut self, entity: Handle<Node>) {
+        for storage in self.storages.values_mut() {
+            storage.remove_entity(entity);
+        }
+    }
+
+    pub fn clear(&mut self) {
+        self.storages.clear();
+    }
+
+    // Nodes with at least one component
+    pub fn entity_count(&self) -> usize {
+        self.storages
+            .values()
+            .flat_map(|storage| storage.entities())
+            .collect::<BTreeSet<_>>()
+            .len()
+    }
+
+    fn storage<T: Component>(&self) -> Option<&BTreeMap<Handle<Node>, T>> {
+        self.storages
+            .get(&TypeId::of::<T>())
+            .and_then(|storage| storage.as_any().downcast_ref())
+    }
+
+    fn storage_mut<T: Component>(&mut self) -> &mut BTreeMap<Handle<Node>, T> {
+        self.storages
+            .entry(TypeId::of::<T>())
+            .or_insert_with(|| Box::new(BTreeMap::<Handle<Node>, T>::new()))
+            .as_any_mut()
+            .downcast_mut()
+            .expect("component storage registered under the wrong type")
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn entity(index: u32) -> Handle<Node> {
+        Handle::new(index, 1)
+    }
+
+    #[test]
+    fn inserting_again_replaces_and_returns_the_old_component() {
+        let mut store = ComponentStore::new();
+        assert_eq!(store.insert(entity(1), Health::new(100.0)), None);
+        assert_eq!(
+            store.insert(entity(1), Health::new(50.0)),
+            Some(Health::new(100.0))
+        );
+        assert_eq!(store.get::<Health>(entity(1)), Some(&Health::new(50.0)));
+        assert_eq!(store.entity_count(), 1);
+    }
+
+    #[test]
+    fn entities_are_visited_in_handle_order() {
+        let mut store = ComponentStore::new();
+        for index in [3, 1, 2] {
+            store.insert(entity(index), Health::new(index as f32));
+        }
+        let visited: Vec<_> = store.iter::<Health>().map(|(entity, _)| entity).collect();
+        assert_eq!(visited, vec![entity(1), entity(2), entity(3)]);
+
+        // Types nothing was inserted for have nothing to visit
+        assert_eq!(store.iter::<Stamina>().count(), 0);
+    }
+
+    #[test]
+    fn removing_takes_one_type_or_the_whole_entity() {
+        let mut store = ComponentStore::new();
+        store.insert(entity(1), Health::new(100.0));
+        store.insert(entity(1), Stamina::new(100.0));
+        store.insert(entity(2), Health::new(30.0));
+
+        assert_eq!(store.remove::<Health>(entity(1)), Some(Health::new(100.0)));
+        assert!(!store.has::<Health>(entity(1)));
+        assert!(store.has::<Stamina>(entity(1)));
+
+        store.remove_entity(entity(1));
+        assert!(!store.has::<Stamina>(entity(1)));
+        assert!(store.has::<Health>(entity(2)));
+        assert_eq!(store.entity_count(), 1);
+    }
+}
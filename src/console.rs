@@ -0,0 +1,354 @@
+use crate::game::Game;
+use fyrox::{
+    core::{color::Color, pool::Handle},
+    gui::{
+        border::BorderBuilder,
+        brush::Brush,
+        message::{MessageDirection, UiMessage},
+        stack_panel::StackPanelBuilder,
+        text::{TextBuilder, TextMessage},
+        text_box::TextBoxBuilder,
+        widget::{WidgetBuilder, WidgetMessage},
+        BuildContext, HorizontalAlignment, Thickness, UiNode, UserInterface, VerticalAlignment,
+    },
+    plugin::PluginContext,
+};
+use std::collections::{BTreeMap, VecDeque};
+
+// Lines of output shown above the input box
+const VISIBLE_LINES: usize = 14;
+
+// Entered lines kept for browsing with the arrow keys
+const MAX_HISTORY: usize = 50;
+
+// Runs a command with its arguments, returning what to print
+pub type CommandHandler = fn(&mut Game, &mut PluginContext, &[&str]) -> Result<String, String>;
+
+struct Command {
+    usage: &'static str,
+    help: &'static str,
+    handler: CommandHandler,
+    // Candidates for the first argument, for tab completion
+    values: Option<fn() -> Vec<String>>,
+}
+
+// Every command the console knows, filled in by the systems that own them
+#[derive(Default)]
+pub struct CommandRegistry {
+    commands: BTreeMap<&'static str, Command>,
+}
+
+impl CommandRegistry {
+    // `usage` is the argument list shown by help, e.g. "ITEM [DISTANCE]"
+    pub fn register(
+        &mut self,
+        name: &'static str,
+        usage: &'static str,
+        help: &'static str,
+        handler: CommandHandler,
+    ) {
+        self.commands.insert(
+            name,
+            Command {
+                usage,
+                help,
+                handler,
+                values: None,
+            },
+        );
+    }
+
+    // Lets tab complete the first argument of a registered command
+    pub fn complete_with(&mut self, name: &str, values: fn() -> Vec<String>) {
+        if let Some(command) = self.commands.get_mut(name) {
+            command.values = Some(values);
+        }
+    }
+}
+
+// Drop-down console at the top of the screen, toggled with the backquote key.
+// Lines are run at the start of the next update, where the game and the engine
+// context are both at hand.
+#[derive(Default)]
+pub struct Console {
+    panel: Handle<UiNode>,
+    input: Handle<UiNode>,
+    line_slots: Vec<Handle<UiNode>>,
+    registry: CommandRegistry,
+    output: VecDeque<String>,
+    history: Vec<String>,
+    // Position while browsing the history, None when editing a fresh line
+    history_cursor: Option<usize>,
+    pending: Vec<String>,
+    draft: String,
+    open: bool,
+    output_dirty: bool,
+}
+
+impl Console {
+    pub fn new(ctx: &mut BuildContext, registry: CommandRegistry) -> Self {
+        let line_slots: Vec<_> = (0..VISIBLE_LINES)
+            .map(|_| {
+                TextBuilder::new(
+                    WidgetBuilder::new()
+                        .with_foreground(Brush::Solid(Color::opaque(220, 220, 220))),
+                )
+                .build(ctx)
+            })
+            .collect();
+        let input = TextBoxBuilder::new(WidgetBuilder::new().with_height(24.0)).build(ctx);
+
+        let mut children = line_slots.clone();
+        children.push(input);
+        let panel = BorderBuilder::new(
+            WidgetBuilder::new()
+                .with_visibility(false)
+                .with_horizontal_alignment(HorizontalAlignment::Stretch)
+                .with_vertical_alignment(VerticalAlignment::Top)
+                .with_background(Brush::Solid(Color::from_rgba(0, 0, 0, 200)))
+                .with_child(
+                    StackPanelBuilder::new(
+                        WidgetBuilder::new()
+                            .with_margin(Thickness::uniform(6.0))
+                            .with_children(children),
+                    )
+                    .build(ctx),
+                ),
+        )
+        .build(ctx);
+
+        Self {
+            panel,
+            input,
+            line_slots,
+            registry,
+            output: VecDeque::new(),
+            history: Vec::new(),
+            history_cursor: None,
+            pending: Vec::new(),
+            draft: String::new(),
+            open: false,
+            output_dirty: true,
+        }
+    }
+
+    pub fn is_open(&self) -> bool {
+        self.open
+    }
+
+    pub fn toggle(&mut self, ui: &UserInterface) {
+        self.open = !self.open;
+        self.history_cursor = None;
+        self.set_input(ui, String::new());
+        ui.send_message(WidgetMessage::visibility(
+            self.panel,
+            MessageDirection::ToWidget,
+            self.open,
+        ));
+        let focus = if self.open {
+            WidgetMessage::focus(self.input, MessageDirection::ToWidget)
+        } else {
+            WidgetMessage::unfocus(self.input, MessageDirection::ToWidget)
+        };
+        ui.send_message(focus);
+    }
+
+    pub fn print(&mut self, text: &str) {
+        for line in text.lines() {
+            self.output.push_back(line.to_string());
+        }
+        while self.output.len() > VISIBLE_LINES {
+            self.output.pop_front();
+        }
+        self.output_dirty = true;
+    }
+
+    // Queues the typed line to run with the next update
+    pub fn submit(&mut self, ui: &UserInterface) {
+        // The toggle key itself may have ended up in the box
+        let line = self.draft.replace('`', "").trim().to_string();
+        self.set_input(ui, String::new());
+        self.history_cursor = None;
+        if line.is_empty() {
+            return;
+        }
+
+        self.print(&format!("> {line}"));
+        if self.history.last() != Some(&line) {
+            self.history.push(line.clone());
+            if self.history.len() > MAX_HISTORY {
+                self.history.remove(0);
+            }
+        }
+        self.pending.push(line);
+    }
+
+    // Steps through earlier lines, `older` with the up arrow
+    pub fn browse_history(&mut self, ui: &UserInterface, older: bool) {
+        if self.history.is_empty() {
+            return;
+        }
+        let last = self.history.len() - 1;
+        self.history_cursor = match (self.history_cursor, older) {
+            (None, true) => Some(last),
+            (None, false) => None,
+            (Some(index), true) => Some(index.saturating_sub(1)),
+            (Some(index), false) if index < last => Some(index + 1),
+            (Some(_), false) => None,
+        };
+        let text = self
+            .history_cursor
+            .map_or_else(String::new, |index| self.history[index].clone());
+        self.set_input(ui, text);
+    }
+
+    // Completes the command name, or its first argument once the name is typed.
+    // With several candidates the common part is filled in and the rest listed.
+    pub fn complete(&mut self, ui: &UserInterface) {
+        let draft = self.draft.replace('`', "");
+        let words: Vec<_> = draft.split_whitespace().collect();
+        let (prefix, partial, candidates): (String, &str, Vec<String>) =
+            match (words.as_slice(), draft.ends_with(' ')) {
+                ([] | [_], false) => (
+                    String::new(),
+                    words.first().copied().unwrap_or(""),
+                    self.registry
+                        .commands
+                        .keys()
+                        .map(|name| name.to_string())
+                        .collect(),
+                ),
+                ([name], true) | ([name, _], false) => {
+                    let values = self
+                        .registry
+                        .commands
+                        .get(name)
+                        .and_then(|command| command.values)
+                        .map(|values| values())
+                        .unwrap_or_default();
+                    (
+                        format!("{name} "),
+                        words.get(1).copied().unwrap_or(""),
+                        values,
+                    )
+                }
+                _ => return,
+            };
+
+        let matches: Vec<_> = candidates
+            .into_iter()
+            .filter(|candidate| candidate.starts_with(partial))
+            .collect();
+        match matches.as_slice() {
+            [] => {}
+            [only] => self.set_input(ui, format!("{prefix}{only} ")),
+            _ => {
+                let common = common_prefix(&matches);
+                self.print(&matches.join("  "));
+                self.set_input(ui, format!("{prefix}{common}"));
+            }
+        }
+    }
+
+    pub fn handle_ui_message(&mut self, message: &UiMessage) {
+        if let Some(TextMessage::Text(text)) = message.data() {
+            if message.destination() == self.input
+                && message.direction() == MessageDirection::FromWidget
+            {
+                self.draft = text.clone();
+            }
+        }
+    }
+
+    // Lines entered since the last call, to be run in order
+    pub fn take_pending(&mut self) -> Vec<String> {
+        std::mem::take(&mut self.pending)
+    }
+
+    // Handles the console's own commands, or returns the handler of a registered one
+    pub fn execute(&mut self, line: &str) -> Result<Option<CommandHandler>, String> {
+        let mut words = line.split_whitespace();
+        let name = words.next().unwrap_or_default();
+        match name {
+            "help" => {
+                let text = match words.next() {
+                    Some(topic) => {
+                        let command = self
+                            .registry
+                            .commands
+                            .get(topic)
+                            .ok_or_else(|| format!("no command {topic:?}"))?;
+                        format!("{topic} {} - {}", command.usage, command.help)
+                    }
+                    None => {
+                        let mut names: Vec<_> = self.registry.commands.keys().copied().collect();
+                        names.extend(["clear", "help", "history"]);
+                        names.sort_unstable();
+                        format!("Commands: {}. help COMMAND for details", names.join(", "))
+                    }
+                };
+                self.print(&text);
+                Ok(None)
+            }
+            "clear" => {
+                self.output.clear();
+                self.output_dirty = true;
+                Ok(None)
+            }
+            "history" => {
+                let text = self.history.join("\n");
+                self.print(&text);
+                Ok(None)
+            }
+            _ => self
+                .registry
+                .commands
+                .get(name)
+                .map(|command| Some(command.handler))
+                .ok_or_else(|| format!("unknown command {name:?}, try help")),
+        }
+    }
+
+    pub fn update(&mut self, ui: &UserInterface) {
+        if !self.output_dirty {
+            return;
+        }
+        // Newest line goes into the bottom slot
+        let first_slot = VISIBLE_LINES - self.output.len();
+        for (index, slot) in self.line_slots.iter().enumerate() {
+            let text = index
+                .checked_sub(first_slot)
+                .and_then(|line| self.output.get(line))
+                .cloned()
+                .unwrap_or_default();
+            ui.send_message(TextMessage::text(*slot, MessageDirection::ToWidget, text));
+        }
+        self.output_dirty = false;
+    }
+
+    fn set_input(&mut self, ui: &UserInterface, text: String) {
+        self.draft = text.clone();
+        ui.send_message(TextMessage::text(
+            self.input,
+            MessageDirection::ToWidget,
+            text,
+        ));
+    }
+}
+
+fn common_prefix(words: &[String]) -> String {
+    let Some(first) = words.first() else {
+        return String::new();
+    };
+    let mut length = first.len();
+    for word in &words[1..] {
+        length = first
+            .chars()
+            .zip(word.chars())
+            .take_while(|(a, b)| a == b)
+            .map(|(a, _)| a.len_utf8())
+            .sum::<usize>()
+            .min(length);
+    }
+    first[..length].to_string()
+}
@@ -0,0 +1,153 @@
+use crate::{logging, paths::data_dir, save_file};
+use log::warn;
+use serde::{Deserialize, Serialize};
+use std::{
+    backtrace::Backtrace,
+    fs, io,
+    path::PathBuf,
+    sync::{Mutex, PoisonError},
+    time::{SystemTime, UNIX_EPOCH},
+};
+
+// Reports go here inside the data directory, one file per crash
+const CRASH_DIR: &str = "crashes";
+// Copy of the last report until the player has decided what to do about it, with a
+// checksum since its emergency save can be restored
+const PENDING_FILE: &str = "pending.json";
+
+// Just enough of a running level to put the player back into it
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct EmergencySave {
+    pub level: String,
+    pub tick: u64,
+    pub position: [f32; 3],
+    pub health: f32,
+    pub coins: u32,
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct CrashReport {
+    // Unix time in seconds
+    pub time: u64,
+    pub version: String,
+    pub message: String,
+    pub location: Option<String>,
+    pub thread: String,
+    pub backtrace: String,
+    // The last lines logged before the panic, oldest first
+    pub log_tail: Vec<String>,
+    // Set in deterministic runs, which can be played again with it
+    pub seed: Option<u64>,
+    pub save: Option<EmergencySave>,
+}
+
+struct CrashContext {
+    seed: Option<u64>,
+    save: Option<EmergencySave>,
+}
+
+// What the game was doing, kept current for the panic hook
+static CONTEXT: Mutex<CrashContext> = Mutex::new(CrashContext {
+    seed: None,
+    save: None,
+});
+
+pub fn set_seed(seed: Option<u64>) {
+    CONTEXT.lock().unwrap_or_else(PoisonError::into_inner).seed = seed;
+}
+
+// Called every tick with the running level, None once there is nothing to restore
+pub fn set_emergency_save(save: Option<EmergencySave>) {
+    CONTEXT.lock().unwrap_or_else(PoisonError::into_inner).save = save;
+}
+
+// Writes a report for every panic, on whatever thread, before the default hook prints
+// it as usual. Anything locked by the panicking code is left alone.
+pub fn install() {
+    let default_hook = std::panic::take_hook();
+    std::panic::set_hook(Box::new(move |info| {
+        let message = info
+            .payload()
+            .downcast_ref::<&str>()
+            .map(|message| message.to_string())
+            .or_else(|| info.payload().downcast_ref::<String>().cloned())
+            .unwrap_or_else(|| "unknown panic".to_string());
+        let location = info.location().map(|location| location.to_string());
+        match write_report(message, location) {
+            Ok(path) => eprintln!("Crash report written to {}", path.display()),
+            Err(err) => eprintln!("Couldn't write a crash report: {err}"),
+        }
+        default_hook(info);
+    }));
+}
+
+fn write_report(message: String, location: Option<String>) -> io::Result<PathBuf> {
+    let (seed, save) = match CONTEXT.try_lock() {
+        Ok(context) => (context.seed, context.save.clone()),
+        Err(_) => (None, None),
+    };
+    let time = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map_or(0, |time| time.as_secs());
+    let report = CrashReport {
+        time,
+        version: env!("CARGO_PKG_VERSION").to_string(),
+        message,
+        location,
+        thread: std::thread::current()
+            .name()
+            .unwrap_or("unnamed")
+            .to_string(),
+        backtrace: Backtrace::force_capture().to_string(),
+        log_tail: logging::tail(),
+        seed,
+        save,
+    };
+
+    let dir = data_dir().join(CRASH_DIR);
+    fs::create_dir_all(&dir)?;
+    let path = dir.join(format!("crash-{time}.json"));
+    fs::write(&path, serde_json::to_vec_pretty(&report)?)?;
+    save_file::write(&dir.join(PENDING_FILE), &report)?;
+    Ok(path)
+}
+
+pub struct PendingCrash {
+    pub report: CrashReport,
+    // Where the full report is
+    pub path: PathBuf,
+    // The pending copy failed its checksum, it is shown but its save isn't restored
+    pub modified: bool,
+}
+
+impl PendingCrash {
+    pub fn restorable_save(&self) -> Option<&EmergencySave> {
+        self.report.save.as_ref().filter(|_| !self.modified)
+    }
+}
+
+// The last run's crash if the player hasn't seen it yet
+pub fn pending() -> Option<PendingCrash> {
+    let dir = data_dir().join(CRASH_DIR);
+    let pending = dir.join(PENDING_FILE);
+    let (report, trusted) = match save_file::read_flagged::<CrashReport>(&pending) {
+        Ok(pending) => pending,
+        Err(err) if err.is_missing() => return None,
+        Err(err) => {
+            warn!("Ignoring the last crash: {err}");
+            save_file::reject(&pending);
+            return None;
+        }
+    };
+    let path = dir.join(format!("crash-{}.json", report.time));
+    Some(PendingCrash {
+        report,
+        path,
+        modified: !trusted,
+    })
+}
+
+// The player has seen the crash, it isn't offered again. The report itself stays.
+pub fn dismiss() {
+    let _ = fs::remove_file(data_dir().join(CRASH_DIR).join(PENDING_FILE));
+}
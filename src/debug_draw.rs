@@ -0,0 +1,268 @@
+use fyrox::{
+    core::{
+        algebra::{UnitQuaternion, Vector2, Vector3},
+        color::Color,
+        pool::Handle,
+    },
+    keyboard::KeyCode,
+    scene::{camera::Camera, debug::Line, node::Node, Scene},
+};
+use std::{
+    f32::consts::TAU,
+    sync::{
+        atomic::{AtomicU32, Ordering},
+        Mutex,
+    },
+};
+
+// Segments used for the circles spheres are drawn from
+const CIRCLE_SEGMENTS: usize = 24;
+
+// Anything past this in one frame is dropped, so a runaway loop can't eat memory
+const MAX_LINES: usize = 20_000;
+const MAX_LABELS: usize = 256;
+
+// Groups of debug shapes that are shown or hidden together
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum DebugCategory {
+    // Where enemies are heading and how far they can see
+    AiPaths,
+    // Movement of everything with a simulated transform
+    Velocities,
+    // Trigger and pickup radii
+    TriggerBounds,
+    // Everything else, e.g. shapes drawn by plugins
+    General,
+}
+
+impl DebugCategory {
+    pub const ALL: [DebugCategory; 4] = [
+        DebugCategory::AiPaths,
+        DebugCategory::Velocities,
+        DebugCategory::TriggerBounds,
+        DebugCategory::General,
+    ];
+
+    pub fn name(self) -> &'static str {
+        match self {
+            DebugCategory::AiPaths => "ai paths",
+            DebugCategory::Velocities => "velocities",
+            DebugCategory::TriggerBounds => "trigger bounds",
+            DebugCategory::General => "general",
+        }
+    }
+
+    // The function key that toggles the category in game
+    pub fn key(self) -> KeyCode {
+        match self {
+            DebugCategory::AiPaths => KeyCode::F5,
+            DebugCategory::Velocities => KeyCode::F6,
+            DebugCategory::TriggerBounds => KeyCode::F7,
+            DebugCategory::General => KeyCode::F8,
+        }
+    }
+
+    pub fn from_key(key: KeyCode) -> Option<Self> {
+        Self::ALL.into_iter().find(|category| category.key() == key)
+    }
+
+    fn bit(self) -> u32 {
+        1 << self as u32
+    }
+}
+
+// Text anchored to a point in the world
+#[derive(Clone, Debug)]
+pub struct Label {
+    pub position: Vector3<f32>,
+    pub text: String,
+    pub color: Color,
+}
+
+// Everything drawn since the last frame was taken
+#[derive(Default)]
+pub struct DebugFrame {
+    pub lines: Vec<Line>,
+    pub labels: Vec<Label>,
+}
+
+impl DebugFrame {
+    // Replaces the shapes of the previous frame in the scene's debug drawing
+    pub fn submit(&self, scene: &mut Scene) {
+        scene.drawing_context.clear_lines();
+        for line in &self.lines {
+            scene.drawing_context.add_line(line.clone());
+        }
+    }
+
+    // Labels in screen coordinates, leaving out the ones behind the camera
+    pub fn project_labels(
+        &self,
+        scene: &Scene,
+        camera: Handle<Node>,
+        screen_size: Vector2<f32>,
+    ) -> Vec<(Vector2<f32>, &Label)> {
+        let Some(camera) = scene
+            .graph
+            .try_get(camera)
+            .and_then(|node| node.query_component_ref::<Camera>())
+        else {
+            return Vec::new();
+        };
+        self.labels
+            .iter()
+            .filter_map(|label| Some((camera.project(label.position, screen_size)?, label)))
+            .collect()
+    }
+}
+
+// Immediate-mode drawing: shapes only live for the frame they were drawn in, so
+// whatever wants to stay visible is drawn again every update. Safe to call from the
+// worker threads of the gameplay systems, and cheap when the category is hidden.
+static ENABLED: AtomicU32 = AtomicU32::new(0);
+static FRAME: Mutex<DebugFrame> = Mutex::new(DebugFrame {
+    lines: Vec::new(),
+    labels: Vec::new(),
+});
+
+pub fn is_enabled(category: DebugCategory) -> bool {
+    ENABLED.load(Ordering::Relaxed) & category.bit() != 0
+}
+
+pub fn set_enabled(category: DebugCategory, enabled: bool) {
+    if enabled {
+        ENABLED.fetch_or(category.bit(), Ordering::Relaxed);
+    } else {
+        ENABLED.fetch_and(!category.bit(), Ordering::Relaxed);
+    }
+}
+
+// Returns whether the category is now shown
+pub fn toggle(category: DebugCategory) -> bool {
+    let enabled = !is_enabled(category);
+    set_enabled(category, enabled);
+    enabled
+}
+
+// Takes what was drawn since the last call
+pub fn take_frame() -> DebugFrame {
+    std::mem::take(&mut *FRAME.lock().unwrap())
+}
+
+pub fn line(category: DebugCategory, begin: Vector3<f32>, end: Vector3<f32>, color: Color) {
+    if is_enabled(category) {
+        push_lines([(begin, end)], color);
+    }
+}
+
+// Wire box around `center`, `half_extents` along each axis
+pub fn wire_box(
+    category: DebugCategory,
+    center: Vector3<f32>,
+    half_extents: Vector3<f32>,
+    color: Color,
+) {
+    if !is_enabled(category) {
+        return;
+    }
+    let corner = |x: f32, y: f32, z: f32| {
+        center + Vector3::new(x * half_extents.x, y * half_extents.y, z * half_extents.z)
+    };
+    let mut edges = Vec::with_capacity(12);
+    for (y, z) in [(-1.0, -1.0), (-1.0, 1.0), (1.0, -1.0), (1.0, 1.0)] {
+        edges.push((corner(-1.0, y, z), corner(1.0, y, z)));
+        edges.push((corner(y, -1.0, z), corner(y, 1.0, z)));
+        edges.push((corner(y, z, -1.0), corner(y, z, 1.0)));
+    }
+    push_lines(edges, color);
+}
+
+// Three circles, one around each axis
+pub fn wire_sphere(category: DebugCategory, center: Vector3<f32>, radius: f32, color: Color) {
+    if !is_enabled(category) {
+        return;
+    }
+    let mut edges = Vec::with_capacity(CIRCLE_SEGMENTS * 3);
+    for axis in [Vector3::x_axis(), Vector3::y_axis(), Vector3::z_axis()] {
+        edges.extend(circle_edges(center, radius, axis.into_inner()));
+    }
+    push_lines(edges, color);
+}
+
+// Flat circle on the ground plane, how trigger and aggro radii are measured
+pub fn ground_circle(category: DebugCategory, center: Vector3<f32>, radius: f32, color: Color) {
+    if is_enabled(category) {
+        push_lines(circle_edges(center, radius, Vector3::y()), color);
+    }
+}
+
+pub fn arrow(category: DebugCategory, from: Vector3<f32>, to: Vector3<f32>, color: Color) {
+    if !is_enabled(category) {
+        return;
+    }
+    let direction = to - from;
+    let length = direction.norm();
+    if length <= f32::EPSILON {
+        return;
+    }
+
+    // Two barbs just behind the tip, spread sideways across the up axis (or across x
+    // when the arrow points straight up)
+    let forward = direction / length;
+    let up = if forward.y.abs() > 0.99 {
+        Vector3::x()
+    } else {
+        Vector3::y()
+    };
+    let side = forward.cross(&up).normalize();
+    let barb = length.min(1.0) * 0.25;
+    let back = to - forward * barb;
+    push_lines(
+        [
+            (from, to),
+            (to, back + side * barb * 0.5),
+            (to, back - side * barb * 0.5),
+        ],
+        color,
+    );
+}
+
+pub fn text(category: DebugCategory, position: Vector3<f32>, text: &str, color: Color) {
+    if !is_enabled(category) {
+        return;
+    }
+    let mut frame = FRAME.lock().unwrap();
+    if frame.labels.len() < MAX_LABELS {
+        frame.labels.push(Label {
+            position,
+            text: text.to_string(),
+            color,
+        });
+    }
+}
+
+fn push_lines(edges: impl IntoIterator<Item = (Vector3<f32>, Vector3<f32>)>, color: Color) {
+    let mut frame = FRAME.lock().unwrap();
+    for (begin, end) in edges {
+        if frame.lines.len() >= MAX_LINES {
+            return;
+        }
+        frame.lines.push(Line { begin, end, color });
+    }
+}
+
+fn circle_edges(
+    center: Vector3<f32>,
+    radius: f32,
+    axis: Vector3<f32>,
+) -> Vec<(Vector3<f32>, Vector3<f32>)> {
+    let rotation = UnitQuaternion::rotation_between(&Vector3::y(), &axis)
+        .unwrap_or_else(|| UnitQuaternion::from_axis_angle(&Vector3::x_axis(), TAU / 2.0));
+    let point = |index: usize| {
+        let angle = index as f32 / CIRCLE_SEGMENTS as f32 * TAU;
+        center + rotation * Vector3::new(angle.cos() * radius, 0.0, angle.sin() * radius)
+    };
+    (0..CIRCLE_SEGMENTS)
+        .map(|index| (point(index), point(index + 1)))
+        .collect()
+}
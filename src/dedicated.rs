@@ -0,0 +1,68 @@
+use crate::net::server::{NetServer, ServerEvent};
+use log::info;
+use std::{
+    io, thread,
+    time::{Duration, Instant},
+};
+
+pub struct DedicatedConfig {
+    pub port: u16,
+    pub tick_rate: u32,
+    pub session_name: String,
+    pub level: String,
+}
+
+// Runs the authoritative simulation and networking without a window or renderer.
+// Rounds start by themselves once every connected player is ready and end when
+// the last player leaves.
+pub fn run(config: DedicatedConfig) -> io::Result<()> {
+    let tick_rate = config.tick_rate.max(1);
+    let mut server = NetServer::bind_dedicated(config.port, tick_rate, &config.session_name)?;
+    server.set_level(&config.level);
+
+    info!(
+        "Dedicated server \"{}\" listening on port {} ({} ticks/s, level {})",
+        config.session_name,
+        config.port,
+        tick_rate,
+        server.level()
+    );
+
+    let tick_duration = Duration::from_secs_f32(1.0 / tick_rate as f32);
+    let mut ticks: u32 = 0;
+
+    loop {
+        let tick_start = Instant::now();
+
+        for event in server.poll() {
+            match event {
+                ServerEvent::PlayerJoined(id) => info!("Player {id} joined"),
+                ServerEvent::PlayerLeft(id) => info!("Player {id} left"),
+                ServerEvent::Chat(line) => info!(target: "chat", "{}: {}", line.sender, line.text),
+                ServerEvent::PlayerMoved(_) => {}
+            }
+        }
+
+        if server.in_game() {
+            if server.player_count() == 0 {
+                info!("Everybody left, back to the lobby");
+                server.end_round();
+            }
+        } else if server.start_game() {
+            info!("Round started on {}", server.level());
+        }
+
+        server.flush_chat();
+
+        // Once a second is enough to keep lobby info fresh during a round
+        if !server.in_game() || ticks % tick_rate == 0 {
+            server.broadcast_lobby();
+        }
+        if server.in_game() {
+            server.broadcast_snapshot(None);
+        }
+        ticks = ticks.wrapping_add(1);
+
+        thread::sleep(tick_duration.saturating_sub(tick_start.elapsed()));
+    }
+}
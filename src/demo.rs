@@ -0,0 +1,26 @@
+use crate::replay::Replay;
+#[cfg(not(any(target_arch = "wasm32", target_os = "android")))]
+use std::path::Path;
+
+// Seconds the main menu sits untouched before the demo starts
+pub const DEFAULT_DEMO_DELAY: f32 = 60.0;
+
+// A run through the first level in the replay format: a wander past the obstacles and
+// around the edge of the meadow. Written by hand rather than recorded, a recording made
+// with --record-replay and a finished level can replace it.
+pub const DEMO_REPLAY_PATH: &str = "assets/replays/demo.json";
+
+// Browsers and Android builds carry the replay inside, like the asset manifest
+#[cfg(any(target_arch = "wasm32", target_os = "android"))]
+const BUILT_IN_DEMO: &str = include_str!("../assets/replays/demo.json");
+
+// What the attract mode plays. The demo ends with the replay.
+#[cfg(not(any(target_arch = "wasm32", target_os = "android")))]
+pub fn demo_replay() -> Result<Replay, String> {
+    Replay::load(Path::new(DEMO_REPLAY_PATH))
+}
+
+#[cfg(any(target_arch = "wasm32", target_os = "android"))]
+pub fn demo_replay() -> Result<Replay, String> {
+    Replay::parse(BUILT_IN_DEMO).map_err(|err| format!("built-in demo: {err}"))
+}
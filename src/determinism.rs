@@ -0,0 +1,235 @@
+use fyrox::core::{
+    algebra::{UnitQuaternion, Vector3},
+    pool::Handle,
+};
+use std::{
+    fs::{self, File},
+    io::{self, BufWriter, Write},
+    path::Path,
+    time::{SystemTime, UNIX_EPOCH},
+};
+
+// Small seeded generator (SplitMix64). Everything random in the simulation draws
+// from one of these, so a run can be reproduced from its seed.
+#[derive(Clone, Debug)]
+pub struct Rng {
+    state: u64,
+}
+
+impl Rng {
+    pub fn new(seed: u64) -> Self {
+        Self { state: seed }
+    }
+
+    // Different every run, for when nobody asked for a particular seed
+    pub fn from_time() -> Self {
+        // Browsers have no system clock for std, asking it panics
+        #[cfg(target_arch = "wasm32")]
+        let nanos = (js_sys::Date::now() * 1_000_000.0) as u64;
+        #[cfg(not(target_arch = "wasm32"))]
+        let nanos = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map_or(0, |time| time.as_nanos() as u64);
+        Self::new(nanos)
+    }
+
+    pub fn next_u64(&mut self) -> u64 {
+        self.state = self.state.wrapping_add(0x9e37_79b9_7f4a_7c15);
+        let mut z = self.state;
+        z = (z ^ (z >> 30)).wrapping_mul(0xbf58_476d_1ce4_e5b9);
+        z = (z ^ (z >> 27)).wrapping_mul(0x94d0_49bb_1331_11eb);
+        z ^ (z >> 31)
+    }
+
+    // Uniform in [0, 1)
+    pub fn next_f32(&mut self) -> f32 {
+        (self.next_u64() >> 40) as f32 / (1u64 << 24) as f32
+    }
+
+    pub fn range(&mut self, min: f32, max: f32) -> f32 {
+        min + (max - min) * self.next_f32()
+    }
+}
+
+// FNV-1a over the exact bits of the simulation state. Unlike std's hashers it is
+// the same in every process, so checksums can be compared across runs.
+pub struct StateHasher {
+    hash: u64,
+}
+
+impl Default for StateHasher {
+    fn default() -> Self {
+        Self {
+            hash: 0xcbf2_9ce4_8422_2325,
+        }
+    }
+}
+
+impl StateHasher {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn write_u64(&mut self, value: u64) {
+        for byte in value.to_le_bytes() {
+            self.hash ^= byte as u64;
+            self.hash = self.hash.wrapping_mul(0x0100_0000_01b3);
+        }
+    }
+
+    pub fn write_f32(&mut self, value: f32) {
+        self.write_u64(value.to_bits() as u64);
+    }
+
+    pub fn write_handle<T>(&mut self, handle: Handle<T>) {
+        self.write_u64(((handle.index() as u64) << 32) | handle.generation() as u64);
+    }
+
+    pub fn write_vector(&mut self, vector: Vector3<f32>) {
+        for value in vector.iter() {
+            self.write_f32(*value);
+        }
+    }
+
+    pub fn write_rotation(&mut self, rotation: UnitQuaternion<f32>) {
+        for value in rotation.coords.iter() {
+            self.write_f32(*value);
+        }
+    }
+
+    pub fn finish(&self) -> u64 {
+        self.hash
+    }
+}
+
+// Where a run stopped matching the reference it is checked against
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Divergence {
+    pub tick: u64,
+    pub expected: u64,
+    pub actual: u64,
+}
+
+// Per-tick checksums of a run, either written to a file or compared against one
+// written earlier. Lines are "tick checksum", lines starting with # are notes.
+pub struct ChecksumLog {
+    writer: Option<BufWriter<File>>,
+    reference: Option<Vec<(u64, u64)>>,
+    // Checksums seen so far, the position into the reference
+    recorded: usize,
+    divergence: Option<Divergence>,
+}
+
+impl ChecksumLog {
+    pub fn record(path: &Path) -> io::Result<Self> {
+        Ok(Self {
+            writer: Some(BufWriter::new(File::create(path)?)),
+            reference: None,
+            recorded: 0,
+            divergence: None,
+        })
+    }
+
+    pub fn verify(path: &Path) -> io::Result<Self> {
+        let reference = fs::read_to_string(path)?
+            .lines()
+            .filter(|line| !line.starts_with('#'))
+            .filter_map(|line| {
+                let (tick, checksum) = line.split_once(' ')?;
+                Some((tick.parse().ok()?, u64::from_str_radix(checksum, 16).ok()?))
+            })
+            .collect();
+
+        Ok(Self {
+            writer: None,
+            reference: Some(reference),
+            recorded: 0,
+            divergence: None,
+        })
+    }
+
+    // Marks the start of a level in the written log
+    pub fn note(&mut self, text: &str) {
+        if let Some(writer) = self.writer.as_mut() {
+            let _ = writeln!(writer, "# {text}");
+        }
+    }
+
+    // Adds one tick, returns the divergence the first time the run stops matching
+    pub fn push(&mut self, tick: u64, checksum: u64) -> Option<Divergence> {
+        if let Some(writer) = self.writer.as_mut() {
+            let _ = writeln!(writer, "{tick} {checksum:016x}");
+        }
+
+        let index = self.recorded;
+        self.recorded += 1;
+        if self.divergence.is_some() {
+            return None;
+        }
+        let &(expected_tick, expected) = self.reference.as_ref()?.get(index)?;
+        if expected_tick != tick || expected != checksum {
+            self.divergence = Some(Divergence {
+                tick,
+                expected,
+                actual: checksum,
+            });
+            return self.divergence;
+        }
+        None
+    }
+
+    pub fn divergence(&self) -> Option<Divergence> {
+        self.divergence
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn the_same_seed_draws_the_same_numbers() {
+        let mut first = Rng::new(42);
+        let mut second = Rng::new(42);
+        for _ in 0..100 {
+            assert_eq!(first.next_u64(), second.next_u64());
+        }
+        assert_eq!(first.range(-1.0, 1.0), second.range(-1.0, 1.0));
+
+        // A copy goes on from where the original was
+        let mut copy = first.clone();
+        assert_eq!(copy.next_u64(), first.next_u64());
+    }
+
+    #[test]
+    fn other_seeds_draw_other_numbers() {
+        let drawn = |seed| {
+            let mut rng = Rng::new(seed);
+            (0..8).map(|_| rng.next_u64()).collect::<Vec<_>>()
+        };
+        assert_ne!(drawn(1), drawn(2));
+        assert_ne!(drawn(0), drawn(u64::MAX));
+    }
+
+    #[test]
+    fn floats_stay_in_their_range() {
+        let mut rng = Rng::new(7);
+        for _ in 0..1000 {
+            let unit = rng.next_f32();
+            assert!((0.0..1.0).contains(&unit));
+            let ranged = rng.range(-3.0, 5.0);
+            assert!((-3.0..5.0).contains(&ranged));
+        }
+    }
+
+    #[test]
+    fn checksums_follow_the_exact_bits() {
+        let hash = |value: f32| {
+            let mut hasher = StateHasher::new();
+            hasher.write_f32(value);
+            hasher.finish()
+        };
+        assert_eq!(hash(1.0), hash(1.0));
+        assert_ne!(hash(0.0), hash(-0.0));
+    }
+}
@@ -0,0 +1,125 @@
+// Discord Rich Presence, only built with `--features discord`. It talks to the
+// Discord app on this machine, and when that isn't running every call quietly does
+// nothing while the plugin keeps looking for it.
+use crate::{plugins::GamePlugin, presence::Presence};
+use discord_rich_presence::{activity, DiscordIpc, DiscordIpcClient};
+use fyrox::core::instant::Instant;
+use log::{debug, info};
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+// Application id from the Discord developer portal. None is registered for the game
+// yet, Discord turns this placeholder away like a missing app until it is replaced.
+pub const DISCORD_CLIENT_ID: &str = "000000000000000000";
+
+// How often a missing Discord is looked for again
+const RETRY_INTERVAL: Duration = Duration::from_secs(15);
+
+pub struct DiscordPlugin {
+    client: Option<DiscordIpcClient>,
+    connected: bool,
+    last_attempt: Option<Instant>,
+    // Shown again after reconnecting
+    presence: Option<Presence>,
+}
+
+impl Default for DiscordPlugin {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl DiscordPlugin {
+    pub fn new() -> Self {
+        Self {
+            client: DiscordIpcClient::new(DISCORD_CLIENT_ID)
+                .map_err(|err| debug!("No Discord client: {err}"))
+                .ok(),
+            connected: false,
+            last_attempt: None,
+            presence: None,
+        }
+    }
+
+    // Connects if Discord has shown up since the last attempt, returns whether it is there
+    fn connect(&mut self) -> bool {
+        if self.connected {
+            return true;
+        }
+        let Some(client) = self.client.as_mut() else {
+            return false;
+        };
+        if self
+            .last_attempt
+            .is_some_and(|attempt| attempt.elapsed() < RETRY_INTERVAL)
+        {
+            return false;
+        }
+        self.last_attempt = Some(Instant::now());
+        match client.connect() {
+            Ok(()) => {
+                info!("Connected to Discord");
+                self.connected = true;
+                if let Some(presence) = self.presence.clone() {
+                    self.show(&presence);
+                }
+            }
+            Err(err) => debug!("Discord isn't running: {err}"),
+        }
+        self.connected
+    }
+
+    fn show(&mut self, presence: &Presence) {
+        let Some(client) = self.client.as_mut() else {
+            return;
+        };
+        let doing = presence.doing();
+        let session = presence.session();
+        let mut activity = activity::Activity::new()
+            .details(&doing)
+            .timestamps(activity::Timestamps::new().start(unix_time(presence.since)));
+        if let Some(session) = session.as_deref() {
+            activity = activity.state(session);
+        }
+        // Discord went away, try again later
+        if let Err(err) = client.set_activity(activity) {
+            debug!("Lost Discord: {err}");
+            self.connected = false;
+        }
+    }
+}
+
+impl GamePlugin for DiscordPlugin {
+    fn name(&self) -> &str {
+        "discord"
+    }
+
+    fn update_frame(&mut self) {
+        self.connect();
+    }
+
+    fn presence_changed(&mut self, presence: &Presence) {
+        self.presence = Some(presence.clone());
+        // Connecting shows what was kept
+        if self.connected {
+            self.show(presence);
+        } else {
+            self.connect();
+        }
+    }
+}
+
+impl Drop for DiscordPlugin {
+    fn drop(&mut self) {
+        if let (true, Some(client)) = (self.connected, self.client.as_mut()) {
+            let _ = client.close();
+        }
+    }
+}
+
+// Discord counts "elapsed" from a Unix time in seconds
+fn unix_time(instant: Instant) -> i64 {
+    let started = SystemTime::now() - instant.elapsed();
+    started
+        .duration_since(UNIX_EPOCH)
+        .map_or(0, |time| time.as_secs() as i64)
+}
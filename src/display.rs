@@ -0,0 +1,231 @@
+use crate::paths::data_dir;
+use fyrox::{
+    dpi::{LogicalSize, PhysicalPosition, PhysicalSize},
+    monitor::MonitorHandle,
+    window::{Fullscreen, Window},
+};
+use log::warn;
+use serde::{Deserialize, Serialize};
+use std::{fs, io, path::PathBuf};
+
+const SETTINGS_FILE: &str = "display.json";
+
+// How the window is shown, kept between runs in the data directory
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct DisplaySettings {
+    pub fullscreen: bool,
+    // Where the window was last while windowed, so leaving fullscreen (or the next
+    // start) puts it back there. The size is in logical pixels and comes back the
+    // same on a screen with another scale factor, the position in physical ones
+    // like the desktop's monitor layout.
+    pub window_size: Option<(f64, f64)>,
+    pub window_position: Option<(i32, i32)>,
+    // Name of the monitor fullscreen goes to, the window's own if unset or unplugged
+    pub monitor: Option<String>,
+    // The player's UI scale, on top of the display's scale factor
+    pub ui_scale: f32,
+}
+
+impl Default for DisplaySettings {
+    fn default() -> Self {
+        Self {
+            fullscreen: false,
+            window_size: None,
+            window_position: None,
+            monitor: None,
+            // Fingers need bigger buttons than a mouse pointer
+            ui_scale: if cfg!(target_os = "android") {
+                1.5
+            } else {
+                1.0
+            },
+        }
+    }
+}
+
+impl DisplaySettings {
+    pub fn path() -> PathBuf {
+        data_dir().join(SETTINGS_FILE)
+    }
+
+    // The saved settings, the defaults if there are none or they don't parse
+    pub fn load() -> Self {
+        let path = Self::path();
+        match fs::read_to_string(&path) {
+            Ok(text) => serde_json::from_str(&text).unwrap_or_else(|err| {
+                warn!("Ignoring {}: {err}", path.display());
+                Self::default()
+            }),
+            Err(_) => Self::default(),
+        }
+    }
+
+    pub fn save(&self) -> io::Result<()> {
+        // The browser decides about the page's size, there is nothing worth keeping
+        if cfg!(target_arch = "wasm32") {
+            return Ok(());
+        }
+        let path = Self::path();
+        if let Some(dir) = path.parent() {
+            fs::create_dir_all(dir)?;
+        }
+        fs::write(path, serde_json::to_vec_pretty(self)?)
+    }
+
+    // The chosen monitor among the connected ones
+    pub fn find_monitor(
+        &self,
+        mut monitors: impl Iterator<Item = MonitorHandle>,
+    ) -> Option<MonitorHandle> {
+        let name = self.monitor.as_deref()?;
+        monitors.find(|monitor| monitor.name().as_deref() == Some(name))
+    }
+
+    // Borderless, exclusive modes change the resolution and take ages to switch back
+    // from. Without a monitor it is the one the window is on.
+    pub fn fullscreen_mode(&self, monitor: Option<MonitorHandle>) -> Option<Fullscreen> {
+        self.fullscreen.then_some(Fullscreen::Borderless(monitor))
+    }
+}
+
+pub fn monitor_name(monitor: &MonitorHandle) -> String {
+    monitor
+        .name()
+        .unwrap_or_else(|| "Unknown monitor".to_string())
+}
+
+// Switches the window between windowed and borderless fullscreen. The hotkeys only
+// ask for it, the switch happens where the window is at hand.
+pub struct Display {
+    settings: DisplaySettings,
+    toggle_requested: bool,
+    // Of the monitor the window is on, for turning its physical size into a logical one
+    scale_factor: f64,
+}
+
+impl Default for Display {
+    fn default() -> Self {
+        Self::new(DisplaySettings::default())
+    }
+}
+
+impl Display {
+    pub fn new(settings: DisplaySettings) -> Self {
+        Self {
+            settings,
+            toggle_requested: false,
+            scale_factor: 1.0,
+        }
+    }
+
+    pub fn set_scale_factor(&mut self, scale_factor: f64) {
+        self.scale_factor = scale_factor;
+    }
+
+    pub fn ui_scale(&self) -> f32 {
+        self.settings.ui_scale
+    }
+
+    pub fn set_ui_scale(&mut self, scale: f32) {
+        self.settings.ui_scale = scale;
+        self.save();
+    }
+
+    pub fn is_fullscreen(&self) -> bool {
+        self.settings.fullscreen
+    }
+
+    pub fn request_toggle(&mut self) {
+        self.toggle_requested = true;
+    }
+
+    // Follows the window while it is windowed, from its Resized and Moved events
+    pub fn window_resized(&mut self, size: PhysicalSize<u32>) {
+        if !self.settings.fullscreen {
+            let size: LogicalSize<f64> = size.to_logical(self.scale_factor);
+            self.settings.window_size = Some((size.width, size.height));
+        }
+    }
+
+    pub fn window_moved(&mut self, position: PhysicalPosition<i32>) {
+        if !self.settings.fullscreen {
+            self.settings.window_position = Some((position.x, position.y));
+        }
+    }
+
+    // Carries out a requested toggle, called every frame
+    pub fn update(&mut self, window: &Window) {
+        if !std::mem::take(&mut self.toggle_requested) {
+            return;
+        }
+
+        if self.settings.fullscreen {
+            self.settings.fullscreen = false;
+            window.set_fullscreen(None);
+            if let Some((width, height)) = self.settings.window_size {
+                let _ = window.request_inner_size(LogicalSize::new(width, height));
+            }
+            if let Some((x, y)) = self.settings.window_position {
+                window.set_outer_position(PhysicalPosition::new(x, y));
+            }
+        } else {
+            // Remember the window as it is right now, events may not have told us
+            self.scale_factor = window.scale_factor();
+            self.window_resized(window.inner_size());
+            if let Ok(position) = window.outer_position() {
+                self.window_moved(position);
+            }
+            self.settings.fullscreen = true;
+            window.set_fullscreen(Some(Fullscreen::Borderless(self.monitor(window))));
+        }
+        self.save();
+    }
+
+    // The monitor fullscreen uses
+    pub fn monitor(&self, window: &Window) -> Option<MonitorHandle> {
+        self.settings
+            .find_monitor(window.available_monitors())
+            .or_else(|| window.current_monitor())
+    }
+
+    // Makes `monitor` the fullscreen one and moves the game over to it: fullscreen
+    // right away, a window centered on it
+    pub fn set_monitor(&mut self, window: &Window, monitor: MonitorHandle) {
+        self.settings.monitor = monitor.name();
+        if self.settings.fullscreen {
+            window.set_fullscreen(Some(Fullscreen::Borderless(Some(monitor))));
+        } else {
+            let origin = monitor.position();
+            let free = monitor.size().cast::<i32>();
+            let size = window.outer_size().cast::<i32>();
+            let position = PhysicalPosition::new(
+                origin.x + (free.width - size.width).max(0) / 2,
+                origin.y + (free.height - size.height).max(0) / 2,
+            );
+            window.set_outer_position(position);
+            self.window_moved(position);
+        }
+        self.save();
+    }
+
+    // Moves on to the next connected monitor, returns its name
+    pub fn cycle_monitor(&mut self, window: &Window) -> Option<String> {
+        let monitors: Vec<_> = window.available_monitors().collect();
+        let current = self.monitor(window);
+        let index = monitors
+            .iter()
+            .position(|monitor| Some(monitor) == current.as_ref())
+            .map_or(0, |index| (index + 1) % monitors.len());
+        let monitor = monitors.get(index)?.clone();
+        let name = monitor_name(&monitor);
+        self.set_monitor(window, monitor);
+        Some(name)
+    }
+
+    pub fn save(&self) {
+        if let Err(err) = self.settings.save() {
+            warn!("Couldn't save the display settings: {err}");
+        }
+    }
+}
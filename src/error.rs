@@ -0,0 +1,36 @@
+use fyrox::material::MaterialError;
+use std::{fmt, io};
+
+// Failures that stop the game from starting or a level from loading. They are
+// reported to the player instead of panicking.
+#[derive(Debug)]
+pub enum GameError {
+    // The window system couldn't be set up
+    EventLoop(String),
+    Material(String),
+    Io(io::Error),
+}
+
+impl fmt::Display for GameError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            GameError::EventLoop(err) => write!(f, "couldn't open a window: {err}"),
+            GameError::Material(err) => write!(f, "couldn't set up a material: {err}"),
+            GameError::Io(err) => write!(f, "{err}"),
+        }
+    }
+}
+
+impl std::error::Error for GameError {}
+
+impl From<MaterialError> for GameError {
+    fn from(err: MaterialError) -> Self {
+        GameError::Material(format!("{err:?}"))
+    }
+}
+
+impl From<io::Error> for GameError {
+    fn from(err: io::Error) -> Self {
+        GameError::Io(err)
+    }
+}
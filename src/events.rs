@@ -0,0 +1,123 @@
+use crate::{
+    components::{PickupKind, TriggerKind},
+    handles::EnemyHandle,
+};
+use fyrox::{core::pool::Handle, scene::node::Node};
+
+#[derive(Clone, Debug, PartialEq)]
+pub enum GameEvent {
+    DamageDealt {
+        target: Handle<Node>,
+        amount: f32,
+        source: Option<Handle<Node>>,
+    },
+    PickupCollected {
+        collector: Handle<Node>,
+        kind: PickupKind,
+        value: u32,
+    },
+    TriggerEntered {
+        trigger: Handle<Node>,
+        kind: TriggerKind,
+    },
+    EnemyDied {
+        enemy: EnemyHandle,
+    },
+    // A dash started, paid for or not
+    Dashed {
+        player: Handle<Node>,
+    },
+    // The player used something with an Interactable
+    Interacted {
+        player: Handle<Node>,
+        target: Handle<Node>,
+    },
+}
+
+// Tick event queue. Gameplay code publishes what happened, and everything reacting
+// to it (UI, audio, scoring) reads it after flush(), so publishers don't need to know
+// who is listening. The level flushes once a tick after its systems have run, so
+// readers see that tick's events; only what the readers publish themselves (an
+// EnemyDied from handle_events, say) waits for the next tick.
+#[derive(Default)]
+pub struct GameEvents {
+    incoming: Vec<GameEvent>,
+    current: Vec<GameEvent>,
+}
+
+impl GameEvents {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn publish(&mut self, event: GameEvent) {
+        self.incoming.push(event);
+    }
+
+    // Makes everything published since the last flush readable and drops the
+    // events that were read before. Called once per tick.
+    pub fn flush(&mut self) {
+        self.current.clear();
+        std::mem::swap(&mut self.current, &mut self.incoming);
+    }
+
+    pub fn read(&self) -> &[GameEvent] {
+        &self.current
+    }
+
+    pub fn clear(&mut self) {
+        self.incoming.clear();
+        self.current.clear();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn damage(amount: f32) -> GameEvent {
+        GameEvent::DamageDealt {
+            target: Handle::new(1, 1),
+            amount,
+            source: None,
+        }
+    }
+
+    #[test]
+    fn events_are_readable_from_the_next_flush_on() {
+        let mut events = GameEvents::new();
+        events.publish(damage(1.0));
+        assert!(events.read().is_empty());
+
+        events.flush();
+        events.publish(damage(2.0));
+        assert_eq!(events.read(), [damage(1.0)]);
+    }
+
+    #[test]
+    fn a_flush_drops_what_was_read_before() {
+        let mut events = GameEvents::new();
+        events.publish(damage(1.0));
+        events.flush();
+        events.publish(damage(2.0));
+        events.publish(damage(3.0));
+        events.flush();
+        assert_eq!(events.read(), [damage(2.0), damage(3.0)]);
+
+        events.flush();
+        assert!(events.read().is_empty());
+    }
+
+    #[test]
+    fn clearing_drops_published_and_readable_events() {
+        let mut events = GameEvents::new();
+        events.publish(damage(1.0));
+        events.flush();
+        events.publish(damage(2.0));
+        events.clear();
+        assert!(events.read().is_empty());
+
+        events.flush();
+        assert!(events.read().is_empty());
+    }
+}
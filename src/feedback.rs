@@ -0,0 +1,113 @@
+use crate::comfort::{self, Effect};
+use fyrox::core::algebra::Vector3;
+
+// Damage that shakes the camera as hard as it gets
+const FULL_SHAKE_DAMAGE: f32 = 40.0;
+// Furthest the camera is thrown off, in meters
+const MAX_SHAKE_OFFSET: f32 = 0.3;
+// Trauma lost per second, the shake itself falls off with its square
+const TRAUMA_DECAY: f32 = 1.5;
+
+const FLASH_SECONDS: f32 = 0.25;
+const MAX_FLASH_OPACITY: f32 = 0.45;
+
+// How long the simulation stands still when an enemy goes down
+const HITSTOP_SECONDS: f32 = 0.08;
+
+// The screen's reaction to hits: camera shake and a red flash when the player takes
+// damage, a short hitstop when an enemy dies. Everything is played through the
+// comfort settings, and shake and flash follow them even while already playing.
+#[derive(Default)]
+pub struct ScreenFeedback {
+    trauma: f32,
+    flash_left: f32,
+    hitstop_left: f32,
+    // Real seconds played, drives the shake
+    time: f32,
+}
+
+impl ScreenFeedback {
+    pub fn player_hit(&mut self, damage: f32) {
+        self.trauma = (self.trauma + damage / FULL_SHAKE_DAMAGE).min(1.0);
+        self.flash_left = FLASH_SECONDS;
+    }
+
+    // Reduced hitstop is shorter rather than weaker, there is no half standing still
+    pub fn enemy_killed(&mut self) {
+        let seconds = HITSTOP_SECONDS * comfort::scale(Effect::Hitstop);
+        self.hitstop_left = self.hitstop_left.max(seconds);
+    }
+
+    // Called once per update with the real frame time
+    pub fn update(&mut self, dt: f32) {
+        self.time += dt;
+        self.trauma = (self.trauma - TRAUMA_DECAY * dt).max(0.0);
+        self.flash_left = (self.flash_left - dt).max(0.0);
+        self.hitstop_left = (self.hitstop_left - dt).max(0.0);
+    }
+
+    pub fn clear(&mut self) {
+        *self = Self::default();
+    }
+
+    pub fn in_hitstop(&self) -> bool {
+        self.hitstop_left > 0.0
+    }
+
+    // Added to the camera's drawn position. Sines of unrelated frequencies, so the
+    // shake doesn't repeat visibly.
+    pub fn shake_offset(&self) -> Vector3<f32> {
+        let amount =
+            self.trauma * self.trauma * MAX_SHAKE_OFFSET * comfort::scale(Effect::CameraShake);
+        if amount <= 0.0 {
+            return Vector3::default();
+        }
+        let t = self.time;
+        Vector3::new(
+            (t * 37.0).sin() * 0.6 + (t * 61.0).sin() * 0.4,
+            (t * 43.0).sin() * 0.6 + (t * 29.0).sin() * 0.4,
+            (t * 53.0).sin() * 0.3,
+        ) * amount
+    }
+
+    // Opacity of the damage flash over the whole screen
+    pub fn flash_opacity(&self) -> f32 {
+        self.flash_left / FLASH_SECONDS * MAX_FLASH_OPACITY * comfort::scale(Effect::ScreenFlash)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::comfort::{set_settings, ComfortSettings, EffectLevel};
+
+    // Comfort settings are global, so every level is checked in one test
+    #[test]
+    fn comfort_levels_scale_every_effect() {
+        let played = |level: EffectLevel| {
+            set_settings(ComfortSettings::all(level));
+            let mut feedback = ScreenFeedback::default();
+            feedback.player_hit(FULL_SHAKE_DAMAGE);
+            feedback.enemy_killed();
+            feedback.update(0.01);
+            (
+                feedback.shake_offset().norm(),
+                feedback.flash_opacity(),
+                feedback.hitstop_left,
+            )
+        };
+
+        let full = played(EffectLevel::Full);
+        let reduced = played(EffectLevel::Reduced);
+        let off = played(EffectLevel::Off);
+        set_settings(ComfortSettings::default());
+
+        assert!(full.0 > 0.0 && full.1 > 0.0 && full.2 > 0.0);
+        let scale = EffectLevel::Reduced.scale();
+        assert!((reduced.0 - full.0 * scale).abs() < 1e-5);
+        assert!((reduced.1 - full.1 * scale).abs() < 1e-5);
+        // Hitstop is shortened, and has been playing as long as the full one
+        assert!((reduced.2 - (HITSTOP_SECONDS * scale - 0.01)).abs() < 1e-5);
+        assert_eq!(off, (0.0, 0.0, 0.0));
+    }
+}
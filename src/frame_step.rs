@@ -0,0 +1,42 @@
+// Frame-step debugging: the simulation stands still and only moves on when asked,
+// one fixed tick at a time. Everything outside the simulation (UI, console, the
+// debug camera) keeps running at the normal rate.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct FrameStep {
+    frozen: bool,
+    // Ticks asked for that haven't run yet
+    pending: u32,
+    // Whether the simulation runs in the current update
+    ticking: bool,
+}
+
+impl FrameStep {
+    pub fn is_frozen(&self) -> bool {
+        self.frozen
+    }
+
+    pub fn set_frozen(&mut self, frozen: bool) {
+        self.frozen = frozen;
+        self.pending = 0;
+    }
+
+    // Lets `ticks` more ticks run, freezing first if the simulation was running
+    pub fn step(&mut self, ticks: u32) {
+        self.frozen = true;
+        self.pending = self.pending.saturating_add(ticks);
+    }
+
+    // Called once at the start of every update, says whether the simulation runs
+    pub fn advance(&mut self) -> bool {
+        self.ticking = !self.frozen || self.pending > 0;
+        if self.frozen && self.ticking {
+            self.pending -= 1;
+        }
+        self.ticking
+    }
+
+    // Node scripts that move things check this, the plugin decided for the update
+    pub fn is_ticking(&self) -> bool {
+        self.ticking
+    }
+}
@@ -0,0 +1,3259 @@
+use crate::{
+    assets::{AssetManifest, Preloader, DEFAULT_MANIFEST_PATH},
+    benchmark::{Benchmark, BenchmarkConfig},
+    camera::{create_camera, CameraController},
+    cheats::{self, Cheat, Cheats},
+    clip::{self, ClipRecorder},
+    comfort::{self, ComfortSettings, EffectLevel},
+    console::{CommandRegistry, Console},
+    crash::{self, EmergencySave},
+    debug_draw::{self, DebugCategory, DebugFrame},
+    demo::{demo_replay, DEFAULT_DEMO_DELAY},
+    components::{
+        ComponentStore, ContactDamage, EnemyAi, EnemyState, Health, Interactable, Pickup,
+        PickupKind, SimTransform, Stamina, Trigger, TriggerKind,
+    },
+    determinism::{ChecksumLog, Rng, StateHasher},
+    display::{monitor_name, Display, DisplaySettings},
+    error::GameError,
+    events::{GameEvent, GameEvents},
+    feedback::ScreenFeedback,
+    frame_step::FrameStep,
+    handles::{CameraHandle, EnemyHandle, PlayerHandle},
+    input::InputState,
+    input_script::InputStep,
+    leaderboard::{score_for_time, LeaderboardClient, LeaderboardConfig, ScoreSubmission},
+    logging::{self, LogFilter},
+    materials::{MaterialLibrary, DEFAULT_MATERIALS_PATH},
+    mods::{self, ItemDefinition, DEFAULT_MODS_DIR},
+    localization::{self, DEFAULT_LANGUAGE},
+    level::{
+        all_levels, create_scene, cycle_level, default_level, find_level, LevelConfig, Prefab,
+        GOAL_NODE_NAME, GOAL_RADIUS,
+    },
+    movement::{
+        simulate_movement_at, MoveInput, DASH_COST, DASH_DAMAGE, DASH_SPEED_FACTOR, DASH_TIME,
+        STAMINA_MAX, STAMINA_REGEN,
+    },
+    net::{
+        browser::SessionBrowser,
+        protocol::PlayerId,
+        replication::{ConnectionQuality, InterpolationSettings},
+        NetConfig, NetRole, Network, DEFAULT_PORT, DEFAULT_TICK_RATE,
+    },
+    palette::{self, ColorRole, ColorVision},
+    player::{create_actor, create_cube_surface, create_player, Player},
+    plugins::{GamePlugin, LevelContext},
+    presence::{Activity, GameMode, Presence},
+    pooling::{PoolStats, PrefabPool},
+    profiler::{Phase, Profiler},
+    replay::{ReplayPlayer, ReplayRecorder},
+    rumble::{self, Rumble},
+    scripting::{script_path, LevelScript, ScriptCommand, ScriptView},
+    spectator::SpectatorCamera,
+    stats::StatsCollector,
+    systems::{self, SystemOutput, WorldSnapshot},
+    telemetry::{self, Telemetry},
+    tuning::{Tuning, TuningWatcher},
+    ui::{
+        chat::ChatBox,
+        debug_labels::DebugLabels,
+        flash::ScreenFlash,
+        inspector::{self, Inspector, InspectorEdit, InspectorField},
+        menu::{MainMenu, MenuAction, MenuScreen, ResultsSubmit},
+        profiler::ProfilerOverlay,
+        prompt::InteractPrompt,
+        scale::UiScale,
+    },
+    touch::TouchControls,
+    window::{CursorGrab, WindowVisibility},
+};
+use fyrox::{
+    asset::manager::ResourceManager,
+    core::{
+        algebra::{Vector2, Vector3},
+        color::Color,
+        instant::Instant,
+        math::ray::Ray,
+        pool::Handle,
+        reflect::prelude::*,
+        visitor::prelude::*,
+    },
+    engine::GraphicsContext,
+    event::{DeviceEvent, ElementState, Event, KeyEvent, MouseButton, WindowEvent},
+    gui::{message::UiMessage, UserInterface},
+    keyboard::{KeyCode, PhysicalKey},
+    plugin::{Plugin, PluginContext, PluginRegistrationContext},
+    scene::{
+        base::BaseBuilder,
+        mesh::{surface::Surface, Mesh},
+        node::Node,
+        sound::{SoundBuffer, SoundBuilder, Status},
+        Scene,
+    },
+};
+use log::{error, info, warn};
+use std::{collections::BTreeMap, net::SocketAddr, path::PathBuf};
+
+// Length of one simulation tick, rendering runs at whatever rate the display allows
+pub const FIXED_TIMESTEP: f32 = 1.0 / 60.0;
+
+// How far `tp aim` looks for something to stand on
+const AIM_DISTANCE: f32 = 200.0;
+
+// Game plugin: owns the menus, the network session and the level that is
+// currently loaded. Per-node behaviour (player movement, follow camera) lives
+// in scripts that read what they need from here.
+#[derive(Visit, Reflect)]
+pub struct Game {
+    scene: Handle<Scene>,
+    player: PlayerHandle,
+    camera: CameraHandle,
+    #[visit(skip)]
+    #[reflect(hidden)]
+    input_state: InputState,
+    #[visit(skip)]
+    #[reflect(hidden)]
+    network: Option<Network>,
+    #[visit(skip)]
+    #[reflect(hidden)]
+    remote_players: BTreeMap<PlayerId, Handle<Node>>,
+    // Gameplay state of the nodes in the current level
+    #[visit(skip)]
+    #[reflect(hidden)]
+    components: ComponentStore,
+    #[visit(skip)]
+    #[reflect(hidden)]
+    events: GameEvents,
+    #[visit(skip)]
+    #[reflect(hidden)]
+    tuning: Tuning,
+    // Shared level materials, loaded with the first level and reloaded when their files change
+    #[visit(skip)]
+    #[reflect(hidden)]
+    materials: Option<MaterialLibrary>,
+    // Present when a tuning file is being watched for live edits
+    #[visit(skip)]
+    #[reflect(hidden)]
+    tuning_watcher: Option<TuningWatcher>,
+    // Seeded from --seed in deterministic mode, anything random draws from it
+    #[visit(skip)]
+    #[reflect(hidden)]
+    rng: Rng,
+    // Ticks simulated since the current level was built
+    #[visit(skip)]
+    #[reflect(hidden)]
+    level_tick: u64,
+    // Present when per-tick checksums are recorded or verified
+    #[visit(skip)]
+    #[reflect(hidden)]
+    checksums: Option<ChecksumLog>,
+    // When the last simulation tick ran, for blending rendered transforms
+    #[visit(skip)]
+    #[reflect(hidden)]
+    last_tick: Instant,
+    // Shared by all remote player cubes, created once the resource manager is available
+    #[visit(skip)]
+    #[reflect(hidden)]
+    remote_player_surface: Option<Surface>,
+    #[visit(skip)]
+    #[reflect(hidden)]
+    state: GameState,
+    #[visit(skip)]
+    #[reflect(hidden)]
+    menu: MainMenu,
+    #[visit(skip)]
+    #[reflect(hidden)]
+    chat: ChatBox,
+    #[visit(skip)]
+    #[reflect(hidden)]
+    console: Console,
+    // Shake, damage flash and hitstop, toned down by the comfort settings
+    #[visit(skip)]
+    #[reflect(hidden)]
+    feedback: ScreenFeedback,
+    #[visit(skip)]
+    #[reflect(hidden)]
+    screen_flash: ScreenFlash,
+    // What pressing F would use, shown while the player is near it
+    #[visit(skip)]
+    #[reflect(hidden)]
+    interact_prompt: InteractPrompt,
+    #[visit(skip)]
+    #[reflect(hidden)]
+    interact_target: Option<Handle<Node>>,
+    // Gamepad vibration, missing where gamepads can't be opened
+    #[visit(skip)]
+    #[reflect(hidden)]
+    rumble: Option<Rumble>,
+    // Multiplier on the frame time, changed from the console for slow motion
+    #[visit(skip)]
+    #[reflect(hidden)]
+    time_scale: f32,
+    // Developer cheats switched on from the console or with --dev
+    #[visit(skip)]
+    #[reflect(hidden)]
+    cheats: Cheats,
+    // Per-phase frame timings, always measured and shown with F3
+    #[visit(skip)]
+    #[reflect(hidden)]
+    profiler: Profiler,
+    // Renderer and game counts under the timings, only gathered while they are shown
+    #[visit(skip)]
+    #[reflect(hidden)]
+    stats: StatsCollector,
+    #[visit(skip)]
+    #[reflect(hidden)]
+    profiler_overlay: ProfilerOverlay,
+    #[visit(skip)]
+    #[reflect(hidden)]
+    inspector: Inspector,
+    #[visit(skip)]
+    #[reflect(hidden)]
+    debug_labels: DebugLabels,
+    // Shapes of the last tick, kept on screen while the simulation is frozen
+    #[visit(skip)]
+    #[reflect(hidden)]
+    debug_frame: DebugFrame,
+    // F10 freezes the simulation, the period key steps it one tick
+    #[visit(skip)]
+    #[reflect(hidden)]
+    frame_step: FrameStep,
+    // Whether the simulation runs in the current update
+    #[visit(skip)]
+    #[reflect(hidden)]
+    ticking: bool,
+    // Free camera of a frozen player's view
+    #[visit(skip)]
+    #[reflect(hidden)]
+    step_camera: Option<SpectatorCamera>,
+    // Windowed or borderless fullscreen, switched with F11 and Alt+Enter
+    #[visit(skip)]
+    #[reflect(hidden)]
+    display: Display,
+    // The display's scale factor times the player's UI scale
+    #[visit(skip)]
+    #[reflect(hidden)]
+    ui_scale: UiScale,
+    // Hidden and held in the window during play, free while menus or the console are open
+    #[visit(skip)]
+    #[reflect(hidden)]
+    cursor_grab: CursorGrab,
+    // Minimized or covered windows are drawn less often and single player games wait
+    #[visit(skip)]
+    #[reflect(hidden)]
+    visibility: WindowVisibility,
+    // The on-screen stick and the look area on touch screens
+    #[visit(skip)]
+    #[reflect(hidden)]
+    touch: TouchControls,
+    // The app is in the background without a graphics context, e.g. on Android
+    #[visit(skip)]
+    #[reflect(hidden)]
+    suspended: bool,
+    // Last known mouse position in window pixels, for picking with the inspector
+    #[visit(skip)]
+    #[reflect(hidden)]
+    cursor_position: Vector2<f32>,
+    // The --benchmark run in progress
+    #[visit(skip)]
+    #[reflect(hidden)]
+    benchmark: Option<Benchmark>,
+    // The attract mode's demo while it plays
+    #[visit(skip)]
+    #[reflect(hidden)]
+    demo: Option<ReplayPlayer>,
+    // The solo run's input so far, with --record-replay
+    #[visit(skip)]
+    #[reflect(hidden)]
+    recorder: Option<ReplayRecorder>,
+    // Last time the player did anything, the demo starts after a while without
+    #[visit(skip)]
+    #[reflect(hidden)]
+    idle_since: Instant,
+    // Present while watching a round without a player of our own
+    #[visit(skip)]
+    #[reflect(hidden)]
+    spectator: Option<SpectatorCamera>,
+    #[visit(skip)]
+    #[reflect(hidden)]
+    session_browser: Option<SessionBrowser>,
+    // Timing of the local player's attempt at the current level
+    #[visit(skip)]
+    #[reflect(hidden)]
+    run: Option<LevelRun>,
+    // Event handlers of the current level, if it has a script
+    #[visit(skip)]
+    #[reflect(hidden)]
+    level_script: Option<LevelScript>,
+    // Recycles the nodes of prefabs spawned into the current level
+    #[visit(skip)]
+    #[reflect(hidden)]
+    prefab_pool: PrefabPool,
+    #[visit(skip)]
+    #[reflect(hidden)]
+    leaderboard: Option<LeaderboardClient>,
+    // Anonymous session stats, only sent if the player opted in
+    #[visit(skip)]
+    #[reflect(hidden)]
+    telemetry: Telemetry,
+    // The last 30 seconds on screen, while recording
+    #[visit(skip)]
+    #[reflect(hidden)]
+    clips: ClipRecorder,
+    // Present until every asset in the manifest has been loaded
+    #[visit(skip)]
+    #[reflect(hidden)]
+    preloader: Option<Preloader>,
+    // What plugins were last told the player is doing
+    #[visit(skip)]
+    #[reflect(hidden)]
+    presence: Presence,
+    // Extensions added by other crates, called in the order they were added
+    #[visit(skip)]
+    #[reflect(hidden)]
+    plugins: Vec<Box<dyn GamePlugin>>,
+    #[visit(skip)]
+    #[reflect(hidden)]
+    options: LaunchOptions,
+}
+
+impl std::fmt::Debug for Game {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("Game")
+            .field("scene", &self.scene)
+            .field("state", &self.state)
+            .field(
+                "plugins",
+                &self.plugins.iter().map(|plugin| plugin.name()).collect::<Vec<_>>(),
+            )
+            .finish_non_exhaustive()
+    }
+}
+
+struct LevelRun {
+    level_id: String,
+    finished: bool,
+    coins: u32,
+    // Cheats or console commands changed the run, its time isn't submitted
+    tainted: bool,
+    // Where the player starts, and starts over after running out of health
+    spawn: Vector3<f32>,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+enum GameState {
+    Loading,
+    MainMenu,
+    Lobby,
+    Playing,
+}
+
+// Command line options, also used as the template for sessions started from the menu
+pub struct LaunchOptions {
+    pub player_name: String,
+    pub port: u16,
+    pub quality: ConnectionQuality,
+    // Go straight into a hosted lobby
+    pub host: bool,
+    // Go straight into someone else's lobby
+    pub connect: Option<SocketAddr>,
+    // Join as a spectator instead of a player
+    pub spectate: bool,
+    // Run a headless dedicated server instead of the game
+    pub dedicated: bool,
+    pub session_name: Option<String>,
+    pub level: Option<String>,
+    // Leaderboard service base URL, leaderboards are off without one
+    pub leaderboard_url: Option<String>,
+    // Where opted-in session stats go, nothing is sent without one
+    pub telemetry_url: Option<String>,
+    pub friends: Vec<String>,
+    // JSON file with movement/camera tuning that is reloaded whenever it changes
+    pub tuning_path: Option<PathBuf>,
+    // Asset manifest preloaded before the main menu opens
+    pub manifest_path: PathBuf,
+    // Looks of the level geometry, reloaded live when it or its textures change
+    pub materials_path: PathBuf,
+    // Deterministic mode: seeded randomness and no live tuning, so runs can be reproduced
+    pub seed: Option<u64>,
+    // Per-tick state checksums written to, or compared against, this file
+    pub record_checksums: Option<PathBuf>,
+    pub verify_checksums: Option<PathBuf>,
+    // Where the input of the last finished solo run is written, for playing it back
+    pub record_replay: Option<PathBuf>,
+    // Log levels per module, and where the rotating log file goes (none without a directory)
+    pub log_filter: LogFilter,
+    pub log_dir: Option<PathBuf>,
+    // Folder scanned for mods at startup, mods are off without one
+    pub mods_dir: Option<PathBuf>,
+    // Cheats that are on from the start
+    pub cheats: Cheats,
+    // Play the standard benchmark path and quit, writing a timing report
+    pub benchmark: Option<BenchmarkConfig>,
+    // Language code of the string table the UI starts in
+    pub language: String,
+    // Palette for players with color vision deficiencies
+    pub color_vision: ColorVision,
+    // How much camera shake, flashing and hitstop there is
+    pub comfort: ComfortSettings,
+    // Gamepad vibration on hits and pickups
+    pub rumble: bool,
+    // Keep the last 30 seconds on screen for saving as a clip
+    pub record_clips: bool,
+    // Seconds of an untouched main menu before the demo plays, no demo without
+    pub demo_delay: Option<f32>,
+    // Fullscreen and the windowed size, saved by the last run
+    pub display: DisplaySettings,
+}
+
+impl Default for LaunchOptions {
+    fn default() -> Self {
+        Self {
+            player_name: "Player".to_string(),
+            port: DEFAULT_PORT,
+            quality: ConnectionQuality::Good,
+            host: false,
+            connect: None,
+            spectate: false,
+            dedicated: false,
+            session_name: None,
+            level: None,
+            leaderboard_url: None,
+            telemetry_url: None,
+            friends: Vec::new(),
+            tuning_path: None,
+            manifest_path: PathBuf::from(DEFAULT_MANIFEST_PATH),
+            materials_path: PathBuf::from(DEFAULT_MATERIALS_PATH),
+            seed: None,
+            record_checksums: None,
+            verify_checksums: None,
+            record_replay: None,
+            log_filter: LogFilter::default(),
+            log_dir: Some(logging::default_log_dir()),
+            mods_dir: Some(PathBuf::from(DEFAULT_MODS_DIR)),
+            cheats: Cheats::default(),
+            benchmark: None,
+            language: DEFAULT_LANGUAGE.to_string(),
+            color_vision: ColorVision::Normal,
+            comfort: ComfortSettings::default(),
+            rumble: true,
+            record_clips: false,
+            demo_delay: Some(DEFAULT_DEMO_DELAY),
+            display: DisplaySettings::default(),
+        }
+    }
+}
+
+impl LaunchOptions {
+    // Reads options from the command line:
+    //   --name NAME                  player name shown in lobbies
+    //   --host [--port N]            open a lobby as host right away
+    //   --connect ADDR [--spectate]  join a lobby right away, optionally as a spectator
+    //   --net-quality lan|good|poor  snapshot buffering preset
+    //   --server [--port N]          run a headless dedicated server
+    //   --session-name NAME          dedicated server name shown in browsers
+    //   --level ID                   level the dedicated server plays
+    //   --leaderboard URL            leaderboard service (also LEADERBOARD_URL)
+    //   --friends A,B,C              players shown in the friends ranking
+    //   --telemetry URL              where opted-in session stats go (also TELEMETRY_URL)
+    //   --tuning FILE                live-reloaded movement/camera tuning (JSON)
+    //   --assets FILE                asset manifest to preload (JSON)
+    //   --materials FILE             live-reloaded level materials (JSON)
+    //   --seed N                     deterministic mode with the given random seed
+    //   --record-checksums FILE      write per-tick state checksums (implies --seed 0)
+    //   --verify-checksums FILE      report the first tick that differs from a recording
+    //   --record-replay FILE         write the input of each finished solo run as a replay
+    //   --log SPEC                   log levels, e.g. "warn,simple_fyrox_game::net=debug" (also GAME_LOG)
+    //   --log-dir DIR                where the rotating log file goes
+    //   --no-log-file                log to the console only
+    //   --mods DIR                   folder to load mods from
+    //   --no-mods                    start without any mods
+    //   --dev [CHEATS]               start with cheats on, god mode unless a list like god,noclip is given
+    //   --benchmark [SECONDS]        play the standard benchmark path on the first level and quit
+    //   --benchmark-report FILE      where the benchmark's JSON report goes (implies --benchmark)
+    //   --lang CODE                  UI language, e.g. de (also GAME_LANG)
+    //   --palette NAME               colors for deuteranopia, protanopia or tritanopia
+    //   --comfort full|reduced|off   how much camera shake, screen flashes and hitstop
+    //   --no-rumble                  no gamepad vibration
+    //   --record-clips               keep the last 30 seconds for F12 to save as a video
+    //   --demo-after SECONDS         idle time in the main menu before the demo plays
+    //   --no-demo                    never play the demo
+    pub fn from_args(args: impl Iterator<Item = String>) -> Self {
+        let mut args = args.peekable();
+        let mut options = LaunchOptions {
+            leaderboard_url: std::env::var("LEADERBOARD_URL").ok(),
+            telemetry_url: std::env::var("TELEMETRY_URL").ok(),
+            language: std::env::var("GAME_LANG").unwrap_or_else(|_| DEFAULT_LANGUAGE.to_string()),
+            ..Default::default()
+        };
+        let mut log_spec = std::env::var("GAME_LOG").ok();
+        
+        while let Some(arg) = args.next() {
+            match arg.as_str() {
+                "--name" => {
+                    if let Some(name) = args.next() {
+                        options.player_name = name;
+                    }
+                }
+                "--host" => options.host = true,
+                "--spectate" => options.spectate = true,
+                "--server" => options.dedicated = true,
+                "--session-name" => options.session_name = args.next(),
+                "--level" => options.level = args.next(),
+                "--palette" => {
+                    match args.next().as_deref().map(|name| (name, ColorVision::from_name(name))) {
+                        Some((_, Some(vision))) => options.color_vision = vision,
+                        Some((name, None)) => eprintln!("Ignoring unknown palette {name:?}"),
+                        None => {}
+                    }
+                }
+                "--comfort" => {
+                    match args.next().as_deref().map(|name| (name, EffectLevel::from_name(name))) {
+                        Some((_, Some(level))) => options.comfort = ComfortSettings::all(level),
+                        Some((name, None)) => eprintln!("Ignoring unknown comfort level {name:?}"),
+                        None => {}
+                    }
+                }
+                "--no-rumble" => options.rumble = false,
+                "--record-clips" => options.record_clips = true,
+                "--demo-after" => {
+                    if let Some(seconds) = args.next().and_then(|value| value.parse::<f32>().ok()) {
+                        options.demo_delay = Some(seconds.max(1.0));
+                    }
+                }
+                "--no-demo" => options.demo_delay = None,
+                "--lang" => {
+                    if let Some(language) = args.next() {
+                        options.language = language;
+                    }
+                }
+                "--leaderboard" => options.leaderboard_url = args.next(),
+                "--telemetry" => options.telemetry_url = args.next(),
+                "--tuning" => options.tuning_path = args.next().map(PathBuf::from),
+                "--assets" => {
+                    if let Some(path) = args.next() {
+                        options.manifest_path = PathBuf::from(path);
+                    }
+                }
+                "--materials" => {
+                    if let Some(path) = args.next() {
+                        options.materials_path = PathBuf::from(path);
+                    }
+                }
+                "--friends" => {
+                    if let Some(list) = args.next() {
+                        options.friends = list
+                            .split(',')
+                            .map(|name| name.trim().to_string())
+                            .filter(|name| !name.is_empty())
+                            .collect();
+                    }
+                }
+                "--connect" => options.connect = args.next().and_then(|addr| addr.parse().ok()),
+                "--port" => {
+                    if let Some(value) = args.next().and_then(|value| value.parse().ok()) {
+                        options.port = value;
+                    }
+                }
+                "--seed" => options.seed = args.next().and_then(|value| value.parse().ok()),
+                "--record-checksums" => options.record_checksums = args.next().map(PathBuf::from),
+                "--verify-checksums" => options.verify_checksums = args.next().map(PathBuf::from),
+                "--record-replay" => options.record_replay = args.next().map(PathBuf::from),
+                "--log" => log_spec = args.next(),
+                "--log-dir" => {
+                    if let Some(dir) = args.next() {
+                        options.log_dir = Some(PathBuf::from(dir));
+                    }
+                }
+                "--no-log-file" => options.log_dir = None,
+                "--mods" => {
+                    if let Some(dir) = args.next() {
+                        options.mods_dir = Some(PathBuf::from(dir));
+                    }
+                }
+                "--no-mods" => options.mods_dir = None,
+                "--dev" => {
+                    let list = args.next_if(|next| !next.starts_with("--"));
+                    match Cheats::parse(list.as_deref().unwrap_or(Cheat::GodMode.name())) {
+                        Ok(cheats) => options.cheats = cheats,
+                        Err(err) => eprintln!("Ignoring --dev: {err}"),
+                    }
+                }
+                "--benchmark" => {
+                    let seconds = args.next_if(|next| !next.starts_with("--"));
+                    let benchmark = options.benchmark.get_or_insert_with(BenchmarkConfig::default);
+                    if let Some(seconds) = seconds.and_then(|value| value.parse().ok()) {
+                        benchmark.seconds = seconds;
+                    }
+                }
+                "--benchmark-report" => {
+                    if let Some(path) = args.next() {
+                        options.benchmark.get_or_insert_with(BenchmarkConfig::default).report_path = PathBuf::from(path);
+                    }
+                }
+                "--net-quality" => {
+                    if let Some(value) = args.next().and_then(|value| ConnectionQuality::from_name(&value)) {
+                        options.quality = value;
+                    }
+                }
+                _ => {}
+            }
+        }
+        
+        // Logging isn't up yet, so a bad spec can only go to stderr
+        if let Some(spec) = log_spec {
+            match LogFilter::parse(&spec) {
+                Ok(filter) => options.log_filter = filter,
+                Err(err) => eprintln!("Ignoring log spec: {err}"),
+            }
+        }
+        
+        // Checksums only mean something if runs are reproducible
+        if options.record_checksums.is_some() || options.verify_checksums.is_some() {
+            options.seed.get_or_insert(0);
+        }
+        
+        // Benchmarks are only comparable if every run plays exactly the same game: the
+        // built-in level and the same randomness. The player can't die halfway either,
+        // see player_invulnerable().
+        if options.benchmark.is_some() {
+            options.seed.get_or_insert(0);
+            options.mods_dir = None;
+            options.tuning_path = None;
+            options.leaderboard_url = None;
+            options.telemetry_url = None;
+        }
+        
+        options
+    }
+    
+    pub fn deterministic(&self) -> bool {
+        self.seed.is_some()
+    }
+    
+    fn net_config(&self, role: NetRole) -> NetConfig {
+        NetConfig {
+            role,
+            player_name: self.player_name.clone(),
+            tick_rate: DEFAULT_TICK_RATE,
+            interpolation: InterpolationSettings::for_quality(self.quality),
+        }
+    }
+}
+
+impl Plugin for Game {
+    fn register(&self, context: PluginRegistrationContext) {
+        // Makes the scripts available to FyroxEd and to scene loading
+        let scripts = &context.serialization_context.script_constructors;
+        scripts.add::<Player>("Player");
+        scripts.add::<CameraController>("Camera Controller");
+    }
+    
+    fn init(&mut self, _scene_path: Option<&str>, mut context: PluginContext) {
+        // Before anything is colored, the menu included
+        palette::set_vision(self.options.color_vision);
+        comfort::set_settings(self.options.comfort);
+        rumble::set_enabled(self.options.rumble);
+        telemetry::load_consent();
+        crash::set_seed(self.options.seed);
+        
+        // Remote players are red cubes, the surface is shared by all of them
+        self.remote_player_surface =
+            create_cube_surface(context.resource_manager, ColorRole::RemotePlayer)
+                .map_err(|err| warn!("Remote players won't be shown: {err}"))
+                .ok();
+        
+        // Nothing is loaded until a level is picked from the menu
+        let ui = context.user_interfaces.first_mut();
+        // Under everything else, the menus and the console stay readable through a flash
+        self.screen_flash = ScreenFlash::new(&mut ui.build_ctx());
+        self.interact_prompt = InteractPrompt::new(&mut ui.build_ctx());
+        self.menu = MainMenu::new(&mut ui.build_ctx());
+        self.chat = ChatBox::new(&mut ui.build_ctx());
+        let mut commands = CommandRegistry::default();
+        Self::register_commands(&mut commands);
+        logging::register_commands(&mut commands);
+        cheats::register_commands(&mut commands);
+        localization::register_commands(&mut commands);
+        palette::register_commands(&mut commands);
+        comfort::register_commands(&mut commands);
+        rumble::register_commands(&mut commands);
+        telemetry::register_commands(&mut commands);
+        clip::register_commands(&mut commands);
+        self.console = Console::new(&mut ui.build_ctx(), commands);
+        self.profiler_overlay = ProfilerOverlay::new(&mut ui.build_ctx());
+        self.inspector = Inspector::new(&mut ui.build_ctx());
+        self.debug_labels = DebugLabels::new(&mut ui.build_ctx());
+        self.menu.show_mods(ui, mods::installed());
+        self.menu.set_ui_scale(ui, self.ui_scale.manual());
+        for plugin in self.plugins.iter_mut() {
+            plugin.init(context.resource_manager);
+            plugin.build_ui(&mut ui.build_ctx());
+        }
+        
+        self.rumble = Rumble::new()
+            .map_err(|err| warn!("No gamepad rumble: {err}"))
+            .ok();
+        
+        self.telemetry = Telemetry::new(self.options.telemetry_url.clone());
+        if let Err(err) = self.clips.set_recording(self.options.record_clips) {
+            warn!("Not recording clips: {err}");
+        }
+        
+        self.leaderboard = self.options.leaderboard_url.clone().map(|endpoint| {
+            LeaderboardClient::new(LeaderboardConfig {
+                endpoint,
+                friends: self.options.friends.clone(),
+            })
+        });
+        
+        // Live edits would make a deterministic run impossible to reproduce
+        if self.options.deterministic() && self.options.tuning_path.is_some() {
+            warn!("Ignoring --tuning in deterministic mode");
+        } else {
+            self.tuning_watcher = self.options.tuning_path.clone().map(TuningWatcher::new);
+        }
+        
+        // Everything the manifest lists is requested now and waited for on the loading screen
+        let manifest = AssetManifest::load(&self.options.manifest_path).unwrap_or_else(|err| {
+            warn!("No asset manifest, nothing is preloaded: {err}");
+            AssetManifest::default()
+        });
+        self.preloader = Some(Preloader::start(&manifest, context.resource_manager));
+    }
+    
+    // The windowed size and position of this run are kept for the next one, and the
+    // session's stats go out if the player agreed to that
+    fn on_deinit(&mut self, _context: PluginContext) {
+        self.display.save();
+        self.telemetry.send();
+    }
+    
+    fn on_graphics_context_initialized(&mut self, context: PluginContext) {
+        if let GraphicsContext::Initialized(graphics_context) = context.graphics_context {
+            graphics_context
+                .renderer
+                .set_backbuffer_clear_color(Color::opaque(100, 150, 200));
+            self.clips.install(&mut graphics_context.renderer);
+            let scale_factor = graphics_context.window.scale_factor();
+            self.ui_scale.set_dpi(scale_factor);
+            self.display.set_scale_factor(scale_factor);
+        }
+        self.show_monitor(&context);
+        self.suspended = false;
+    }
+    
+    // Mobile platforms take the window away while the app is in the background and
+    // the engine makes a new one on resume. The game keeps everything else, but lets
+    // go of what the player was holding and stands still until it is back.
+    fn on_graphics_context_destroyed(&mut self, _context: PluginContext) {
+        self.suspended = true;
+        self.input_state.release_keys();
+        self.touch.release(&mut self.input_state);
+        self.cursor_grab.window_lost();
+        if let Some(rumble) = self.rumble.as_mut() {
+            rumble.stop();
+        }
+        // Android may end a suspended app without another word
+        self.display.save();
+    }
+    
+    fn update(&mut self, context: &mut PluginContext) {
+        // Everything measured since the last update, scripts included, counts as one frame
+        self.profiler.end_frame();
+        if let Some(benchmark) = self.benchmark.as_mut() {
+            benchmark.record_phases(&self.profiler);
+        }
+        
+        // Runs at the executor's fixed rate, so dt is always the same. Deterministic
+        // runs don't even trust that and use the exact constant.
+        let dt = if self.options.deterministic() {
+            FIXED_TIMESTEP
+        } else {
+            context.dt * self.time_scale()
+        };
+        // Online sessions go on without us, the others wait while the app is away or
+        // its window can't be seen
+        let away = self.suspended || self.visibility.is_hidden();
+        // A hitstop holds whole ticks, the same for the windowed game, deterministic
+        // runs and the headless sim. Frame-stepping keeps its ticks until it's over.
+        let held = self.in_hitstop();
+        self.ticking = !held && self.frame_step.advance() && !(away && self.network.is_none());
+        let ticking = self.ticking;
+        if ticking {
+            self.begin_tick();
+        }
+        if self.state == GameState::Playing {
+            self.play_benchmark(context);
+            if ticking {
+                self.play_demo(context);
+            }
+        }
+        
+        // Console commands run between ticks, never in the middle of one
+        self.run_console_commands(context);
+        
+        // Submission results and rankings for the results screen
+        if let Some(leaderboard) = self.leaderboard.as_ref() {
+            for event in leaderboard.poll() {
+                self.menu
+                    .handle_leaderboard_event(context.user_interfaces.first(), event);
+            }
+        }
+        
+        // Pick up edits to the tuning file without touching the running game
+        if let Some(result) = self.tuning_watcher.as_mut().and_then(|watcher| watcher.poll()) {
+            match result {
+                Ok(tuning) => {
+                    self.tuning = tuning;
+                    if let Some(scene) = context.scenes.try_get_mut(self.scene) {
+                        self.apply_tuning(scene);
+                    }
+                }
+                Err(err) => warn!("Ignoring tuning file: {err}"),
+            }
+        }
+        
+        // Same for the level materials and their textures
+        if let Some(materials) = self.materials.as_mut() {
+            materials.poll(context.resource_manager);
+        }
+        
+        match self.state {
+            GameState::Loading => self.update_loading(context),
+            GameState::MainMenu => {
+                if let Some(browser) = self.session_browser.as_mut() {
+                    browser.update();
+                    self.menu
+                        .update_sessions(context.user_interfaces.first(), browser.sessions());
+                }
+                if self.demo_due() {
+                    self.start_demo(context);
+                }
+            }
+            GameState::Lobby => self.update_lobby(context, dt),
+            GameState::Playing if ticking => self.update_level(context, dt),
+            // Held by a hitstop, the level stays as it is, camera included
+            GameState::Playing if held => {}
+            GameState::Playing => self.update_frozen(context, dt),
+        }
+        
+        // Plugins follow the game whatever state it is in
+        self.update_presence();
+        for plugin in self.plugins.iter_mut() {
+            plugin.update_frame();
+        }
+        
+        // Show chat from other players
+        let scope = self.profiler.begin(Phase::Ui);
+        if let Some(network) = self.network.as_mut() {
+            for line in network.take_chat() {
+                self.chat.push_line(&line.sender, &line.text);
+            }
+        }
+        self.chat.update(context.user_interfaces.first());
+        self.update_feedback(context.dt);
+        if let Some(rumble) = self.rumble.as_mut() {
+            rumble.update();
+        }
+        let ui = context.user_interfaces.first();
+        self.ui_scale.update(ui);
+        self.screen_flash.update(ui, self.feedback.flash_opacity(), self.ui_scale.to_ui(ui.screen_size()));
+        let prompt = self
+            .interact_target
+            .filter(|_| self.state == GameState::Playing && self.demo.is_none())
+            .and_then(|target| self.components.get::<Interactable>(target))
+            .map(|interactable| interactable.prompt.as_str());
+        self.interact_prompt.update(ui, prompt);
+        
+        // Warnings go to the console while F9 has them mirrored
+        for line in logging::take_mirrored() {
+            self.console.print(&line);
+        }
+        self.console.update(context.user_interfaces.first());
+        for plugin in self.plugins.iter_mut() {
+            plugin.update_ui(context.user_interfaces.first());
+        }
+        if self.profiler_overlay.is_visible() {
+            self.collect_stats(context);
+            self.profiler_overlay.update(
+                context.user_interfaces.first(),
+                &self.profiler,
+                &self.stats,
+            );
+        }
+        self.inspector.update(
+            context.user_interfaces.first(),
+            context.scenes.try_get(self.scene),
+            &self.components,
+        );
+        self.show_debug_frame(context);
+        self.profiler.end(scope);
+        
+        if let GraphicsContext::Initialized(graphics_context) = &context.graphics_context {
+            self.display.update(&graphics_context.window);
+            let wanted = self.wants_cursor();
+            self.cursor_grab.update(&graphics_context.window, wanted);
+            self.visibility.update(&graphics_context.window);
+        }
+        self.clips.update();
+        match self.clips.poll() {
+            Some(Ok(path)) => {
+                info!("Saved clip {}", path.display());
+                self.console.print(&format!("clip saved to {}", path.display()));
+            }
+            Some(Err(err)) => {
+                warn!("Clip not saved: {err}");
+                self.console.print(&format!("error: clip not saved: {err}"));
+            }
+            None => {}
+        }
+        if let Some(window_target) = context.window_target {
+            self.visibility.throttle(window_target);
+        }
+    }
+    
+    fn on_os_event(&mut self, event: &Event<()>, mut context: PluginContext) {
+        if is_player_input(event) {
+            self.idle_since = Instant::now();
+            // Anything the player does ends the demo, and does nothing else
+            if self.demo.is_some() {
+                self.stop_demo(&mut context);
+                return;
+            }
+        }
+        
+        let scope = self.profiler.begin(Phase::Input);
+        match event {
+            Event::WindowEvent {
+                event: WindowEvent::KeyboardInput { event, .. },
+                ..
+            } => self.handle_key_input(event, context.user_interfaces.first()),
+            Event::WindowEvent {
+                event: WindowEvent::RedrawRequested,
+                ..
+            } => {
+                if let Some(benchmark) = self.benchmark.as_mut() {
+                    benchmark.record_frame();
+                }
+                // Frames of a hidden window are slowed down on purpose, they'd skew the rate
+                let playing = self.state == GameState::Playing && !self.visibility.is_hidden();
+                self.telemetry.record_frame(playing);
+                self.interpolate_transforms(context);
+            }
+            Event::WindowEvent {
+                event: WindowEvent::CursorMoved { position, .. },
+                ..
+            } => self.cursor_position = Vector2::new(position.x as f32, position.y as f32),
+            Event::WindowEvent {
+                event: WindowEvent::Focused(focused),
+                ..
+            } => self.cursor_grab.set_focused(*focused),
+            Event::WindowEvent {
+                event: WindowEvent::Occluded(occluded),
+                ..
+            } => self.visibility.set_occluded(*occluded),
+            Event::WindowEvent {
+                event: WindowEvent::ModifiersChanged(modifiers),
+                ..
+            } => self.input_state.alt = modifiers.state().alt_key(),
+            Event::WindowEvent {
+                event: WindowEvent::ScaleFactorChanged { scale_factor, .. },
+                ..
+            } => {
+                self.ui_scale.set_dpi(*scale_factor);
+                self.display.set_scale_factor(*scale_factor);
+            }
+            Event::WindowEvent {
+                event: WindowEvent::Resized(size),
+                ..
+            } => self.display.window_resized(*size),
+            Event::WindowEvent {
+                event: WindowEvent::Moved(position),
+                ..
+            } => self.display.window_moved(*position),
+            Event::WindowEvent {
+                event:
+                    WindowEvent::MouseInput {
+                        state: ElementState::Pressed,
+                        button: MouseButton::Right,
+                        ..
+                    },
+                ..
+            } if self.inspector.is_visible() => self.pick_entity(&context),
+            Event::WindowEvent {
+                event: WindowEvent::Touch(touch),
+                ..
+            } => {
+                let playing = self.controls_game();
+                let ui = context.user_interfaces.first_mut();
+                let screen_width = ui.screen_size().x;
+                self.touch.handle_touch(
+                    touch,
+                    playing,
+                    screen_width,
+                    self.ui_scale.factor(),
+                    &mut self.input_state,
+                    ui,
+                );
+            }
+            Event::DeviceEvent { event, .. } => self.handle_device_event(event),
+            _ => {}
+        }
+        self.profiler.end(scope);
+    }
+    
+    fn on_ui_message(&mut self, context: &mut PluginContext, message: &UiMessage) {
+        // React to menu clicks
+        self.chat.handle_ui_message(message);
+        self.console.handle_ui_message(message);
+        if let Some(edit) = self.inspector.handle_ui_message(message) {
+            self.apply_inspector_edit(context, edit);
+        }
+        if let Some(action) = self.menu.handle_ui_message(message) {
+            self.handle_menu_action(context, action);
+        }
+    }
+}
+
+impl Game {
+    pub fn new(options: LaunchOptions) -> Self {
+        let checksums = match (&options.record_checksums, &options.verify_checksums) {
+            (Some(path), _) => Some(ChecksumLog::record(path)),
+            (None, Some(path)) => Some(ChecksumLog::verify(path)),
+            (None, None) => None,
+        }
+        .and_then(|log| log.map_err(|err| warn!("Checksums disabled: {err}")).ok());
+        
+        Self {
+            scene: Handle::NONE,
+            player: PlayerHandle::NONE,
+            camera: CameraHandle::NONE,
+            input_state: InputState::default(),
+            network: None,
+            remote_players: BTreeMap::new(),
+            components: ComponentStore::new(),
+            events: GameEvents::new(),
+            last_tick: Instant::now(),
+            tuning: Tuning::default(),
+            tuning_watcher: None,
+            materials: None,
+            rng: options.seed.map_or_else(Rng::from_time, Rng::new),
+            level_tick: 0,
+            checksums,
+            remote_player_surface: None,
+            state: GameState::Loading,
+            // Widgets are built in init(), once there is a user interface
+            menu: MainMenu::default(),
+            chat: ChatBox::default(),
+            console: Console::default(),
+            feedback: ScreenFeedback::default(),
+            screen_flash: ScreenFlash::default(),
+            interact_prompt: InteractPrompt::default(),
+            interact_target: None,
+            rumble: None,
+            time_scale: 1.0,
+            cheats: options.cheats,
+            profiler: Profiler::default(),
+            stats: StatsCollector::default(),
+            profiler_overlay: ProfilerOverlay::default(),
+            inspector: Inspector::default(),
+            debug_labels: DebugLabels::default(),
+            debug_frame: DebugFrame::default(),
+            frame_step: FrameStep::default(),
+            ticking: false,
+            step_camera: None,
+            display: Display::new(options.display.clone()),
+            ui_scale: UiScale::new(options.display.ui_scale),
+            cursor_grab: CursorGrab::default(),
+            visibility: WindowVisibility::default(),
+            touch: TouchControls::default(),
+            suspended: false,
+            cursor_position: Vector2::default(),
+            benchmark: None,
+            demo: None,
+            recorder: None,
+            idle_since: Instant::now(),
+            spectator: None,
+            session_browser: None,
+            run: None,
+            level_script: None,
+            prefab_pool: PrefabPool::default(),
+            leaderboard: None,
+            telemetry: Telemetry::default(),
+            clips: ClipRecorder::default(),
+            preloader: None,
+            presence: Presence::default(),
+            plugins: Vec::new(),
+            options,
+        }
+    }
+    
+    // Plugins have to be added before the executor starts running the game
+    pub fn add_plugin(&mut self, plugin: impl GamePlugin) {
+        self.plugins.push(Box::new(plugin));
+    }
+    
+    // Works out what the player is doing and tells the plugins when that changes
+    fn update_presence(&mut self) {
+        let activity = match self.state {
+            GameState::Loading => Activity::Loading,
+            GameState::MainMenu => Activity::MainMenu,
+            // Nobody is playing the demo, it is part of the menu
+            GameState::Playing if self.demo.is_some() => Activity::MainMenu,
+            GameState::Lobby => Activity::Lobby,
+            GameState::Playing if self.run.as_ref().is_some_and(|run| run.finished) => {
+                Activity::Finished
+            }
+            GameState::Playing => Activity::Playing,
+        };
+        let mode = match self.network.as_ref() {
+            None => GameMode::Solo,
+            Some(network) if network.is_host() => GameMode::Host,
+            Some(network) if network.is_spectator() => GameMode::Spectator,
+            Some(_) => GameMode::Client,
+        };
+        let lobby = self.network.as_ref().map(|network| network.lobby());
+        // Spectators have no run of their own, the lobby knows what is played
+        let level = match activity {
+            Activity::Loading | Activity::MainMenu => None,
+            _ => self
+                .run
+                .as_ref()
+                .map(|run| run.level_id.clone())
+                .or_else(|| lobby.as_ref().map(|lobby| lobby.level.clone()))
+                .map(|id| find_level(&id).map_or(id, |level| level.name.to_string())),
+        };
+        let players = lobby.map_or(1, |lobby| lobby.members.len());
+        
+        if self.presence.update(activity, mode, level, players) {
+            for plugin in self.plugins.iter_mut() {
+                plugin.presence_changed(&self.presence);
+            }
+        }
+    }
+    
+    // Calls a hook on every plugin with the current level
+    fn run_plugins(
+        &mut self,
+        scene: &mut Scene,
+        resource_manager: &ResourceManager,
+        mut hook: impl FnMut(&mut dyn GamePlugin, &mut LevelContext),
+    ) {
+        let mut ctx = LevelContext {
+            scene,
+            components: &mut self.components,
+            events: &mut self.events,
+            resource_manager,
+            level_time: self.level_tick as f32 * FIXED_TIMESTEP,
+        };
+        for plugin in self.plugins.iter_mut() {
+            hook(plugin.as_mut(), &mut ctx);
+        }
+    }
+    
+    fn update_loading(&mut self, context: &mut PluginContext) {
+        let Some(preloader) = self.preloader.as_mut() else {
+            return;
+        };
+        let progress = preloader.update();
+        let ui = context.user_interfaces.first();
+        self.menu.set_loading_progress(ui, progress);
+        if !progress.is_finished() {
+            return;
+        }
+        
+        // Report everything that's missing at once, before any of it is needed
+        for asset in preloader.missing() {
+            warn!("Missing {asset}");
+        }
+        self.menu.show_missing_assets(ui, preloader.missing());
+        self.menu.set_screen(ui, MenuScreen::Main);
+        self.state = GameState::MainMenu;
+        
+        // Command line sessions only start once the assets are in
+        if let Some(config) = self.options.benchmark.as_ref() {
+            self.benchmark = Some(Benchmark::new(config));
+            let level_id = default_level().id.to_string();
+            self.start_level(context, LevelConfig { level_id, ..Default::default() });
+        } else if let Some(server) = self.options.connect {
+            self.join_session(context, server, self.options.spectate);
+        } else if self.options.host {
+            self.host_session(context);
+        } else if let Some(crash) = crash::pending() {
+            // The last run crashed, offer to pick up where it left off
+            let level = crash
+                .restorable_save()
+                .map(|save| save.level.as_str())
+                .filter(|level| find_level(level).is_some());
+            self.menu.show_crash(
+                context.user_interfaces.first(),
+                &crash.report.message,
+                &crash.path.display().to_string(),
+                level,
+                crash.modified,
+            );
+        }
+    }
+    
+    // Scripted input for the coming tick, as if it came from the keyboard and mouse
+    fn feed_input(&mut self, steps: Vec<InputStep>, ui: &UserInterface) {
+        for step in steps {
+            match step {
+                InputStep::Press(key) => self.handle_key(key, true, ui),
+                InputStep::Release(key) => self.handle_key(key, false, ui),
+                InputStep::Device(event) => self.handle_device_event(&event),
+                InputStep::Wait(_) => {}
+            }
+        }
+    }
+    
+    // The main menu was left alone long enough, and nobody would miss it being covered
+    fn demo_due(&self) -> bool {
+        let Some(delay) = self.options.demo_delay else {
+            return false;
+        };
+        self.menu.screen() == MenuScreen::Main
+            && !self.console.is_open()
+            && !self.visibility.is_hidden()
+            && self.idle_since.elapsed().as_secs_f32() >= delay
+    }
+    
+    // Attract mode: the recorded demo run plays back in its level, with a banner
+    // instead of the menu. Any input ends it.
+    fn start_demo(&mut self, context: &mut PluginContext) {
+        let replay = match demo_replay() {
+            Ok(replay) => replay,
+            Err(err) => {
+                // Not again until the menu was left alone for another while
+                warn!("No demo to play: {err}");
+                self.idle_since = Instant::now();
+                return;
+            }
+        };
+        info!("Nobody around, playing the demo");
+        self.demo = Some(ReplayPlayer::new(&replay));
+        self.start_level(context, replay.level_config());
+        if self.state == GameState::Playing {
+            self.menu
+                .set_screen(context.user_interfaces.first(), MenuScreen::Demo);
+        } else {
+            self.demo = None;
+        }
+    }
+    
+    // Feeds the demo's input for this tick, back to the menu once it is over
+    fn play_demo(&mut self, context: &mut PluginContext) {
+        let Some(demo) = self.demo.as_mut() else {
+            return;
+        };
+        let Some((input, dash)) = demo.next_tick() else {
+            self.stop_demo(context);
+            return;
+        };
+        self.input_state.set_move_input(input);
+        if dash {
+            self.input_state.request_dash();
+        }
+    }
+    
+    fn stop_demo(&mut self, context: &mut PluginContext) {
+        self.demo = None;
+        self.input_state.release_keys();
+        self.leave_session(context);
+        self.idle_since = Instant::now();
+    }
+    
+    // Feeds the benchmark path's input for this tick, or writes the report and quits
+    // once it is over
+    fn play_benchmark(&mut self, context: &mut PluginContext) {
+        let Some(benchmark) = self.benchmark.as_mut() else {
+            return;
+        };
+        match benchmark.next_tick() {
+            Some(steps) => self.feed_input(steps, context.user_interfaces.first()),
+            None => {
+                let Some(benchmark) = self.benchmark.take() else {
+                    return;
+                };
+                let level = self.run.as_ref().map_or("", |run| run.level_id.as_str());
+                let report = benchmark.report(level);
+                match report.write(benchmark.report_path()) {
+                    Ok(()) => info!(
+                        "Benchmark done, {:.2} ms per frame (p99 {:.2} ms), report in {}",
+                        report.frame_ms.average,
+                        report.frame_ms.p99,
+                        benchmark.report_path().display()
+                    ),
+                    Err(err) => error!("Couldn't write the benchmark report: {err}"),
+                }
+                if let Some(window_target) = context.window_target {
+                    window_target.exit();
+                }
+            }
+        }
+    }
+    
+    pub fn components(&self) -> &ComponentStore {
+        &self.components
+    }
+    
+    pub fn components_mut(&mut self) -> &mut ComponentStore {
+        &mut self.components
+    }
+    
+    pub fn input_state_mut(&mut self) -> &mut InputState {
+        &mut self.input_state
+    }
+    
+    pub fn player(&self) -> PlayerHandle {
+        self.player
+    }
+    
+    // Coins the local player picked up in the current level
+    pub fn coins_collected(&self) -> u32 {
+        self.run.as_ref().map_or(0, |run| run.coins)
+    }
+    
+    pub fn level_finished(&self) -> bool {
+        self.run.as_ref().is_some_and(|run| run.finished)
+    }
+    
+    pub fn prefab_pool_stats(&self) -> PoolStats {
+        self.prefab_pool.stats()
+    }
+    
+    pub fn time_scale(&self) -> f32 {
+        self.time_scale
+    }
+    
+    // Node scripts that move things check this, the plugin decided for the update
+    pub fn is_ticking(&self) -> bool {
+        self.ticking
+    }
+    
+    pub fn in_hitstop(&self) -> bool {
+        self.feedback.in_hitstop()
+    }
+    
+    // Called once per update with the real frame time, ticked or not
+    pub fn update_feedback(&mut self, dt: f32) {
+        self.feedback.update(dt);
+    }
+    
+    pub fn cheats(&self) -> Cheats {
+        self.cheats
+    }
+    
+    pub fn set_cheat(&mut self, cheat: Cheat, on: bool) -> Result<(), String> {
+        self.require_offline()?;
+        self.cheats.set(cheat, on);
+        if on {
+            self.taint_run();
+        }
+        Ok(())
+    }
+    
+    // Whether the running level's time is kept off the leaderboard
+    pub fn run_withheld(&self) -> bool {
+        self.run.as_ref().is_some_and(|run| run.tainted)
+    }
+    
+    // The running level no longer counts for the leaderboard. Its keys don't tell what
+    // the cheats did either, so it isn't recorded any further.
+    fn taint_run(&mut self) {
+        if let Some(run) = self.run.as_mut() {
+            run.tainted = true;
+        }
+        self.recorder = None;
+    }
+    
+    pub fn frame_step(&self) -> FrameStep {
+        self.frame_step
+    }
+    
+    pub fn telemetry(&self) -> &Telemetry {
+        &self.telemetry
+    }
+    
+    pub fn clips(&self) -> &ClipRecorder {
+        &self.clips
+    }
+    
+    pub fn clips_mut(&mut self) -> &mut ClipRecorder {
+        &mut self.clips
+    }
+    
+    // Starts writing the last 30 seconds to a video, the console hears when it is done
+    pub fn save_clip(&mut self) -> Result<String, String> {
+        if !self.clips.is_recording() {
+            return Err("not recording clips, turn it on with clip on".to_string());
+        }
+        let path = self.clips.save()?;
+        Ok(format!("saving clip to {}", path.display()))
+    }
+    
+    // Recolors the open level and everything created from now on
+    pub fn set_color_vision(&mut self, context: &mut PluginContext, vision: ColorVision) {
+        palette::set_vision(vision);
+        if let Some(materials) = self.materials.as_mut() {
+            materials.refresh(context.resource_manager);
+        }
+        self.menu.refresh_palette(context.user_interfaces.first());
+    }
+    
+    pub fn set_comfort(&mut self, context: &mut PluginContext, settings: ComfortSettings) {
+        comfort::set_settings(settings);
+        self.menu.refresh_comfort(context.user_interfaces.first());
+    }
+    
+    pub fn set_telemetry(&mut self, context: &mut PluginContext, on: bool) {
+        telemetry::set_enabled(on);
+        self.menu.refresh_telemetry(context.user_interfaces.first());
+    }
+    
+    pub fn set_rumble(&mut self, context: &mut PluginContext, on: bool) {
+        rumble::set_enabled(on);
+        // Whatever is still playing stops too
+        if let Some(rumble) = self.rumble.as_mut().filter(|_| !on) {
+            rumble.stop();
+        }
+        self.menu.refresh_rumble(context.user_interfaces.first());
+    }
+    
+    pub fn set_ui_scale(&mut self, ui: &UserInterface, scale: f32) -> f32 {
+        let scale = self.ui_scale.set_manual(scale);
+        if scale != self.display.ui_scale() {
+            self.display.set_ui_scale(scale);
+        }
+        self.menu.set_ui_scale(ui, scale);
+        scale
+    }
+    
+    // Switches every string table backed text over, the menus right away
+    pub fn set_language(&mut self, ui: &UserInterface, code: &str) -> Result<(), String> {
+        localization::set_language(code)?;
+        self.menu.refresh_language(ui);
+        Ok(())
+    }
+    
+    pub fn set_frozen(&mut self, frozen: bool) -> Result<(), String> {
+        self.require_offline()?;
+        self.frame_step.set_frozen(frozen);
+        if !frozen {
+            // Back to the follow camera, which catches up from wherever this one went
+            self.step_camera = None;
+        }
+        Ok(())
+    }
+    
+    // Runs `ticks` more ticks of a frozen simulation, freezing it first if needed
+    pub fn step_ticks(&mut self, ticks: u32) -> Result<(), String> {
+        self.require_offline()?;
+        self.frame_step.step(ticks);
+        Ok(())
+    }
+    
+    // Cheats only count offline, a session would desync (or let one player cheat)
+    fn cheat_active(&self, cheat: Cheat) -> bool {
+        self.network.is_none() && self.cheats.is_on(cheat)
+    }
+    
+    // God mode, or a benchmark, whose player has to make it through the whole path
+    // without the cheat being switched on
+    fn player_invulnerable(&self) -> bool {
+        self.cheat_active(Cheat::GodMode) || self.benchmark.is_some()
+    }
+    
+    // Hands the local player one of every pickup item, through the same event a pickup
+    // they walked into would publish. Returns how many there were.
+    pub fn give_all_items(&mut self) -> Result<usize, String> {
+        self.require_offline()?;
+        if self.run.is_none() || self.player.handle().is_none() {
+            return Err("no level is running".to_string());
+        }
+        
+        let mut items: Vec<_> = [Prefab::Coin, Prefab::HealthPack]
+            .into_iter()
+            .map(ItemDefinition::base)
+            .collect();
+        items.extend(mods::item_ids().iter().filter_map(|id| mods::item(id)).cloned());
+        let pickups: Vec<_> = items.iter().filter_map(pickup_for).collect();
+        self.taint_run();
+        for pickup in &pickups {
+            self.events.publish(GameEvent::PickupCollected {
+                collector: self.player.handle(),
+                kind: pickup.kind,
+                value: pickup.value,
+            });
+        }
+        Ok(pickups.len())
+    }
+    
+    // For node scripts to time their part of the frame
+    pub fn profiler_mut(&mut self) -> &mut Profiler {
+        &mut self.profiler
+    }
+    
+    // Remembers where everything was before this tick moves it
+    pub fn begin_tick(&mut self) {
+        self.last_tick = Instant::now();
+        for (_, sim) in self.components.iter_mut::<SimTransform>() {
+            // The last tick's movement, drawn as where it would get in a quarter second
+            let velocity = (sim.position - sim.previous_position) / FIXED_TIMESTEP;
+            if velocity.norm() > 0.01 {
+                debug_draw::arrow(
+                    DebugCategory::Velocities,
+                    sim.position,
+                    sim.position + velocity * 0.25,
+                    Color::opaque(80, 220, 255),
+                );
+            }
+            sim.begin_tick();
+        }
+    }
+    
+    // Where the simulation has the local player, which may be ahead of the rendered cube
+    pub fn player_position(&self, scene: &Scene) -> Option<Vector3<f32>> {
+        match self.components.get::<SimTransform>(self.player.handle()) {
+            Some(sim) => Some(sim.position),
+            None => self
+                .player
+                .get(&scene.graph)
+                .map(|player_node| **player_node.local_transform().position()),
+        }
+    }
+    
+    // Places interpolated nodes between the last two ticks just before a frame is drawn
+    fn interpolate_transforms(&mut self, context: PluginContext) {
+        let Some(scene) = context.scenes.try_get_mut(self.scene) else {
+            return;
+        };
+        
+        let alpha = (self.last_tick.elapsed().as_secs_f32() / FIXED_TIMESTEP).clamp(0.0, 1.0);
+        for (entity, sim) in self.components.iter::<SimTransform>() {
+            if let Some(node) = scene.graph.try_get_mut(entity) {
+                let (position, rotation) = sim.interpolate(alpha);
+                node.local_transform_mut()
+                    .set_position(position)
+                    .set_rotation(rotation);
+            }
+        }
+        
+        // Shake only moves the drawn camera, the simulated one keeps following smoothly
+        let shake = self.feedback.shake_offset();
+        if shake != Vector3::default() && !self.frame_step.is_frozen() {
+            if let (Some(sim), Some(node)) = (
+                self.components.get::<SimTransform>(self.camera.handle()),
+                scene.graph.try_get_mut(self.camera.handle()),
+            ) {
+                let (position, _) = sim.interpolate(alpha);
+                node.local_transform_mut().set_position(position + shake);
+            }
+        }
+    }
+    
+    // Movement keys the player script should act on this frame
+    pub fn player_input(&self) -> MoveInput {
+        // The player stands still on the goal while the results are shown
+        match self.run.as_ref() {
+            Some(run) if run.finished => MoveInput::default(),
+            _ => self.input_state.move_input(),
+        }
+    }
+    
+    // Moves the local player one frame. Online clients predict locally and let
+    // the server correct them later.
+    pub fn step_player(
+        &mut self,
+        input: MoveInput,
+        dt: f32,
+        position: Vector3<f32>,
+    ) -> Vector3<f32> {
+        if self.cheat_active(Cheat::Noclip) {
+            return cheats::noclip_movement(
+                position,
+                input,
+                self.input_state.move_up,
+                self.input_state.move_down,
+                dt,
+                self.tuning.player_speed,
+            );
+        }
+        match self.network.as_mut() {
+            Some(network) => network.predict(input, dt, position),
+            None => {
+                let dash = self.input_state.take_dash() && input != MoveInput::default();
+                if let Some(recorder) = self.recorder.as_mut() {
+                    recorder.record(input, dash);
+                }
+                let speed = self.tuning.player_speed * self.update_stamina(dash, dt);
+                simulate_movement_at(position, input, dt, speed)
+            }
+        }
+    }
+    
+    // Starts a dash if one was asked for and paid for, and recovers stamina otherwise.
+    // Returns how much faster than walking the player moves this tick. Offline only,
+    // online everybody moves at the server's speed.
+    fn update_stamina(&mut self, wants_dash: bool, dt: f32) -> f32 {
+        let free = self.cheat_active(Cheat::InfiniteStamina);
+        let Some(stamina) = self.components.get_mut::<Stamina>(self.player.handle()) else {
+            return 1.0;
+        };
+        if wants_dash && stamina.dash_left <= 0.0 && (free || stamina.spend(DASH_COST)) {
+            stamina.dash_left = DASH_TIME;
+            self.events.publish(GameEvent::Dashed {
+                player: self.player.handle(),
+            });
+        }
+        if stamina.dash_left > 0.0 {
+            stamina.dash_left -= dt;
+            DASH_SPEED_FACTOR
+        } else {
+            stamina.recover(STAMINA_REGEN * dt);
+            1.0
+        }
+    }
+    
+    // Pushes the current tuning into the scripts of the loaded level
+    fn apply_tuning(&self, scene: &mut Scene) {
+        let Some(camera) = self.camera.get_mut(&mut scene.graph) else {
+            return;
+        };
+        if let Some(controller) = camera.try_get_script_mut::<CameraController>() {
+            controller.offset = self.tuning.camera_offset.into();
+            controller.smoothing = self.tuning.camera_smoothing;
+        }
+    }
+    
+    fn update_level(&mut self, context: &mut PluginContext, dt: f32) {
+        // The host went away, nothing left to play on
+        if self.network.as_ref().is_some_and(|network| network.is_disconnected()) {
+            self.leave_session(context);
+            return;
+        }
+        
+        // Get the scene
+        let Some(scene) = context.scenes.try_get_mut(self.scene) else {
+            return;
+        };
+        self.simulate_level(
+            scene,
+            context.resource_manager,
+            context.user_interfaces.first(),
+            dt,
+        );
+        crash::set_emergency_save(self.emergency_save(scene));
+    }
+    
+    // What a crash report keeps of the running level. Only solo runs, an online session
+    // can't be rejoined where it was.
+    fn emergency_save(&self, scene: &Scene) -> Option<EmergencySave> {
+        let run = self
+            .run
+            .as_ref()
+            .filter(|run| !run.finished && self.network.is_none() && self.demo.is_none())?;
+        let player = self.player.handle();
+        scene.graph.try_get(player)?;
+        let position = self.components.get::<SimTransform>(player)?.position;
+        Some(EmergencySave {
+            level: run.level_id.clone(),
+            tick: self.level_tick,
+            position: position.into(),
+            health: self.components.get::<Health>(player)?.current,
+            coins: run.coins,
+        })
+    }
+    
+    // A frame of a frozen level: nothing moves but the camera, which flies freely
+    // until the simulation runs on. Player cameras start from the follow view.
+    fn update_frozen(&mut self, context: &mut PluginContext, dt: f32) {
+        let Some(scene) = context.scenes.try_get_mut(self.scene) else {
+            return;
+        };
+        let input = self.input_state.spectator_input();
+        self.input_state.mouse_delta = Vector3::default();
+        
+        let camera_handle = self.camera.handle();
+        let camera = match self.spectator.as_mut() {
+            Some(spectator) => spectator,
+            None => self.step_camera.get_or_insert_with(|| {
+                let (position, direction) = match self.camera.get(&scene.graph) {
+                    Some(node) => (node.global_position(), node.look_vector()),
+                    None => (Vector3::new(0.0, 3.0, 5.0), -Vector3::z()),
+                };
+                SpectatorCamera::looking(position, direction)
+            }),
+        };
+        camera.update(scene, self.camera, &self.remote_players, &input, dt);
+        
+        // The follow camera is drawn from its simulated transform, which has to come along
+        if let (Some(sim), Some(node)) = (
+            self.components.get_mut::<SimTransform>(camera_handle),
+            scene.graph.try_get(camera_handle),
+        ) {
+            let transform = node.local_transform();
+            *sim = SimTransform::new(**transform.position(), **transform.rotation());
+        }
+    }
+    
+    // One tick of everything in the level except what node scripts do themselves
+    pub fn simulate_level(
+        &mut self,
+        scene: &mut Scene,
+        resource_manager: &ResourceManager,
+        ui: &UserInterface,
+        dt: f32,
+    ) {
+        // Exchange transforms with other players
+        let scope = self.profiler.begin(Phase::Network);
+        self.update_network(scene, dt);
+        self.profiler.end(scope);
+        
+        // Spectators only steer their camera, players are moved by their script
+        if let Some(spectator) = self.spectator.as_mut() {
+            let scope = self.profiler.begin(Phase::Camera);
+            let input = self.input_state.spectator_input();
+            spectator.update(scene, self.camera, &self.remote_players, &input, dt);
+            self.profiler.end(scope);
+        }
+        
+        // Mouse motion is accumulated between frames
+        self.input_state.mouse_delta = Vector3::default();
+        
+        // Gameplay systems only read the world and run side by side on worker threads,
+        // then their results are applied and published one after another...
+        let scope = self.profiler.begin(Phase::Ai);
+        let snapshot = WorldSnapshot::capture(scene, &self.components, self.player_position(scene));
+        let output = systems::run_parallel(&snapshot, &self.components, dt);
+        self.apply_system_output(scene, output, dt);
+        self.profiler.end(scope);
+        
+        // ...and everything interested reacts to it
+        let scope = self.profiler.begin(Phase::Events);
+        self.run_plugins(scene, resource_manager, |plugin, ctx| plugin.update(ctx, dt));
+        self.events.flush();
+        self.handle_events(scene, ui);
+        let events = self.events.read().to_vec();
+        if let Some(rumble) = self.rumble.as_mut() {
+            for curve in events
+                .iter()
+                .filter_map(|event| rumble::curve_for(event, self.player.handle()))
+            {
+                rumble.play(curve);
+            }
+        }
+        self.run_plugins(scene, resource_manager, |plugin, ctx| {
+            for event in &events {
+                plugin.on_event(event, ctx);
+            }
+        });
+        self.run_level_script(scene, resource_manager, dt);
+        self.profiler.end(scope);
+        
+        self.level_tick += 1;
+        self.record_checksum();
+        self.draw_debug(&snapshot);
+    }
+    
+    // Game-side debug shapes, the systems draw their own while they run
+    fn draw_debug(&self, snapshot: &WorldSnapshot) {
+        for (entity, trigger) in self.components.iter::<Trigger>() {
+            if let Some(position) = snapshot.position(entity) {
+                let color = if trigger.fired {
+                    Color::opaque(120, 120, 120)
+                } else {
+                    palette::color(ColorRole::Goal)
+                };
+                debug_draw::ground_circle(DebugCategory::TriggerBounds, position, trigger.radius, color);
+            }
+        }
+        for (entity, pickup) in self.components.iter::<Pickup>() {
+            if let Some(position) = snapshot.position(entity) {
+                debug_draw::wire_sphere(
+                    DebugCategory::TriggerBounds,
+                    position,
+                    pickup.radius,
+                    Color::opaque(80, 220, 120),
+                );
+            }
+        }
+        for (entity, ai) in self.components.iter::<EnemyAi>() {
+            if let Some(position) = snapshot.position(entity) {
+                let color = palette::color(ColorRole::Enemy);
+                debug_draw::ground_circle(DebugCategory::AiPaths, position, ai.aggro_radius, color);
+                debug_draw::wire_box(DebugCategory::AiPaths, ai.home, Vector3::new(0.2, 0.2, 0.2), color);
+                debug_draw::text(
+                    DebugCategory::AiPaths,
+                    position + Vector3::new(0.0, 1.2, 0.0),
+                    &format!("{:?}", ai.state),
+                    Color::opaque(255, 160, 240),
+                );
+            }
+        }
+    }
+    
+    // Hands this frame's debug shapes to the scene and places their labels on screen
+    fn show_debug_frame(&mut self, context: &mut PluginContext) {
+        let frame = debug_draw::take_frame();
+        if self.ticking {
+            self.debug_frame = frame;
+        }
+        let ui = context.user_interfaces.first();
+        match context.scenes.try_get_mut(self.scene) {
+            Some(scene) => {
+                self.debug_frame.submit(scene);
+                let labels: Vec<_> = self
+                    .debug_frame
+                    .project_labels(scene, self.camera.handle(), ui.screen_size())
+                    .into_iter()
+                    .map(|(position, label)| (self.ui_scale.to_ui(position), label))
+                    .collect();
+                self.debug_labels.update(ui, &labels);
+            }
+            None => self.debug_labels.update(ui, &[]),
+        }
+    }
+    
+    // Numbers for the profiler overlay. The renderer's count the previous frame, this
+    // one hasn't been drawn yet.
+    fn collect_stats(&mut self, context: &PluginContext) {
+        if let GraphicsContext::Initialized(graphics_context) = &context.graphics_context {
+            self.stats
+                .collect_render(&graphics_context.renderer, context.resource_manager);
+        }
+        self.stats.collect_game(
+            context.scenes.try_get(self.scene),
+            &self.components,
+            &self.prefab_pool,
+        );
+    }
+    
+    // Seconds of simulation since the level started, counted in ticks so a replayed
+    // run gets exactly the same time
+    pub fn level_time(&self) -> f32 {
+        self.level_tick as f32 * FIXED_TIMESTEP
+    }
+    
+    // Hash of everything the simulation decides, equal for runs that haven't diverged
+    pub fn state_checksum(&self) -> u64 {
+        let mut hasher = StateHasher::new();
+        hasher.write_u64(self.level_tick);
+        for (entity, sim) in self.components.iter::<SimTransform>() {
+            hasher.write_handle(entity);
+            hasher.write_vector(sim.position);
+            hasher.write_rotation(sim.rotation);
+        }
+        for (entity, health) in self.components.iter::<Health>() {
+            hasher.write_handle(entity);
+            hasher.write_f32(health.current);
+        }
+        for (entity, pickup) in self.components.iter::<Pickup>() {
+            hasher.write_handle(entity);
+            hasher.write_u64(pickup.value as u64);
+        }
+        for (entity, ai) in self.components.iter::<EnemyAi>() {
+            hasher.write_handle(entity);
+            hasher.write_f32(ai.speed);
+            hasher.write_u64(ai.state as u64);
+        }
+        for (entity, trigger) in self.components.iter::<Trigger>() {
+            hasher.write_handle(entity);
+            hasher.write_u64(trigger.fired as u64);
+        }
+        for (entity, contact) in self.components.iter::<ContactDamage>() {
+            hasher.write_handle(entity);
+            hasher.write_f32(contact.cooldown);
+        }
+        for (entity, stamina) in self.components.iter::<Stamina>() {
+            hasher.write_handle(entity);
+            hasher.write_f32(stamina.current);
+            hasher.write_f32(stamina.dash_left);
+        }
+        if let Some(run) = self.run.as_ref() {
+            hasher.write_u64(run.coins as u64);
+            hasher.write_u64(run.finished as u64);
+        }
+        hasher.finish()
+    }
+    
+    fn record_checksum(&mut self) {
+        if self.checksums.is_none() {
+            return;
+        }
+        let checksum = self.state_checksum();
+        let tick = self.level_tick;
+        if let Some(divergence) = self
+            .checksums
+            .as_mut()
+            .and_then(|checksums| checksums.push(tick, checksum))
+        {
+            error!(
+                "Simulation diverged at tick {}: expected {:016x}, got {:016x}",
+                divergence.tick, divergence.expected, divergence.actual
+            );
+        }
+    }
+    
+    // The sync point after the parallel systems: the only place their decisions touch the scene
+    fn apply_system_output(&mut self, scene: &mut Scene, output: SystemOutput, dt: f32) {
+        for (entity, kind) in output.entered_triggers {
+            if let Some(trigger) = self.components.get_mut::<Trigger>(entity) {
+                trigger.fired = true;
+            }
+            self.events.publish(GameEvent::TriggerEntered {
+                trigger: entity,
+                kind,
+            });
+        }
+        
+        for (entity, pickup) in output.collected_pickups {
+            self.prefab_pool.release(scene, entity);
+            self.components.remove_entity(entity);
+            self.events.publish(GameEvent::PickupCollected {
+                collector: self.player.handle(),
+                kind: pickup.kind,
+                value: pickup.value,
+            });
+        }
+        
+        for step in output.enemy_steps {
+            if let Some(ai) = self.components.get_mut::<EnemyAi>(step.enemy) {
+                ai.state = step.state;
+            }
+            if let Some(sim) = self.components.get_mut::<SimTransform>(step.enemy) {
+                sim.position = step.position;
+            }
+        }
+        
+        for (_, contact) in self.components.iter_mut::<ContactDamage>() {
+            contact.cooldown = (contact.cooldown - dt).max(0.0);
+        }
+        // A dashing player is the one who hits, and the touch is used up either way
+        let player = self.player.handle();
+        let dashing = self
+            .components
+            .get::<Stamina>(player)
+            .is_some_and(|stamina| stamina.dash_left > 0.0);
+        // Using things changes the level, online everybody would have to agree on that
+        self.interact_target = output.interactable.filter(|_| self.network.is_none());
+        let interact = self.input_state.take_interact();
+        if let Some(target) = self.interact_target.filter(|_| interact) {
+            self.events.publish(GameEvent::Interacted { player, target });
+        }
+        
+        for (entity, amount) in output.contact_hits {
+            if let Some(contact) = self.components.get_mut::<ContactDamage>(entity) {
+                contact.cooldown = contact.interval;
+            }
+            let (target, amount, source) = if dashing {
+                (entity, DASH_DAMAGE, player)
+            } else {
+                (player, amount, entity)
+            };
+            self.events.publish(GameEvent::DamageDealt {
+                target,
+                amount,
+                source: Some(source),
+            });
+        }
+    }
+    
+    // Reacts to the events flushed this frame
+    fn handle_events(&mut self, scene: &mut Scene, ui: &UserInterface) {
+        for event in self.events.read().to_vec() {
+            match event {
+                GameEvent::TriggerEntered {
+                    kind: TriggerKind::Goal,
+                    ..
+                } => self.finish_level(ui),
+                // God mode leaves the player's health alone, the event still happened
+                GameEvent::DamageDealt { target, .. }
+                    if target == self.player.handle() && self.player_invulnerable() => {}
+                GameEvent::DamageDealt { target, amount, .. } => {
+                    if target == self.player.handle() {
+                        self.feedback.player_hit(amount);
+                    }
+                    let Some(health) = self.components.get_mut::<Health>(target) else {
+                        continue;
+                    };
+                    if health.damage(amount) > 0.0 && health.is_dead() {
+                        if self.components.has::<EnemyAi>(target) {
+                            self.events.publish(GameEvent::EnemyDied {
+                                enemy: EnemyHandle::new(&self.components, target),
+                            });
+                        } else if target == self.player.handle() {
+                            if let Some(run) = self.run.as_ref().filter(|_| self.demo.is_none()) {
+                                self.telemetry.player_died(&run.level_id);
+                            }
+                            self.respawn_player(scene);
+                        }
+                    }
+                }
+                GameEvent::PickupCollected {
+                    collector,
+                    kind: PickupKind::Health,
+                    value,
+                } => {
+                    if let Some(health) = self.components.get_mut::<Health>(collector) {
+                        health.heal(value as f32);
+                    }
+                }
+                GameEvent::EnemyDied { enemy } => {
+                    // Online the others wouldn't stop. Held ticks change nothing, so recorded
+                    // and deterministic runs play out the same with a hitstop.
+                    if self.network.is_none() {
+                        self.feedback.enemy_killed();
+                    }
+                    self.prefab_pool.release(scene, enemy.handle());
+                    self.components.remove_entity(enemy.handle());
+                }
+                GameEvent::PickupCollected {
+                    kind: PickupKind::Coin,
+                    ..
+                } => {
+                    if let Some(run) = self.run.as_mut() {
+                        run.coins = run.coins.saturating_add(1);
+                    }
+                }
+                GameEvent::Interacted { player, target } => {
+                    let contents = self
+                        .components
+                        .get::<Interactable>(target)
+                        .and_then(|interactable| interactable.contents);
+                    if let Some((kind, value)) = contents {
+                        self.prefab_pool.release(scene, target);
+                        self.components.remove_entity(target);
+                        self.events.publish(GameEvent::PickupCollected {
+                            collector: player,
+                            kind,
+                            value,
+                        });
+                    }
+                }
+                GameEvent::Dashed { .. } => {}
+            }
+        }
+    }
+    
+    // Hands this frame's events and a tick to the level script, then carries out what it asked for
+    fn run_level_script(&mut self, scene: &mut Scene, resource_manager: &ResourceManager, dt: f32) {
+        let Some(script) = self.level_script.as_ref() else {
+            return;
+        };
+        script.set_view(self.script_view(scene));
+        
+        for event in self.events.read() {
+            match *event {
+                GameEvent::PickupCollected { kind, value, .. } => {
+                    let kind = match kind {
+                        PickupKind::Coin => "coin",
+                        PickupKind::Health => "health",
+                    };
+                    script.call("on_pickup", (kind.to_string(), value as i64));
+                }
+                GameEvent::TriggerEntered {
+                    kind: TriggerKind::Goal,
+                    ..
+                } => script.call("on_trigger", ("goal".to_string(),)),
+                GameEvent::DamageDealt { target, amount, .. } if target == self.player.handle() => {
+                    script.call("on_damage", (amount as f64,));
+                }
+                GameEvent::EnemyDied { .. } => script.call("on_enemy_died", ()),
+                GameEvent::Interacted { target, .. } => {
+                    if let Some(node) = scene.graph.try_get(target) {
+                        script.call("on_interact", (node.name().to_string(),));
+                    }
+                }
+                GameEvent::DamageDealt { .. } | GameEvent::Dashed { .. } => {}
+            }
+        }
+        script.call("on_tick", (dt as f64,));
+        
+        let commands = script.take_commands();
+        self.apply_script_commands(scene, resource_manager, commands);
+    }
+    
+    fn script_view(&self, scene: &Scene) -> ScriptView {
+        ScriptView {
+            player_position: self.player_position(scene),
+            player_health: self
+                .components
+                .get::<Health>(self.player.handle())
+                .map(|health| health.current),
+            level_time: self.level_time(),
+        }
+    }
+    
+    fn apply_script_commands(
+        &mut self,
+        scene: &mut Scene,
+        resource_manager: &ResourceManager,
+        commands: Vec<ScriptCommand>,
+    ) {
+        for command in commands {
+            match command {
+                ScriptCommand::SpawnPrefab {
+                    prefab,
+                    name,
+                    position,
+                } => {
+                    // Items from mods first, they may replace the plain prefabs
+                    let item = mods::item(&prefab)
+                        .cloned()
+                        .or_else(|| Prefab::from_name(&prefab).map(ItemDefinition::base));
+                    match item {
+                        Some(item) => {
+                            if let Err(err) =
+                                self.spawn_prefab(scene, resource_manager, &item, &name, position)
+                            {
+                                warn!("Couldn't spawn {name:?}: {err}");
+                            }
+                        }
+                        None => warn!("Level script asked for unknown prefab {prefab:?}"),
+                    }
+                }
+                ScriptCommand::MoveNode { name, position } => {
+                    let Some((node, _)) = scene.graph.find_by_name_from_root(&name) else {
+                        continue;
+                    };
+                    scene.graph[node].local_transform_mut().set_position(position);
+                    // Don't let the next interpolated frame drag it back
+                    if let Some(sim) = self.components.get_mut::<SimTransform>(node) {
+                        *sim = SimTransform::new(position, sim.rotation);
+                    }
+                }
+                ScriptCommand::PlaySound { path } => {
+                    let buffer = resource_manager.request::<SoundBuffer>(&path);
+                    SoundBuilder::new(BaseBuilder::new())
+                        .with_buffer(Some(buffer))
+                        .with_status(Status::Playing)
+                        .with_play_once(true)
+                        .build(&mut scene.graph);
+                }
+                ScriptCommand::SetEnemySpeed { name, speed } => {
+                    let Some((node, _)) = scene.graph.find_by_name_from_root(&name) else {
+                        continue;
+                    };
+                    if let Some(ai) = self.components.get_mut::<EnemyAi>(node) {
+                        ai.speed = speed;
+                    }
+                }
+                ScriptCommand::ShowDialogue { id } => match mods::dialogue(&id) {
+                    Some(lines) => {
+                        // Lines are keys into the string tables, or plain text if they aren't
+                        for line in lines {
+                            self.chat.push_line(
+                                &localization::tr_or(&line.speaker, &line.speaker),
+                                &localization::tr_or(&line.text, &line.text),
+                            );
+                        }
+                    }
+                    None => warn!("Level script asked for unknown dialogue {id:?}"),
+                },
+            }
+        }
+    }
+    
+    // Places an item's prefab and gives it the components its kind comes with
+    pub fn spawn_prefab(
+        &mut self,
+        scene: &mut Scene,
+        resource_manager: &ResourceManager,
+        item: &ItemDefinition,
+        name: &str,
+        position: Vector3<f32>,
+    ) -> Result<Handle<Node>, GameError> {
+        let node = self
+            .prefab_pool
+            .acquire(scene, resource_manager, item.prefab, name, position)?;
+        if let Some(pickup) = pickup_for(item) {
+            self.components.insert(node, pickup);
+        }
+        if item.prefab == Prefab::Enemy {
+            self.components.insert(
+                node,
+                EnemyAi {
+                    speed: 3.0,
+                    aggro_radius: 6.0,
+                    home: position,
+                    state: EnemyState::Idle,
+                },
+            );
+            self.components.insert(node, Health::new(30.0));
+            self.components.insert(node, ContactDamage::new(10.0, 0.8, 1.0));
+            // Moved by the AI each tick and drawn interpolated like the player
+            let rotation = **scene.graph[node].local_transform().rotation();
+            self.components.insert(node, SimTransform::new(position, rotation));
+        }
+        if item.prefab == Prefab::Crate {
+            self.components.insert(
+                node,
+                Interactable {
+                    prompt: "prompt.open_crate".to_string(),
+                    radius: 1.5,
+                    contents: Some((PickupKind::Coin, item.value.unwrap_or(1))),
+                },
+            );
+        }
+        Ok(node)
+    }
+    
+    fn finish_level(&mut self, ui: &UserInterface) {
+        // The demo is no run of anybody's, it has no results and submits nothing
+        if self.demo.is_some() {
+            return;
+        }
+        let Some(run) = self.run.as_mut().filter(|run| !run.finished) else {
+            return;
+        };
+        run.finished = true;
+        self.telemetry.level_completed(&run.level_id);
+        if let (Some(recorder), Some(path)) = (self.recorder.take(), &self.options.record_replay) {
+            match recorder.finish().save(path) {
+                Ok(()) => info!("Replay written to {}", path.display()),
+                Err(err) => warn!("Replay not written: {err}"),
+            }
+        }
+        
+        let time = self.level_tick as f32 * FIXED_TIMESTEP;
+        let tainted = run.tainted;
+        let level = find_level(&run.level_id).unwrap_or_else(default_level);
+        let submission = ScoreSubmission {
+            level: level.id.to_string(),
+            player: self.options.player_name.clone(),
+            time_ms: (time * 1000.0) as u64,
+            score: score_for_time(time, level.par_time),
+        };
+        
+        self.menu.show_results(
+            ui,
+            &submission.level,
+            &submission.player,
+            submission.time_ms,
+            submission.score,
+            match self.leaderboard {
+                None => ResultsSubmit::NotConfigured,
+                Some(_) if tainted => ResultsSubmit::Withheld,
+                Some(_) => ResultsSubmit::Submitting,
+            },
+        );
+        
+        // Rankings are fetched after the submission so they already include this run.
+        // A cheated run is only compared, never submitted.
+        if let Some(leaderboard) = self.leaderboard.as_ref() {
+            let (level, player) = (submission.level.clone(), submission.player.clone());
+            if !tainted {
+                leaderboard.submit(submission);
+            }
+            leaderboard.fetch_rankings(&level, &player);
+        }
+    }
+    
+    fn update_lobby(&mut self, context: &mut PluginContext, dt: f32) {
+        let Some(network) = self.network.as_mut() else {
+            return;
+        };
+        
+        network.update(dt, None);
+        
+        if network.is_disconnected() {
+            self.leave_session(context);
+            return;
+        }
+        
+        let lobby = network.lobby();
+        let local_id = network.local_player_id();
+        self.menu.update_lobby(
+            context.user_interfaces.first(),
+            &lobby,
+            network.is_host(),
+            local_id,
+        );
+        
+        // The host started the round, load the level everybody agreed on
+        if lobby.in_game {
+            let config = LevelConfig {
+                level_id: lobby.level,
+                spawn_index: local_id.unwrap_or_default() as usize,
+                spectator: network.is_spectator(),
+                ..Default::default()
+            };
+            self.start_level(context, config);
+        }
+    }
+    
+    fn handle_menu_action(&mut self, context: &mut PluginContext, action: MenuAction) {
+        match action {
+            MenuAction::PlaySolo => self.start_level(context, LevelConfig::default()),
+            MenuAction::HostSession => self.host_session(context),
+            MenuAction::OpenBrowser => {
+                self.session_browser = SessionBrowser::new()
+                    .map_err(|err| warn!("Session browser unavailable: {err}"))
+                    .ok();
+                self.menu
+                    .set_screen(context.user_interfaces.first(), MenuScreen::Browser);
+            }
+            MenuAction::Quit => {
+                if let Some(window_target) = context.window_target {
+                    window_target.exit();
+                }
+            }
+            MenuAction::AddServer(address) => {
+                if let (Some(browser), Ok(addr)) =
+                    (self.session_browser.as_mut(), address.trim().parse())
+                {
+                    browser.add_server(addr);
+                }
+            }
+            MenuAction::RefreshSessions => {
+                if let Some(browser) = self.session_browser.as_mut() {
+                    browser.refresh();
+                }
+            }
+            MenuAction::CyclePalette => self.set_color_vision(context, palette::vision().next()),
+            MenuAction::ToggleRumble => self.set_rumble(context, !rumble::enabled()),
+            MenuAction::ToggleTelemetry => self.set_telemetry(context, !telemetry::enabled()),
+            MenuAction::SetUiScale(scale) => {
+                self.set_ui_scale(context.user_interfaces.first(), scale);
+            }
+            MenuAction::CycleMonitor => {
+                if let GraphicsContext::Initialized(graphics_context) = &context.graphics_context {
+                    self.display.cycle_monitor(&graphics_context.window);
+                }
+                self.show_monitor(context);
+            }
+            MenuAction::CycleComfort => {
+                let level = comfort::settings()
+                    .common_level()
+                    .map_or(EffectLevel::Full, EffectLevel::next);
+                self.set_comfort(context, ComfortSettings::all(level));
+            }
+            MenuAction::CycleLanguage => {
+                let languages = localization::languages();
+                let current = localization::language();
+                let next = languages
+                    .iter()
+                    .position(|code| *code == current)
+                    .map_or(0, |index| (index + 1) % languages.len());
+                if let Err(err) = self.set_language(context.user_interfaces.first(), &languages[next]) {
+                    warn!("Couldn't switch the language: {err}");
+                }
+            }
+            MenuAction::ToggleSpectate => {
+                let spectator = !self.menu.join_as_spectator();
+                self.menu
+                    .set_join_as_spectator(context.user_interfaces.first(), spectator);
+            }
+            MenuAction::JoinSession { addr, spectator } => self.join_session(context, addr, spectator),
+            MenuAction::RestoreCrash => self.restore_crash(context),
+            MenuAction::DismissCrash => {
+                crash::dismiss();
+                self.menu
+                    .set_screen(context.user_interfaces.first(), MenuScreen::Main);
+            }
+            MenuAction::BackToMain => {
+                self.session_browser = None;
+                self.menu
+                    .set_screen(context.user_interfaces.first(), MenuScreen::Main);
+            }
+            MenuAction::ToggleReady => {
+                if let Some(network) = self.network.as_mut() {
+                    let local_id = network.local_player_id();
+                    let ready = network
+                        .lobby()
+                        .members
+                        .iter()
+                        .any(|member| Some(member.id) == local_id && member.ready);
+                    network.set_ready(!ready);
+                }
+            }
+            MenuAction::CycleLevel(step) => {
+                if let Some(network) = self.network.as_mut() {
+                    let level = cycle_level(&network.lobby().level, step);
+                    network.select_level(level.id);
+                }
+            }
+            MenuAction::StartGame => {
+                if let Some(network) = self.network.as_mut() {
+                    network.start_game();
+                }
+            }
+            MenuAction::LeaveLobby | MenuAction::CloseResults => self.leave_session(context),
+        }
+    }
+    
+    fn host_session(&mut self, context: &mut PluginContext) {
+        let config = self.options.net_config(NetRole::Host {
+            port: self.options.port,
+        });
+        self.enter_lobby(context, config);
+    }
+    
+    fn join_session(&mut self, context: &mut PluginContext, server: SocketAddr, spectator: bool) {
+        let config = self.options.net_config(NetRole::Client { server, spectator });
+        self.enter_lobby(context, config);
+    }
+    
+    fn enter_lobby(&mut self, context: &mut PluginContext, config: NetConfig) {
+        // A failed start (e.g. port in use) leaves us in the menu with the reason shown
+        let network = match Network::start(config) {
+            Ok(network) => network,
+            Err(err) => {
+                error!("Couldn't start the network session: {err}");
+                self.menu.show_error(
+                    context.user_interfaces.first(),
+                    &localization::tr_with("menu.network_failed", &[("error", &err.to_string())]),
+                );
+                return;
+            }
+        };
+        
+        self.network = Some(network);
+        self.session_browser = None;
+        self.state = GameState::Lobby;
+        self.menu
+            .set_screen(context.user_interfaces.first(), MenuScreen::Lobby);
+    }
+    
+    fn leave_session(&mut self, context: &mut PluginContext) {
+        self.network = None;
+        self.chat.clear(context.user_interfaces.first());
+        self.unload_level(context);
+        self.state = GameState::MainMenu;
+        self.menu
+            .set_screen(context.user_interfaces.first(), MenuScreen::Main);
+    }
+    
+    // Level loader: builds the scene described by the agreed configuration
+    fn start_level(&mut self, context: &mut PluginContext, config: LevelConfig) {
+        self.unload_level(context);
+        
+        // A level that can't be built sends everybody back to the menu instead of crashing
+        let scene = match self.build_level(context.resource_manager, &config) {
+            Ok(scene) => scene,
+            Err(err) => {
+                error!("Couldn't load level {}: {err}", config.level_id);
+                self.leave_session(context);
+                self.menu.show_error(
+                    context.user_interfaces.first(),
+                    &localization::tr_with("menu.level_failed", &[("error", &err)]),
+                );
+                return;
+            }
+        };
+        
+        // Add the scene to the engine
+        self.scene = context.scenes.add(scene);
+        
+        self.state = GameState::Playing;
+        self.menu
+            .set_screen(context.user_interfaces.first(), MenuScreen::Hidden);
+        if let Some(run) = self.run.as_ref().filter(|_| self.demo.is_none()) {
+            self.telemetry.level_started(&run.level_id);
+        }
+    }
+    
+    // Starts the level the last run crashed in and puts the player back the way the
+    // emergency save has them. Pickups taken before the crash are back in the level.
+    fn restore_crash(&mut self, context: &mut PluginContext) {
+        let save = crash::pending().and_then(|crash| crash.restorable_save().cloned());
+        crash::dismiss();
+        let Some(save) = save else {
+            self.menu
+                .set_screen(context.user_interfaces.first(), MenuScreen::Main);
+            return;
+        };
+        
+        self.start_level(context, LevelConfig { level_id: save.level.clone(), ..Default::default() });
+        let Some(scene) = context.scenes.try_get_mut(self.scene) else {
+            return;
+        };
+        // Picking up where the run was is no console teleport, the run still counts. Its
+        // replay would start halfway, there is none.
+        self.recorder = None;
+        let _ = self.place_player(scene, save.position.into());
+        if let Some(health) = self.components.get_mut::<Health>(self.player.handle()) {
+            health.current = save.health.min(health.max);
+        }
+        if let Some(run) = self.run.as_mut() {
+            run.coins = save.coins;
+        }
+        self.level_tick = save.tick;
+        info!("Restored {} from the emergency save", save.level);
+    }
+    
+    // Creates the level's scene along with the gameplay state of everything in it
+    pub fn build_level(
+        &mut self,
+        resource_manager: &ResourceManager,
+        config: &LevelConfig,
+    ) -> Result<Scene, GameError> {
+        let level = find_level(&config.level_id).unwrap_or_else(default_level);
+        let materials = match self.materials.take() {
+            Some(materials) => materials,
+            None => MaterialLibrary::load(resource_manager, self.options.materials_path.clone())?,
+        };
+        let mut scene = create_scene(&materials, level);
+        self.materials = Some(materials);
+        self.level_tick = 0;
+        if let Some(checksums) = self.checksums.as_mut() {
+            checksums.note(&format!("level {}", level.id));
+        }
+        
+        if let Some((goal, _)) = scene.graph.find_by_name_from_root(GOAL_NODE_NAME) {
+            self.components
+                .insert(goal, Trigger::new(TriggerKind::Goal, GOAL_RADIUS));
+        }
+        
+        if config.spectator {
+            // Start with an overview of the level, players can be followed with Tab
+            let overview = level.spawn_point(0) + Vector3::new(0.0, 6.0, 10.0);
+            self.spectator = Some(SpectatorCamera::new(overview));
+            self.camera = create_camera(&mut scene, None);
+        } else {
+            // Create player (a simple colored cube) and a camera following it
+            self.player = create_player(
+                &mut scene,
+                resource_manager,
+                level.spawn_point(config.spawn_index),
+            )?;
+            self.camera = create_camera(&mut scene, Some(self.player));
+            self.components.insert(self.player.handle(), Health::new(100.0));
+            self.components.insert(self.player.handle(), Stamina::new(STAMINA_MAX));
+            // A space or F pressed on the menu is no dash, and opens nothing
+            self.input_state.take_dash();
+            self.input_state.take_interact();
+            
+            // Both are moved by fixed-tick scripts and drawn interpolated
+            for node in [self.player.handle(), self.camera.handle()] {
+                let transform = scene.graph[node].local_transform();
+                let sim = SimTransform::new(**transform.position(), **transform.rotation());
+                self.components.insert(node, sim);
+            }
+            self.run = Some(LevelRun {
+                level_id: level.id.to_string(),
+                finished: false,
+                coins: 0,
+                // Cheats only work offline
+                tainted: self.network.is_none() && self.cheats != Cheats::default(),
+                spawn: level.spawn_point(config.spawn_index),
+            });
+        }
+        
+        self.apply_tuning(&mut scene);
+        
+        // A replay plays back with the seed it was recorded with
+        let script_seed = config.script_seed.unwrap_or_else(|| self.rng.next_u64());
+        let recording = self.options.record_replay.is_some()
+            && self.network.is_none()
+            && self.demo.is_none()
+            && self.cheats == Cheats::default()
+            && !config.spectator;
+        self.recorder = recording
+            .then(|| ReplayRecorder::new(level.id, config.spawn_index, script_seed));
+        
+        // Levels without a script simply have no handlers
+        let path = script_path(level.id);
+        self.level_script = if path.exists() {
+            LevelScript::load(&path, script_seed)
+                .map_err(|err| warn!("Level script not loaded: {err}"))
+                .ok()
+        } else {
+            None
+        };
+        if let Some(script) = self.level_script.as_ref() {
+            script.set_view(self.script_view(&scene));
+            script.call("on_start", ());
+            let commands = script.take_commands();
+            self.apply_script_commands(&mut scene, resource_manager, commands);
+        }
+        self.run_plugins(&mut scene, resource_manager, |plugin, ctx| {
+            plugin.level_started(ctx)
+        });
+        
+        Ok(scene)
+    }
+    
+    fn unload_level(&mut self, context: &mut PluginContext) {
+        if context.scenes.try_get(self.scene).is_some() {
+            context.scenes.remove(self.scene);
+        }
+        self.scene = Handle::NONE;
+        self.player = PlayerHandle::NONE;
+        self.camera = CameraHandle::NONE;
+        self.spectator = None;
+        self.run = None;
+        crash::set_emergency_save(None);
+        self.level_script = None;
+        self.feedback.clear();
+        if let Some(rumble) = self.rumble.as_mut() {
+            rumble.stop();
+        }
+        self.remote_players.clear();
+        self.inspector.clear();
+        
+        let stats = self.prefab_pool.stats();
+        if stats.created + stats.reused > 0 {
+            info!(
+                "Prefab pool: {} built, {} reused, {} released ({:.0}% reuse)",
+                stats.created,
+                stats.reused,
+                stats.released,
+                stats.reuse_rate() * 100.0
+            );
+        }
+        self.prefab_pool.clear();
+        self.components.clear();
+        self.events.clear();
+    }
+    
+    fn update_network(&mut self, scene: &mut Scene, dt: f32) {
+        let (Some(network), Some(surface)) =
+            (self.network.as_mut(), self.remote_player_surface.as_ref())
+        else {
+            return;
+        };
+        
+        let local = self
+            .components
+            .get::<SimTransform>(self.player.handle())
+            .map(|sim| (sim.position, sim.rotation));
+        network.update(dt, local);
+        
+        let remote_transforms = network.remote_transforms();
+        
+        // Despawn cubes of players that left
+        let components = &mut self.components;
+        self.remote_players.retain(|id, handle| {
+            let present = remote_transforms.iter().any(|(remote_id, ..)| remote_id == id);
+            if !present {
+                scene.graph.remove_node(*handle);
+                components.remove_entity(*handle);
+            }
+            present
+        });
+        
+        for (id, position, rotation) in remote_transforms {
+            let handle = *self.remote_players.entry(id).or_insert_with(|| {
+                let handle = create_actor(scene, surface.clone(), position);
+                components.insert(handle, SimTransform::new(position, rotation));
+                handle
+            });
+            
+            if let Some(sim) = components.get_mut::<SimTransform>(handle) {
+                sim.position = position;
+                sim.rotation = rotation;
+            }
+        }
+    }
+    
+    fn handle_key_input(&mut self, input: &KeyEvent, ui: &UserInterface) {
+        if let PhysicalKey::Code(key_code) = input.physical_key {
+            self.handle_key(key_code, input.state == ElementState::Pressed, ui);
+        }
+    }
+    
+    // Everything a key does in game. Window key events end up here, and so do the keys
+    // of scripted input, which can't be turned into window events.
+    pub fn handle_key(&mut self, key_code: KeyCode, is_pressed: bool, ui: &UserInterface) {
+        // Window hotkeys work everywhere, even while typing
+        let enter = matches!(key_code, KeyCode::Enter | KeyCode::NumpadEnter);
+        if is_pressed && (key_code == KeyCode::F11 || (enter && self.input_state.alt)) {
+            self.display.request_toggle();
+            return;
+        }
+        
+        // The console grabs the keyboard while it is open
+        if self.console.is_open() {
+            if is_pressed {
+                match key_code {
+                    KeyCode::Enter | KeyCode::NumpadEnter => self.console.submit(ui),
+                    KeyCode::Backquote | KeyCode::Escape => self.console.toggle(ui),
+                    KeyCode::ArrowUp => self.console.browse_history(ui, true),
+                    KeyCode::ArrowDown => self.console.browse_history(ui, false),
+                    KeyCode::Tab => self.console.complete(ui),
+                    _ => {}
+                }
+            }
+            return;
+        }
+        
+        // So does the chat box
+        if self.chat.is_open() {
+            if is_pressed {
+                match key_code {
+                    KeyCode::Enter | KeyCode::NumpadEnter => {
+                        if let (Some(text), Some(network)) = (self.chat.submit(ui), self.network.as_mut()) {
+                            network.send_chat(&text);
+                        }
+                    }
+                    KeyCode::Escape => self.chat.close(ui),
+                    _ => {}
+                }
+            }
+            return;
+        }
+        
+        match key_code {
+            KeyCode::Enter
+                if is_pressed && self.state == GameState::Playing && self.network.is_some() =>
+            {
+                // Let go of movement keys so the player doesn't keep walking while typing
+                self.input_state.release_keys();
+                self.chat.open(ui);
+            }
+            KeyCode::Backquote if is_pressed => {
+                self.input_state.release_keys();
+                self.console.toggle(ui);
+            }
+            KeyCode::F3 if is_pressed => self.profiler_overlay.toggle(ui),
+            KeyCode::F4 if is_pressed => self.inspector.toggle(ui),
+            KeyCode::F5 | KeyCode::F6 | KeyCode::F7 | KeyCode::F8 if is_pressed => {
+                if let Some(category) = DebugCategory::from_key(key_code) {
+                    let shown = debug_draw::toggle(category);
+                    self.console.print(&format!(
+                        "{} {}",
+                        category.name(),
+                        if shown { "shown" } else { "hidden" }
+                    ));
+                }
+            }
+            KeyCode::F10 if is_pressed => {
+                let frozen = !self.frame_step.is_frozen();
+                match self.set_frozen(frozen) {
+                    Ok(()) if frozen => self.console.print("simulation frozen, period steps a tick"),
+                    Ok(()) => self.console.print("simulation running"),
+                    Err(err) => self.console.print(&format!("error: {err}")),
+                }
+            }
+            KeyCode::Period if is_pressed && self.frame_step.is_frozen() => {
+                if let Err(err) = self.step_ticks(1) {
+                    self.console.print(&format!("error: {err}"));
+                }
+            }
+            KeyCode::F12 if is_pressed => match self.save_clip() {
+                Ok(message) => self.console.print(&message),
+                Err(err) => self.console.print(&format!("error: {err}")),
+            },
+            KeyCode::F9 if is_pressed => {
+                let mirror = !logging::mirrors_warnings();
+                logging::set_mirror_warnings(mirror);
+                self.console
+                    .print(if mirror { "showing warnings" } else { "hiding warnings" });
+            }
+            KeyCode::Tab if is_pressed => {
+                // Spectators cycle between following players and flying freely
+                if let Some(spectator) = self.spectator.as_mut() {
+                    let players: Vec<_> = self.remote_players.keys().copied().collect();
+                    spectator.cycle_target(&players);
+                }
+            }
+            _ => {
+                self.input_state.handle_key(key_code, is_pressed);
+            }
+        }
+    }
+    
+    // Mouse motion and other raw device input, from the OS or from scripted input
+    pub fn handle_device_event(&mut self, event: &DeviceEvent) {
+        self.input_state.handle_device_event(event);
+    }
+    
+    // Names the fullscreen monitor on the menu
+    fn show_monitor(&mut self, context: &PluginContext) {
+        if let GraphicsContext::Initialized(graphics_context) = &context.graphics_context {
+            let name = self.display.monitor(&graphics_context.window).map(|monitor| monitor_name(&monitor));
+            self.menu.set_monitor(context.user_interfaces.first(), name);
+        }
+    }
+    
+    // The mouse belongs to the game while a level is played with nothing on top of it,
+    // unless the game is played by touch
+    fn wants_cursor(&self) -> bool {
+        self.controls_game() && !self.touch.is_used()
+    }
+    
+    // Whether input steers the level. Menus, the results screen, chat, the console and
+    // the inspector need the cursor.
+    fn controls_game(&self) -> bool {
+        self.state == GameState::Playing
+            && self.menu.screen() == MenuScreen::Hidden
+            && !self.console.is_open()
+            && !self.chat.is_open()
+            && !self.inspector.is_visible()
+    }
+    
+    // Selects what is under the mouse in the inspector
+    fn pick_entity(&mut self, context: &PluginContext) {
+        let (Some(scene), GraphicsContext::Initialized(graphics_context)) =
+            (context.scenes.try_get(self.scene), &context.graphics_context)
+        else {
+            return;
+        };
+        let size = graphics_context.window.inner_size();
+        let screen_size = Vector2::new(size.width as f32, size.height as f32);
+        if let Some(entity) =
+            inspector::pick(scene, self.camera.handle(), self.cursor_position, screen_size)
+        {
+            self.inspector.select(entity);
+        }
+    }
+    
+    fn apply_inspector_edit(&mut self, context: &mut PluginContext, edit: InspectorEdit) {
+        if let Some(scene) = context.scenes.try_get_mut(self.scene) {
+            self.edit_entity(scene, edit);
+        }
+    }
+    
+    // An inspector edit on the loaded level. Whatever it changes, the run no longer
+    // counts, like after a console cheat.
+    pub fn edit_entity(&mut self, scene: &mut Scene, edit: InspectorEdit) {
+        // Same rule as the console cheats: local tinkering would desync a session
+        if self.network.is_some() {
+            warn!("Inspector edits are only applied offline");
+            return;
+        }
+        let changed = match edit {
+            InspectorEdit::Set {
+                entity,
+                field,
+                value,
+            } => match field {
+                InspectorField::PositionX | InspectorField::PositionY | InspectorField::PositionZ => {
+                    let Some(node) = scene.graph.try_get_mut(entity) else {
+                        return;
+                    };
+                    let mut position = match self.components.get::<SimTransform>(entity) {
+                        Some(sim) => sim.position,
+                        None => **node.local_transform().position(),
+                    };
+                    match field {
+                        InspectorField::PositionX => position.x = value,
+                        InspectorField::PositionY => position.y = value,
+                        _ => position.z = value,
+                    }
+                    node.local_transform_mut().set_position(position);
+                    if let Some(sim) = self.components.get_mut::<SimTransform>(entity) {
+                        *sim = SimTransform::new(position, sim.rotation);
+                    }
+                    true
+                }
+                InspectorField::Health => self
+                    .components
+                    .get_mut::<Health>(entity)
+                    .map(|health| health.current = value.clamp(0.0, health.max))
+                    .is_some(),
+                InspectorField::AiSpeed => self
+                    .components
+                    .get_mut::<EnemyAi>(entity)
+                    .map(|ai| ai.speed = value.max(0.0))
+                    .is_some(),
+                InspectorField::AiAggroRadius => self
+                    .components
+                    .get_mut::<EnemyAi>(entity)
+                    .map(|ai| ai.aggro_radius = value.max(0.0))
+                    .is_some(),
+            },
+            InspectorEdit::NextAiState(entity) => self
+                .components
+                .get_mut::<EnemyAi>(entity)
+                .map(|ai| ai.state = inspector::next_ai_state(ai.state))
+                .is_some(),
+        };
+        if changed {
+            self.taint_run();
+        }
+    }
+    
+    fn run_console_commands(&mut self, context: &mut PluginContext) {
+        for line in self.console.take_pending() {
+            let result = match self.console.execute(&line) {
+                Ok(Some(handler)) => {
+                    let args: Vec<_> = line.split_whitespace().skip(1).collect();
+                    handler(self, context, &args)
+                }
+                Ok(None) => continue,
+                Err(err) => Err(err),
+            };
+            match result {
+                Ok(text) if text.is_empty() => {}
+                Ok(text) => self.console.print(&text),
+                Err(err) => self.console.print(&format!("error: {err}")),
+            }
+        }
+    }
+    
+    fn register_commands(commands: &mut CommandRegistry) {
+        commands.register("spawn", "ITEM [DISTANCE]", "spawn an item next to the player", Self::command_spawn);
+        commands.complete_with("spawn", || {
+            let mut items: Vec<_> = ["coin", "health", "enemy", "crate"].map(String::from).into();
+            items.extend(mods::item_ids());
+            items
+        });
+        commands.register("tp", "X Y Z | aim", "move the player to a position or where the camera looks", Self::command_tp);
+        commands.complete_with("tp", || vec!["aim".to_string()]);
+        commands.register("goto", "SPAWN | NODE", "move the player to a spawn point or above a named node", Self::command_goto);
+        commands.complete_with("goto", || {
+            let mut spawns: Vec<_> = all_levels()
+                .iter()
+                .flat_map(|level| level.spawn_points.iter().map(|(name, _)| name.to_string()))
+                .collect();
+            spawns.sort();
+            spawns.dedup();
+            spawns
+        });
+        commands.register("give", "coins|health AMOUNT", "add coins or health", Self::command_give);
+        commands.complete_with("give", || vec!["coins".to_string(), "health".to_string()]);
+        commands.register("set", "timescale|uiscale VALUE", "change a game setting", Self::command_set);
+        commands.complete_with("set", || vec!["timescale".to_string(), "uiscale".to_string()]);
+        commands.register("freeze", "[on|off]", "stop the simulation for frame-stepping", Self::command_freeze);
+        commands.register("step", "[TICKS]", "run a frozen simulation for some ticks", Self::command_step);
+        commands.register("monitor", "[NUMBER]", "list monitors or pick the one for fullscreen", Self::command_monitor);
+        commands.register("load", "LEVEL", "start a level", Self::command_load);
+        commands.complete_with("load", || {
+            all_levels().iter().map(|level| level.id.to_string()).collect()
+        });
+    }
+    
+    // Commands that change the simulation would only desync a network session
+    fn require_offline(&self) -> Result<(), String> {
+        match self.network {
+            Some(_) => Err("only available offline".to_string()),
+            None => Ok(()),
+        }
+    }
+    
+    fn command_spawn(game: &mut Game, context: &mut PluginContext, args: &[&str]) -> Result<String, String> {
+        game.require_offline()?;
+        let (name, distance) = match args {
+            [name] => (*name, 2.0),
+            [name, distance] => (*name, parse_number(distance)?),
+            _ => return Err("usage: spawn ITEM [DISTANCE]".to_string()),
+        };
+        let scene = context
+            .scenes
+            .try_get_mut(game.scene)
+            .ok_or("no level is running")?;
+        game.spawn_item(scene, context.resource_manager, name, distance)
+    }
+    
+    // Puts a mod item or prefab `distance` units to the right of the player. Free coins
+    // and health packs change the run, it no longer counts.
+    pub fn spawn_item(
+        &mut self,
+        scene: &mut Scene,
+        resource_manager: &ResourceManager,
+        name: &str,
+        distance: f32,
+    ) -> Result<String, String> {
+        self.require_offline()?;
+        let item = mods::item(name)
+            .cloned()
+            .or_else(|| Prefab::from_name(name).map(ItemDefinition::base))
+            .ok_or_else(|| format!("no item {name:?}"))?;
+        let position = self.player_position(scene).ok_or("there is no player")?
+            + Vector3::new(distance, 0.0, 0.0);
+        
+        let node_name = format!("{name}_{}", self.level_tick);
+        self.spawn_prefab(scene, resource_manager, &item, &node_name, position)
+            .map_err(|err| err.to_string())?;
+        self.taint_run();
+        Ok(format!("spawned {node_name}"))
+    }
+    
+    fn command_tp(game: &mut Game, context: &mut PluginContext, args: &[&str]) -> Result<String, String> {
+        game.require_offline()?;
+        let scene = context
+            .scenes
+            .try_get_mut(game.scene)
+            .ok_or("no level is running")?;
+        let position = match args {
+            [x, y, z] => Vector3::new(parse_number(x)?, parse_number(y)?, parse_number(z)?),
+            ["aim"] => game
+                .aim_point(scene)
+                .ok_or("the camera isn't looking at anything")?,
+            _ => return Err("usage: tp X Y Z | tp aim".to_string()),
+        };
+        game.teleport_player(scene, position)
+    }
+    
+    fn command_goto(game: &mut Game, context: &mut PluginContext, args: &[&str]) -> Result<String, String> {
+        game.require_offline()?;
+        let [name] = args else {
+            return Err("usage: goto SPAWN | goto NODE".to_string());
+        };
+        let level = game
+            .run
+            .as_ref()
+            .map(|run| find_level(&run.level_id).unwrap_or_else(default_level))
+            .ok_or("no level is running")?;
+        let scene = context
+            .scenes
+            .try_get_mut(game.scene)
+            .ok_or("no level is running")?;
+        // Spawn points first, they are where a level is meant to be entered
+        let position = level
+            .find_spawn_point(name)
+            .or_else(|| {
+                scene
+                    .graph
+                    .find_by_name_from_root(name)
+                    .map(|(_, node)| **node.local_transform().position() + Vector3::new(0.0, 1.0, 0.0))
+            })
+            .ok_or_else(|| format!("no spawn point or node named {name:?}"))?;
+        game.teleport_player(scene, position)
+    }
+    
+    fn teleport_player(&mut self, scene: &mut Scene, position: Vector3<f32>) -> Result<String, String> {
+        self.place_player(scene, position)?;
+        self.taint_run();
+        Ok(format!("moved to {:.1} {:.1} {:.1}", position.x, position.y, position.z))
+    }
+    
+    // Back to the spawn point with full health and stamina, the level and its clock go on
+    fn respawn_player(&mut self, scene: &mut Scene) {
+        let Some(spawn) = self.run.as_ref().map(|run| run.spawn) else {
+            return;
+        };
+        let _ = self.place_player(scene, spawn);
+        let player = self.player.handle();
+        if let Some(health) = self.components.get_mut::<Health>(player) {
+            health.current = health.max;
+        }
+        if let Some(stamina) = self.components.get_mut::<Stamina>(player) {
+            *stamina = Stamina::new(stamina.max);
+        }
+    }
+    
+    fn place_player(&mut self, scene: &mut Scene, position: Vector3<f32>) -> Result<(), String> {
+        let player = self.player.handle();
+        let node = scene.graph.try_get_mut(player).ok_or("there is no player")?;
+        node.local_transform_mut().set_position(position);
+        // Arrive right away instead of sliding over from the old spot
+        if let Some(sim) = self.components.get_mut::<SimTransform>(player) {
+            *sim = SimTransform::new(position, sim.rotation);
+        }
+        Ok(())
+    }
+    
+    // Where the player would stand on whatever the camera looks at: on top of the
+    // nearest mesh along its view, the player's own meshes left out
+    fn aim_point(&self, scene: &Scene) -> Option<Vector3<f32>> {
+        let camera = scene.graph.try_get(self.camera.handle())?;
+        let ray = Ray::new(camera.global_position(), camera.look_vector().normalize() * AIM_DISTANCE);
+        let own: Vec<_> = match self.player.get(&scene.graph) {
+            Some(_) => scene.graph.traverse_handle_iter(self.player.handle()).collect(),
+            None => Vec::new(),
+        };
+        
+        scene
+            .graph
+            .pair_iter()
+            .filter(|(handle, node)| node.query_component_ref::<Mesh>().is_some() && !own.contains(handle))
+            .filter_map(|(_, node)| {
+                let bounds = node.world_bounding_box();
+                let [hit, _] = ray.box_intersection(&bounds.min, &bounds.max)?;
+                Some(((hit - ray.origin).norm(), Vector3::new(hit.x, bounds.max.y + 1.0, hit.z)))
+            })
+            .min_by(|(a, _), (b, _)| a.total_cmp(b))
+            .map(|(_, position)| position)
+    }
+    
+    fn command_give(game: &mut Game, _context: &mut PluginContext, args: &[&str]) -> Result<String, String> {
+        game.require_offline()?;
+        let [what, amount] = args else {
+            return Err("usage: give coins|health AMOUNT".to_string());
+        };
+        let amount = parse_number(amount)?;
+        if matches!(*what, "coins" | "health") {
+            game.taint_run();
+        }
+        match *what {
+            "coins" => {
+                let run = game.run.as_mut().ok_or("no level is running")?;
+                run.coins = run.coins.saturating_add(amount.max(0.0) as u32);
+                Ok(format!("{} coins", run.coins))
+            }
+            "health" => {
+                let health = game
+                    .components
+                    .get_mut::<Health>(game.player.handle())
+                    .ok_or("there is no player")?;
+                health.heal(amount);
+                Ok(format!("{:.0}/{:.0} health", health.current, health.max))
+            }
+            _ => Err(format!("can't give {what:?}")),
+        }
+    }
+    
+    fn command_set(game: &mut Game, context: &mut PluginContext, args: &[&str]) -> Result<String, String> {
+        match args {
+            ["timescale", value] => {
+                game.require_offline()?;
+                if game.options.deterministic() {
+                    return Err("time scale is fixed in deterministic mode".to_string());
+                }
+                game.time_scale = parse_number(value)?.clamp(0.05, 10.0);
+                game.taint_run();
+                Ok(format!("timescale {}", game.time_scale))
+            }
+            ["uiscale", value] => {
+                let scale = game.set_ui_scale(context.user_interfaces.first(), parse_number(value)?);
+                Ok(format!("uiscale {scale}"))
+            }
+            [setting, _] => Err(format!("no setting {setting:?}")),
+            _ => Err("usage: set SETTING VALUE".to_string()),
+        }
+    }
+    
+    fn command_freeze(game: &mut Game, _context: &mut PluginContext, args: &[&str]) -> Result<String, String> {
+        let frozen = match args {
+            [] => !game.frame_step.is_frozen(),
+            ["on"] => true,
+            ["off"] => false,
+            _ => return Err("usage: freeze [on|off]".to_string()),
+        };
+        game.set_frozen(frozen)?;
+        Ok(if frozen { "simulation frozen" } else { "simulation running" }.to_string())
+    }
+    
+    fn command_step(game: &mut Game, _context: &mut PluginContext, args: &[&str]) -> Result<String, String> {
+        let ticks = match args {
+            [] => 1,
+            [ticks] => ticks.parse().map_err(|_| format!("{ticks:?} isn't a tick count"))?,
+            _ => return Err("usage: step [TICKS]".to_string()),
+        };
+        game.step_ticks(ticks)?;
+        Ok(format!("stepping {ticks} ticks"))
+    }
+    
+    fn command_monitor(game: &mut Game, context: &mut PluginContext, args: &[&str]) -> Result<String, String> {
+        let GraphicsContext::Initialized(graphics_context) = &context.graphics_context else {
+            return Err("no window yet".to_string());
+        };
+        let window = &graphics_context.window;
+        let monitors: Vec<_> = window.available_monitors().collect();
+        match args {
+            [] => {
+                let chosen = game.display.monitor(window);
+                Ok(monitors
+                    .iter()
+                    .enumerate()
+                    .map(|(index, monitor)| {
+                        let size = monitor.size();
+                        let mark = if Some(monitor) == chosen.as_ref() { " (fullscreen)" } else { "" };
+                        format!("{}: {} {}x{}{mark}", index + 1, monitor_name(monitor), size.width, size.height)
+                    })
+                    .collect::<Vec<_>>()
+                    .join("\n"))
+            }
+            [number] => {
+                let monitor = number
+                    .parse::<usize>()
+                    .ok()
+                    .and_then(|number| number.checked_sub(1))
+                    .and_then(|index| monitors.get(index))
+                    .ok_or_else(|| format!("no monitor {number}"))?
+                    .clone();
+                let name = monitor_name(&monitor);
+                game.display.set_monitor(window, monitor);
+                game.show_monitor(context);
+                Ok(format!("fullscreen goes to {name}"))
+            }
+            _ => Err("usage: monitor [NUMBER]".to_string()),
+        }
+    }
+    
+    fn command_load(game: &mut Game, context: &mut PluginContext, args: &[&str]) -> Result<String, String> {
+        game.require_offline()?;
+        let [id] = args else {
+            return Err("usage: load LEVEL".to_string());
+        };
+        let level = find_level(id).ok_or_else(|| format!("no level {id:?}"))?;
+        game.start_level(
+            context,
+            LevelConfig {
+                level_id: level.id.to_string(),
+                ..Default::default()
+            },
+        );
+        Ok(format!("loaded {}", level.name))
+    }
+}
+
+// The pickup an item is collected as, if it is one at all
+fn pickup_for(item: &ItemDefinition) -> Option<Pickup> {
+    let (kind, value) = match item.prefab {
+        Prefab::Coin => (PickupKind::Coin, 1),
+        Prefab::HealthPack => (PickupKind::Health, 25),
+        Prefab::Enemy | Prefab::Crate => return None,
+    };
+    Some(Pickup {
+        kind,
+        value: item.value.unwrap_or(value),
+        radius: item.radius.unwrap_or(0.8),
+    })
+}
+
+// Only finite numbers: "nan" and "inf" parse as floats, but no command can use them
+fn parse_number(text: &str) -> Result<f32, String> {
+    text.parse()
+        .ok()
+        .filter(|number: &f32| number.is_finite())
+        .ok_or_else(|| format!("{text:?} is not a number"))
+}
+
+
+// Whether the player did something at the window: pressed, clicked, scrolled, touched or
+// moved the mouse. Raw device events don't count, some come without the window in front.
+fn is_player_input(event: &Event<()>) -> bool {
+    matches!(
+        event,
+        Event::WindowEvent {
+            event: WindowEvent::KeyboardInput { .. }
+                | WindowEvent::MouseInput { .. }
+                | WindowEvent::MouseWheel { .. }
+                | WindowEvent::CursorMoved { .. }
+                | WindowEvent::Touch(_),
+            ..
+        }
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn commands_only_take_finite_numbers() {
+        assert_eq!(parse_number("2.5"), Ok(2.5));
+        for text in ["NaN", "nan", "inf", "-inf", "infinity", "two"] {
+            assert!(parse_number(text).is_err(), "{text} was accepted");
+        }
+    }
+
+    #[test]
+    fn a_hitstop_holds_whole_ticks() {
+        let forward = MoveInput {
+            forward: true,
+            ..Default::default()
+        };
+        let mut sim = crate::headless::HeadlessSim::new(LevelConfig::default()).unwrap();
+        let start = sim.player_position().unwrap();
+        sim.game_mut().feedback.enemy_killed();
+
+        // How long it lasts depends on the comfort settings, which are global
+        let mut held = 0;
+        while sim.game().in_hitstop() {
+            sim.tick(forward);
+            held += 1;
+            assert_eq!(sim.player_position(), Some(start));
+        }
+        sim.tick(forward);
+        assert_ne!(sim.player_position(), Some(start));
+        assert_eq!(sim.ticks(), held + 1);
+    }
+}
@@ -0,0 +1,144 @@
+use crate::{
+    components::{ComponentStore, EnemyAi},
+    player::Player,
+};
+use fyrox::{
+    core::{pool::Handle, reflect::prelude::*, visitor::prelude::*},
+    scene::{camera::Camera, graph::Graph, node::Node},
+};
+
+// Newtypes around Handle<Node> for the node roles the game cares about, so a
+// camera can't be passed where a player is expected. Debug builds check the
+// node actually fits the role when a handle is wrapped, by its node type or, for
+// roles the scene graph knows nothing about, by its components.
+macro_rules! typed_handle {
+    ($name:ident, $role:literal, component $component:ty) => {
+        typed_handle!(@common $name);
+
+        impl $name {
+            pub fn new(components: &ComponentStore, handle: Handle<Node>) -> Self {
+                debug_assert!(
+                    components.has::<$component>(handle),
+                    concat!("node is not a ", $role)
+                );
+                Self(handle)
+            }
+        }
+    };
+    ($name:ident, $role:literal, $is_kind:expr) => {
+        typed_handle!(@common $name);
+
+        impl $name {
+            pub fn new(graph: &Graph, handle: Handle<Node>) -> Self {
+                debug_assert!(
+                    graph.try_get(handle).is_some_and($is_kind),
+                    concat!("node is not a ", $role)
+                );
+                Self(handle)
+            }
+        }
+    };
+    (@common $name:ident) => {
+        #[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Hash, Visit, Reflect)]
+        pub struct $name(Handle<Node>);
+
+        impl $name {
+            pub const NONE: Self = Self(Handle::NONE);
+
+            pub fn handle(self) -> Handle<Node> {
+                self.0
+            }
+
+            pub fn is_some(self) -> bool {
+                self.0.is_some()
+            }
+
+            pub fn get(self, graph: &Graph) -> Option<&Node> {
+                graph.try_get(self.0)
+            }
+
+            pub fn get_mut(self, graph: &mut Graph) -> Option<&mut Node> {
+                graph.try_get_mut(self.0)
+            }
+        }
+
+        impl From<$name> for Handle<Node> {
+            fn from(handle: $name) -> Self {
+                handle.0
+            }
+        }
+    };
+}
+
+typed_handle!(PlayerHandle, "player", |node: &Node| node
+    .try_get_script::<Player>()
+    .is_some());
+typed_handle!(CameraHandle, "camera", |node: &Node| node
+    .cast::<Camera>()
+    .is_some());
+typed_handle!(EnemyHandle, "enemy", component EnemyAi);
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::components::EnemyState;
+    use fyrox::{
+        core::algebra::Vector3,
+        scene::{base::BaseBuilder, camera::CameraBuilder, pivot::PivotBuilder, Scene},
+    };
+
+    fn enemy(components: &mut ComponentStore, index: u32) -> Handle<Node> {
+        let handle = Handle::new(index, 1);
+        components.insert(
+            handle,
+            EnemyAi {
+                speed: 3.0,
+                aggro_radius: 6.0,
+                home: Vector3::default(),
+                state: EnemyState::Idle,
+            },
+        );
+        handle
+    }
+
+    #[test]
+    fn wrapped_handles_give_back_their_node() {
+        let mut components = ComponentStore::new();
+        let node = enemy(&mut components, 1);
+        let handle = EnemyHandle::new(&components, node);
+
+        assert!(handle.is_some());
+        assert_eq!(handle.handle(), node);
+        assert_eq!(Handle::<Node>::from(handle), node);
+        assert!(!EnemyHandle::NONE.is_some());
+        assert_eq!(EnemyHandle::default(), EnemyHandle::NONE);
+    }
+
+    #[test]
+    fn camera_handles_reach_the_camera_node() {
+        let mut scene = Scene::new();
+        let node = CameraBuilder::new(BaseBuilder::new()).build(&mut scene.graph);
+        let handle = CameraHandle::new(&scene.graph, node);
+
+        assert!(handle.get(&scene.graph).is_some());
+        assert!(handle.get_mut(&mut scene.graph).is_some());
+        assert!(CameraHandle::NONE.get(&scene.graph).is_none());
+    }
+
+    #[cfg(debug_assertions)]
+    #[test]
+    #[should_panic(expected = "node is not a enemy")]
+    fn debug_builds_refuse_nodes_without_the_role_component() {
+        let components = ComponentStore::new();
+        EnemyHandle::new(&components, Handle::new(1, 1));
+    }
+
+    #[cfg(debug_assertions)]
+    #[test]
+    #[should_panic(expected = "node is not a camera")]
+    fn debug_builds_refuse_nodes_of_another_type() {
+        let mut scene = Scene::new();
+        let node = PivotBuilder::new(BaseBuilder::new()).build(&mut scene.graph);
+        CameraHandle::new(&scene.graph, node);
+    }
+}
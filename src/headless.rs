@@ -0,0 +1,195 @@
+use crate::{
+    components::Health,
+    error::GameError,
+    game::{Game, LaunchOptions, FIXED_TIMESTEP},
+    input_script::{InputScript, InputStep},
+    level::{LevelConfig, Prefab},
+    mods::ItemDefinition,
+    movement::MoveInput,
+    player::move_player,
+    replay::{Replay, ReplayPlayer},
+    ui::inspector::InspectorEdit,
+};
+use fyrox::{
+    asset::manager::ResourceManager,
+    core::{
+        algebra::{Vector2, Vector3},
+        pool::Handle,
+        task::TaskPool,
+    },
+    gui::UserInterface,
+    scene::{node::Node, Scene},
+};
+use std::sync::Arc;
+
+// Plays a level without a window, renderer or engine loop, e.g. from integration
+// tests: hold some keys for a number of ticks, then look at where things ended up.
+// Ticks run the same systems as the game; the player is moved directly since there
+// is no script runtime to do it.
+pub struct HeadlessSim {
+    game: Game,
+    scene: Scene,
+    resource_manager: ResourceManager,
+    ui: UserInterface,
+    ticks: u64,
+}
+
+impl HeadlessSim {
+    pub fn new(config: LevelConfig) -> Result<Self, GameError> {
+        Self::with_options(LaunchOptions::default(), config)
+    }
+
+    pub fn with_options(options: LaunchOptions, config: LevelConfig) -> Result<Self, GameError> {
+        let resource_manager = ResourceManager::new(Arc::new(TaskPool::new()));
+        let mut game = Game::new(options);
+        let scene = game.build_level(&resource_manager, &config)?;
+
+        Ok(Self {
+            game,
+            scene,
+            resource_manager,
+            ui: UserInterface::new(Vector2::new(1024.0, 768.0)),
+            ticks: 0,
+        })
+    }
+
+    // Advances one fixed tick with `input` held
+    pub fn tick(&mut self, input: MoveInput) {
+        self.game.input_state_mut().set_move_input(input);
+        self.step();
+    }
+
+    pub fn run(&mut self, ticks: u64, input: MoveInput) {
+        for _ in 0..ticks {
+            self.tick(input);
+        }
+    }
+
+    // Feeds the script's keys and device events to the game as they come and ticks
+    // through its waits with whatever keys are held by then
+    pub fn play(&mut self, script: &InputScript) {
+        for step in script.steps() {
+            match step {
+                InputStep::Press(key) => self.game.handle_key(*key, true, &self.ui),
+                InputStep::Release(key) => self.game.handle_key(*key, false, &self.ui),
+                InputStep::Device(event) => self.game.handle_device_event(event),
+                InputStep::Wait(ticks) => {
+                    for _ in 0..*ticks {
+                        self.step();
+                    }
+                }
+            }
+        }
+    }
+
+    // Holds each tick's recorded input, the way the demo plays a replay back. Ticks
+    // held by a hitstop weren't recorded and don't use any.
+    pub fn play_replay(&mut self, replay: &Replay) {
+        let mut player = ReplayPlayer::new(replay);
+        loop {
+            if !self.game.in_hitstop() {
+                let Some((input, dash)) = player.next_tick() else {
+                    break;
+                };
+                let input_state = self.game.input_state_mut();
+                input_state.set_move_input(input);
+                if dash {
+                    input_state.request_dash();
+                }
+            }
+            self.step();
+        }
+    }
+
+    // One update of the game loop, which simulates a tick unless a hitstop holds it
+    fn step(&mut self) {
+        if !self.game.in_hitstop() {
+            self.game.begin_tick();
+
+            let player = self.game.player().handle();
+            move_player(
+                &mut self.game,
+                &mut self.scene.graph,
+                player,
+                FIXED_TIMESTEP,
+            );
+            self.game.simulate_level(
+                &mut self.scene,
+                &self.resource_manager,
+                &self.ui,
+                FIXED_TIMESTEP,
+            );
+        }
+        self.game.update_feedback(FIXED_TIMESTEP);
+
+        // Nothing reads the menu's messages here, don't let them pile up
+        while self.ui.poll_message().is_some() {}
+        self.ticks += 1;
+    }
+
+    pub fn ticks(&self) -> u64 {
+        self.ticks
+    }
+
+    // Places a prefab the way a level script's spawn_prefab does
+    pub fn spawn(
+        &mut self,
+        prefab: Prefab,
+        name: &str,
+        position: Vector3<f32>,
+    ) -> Result<Handle<Node>, GameError> {
+        self.game.spawn_prefab(
+            &mut self.scene,
+            &self.resource_manager,
+            &ItemDefinition::base(prefab),
+            name,
+            position,
+        )
+    }
+
+    // A console `spawn ITEM [DISTANCE]`
+    pub fn spawn_item(&mut self, name: &str, distance: f32) -> Result<String, String> {
+        self.game
+            .spawn_item(&mut self.scene, &self.resource_manager, name, distance)
+    }
+
+    // An edit typed into the entity inspector
+    pub fn edit(&mut self, edit: InspectorEdit) {
+        self.game.edit_entity(&mut self.scene, edit);
+    }
+
+    pub fn player_position(&self) -> Option<Vector3<f32>> {
+        self.game.player_position(&self.scene)
+    }
+
+    pub fn player_health(&self) -> Option<f32> {
+        self.game
+            .components()
+            .get::<Health>(self.game.player().handle())
+            .map(|health| health.current)
+    }
+
+    pub fn coins_collected(&self) -> u32 {
+        self.game.coins_collected()
+    }
+
+    pub fn level_finished(&self) -> bool {
+        self.game.level_finished()
+    }
+
+    pub fn game(&self) -> &Game {
+        &self.game
+    }
+
+    pub fn game_mut(&mut self) -> &mut Game {
+        &mut self.game
+    }
+
+    pub fn scene(&self) -> &Scene {
+        &self.scene
+    }
+
+    pub fn scene_mut(&mut self) -> &mut Scene {
+        &mut self.scene
+    }
+}
@@ -0,0 +1,168 @@
+use crate::{movement::MoveInput, spectator::SpectatorInput};
+use fyrox::{core::algebra::Vector3, event::DeviceEvent, keyboard::KeyCode};
+
+// Keys and mouse motion collected from OS events between frames
+#[derive(Default)]
+pub struct InputState {
+    pub move_forward: bool,
+    pub move_backward: bool,
+    pub move_left: bool,
+    pub move_right: bool,
+    pub move_up: bool,
+    pub move_down: bool,
+    pub mouse_delta: Vector3<f32>,
+    pub camera_yaw: f32,
+    pub camera_pitch: f32,
+    // Held modifier, from the window's ModifiersChanged events
+    pub alt: bool,
+    // Space was pressed since the player last moved
+    dash: bool,
+    // F was pressed since the last tick
+    interact: bool,
+}
+
+impl InputState {
+    // Updates the held movement keys, returns false for keys that don't move anything
+    pub fn handle_key(&mut self, key_code: KeyCode, is_pressed: bool) -> bool {
+        match key_code {
+            KeyCode::KeyW => self.move_forward = is_pressed,
+            KeyCode::KeyS => self.move_backward = is_pressed,
+            KeyCode::KeyA => self.move_left = is_pressed,
+            KeyCode::KeyD => self.move_right = is_pressed,
+            KeyCode::KeyE => self.move_up = is_pressed,
+            KeyCode::KeyQ => self.move_down = is_pressed,
+            KeyCode::Space => self.dash |= is_pressed,
+            KeyCode::KeyF => self.interact |= is_pressed,
+            _ => return false,
+        }
+        true
+    }
+
+    pub fn handle_device_event(&mut self, device_event: &DeviceEvent) {
+        if let DeviceEvent::MouseMotion { delta } = device_event {
+            self.mouse_delta.x += delta.0 as f32;
+            self.mouse_delta.y += delta.1 as f32;
+        }
+    }
+
+    // Let go of everything, e.g. when the keyboard is taken over by a text box
+    pub fn release_keys(&mut self) {
+        self.move_forward = false;
+        self.move_backward = false;
+        self.move_left = false;
+        self.move_right = false;
+        self.move_up = false;
+        self.move_down = false;
+        self.dash = false;
+        self.interact = false;
+    }
+
+    // Whether a dash was asked for, once per press
+    pub fn take_dash(&mut self) -> bool {
+        std::mem::take(&mut self.dash)
+    }
+
+    // Whether the player asked to use something, once per press
+    pub fn take_interact(&mut self) -> bool {
+        std::mem::take(&mut self.interact)
+    }
+
+    // Asks for a dash as if Space was pressed, for input that doesn't come from keys
+    pub fn request_dash(&mut self) {
+        self.dash = true;
+    }
+
+    // Holds exactly the keys of `input`, for driving the player without a keyboard
+    pub fn set_move_input(&mut self, input: MoveInput) {
+        self.move_forward = input.forward;
+        self.move_backward = input.backward;
+        self.move_left = input.left;
+        self.move_right = input.right;
+    }
+
+    pub fn move_input(&self) -> MoveInput {
+        MoveInput {
+            forward: self.move_forward,
+            backward: self.move_backward,
+            left: self.move_left,
+            right: self.move_right,
+        }
+    }
+
+    pub fn spectator_input(&self) -> SpectatorInput {
+        SpectatorInput {
+            forward: self.move_forward,
+            backward: self.move_backward,
+            left: self.move_left,
+            right: self.move_right,
+            up: self.move_up,
+            down: self.move_down,
+            mouse_delta: (self.mouse_delta.x, self.mouse_delta.y),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn movement_keys_are_held_until_released() {
+        let mut input = InputState::default();
+        assert!(input.handle_key(KeyCode::KeyW, true));
+        assert!(input.handle_key(KeyCode::KeyD, true));
+        assert_eq!(
+            input.move_input(),
+            MoveInput {
+                forward: true,
+                right: true,
+                ..Default::default()
+            }
+        );
+
+        input.handle_key(KeyCode::KeyW, false);
+        assert_eq!(
+            input.move_input(),
+            MoveInput {
+                right: true,
+                ..Default::default()
+            }
+        );
+    }
+
+    #[test]
+    fn other_keys_are_left_to_the_caller() {
+        let mut input = InputState::default();
+        assert!(!input.handle_key(KeyCode::KeyP, true));
+        assert_eq!(input.move_input(), MoveInput::default());
+    }
+
+    #[test]
+    fn release_keys_lets_go_of_everything() {
+        let mut input = InputState::default();
+        for key in [KeyCode::KeyW, KeyCode::KeyA, KeyCode::KeyE, KeyCode::KeyQ] {
+            input.handle_key(key, true);
+        }
+        input.release_keys();
+        assert_eq!(input.move_input(), MoveInput::default());
+        let spectator = input.spectator_input();
+        assert!(!spectator.up && !spectator.down);
+    }
+
+    #[test]
+    fn mouse_motion_accumulates() {
+        let mut input = InputState::default();
+        input.handle_device_event(&DeviceEvent::MouseMotion { delta: (2.0, -1.0) });
+        input.handle_device_event(&DeviceEvent::MouseMotion { delta: (3.0, 0.5) });
+        assert_eq!(input.mouse_delta, Vector3::new(5.0, -0.5, 0.0));
+    }
+
+    #[test]
+    fn a_dash_is_taken_once_per_press() {
+        let mut input = InputState::default();
+        input.handle_key(KeyCode::Space, true);
+        input.handle_key(KeyCode::Space, false);
+        assert!(input.take_dash());
+        assert!(!input.take_dash());
+    }
+}
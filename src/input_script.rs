@@ -0,0 +1,123 @@
+use crate::game::FIXED_TIMESTEP;
+use fyrox::{event::DeviceEvent, keyboard::KeyCode};
+
+// One step of scripted input
+#[derive(Clone, Debug, PartialEq)]
+pub enum InputStep {
+    Press(KeyCode),
+    Release(KeyCode),
+    Device(DeviceEvent),
+    // Fixed ticks that pass with whatever is held at the time
+    Wait(u64),
+}
+
+// A sequence of key presses, device events and waits, played into a HeadlessSim to
+// drive the game the way a player at the keyboard would:
+//
+//     InputScript::new().hold(KeyCode::KeyW, 2.0).wait(0.5)
+//
+// Keys go through Game::handle_key, so they do whatever they do in game, toggles and
+// the console included.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct InputScript {
+    steps: Vec<InputStep>,
+}
+
+impl InputScript {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn press(mut self, key: KeyCode) -> Self {
+        self.steps.push(InputStep::Press(key));
+        self
+    }
+
+    pub fn release(mut self, key: KeyCode) -> Self {
+        self.steps.push(InputStep::Release(key));
+        self
+    }
+
+    // Presses the key, lets `seconds` of ticks pass and releases it again
+    pub fn hold(self, key: KeyCode, seconds: f32) -> Self {
+        self.press(key).wait(seconds).release(key)
+    }
+
+    // Rounded to whole ticks
+    pub fn wait(self, seconds: f32) -> Self {
+        self.wait_ticks(seconds_to_ticks(seconds))
+    }
+
+    pub fn wait_ticks(mut self, ticks: u64) -> Self {
+        self.steps.push(InputStep::Wait(ticks));
+        self
+    }
+
+    pub fn device(mut self, event: DeviceEvent) -> Self {
+        self.steps.push(InputStep::Device(event));
+        self
+    }
+
+    pub fn mouse_motion(self, dx: f64, dy: f64) -> Self {
+        self.device(DeviceEvent::MouseMotion { delta: (dx, dy) })
+    }
+
+    pub fn steps(&self) -> &[InputStep] {
+        &self.steps
+    }
+
+    // How many ticks playing the whole script takes
+    pub fn ticks(&self) -> u64 {
+        self.steps
+            .iter()
+            .map(|step| match step {
+                InputStep::Wait(ticks) => *ticks,
+                _ => 0,
+            })
+            .sum()
+    }
+}
+
+// Hands out a script's steps one tick at a time, for input fed into the running game
+// (the benchmark) instead of a HeadlessSim
+#[derive(Clone, Debug)]
+pub struct ScriptPlayer {
+    steps: Vec<InputStep>,
+    next_step: usize,
+    // Ticks still to wait before the next step
+    waiting: u64,
+}
+
+impl ScriptPlayer {
+    pub fn new(script: &InputScript) -> Self {
+        Self {
+            steps: script.steps().to_vec(),
+            next_step: 0,
+            waiting: 0,
+        }
+    }
+
+    // Key presses and device events to feed the game before the coming tick
+    pub fn next_tick(&mut self) -> Vec<InputStep> {
+        let mut input = Vec::new();
+        while self.waiting == 0 {
+            match self.steps.get(self.next_step) {
+                Some(InputStep::Wait(ticks)) => self.waiting = *ticks,
+                Some(step) => input.push(step.clone()),
+                None => break,
+            }
+            self.next_step += 1;
+        }
+        self.waiting = self.waiting.saturating_sub(1);
+        input
+    }
+
+    // Every step was played and the last wait is over
+    pub fn is_finished(&self) -> bool {
+        self.waiting == 0 && self.next_step >= self.steps.len()
+    }
+}
+
+pub fn seconds_to_ticks(seconds: f32) -> u64 {
+    (seconds.max(0.0) / FIXED_TIMESTEP).round() as u64
+}
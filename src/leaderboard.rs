@@ -0,0 +1,327 @@
+use crate::save_file;
+use log::warn;
+use serde::{Deserialize, Serialize};
+use std::{
+    fs, io,
+    path::PathBuf,
+    sync::mpsc::{self, Receiver, Sender},
+    thread,
+    time::Duration,
+};
+
+// Number of rows fetched for the global ranking
+const GLOBAL_LIMIT: usize = 10;
+
+// Requests that take longer than this count as offline
+const REQUEST_TIMEOUT: Duration = Duration::from_secs(5);
+
+// File inside the data directory holding submissions that couldn't be sent yet
+const QUEUE_FILE: &str = "leaderboard_queue.json";
+
+#[derive(Clone, Debug)]
+pub struct LeaderboardConfig {
+    // Base URL of the leaderboard service, e.g. https://scores.example.com/api
+    pub endpoint: String,
+    // Players shown in the friends ranking next to ourselves
+    pub friends: Vec<String>,
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct ScoreSubmission {
+    pub level: String,
+    pub player: String,
+    pub time_ms: u64,
+    pub score: u32,
+}
+
+#[derive(Clone, Debug, Deserialize)]
+pub struct LeaderboardEntry {
+    pub rank: u32,
+    pub player: String,
+    pub time_ms: u64,
+    pub score: u32,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum RankingScope {
+    Global,
+    Friends,
+}
+
+#[derive(Clone, Debug)]
+pub enum LeaderboardEvent {
+    Submitted,
+    // The service couldn't be reached, the submission is kept for later
+    Queued,
+    // The service turned the submission down, sending it again wouldn't help
+    Rejected,
+    Rankings {
+        level: String,
+        scope: RankingScope,
+        entries: Vec<LeaderboardEntry>,
+    },
+    RankingsUnavailable {
+        level: String,
+        scope: RankingScope,
+    },
+    // Queued scores failed their checksum and were thrown away instead of submitted
+    QueueRejected,
+}
+
+enum Request {
+    Submit(ScoreSubmission),
+    Fetch { level: String, player: String },
+}
+
+// Talks to the leaderboard service on a worker thread so slow or missing
+// connectivity never stalls a frame. Results are picked up with poll().
+pub struct LeaderboardClient {
+    requests: Sender<Request>,
+    events: Receiver<LeaderboardEvent>,
+}
+
+impl LeaderboardClient {
+    pub fn new(config: LeaderboardConfig) -> Self {
+        let (request_sender, request_receiver) = mpsc::channel();
+        let (event_sender, event_receiver) = mpsc::channel();
+
+        thread::spawn(move || {
+            let worker = Worker::new(config, event_sender);
+            // Anything left over from an earlier offline session goes out first
+            worker.flush_queue();
+            for request in request_receiver {
+                worker.handle(request);
+            }
+        });
+
+        Self {
+            requests: request_sender,
+            events: event_receiver,
+        }
+    }
+
+    pub fn submit(&self, submission: ScoreSubmission) {
+        let _ = self.requests.send(Request::Submit(submission));
+    }
+
+    // Asks for both the global and the friends ranking of a level
+    pub fn fetch_rankings(&self, level: &str, player: &str) {
+        let _ = self.requests.send(Request::Fetch {
+            level: level.to_string(),
+            player: player.to_string(),
+        });
+    }
+
+    pub fn poll(&self) -> Vec<LeaderboardEvent> {
+        self.events.try_iter().collect()
+    }
+}
+
+// Score for finishing a level, 1000 at par time and more for beating it
+pub fn score_for_time(time: f32, par_time: f32) -> u32 {
+    (1000.0 * par_time / time.max(0.1)).round() as u32
+}
+
+pub fn format_time(time_ms: u64) -> String {
+    format!(
+        "{}:{:02}.{:03}",
+        time_ms / 60_000,
+        time_ms / 1000 % 60,
+        time_ms % 1000
+    )
+}
+
+struct Worker {
+    config: LeaderboardConfig,
+    agent: ureq::Agent,
+    events: Sender<LeaderboardEvent>,
+    queue_path: PathBuf,
+}
+
+impl Worker {
+    fn new(config: LeaderboardConfig, events: Sender<LeaderboardEvent>) -> Self {
+        let agent = ureq::AgentBuilder::new().timeout(REQUEST_TIMEOUT).build();
+
+        Self {
+            config,
+            agent,
+            events,
+            queue_path: crate::paths::data_dir().join(QUEUE_FILE),
+        }
+    }
+
+    fn handle(&self, request: Request) {
+        match request {
+            Request::Submit(submission) => {
+                let event = match self.post_score(&submission) {
+                    Ok(()) => {
+                        // We're online again, catch up on older runs as well
+                        self.flush_queue();
+                        LeaderboardEvent::Submitted
+                    }
+                    Err(err) if is_temporary(&err) => {
+                        warn!("Score submission queued for later: {err}");
+                        self.enqueue(submission);
+                        LeaderboardEvent::Queued
+                    }
+                    Err(err) => {
+                        warn!("Score submission rejected: {err}");
+                        LeaderboardEvent::Rejected
+                    }
+                };
+                let _ = self.events.send(event);
+            }
+            Request::Fetch { level, player } => {
+                let mut players = self.config.friends.clone();
+                players.push(player);
+
+                let global = self.get_scores(&level, &[("limit", GLOBAL_LIMIT.to_string())]);
+                let friends = self.get_scores(&level, &[("players", players.join(","))]);
+
+                for (scope, result) in [
+                    (RankingScope::Global, global),
+                    (RankingScope::Friends, friends),
+                ] {
+                    let event = match result {
+                        Ok(entries) => LeaderboardEvent::Rankings {
+                            level: level.clone(),
+                            scope,
+                            entries,
+                        },
+                        Err(err) => {
+                            warn!("Rankings for {level} unavailable: {err}");
+                            LeaderboardEvent::RankingsUnavailable {
+                                level: level.clone(),
+                                scope,
+                            }
+                        }
+                    };
+                    let _ = self.events.send(event);
+                }
+            }
+        }
+    }
+
+    fn scores_url(&self, level: &str) -> String {
+        format!(
+            "{}/levels/{}/scores",
+            self.config.endpoint.trim_end_matches('/'),
+            encode_path_segment(level)
+        )
+    }
+
+    fn post_score(&self, submission: &ScoreSubmission) -> Result<(), Box<ureq::Error>> {
+        self.agent
+            .post(&self.scores_url(&submission.level))
+            .send_json(submission)?;
+        Ok(())
+    }
+
+    fn get_scores(
+        &self,
+        level: &str,
+        query: &[(&str, String)],
+    ) -> Result<Vec<LeaderboardEntry>, Box<ureq::Error>> {
+        let mut request = self.agent.get(&self.scores_url(level));
+        for (key, value) in query {
+            request = request.query(key, value);
+        }
+        let entries = request.call()?.into_json().map_err(ureq::Error::from)?;
+        Ok(entries)
+    }
+
+    fn load_queue(&self) -> Vec<ScoreSubmission> {
+        match save_file::read(&self.queue_path) {
+            Ok(queue) => queue,
+            Err(err) if err.is_missing() => Vec::new(),
+            // Nobody gets to post scores by editing the queue
+            Err(err) => {
+                warn!("Discarding the score queue: {err}");
+                save_file::reject(&self.queue_path);
+                let _ = self.events.send(LeaderboardEvent::QueueRejected);
+                Vec::new()
+            }
+        }
+    }
+
+    fn store_queue(&self, queue: &[ScoreSubmission]) -> io::Result<()> {
+        if queue.is_empty() {
+            return match fs::remove_file(&self.queue_path) {
+                Err(err) if err.kind() != io::ErrorKind::NotFound => Err(err),
+                _ => Ok(()),
+            };
+        }
+        save_file::write(&self.queue_path, queue)
+    }
+
+    fn enqueue(&self, submission: ScoreSubmission) {
+        let mut queue = self.load_queue();
+        queue.push(submission);
+        if let Err(err) = self.store_queue(&queue) {
+            warn!("Couldn't save the score queue: {err}");
+        }
+    }
+
+    // Sends queued submissions in order and keeps whatever still fails for a reason
+    // that may go away. Submissions the service turns down are dropped.
+    fn flush_queue(&self) {
+        let queue = self.load_queue();
+        if queue.is_empty() {
+            return;
+        }
+
+        let mut remaining = Vec::new();
+        for submission in queue {
+            if !remaining.is_empty() {
+                remaining.push(submission);
+                continue;
+            }
+            match self.post_score(&submission) {
+                Ok(()) => {}
+                Err(err) if is_temporary(&err) => remaining.push(submission),
+                Err(err) => warn!("Dropping queued score for {}: {err}", submission.level),
+            }
+        }
+        if let Err(err) = self.store_queue(&remaining) {
+            warn!("Couldn't save the score queue: {err}");
+        }
+    }
+}
+
+// Connection problems and server errors may pass, a 4xx answer means the request itself
+// is wrong
+fn is_temporary(err: &ureq::Error) -> bool {
+    match err {
+        ureq::Error::Transport(_) => true,
+        ureq::Error::Status(status, _) => *status >= 500,
+    }
+}
+
+// Level ids come from level files and mods, they may contain anything
+fn encode_path_segment(segment: &str) -> String {
+    let mut encoded = String::with_capacity(segment.len());
+    for byte in segment.bytes() {
+        match byte {
+            b'A'..=b'Z' | b'a'..=b'z' | b'0'..=b'9' | b'-' | b'.' | b'_' | b'~' => {
+                encoded.push(byte as char)
+            }
+            _ => encoded.push_str(&format!("%{byte:02X}")),
+        }
+    }
+    encoded
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn level_ids_are_encoded_in_urls() {
+        assert_eq!(encode_path_segment("meadow_01"), "meadow_01");
+        assert_eq!(
+            encode_path_segment("my mod/level?1"),
+            "my%20mod%2Flevel%3F1"
+        );
+        assert_eq!(encode_path_segment("wiese-ü"), "wiese-%C3%BC");
+    }
+}
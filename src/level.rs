@@ -0,0 +1,370 @@
+use crate::{
+    error::GameError, materials::MaterialLibrary, mods, palette::ColorRole,
+    player::create_cube_surface,
+};
+use fyrox::{
+    asset::manager::ResourceManager,
+    core::{
+        algebra::{UnitQuaternion, Vector3},
+        color::Color,
+        math::Matrix4,
+        pool::Handle,
+    },
+    scene::{
+        base::BaseBuilder,
+        light::{directional::DirectionalLightBuilder, BaseLightBuilder},
+        mesh::{
+            surface::{Surface, SurfaceBuilder, SurfaceData},
+            MeshBuilder, RenderPath,
+        },
+        node::Node,
+        transform::TransformBuilder,
+        Scene,
+    },
+};
+
+// How close (horizontally) the player has to get to the goal pad
+pub const GOAL_RADIUS: f32 = 1.0;
+
+// Name of the goal pad node, the game attaches its trigger by looking this up
+pub const GOAL_NODE_NAME: &str = "Goal";
+
+// Static description of a playable level
+pub struct LevelDefinition {
+    pub id: &'static str,
+    pub name: &'static str,
+    // Half-extent of the square ground plane
+    pub ground_size: f32,
+    pub obstacles: &'static [[f32; 3]],
+    // Named player spawn points, players are spread over them in order
+    pub spawn_points: &'static [(&'static str, [f32; 3])],
+    // Reaching this pad finishes the level
+    pub goal: [f32; 3],
+    // Seconds a decent run takes, used for scoring
+    pub par_time: f32,
+}
+
+impl LevelDefinition {
+    pub fn spawn_point(&self, index: usize) -> Vector3<f32> {
+        match self
+            .spawn_points
+            .get(index % self.spawn_points.len().max(1))
+        {
+            Some((_, [x, y, z])) => Vector3::new(*x, *y, *z),
+            None => Vector3::new(0.0, 1.0, 0.0),
+        }
+    }
+
+    pub fn find_spawn_point(&self, name: &str) -> Option<Vector3<f32>> {
+        self.spawn_points
+            .iter()
+            .find(|(spawn_name, _)| *spawn_name == name)
+            .map(|(_, [x, y, z])| Vector3::new(*x, *y, *z))
+    }
+}
+
+pub static LEVELS: &[LevelDefinition] = &[
+    LevelDefinition {
+        id: "meadow",
+        name: "Meadow",
+        ground_size: 20.0,
+        obstacles: &[
+            [3.0, 0.5, 2.0],
+            [-2.0, 0.5, -3.0],
+            [5.0, 0.5, -1.0],
+            [-4.0, 0.5, 4.0],
+            [1.0, 0.5, -5.0],
+        ],
+        spawn_points: &[
+            ("center", [0.0, 1.0, 0.0]),
+            ("east", [6.0, 1.0, 3.0]),
+            ("west", [-6.0, 1.0, 3.0]),
+            ("north", [0.0, 1.0, -7.0]),
+        ],
+        goal: [8.0, 0.05, -8.0],
+        par_time: 4.0,
+    },
+    LevelDefinition {
+        id: "courtyard",
+        name: "Courtyard",
+        ground_size: 15.0,
+        obstacles: &[
+            [-6.0, 0.5, -6.0],
+            [-6.0, 0.5, 6.0],
+            [6.0, 0.5, -6.0],
+            [6.0, 0.5, 6.0],
+            [0.0, 0.5, -3.0],
+            [0.0, 0.5, 3.0],
+            [-3.0, 0.5, 0.0],
+            [3.0, 0.5, 0.0],
+        ],
+        spawn_points: &[
+            ("south", [0.0, 1.0, 10.0]),
+            ("north", [0.0, 1.0, -10.0]),
+            ("east", [10.0, 1.0, 0.0]),
+            ("west", [-10.0, 1.0, 0.0]),
+        ],
+        goal: [0.0, 0.05, 0.0],
+        par_time: 3.0,
+    },
+];
+
+pub fn default_level() -> &'static LevelDefinition {
+    &LEVELS[0]
+}
+
+// Mods can replace built-in levels, so this is the lookup to use
+pub fn find_level(id: &str) -> Option<&'static LevelDefinition> {
+    mods::level(id).or_else(|| find_builtin_level(id))
+}
+
+pub fn find_builtin_level(id: &str) -> Option<&'static LevelDefinition> {
+    LEVELS.iter().find(|level| level.id == id)
+}
+
+// Built-in levels (as replaced by mods) followed by the ones mods add
+pub fn all_levels() -> Vec<&'static LevelDefinition> {
+    let mut levels: Vec<_> = LEVELS
+        .iter()
+        .map(|level| mods::level(level.id).unwrap_or(level))
+        .collect();
+    levels.extend(mods::extra_levels());
+    levels
+}
+
+// Level after (or before, for a negative step) the given one, wrapping around
+pub fn cycle_level(id: &str, step: isize) -> &'static LevelDefinition {
+    let levels = all_levels();
+    let index = levels.iter().position(|level| level.id == id).unwrap_or(0) as isize;
+    let count = levels.len() as isize;
+    levels[(index + step).rem_euclid(count) as usize]
+}
+
+// Everything the level loader needs to know to start a round
+#[derive(Clone, Debug)]
+pub struct LevelConfig {
+    pub level_id: String,
+    // Which of the level's spawn points the local player starts at
+    pub spawn_index: usize,
+    // Watch with a free camera instead of spawning a player
+    pub spectator: bool,
+    // What the level script is seeded with, a fresh seed from the game's generator if none
+    pub script_seed: Option<u64>,
+}
+
+impl Default for LevelConfig {
+    fn default() -> Self {
+        Self {
+            level_id: default_level().id.to_string(),
+            spawn_index: 0,
+            spectator: false,
+            script_seed: None,
+        }
+    }
+}
+
+// Level geometry uses the shared materials of the library, so edits to them show up live
+pub fn create_scene(materials: &MaterialLibrary, level: &LevelDefinition) -> Scene {
+    let mut scene = Scene::new();
+
+    // Add lighting
+    DirectionalLightBuilder::new(BaseLightBuilder::new(
+        BaseBuilder::new().with_local_transform(
+            TransformBuilder::new()
+                .with_local_position(Vector3::new(0.0, 6.0, 0.0))
+                .with_local_rotation(UnitQuaternion::from_axis_angle(
+                    &Vector3::x_axis(),
+                    -45.0f32.to_radians(),
+                ))
+                .build(),
+        ),
+    ))
+    .with_color(Color::opaque(255, 255, 255))
+    .build(&mut scene.graph);
+
+    // Create ground plane
+    create_ground_plane(&mut scene, materials, level.ground_size);
+
+    // Create some obstacles/decorations
+    create_obstacles(&mut scene, materials, level.obstacles);
+
+    // Mark the finish
+    create_goal(&mut scene, materials, level.goal);
+
+    scene
+}
+
+fn create_ground_plane(scene: &mut Scene, materials: &MaterialLibrary, size: f32) {
+    // Create large plane for ground
+    let surface_data = SurfaceData::make_plane(Matrix4::identity());
+    let surface = SurfaceBuilder::new(surface_data)
+        .with_material(materials.ground())
+        .build();
+
+    MeshBuilder::new(
+        BaseBuilder::new().with_local_transform(
+            TransformBuilder::new()
+                .with_local_position(Vector3::new(0.0, 0.0, 0.0))
+                .with_local_scale(Vector3::new(size, 1.0, size))
+                .build(),
+        ),
+    )
+    .with_surfaces(vec![surface])
+    .with_render_path(RenderPath::Forward)
+    .build(&mut scene.graph);
+}
+
+fn create_goal(scene: &mut Scene, materials: &MaterialLibrary, [x, y, z]: [f32; 3]) {
+    // Flat pad, as wide as the goal radius
+    let surface_data = SurfaceData::make_cube(Matrix4::identity());
+    let surface = SurfaceBuilder::new(surface_data)
+        .with_material(materials.goal())
+        .build();
+
+    MeshBuilder::new(
+        BaseBuilder::new()
+            .with_name(GOAL_NODE_NAME)
+            .with_local_transform(
+                TransformBuilder::new()
+                    .with_local_position(Vector3::new(x, y, z))
+                    .with_local_scale(Vector3::new(GOAL_RADIUS * 2.0, 0.1, GOAL_RADIUS * 2.0))
+                    .build(),
+            ),
+    )
+    .with_surfaces(vec![surface])
+    .with_render_path(RenderPath::Forward)
+    .build(&mut scene.graph);
+}
+
+fn create_obstacles(scene: &mut Scene, materials: &MaterialLibrary, positions: &[[f32; 3]]) {
+    // Create a cube obstacle at every position
+    for [x, y, z] in positions.iter().copied() {
+        let surface_data = SurfaceData::make_cube(Matrix4::identity());
+        let surface = SurfaceBuilder::new(surface_data)
+            .with_material(materials.obstacle())
+            .build();
+
+        MeshBuilder::new(
+            BaseBuilder::new().with_local_transform(
+                TransformBuilder::new()
+                    .with_local_position(Vector3::new(x, y, z))
+                    .with_local_scale(Vector3::new(1.0, 1.0, 1.0))
+                    .build(),
+            ),
+        )
+        .with_surfaces(vec![surface])
+        .with_render_path(RenderPath::Forward)
+        .build(&mut scene.graph);
+    }
+}
+
+// Things level scripts can place at runtime
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum Prefab {
+    Coin,
+    HealthPack,
+    Enemy,
+    Crate,
+}
+
+impl Prefab {
+    pub fn from_name(name: &str) -> Option<Self> {
+        match name {
+            "coin" => Some(Prefab::Coin),
+            "health" => Some(Prefab::HealthPack),
+            "enemy" => Some(Prefab::Enemy),
+            "crate" => Some(Prefab::Crate),
+            _ => None,
+        }
+    }
+
+    fn color_role(self) -> ColorRole {
+        match self {
+            Prefab::Coin => ColorRole::Coin,
+            Prefab::HealthPack => ColorRole::HealthPack,
+            Prefab::Enemy => ColorRole::Enemy,
+            Prefab::Crate => ColorRole::Crate,
+        }
+    }
+
+    fn scale(self) -> Vector3<f32> {
+        match self {
+            Prefab::Coin => Vector3::new(0.3, 0.3, 0.3),
+            Prefab::HealthPack => Vector3::new(0.4, 0.4, 0.4),
+            Prefab::Enemy => Vector3::new(0.5, 1.0, 0.5),
+            Prefab::Crate => Vector3::new(1.0, 1.0, 1.0),
+        }
+    }
+}
+
+// Every instance of a prefab can share one surface, and with it one material
+pub fn create_prefab_surface(
+    resource_manager: &ResourceManager,
+    prefab: Prefab,
+) -> Result<Surface, GameError> {
+    create_cube_surface(resource_manager, prefab.color_role())
+}
+
+// Colored cube standing in for a prefab, the game attaches its gameplay components
+pub fn create_prefab(
+    scene: &mut Scene,
+    surface: Surface,
+    prefab: Prefab,
+    name: &str,
+    position: Vector3<f32>,
+) -> Handle<Node> {
+    MeshBuilder::new(
+        BaseBuilder::new().with_name(name).with_local_transform(
+            TransformBuilder::new()
+                .with_local_position(position)
+                .with_local_scale(prefab.scale())
+                .build(),
+        ),
+    )
+    .with_surfaces(vec![surface])
+    .with_render_path(RenderPath::Forward)
+    .build(&mut scene.graph)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn built_in_levels_are_found_by_id() {
+        assert_eq!(
+            find_level("courtyard").map(|level| level.name),
+            Some("Courtyard")
+        );
+        assert!(find_level("no-such-level").is_none());
+        assert_eq!(default_level().id, LEVELS[0].id);
+    }
+
+    #[test]
+    fn cycling_wraps_around() {
+        let last = LEVELS[LEVELS.len() - 1].id;
+        assert_eq!(cycle_level(last, 1).id, LEVELS[0].id);
+        assert_eq!(cycle_level(LEVELS[0].id, -1).id, last);
+        // Unknown ids count as the first level
+        assert_eq!(cycle_level("no-such-level", 1).id, LEVELS[1].id);
+    }
+
+    #[test]
+    fn spawn_points_wrap_and_are_found_by_name() {
+        let level = find_builtin_level("meadow").unwrap();
+        let count = level.spawn_points.len();
+        assert_eq!(level.spawn_point(count + 1), level.spawn_point(1));
+        assert_eq!(
+            level.find_spawn_point("east"),
+            Some(Vector3::new(6.0, 1.0, 3.0))
+        );
+        assert_eq!(level.find_spawn_point("nowhere"), None);
+    }
+
+    #[test]
+    fn prefab_names() {
+        assert_eq!(Prefab::from_name("coin"), Some(Prefab::Coin));
+        assert_eq!(Prefab::from_name("enemy"), Some(Prefab::Enemy));
+        assert_eq!(Prefab::from_name("dragon"), None);
+    }
+}
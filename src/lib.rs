@@ -0,0 +1,61 @@
+// Game library: everything except the executable's entry point, so the pieces can
+// be reused by other binaries (the dedicated server, tools) and tested in isolation.
+// Android's entry point is the exception, an APK loads a library and not a binary.
+
+#[cfg(target_os = "android")]
+pub mod android;
+pub mod assets;
+pub mod benchmark;
+pub mod camera;
+pub mod cheats;
+pub mod clip;
+pub mod comfort;
+pub mod components;
+pub mod console;
+pub mod crash;
+pub mod debug_draw;
+pub mod dedicated;
+pub mod demo;
+pub mod determinism;
+#[cfg(feature = "discord")]
+pub mod discord;
+pub mod display;
+pub mod error;
+pub mod events;
+pub mod feedback;
+pub mod frame_step;
+pub mod game;
+pub mod handles;
+pub mod headless;
+pub mod input;
+pub mod input_script;
+pub mod leaderboard;
+pub mod level;
+pub mod localization;
+pub mod logging;
+pub mod materials;
+pub mod mods;
+pub mod movement;
+pub mod net;
+pub mod palette;
+pub mod paths;
+pub mod player;
+pub mod plugins;
+pub mod pooling;
+pub mod presence;
+pub mod profiler;
+pub mod replay;
+pub mod rumble;
+pub mod save_file;
+pub mod scripting;
+pub mod spatial;
+pub mod spectator;
+pub mod stats;
+#[cfg(feature = "steam")]
+pub mod steam;
+pub mod systems;
+pub mod telemetry;
+pub mod touch;
+pub mod tuning;
+pub mod ui;
+pub mod window;
@@ -0,0 +1,184 @@
+use crate::{console::CommandRegistry, game::Game, mods};
+use fyrox::plugin::PluginContext;
+use log::warn;
+use std::{
+    collections::{BTreeMap, BTreeSet},
+    fmt::Display,
+    fs,
+    path::{Path, PathBuf},
+    sync::{Mutex, OnceLock, RwLock},
+};
+
+pub const DEFAULT_LANGUAGE: &str = "en";
+pub const DEFAULT_LANG_DIR: &str = "assets/lang";
+
+// Every table names its own language under this key, e.g. "Deutsch"
+pub const LANGUAGE_NAME_KEY: &str = "language.name";
+
+// English is built in, so there is always something to fall back to, even without
+// the assets folder (headless runs, tests)
+const BUILT_IN_ENGLISH: &str = include_str!("../assets/lang/en.json");
+
+type StringTable = BTreeMap<String, String>;
+
+// The language in use and where string tables are looked for
+struct Strings {
+    language: String,
+    // The game's folder first, then every mod's lang folder, later ones win
+    dirs: Vec<PathBuf>,
+    current: StringTable,
+    // en.json files on disk, over the built-in table
+    english: StringTable,
+}
+
+static STRINGS: RwLock<Strings> = RwLock::new(Strings {
+    language: String::new(),
+    dirs: Vec::new(),
+    current: BTreeMap::new(),
+    english: BTreeMap::new(),
+});
+
+// Keys that were asked for but exist nowhere, each one is only warned about once
+static MISSING: Mutex<BTreeSet<String>> = Mutex::new(BTreeSet::new());
+
+fn built_in_english() -> &'static StringTable {
+    static TABLE: OnceLock<StringTable> = OnceLock::new();
+    TABLE.get_or_init(|| serde_json::from_str(BUILT_IN_ENGLISH).unwrap_or_default())
+}
+
+// Sets up the folders string tables come from and switches to `language`, staying
+// in English if it has no strings. Mods have to be installed before this.
+pub fn init(dir: &Path, language: &str) {
+    let mut dirs = vec![dir.to_path_buf()];
+    dirs.extend(mods::lang_dirs());
+    {
+        let mut strings = STRINGS.write().unwrap();
+        strings.english = load_table(&dirs, DEFAULT_LANGUAGE).unwrap_or_default();
+        strings.dirs = dirs;
+    }
+    if let Err(err) = set_language(language) {
+        warn!("Staying in English: {err}");
+    }
+}
+
+pub fn language() -> String {
+    match STRINGS.read().unwrap().language.as_str() {
+        "" => DEFAULT_LANGUAGE.to_string(),
+        language => language.to_string(),
+    }
+}
+
+pub fn set_language(code: &str) -> Result<(), String> {
+    // Codes become file names, nothing else gets through
+    if code.is_empty()
+        || !code
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || c == '-' || c == '_')
+    {
+        return Err(format!("{code:?} isn't a language code"));
+    }
+    let mut strings = STRINGS.write().unwrap();
+    let current = if code == DEFAULT_LANGUAGE {
+        StringTable::new()
+    } else {
+        load_table(&strings.dirs, code).ok_or_else(|| format!("no strings for {code:?}"))?
+    };
+    strings.current = current;
+    strings.language = code.to_string();
+    MISSING.lock().unwrap().clear();
+    Ok(())
+}
+
+// Codes of every language with a string table, English included, in code order
+pub fn languages() -> Vec<String> {
+    let strings = STRINGS.read().unwrap();
+    let mut codes = BTreeSet::from([DEFAULT_LANGUAGE.to_string()]);
+    for dir in &strings.dirs {
+        codes.extend(
+            fs::read_dir(dir)
+                .into_iter()
+                .flatten()
+                .filter_map(|entry| entry.ok())
+                .map(|entry| entry.path())
+                .filter(|path| path.extension().is_some_and(|ext| ext == "json"))
+                .filter_map(|path| Some(path.file_stem()?.to_string_lossy().into_owned())),
+        );
+    }
+    codes.into_iter().collect()
+}
+
+// The text for `key` in the current language, in English if it has none, or the
+// key itself if even English doesn't know it
+pub fn tr(key: &str) -> String {
+    lookup(key).unwrap_or_else(|| {
+        if MISSING.lock().unwrap().insert(key.to_string()) {
+            warn!("No text for {key:?}");
+        }
+        key.to_string()
+    })
+}
+
+// Like tr, for text that may or may not be a key, e.g. dialogue lines and level
+// names from mods. Whatever isn't found is used as it is, without a warning.
+pub fn tr_or(key: &str, fallback: &str) -> String {
+    lookup(key).unwrap_or_else(|| fallback.to_string())
+}
+
+// tr with {name} placeholders filled in
+pub fn tr_with(key: &str, args: &[(&str, &dyn Display)]) -> String {
+    let mut text = tr(key);
+    for (name, value) in args {
+        text = text.replace(&format!("{{{name}}}"), &value.to_string());
+    }
+    text
+}
+
+fn lookup(key: &str) -> Option<String> {
+    let strings = STRINGS.read().unwrap();
+    strings
+        .current
+        .get(key)
+        .or_else(|| strings.english.get(key))
+        .or_else(|| built_in_english().get(key))
+        .cloned()
+}
+
+// `code`.json from every folder merged, None if there is none anywhere
+fn load_table(dirs: &[PathBuf], code: &str) -> Option<StringTable> {
+    let mut table: Option<StringTable> = None;
+    for path in dirs.iter().map(|dir| dir.join(format!("{code}.json"))) {
+        let Ok(text) = fs::read_to_string(&path) else {
+            continue;
+        };
+        match serde_json::from_str::<StringTable>(&text) {
+            Ok(strings) => table.get_or_insert_with(StringTable::new).extend(strings),
+            Err(err) => warn!("Skipping {}: {err}", path.display()),
+        }
+    }
+    table
+}
+
+pub fn register_commands(commands: &mut CommandRegistry) {
+    commands.register(
+        "language",
+        "[CODE]",
+        "show or switch the language",
+        command_language,
+    );
+    commands.complete_with("language", languages);
+}
+
+fn command_language(
+    game: &mut Game,
+    context: &mut PluginContext,
+    args: &[&str],
+) -> Result<String, String> {
+    match args {
+        [] => Ok(format!("{} ({})", tr(LANGUAGE_NAME_KEY), language())),
+        [code] => {
+            game.set_language(context.user_interfaces.first(), code)?;
+            Ok(format!("switched to {}", tr(LANGUAGE_NAME_KEY)))
+        }
+        _ => Err("usage: language [CODE]".to_string()),
+    }
+}
@@ -0,0 +1,308 @@
+use crate::{console::CommandRegistry, game::Game, paths::data_dir};
+use fyrox::{core::instant::Instant, plugin::PluginContext};
+use log::{Level, LevelFilter, Log, Metadata, Record};
+use std::{
+    collections::VecDeque,
+    fs::{self, File, OpenOptions},
+    io::{self, Write},
+    path::{Path, PathBuf},
+    sync::Mutex,
+};
+
+// A log file is moved aside once it grows past this, and only a few old ones are kept
+const MAX_FILE_SIZE: u64 = 1024 * 1024;
+const KEPT_FILES: usize = 4;
+const LOG_FILE: &str = "game.log";
+
+// Warnings waiting to be shown in game, dropped past this so a noisy loop can't pile them up
+const MAX_MIRRORED: usize = 100;
+
+// Recent lines kept in memory for crash reports
+const TAIL_LINES: usize = 200;
+
+// Logs go into the data directory, next to the leaderboard queue and other saves
+pub fn default_log_dir() -> PathBuf {
+    data_dir().join("logs")
+}
+
+// Levels per module, parsed from a spec like "info,simple_fyrox_game::net=debug".
+// The most specific matching target wins, everything else uses the default.
+#[derive(Clone, Debug)]
+pub struct LogFilter {
+    default: LevelFilter,
+    targets: Vec<(String, LevelFilter)>,
+}
+
+impl Default for LogFilter {
+    fn default() -> Self {
+        Self {
+            default: LevelFilter::Info,
+            targets: Vec::new(),
+        }
+    }
+}
+
+impl LogFilter {
+    pub fn parse(spec: &str) -> Result<Self, String> {
+        let mut filter = Self::default();
+        for part in spec
+            .split(',')
+            .map(str::trim)
+            .filter(|part| !part.is_empty())
+        {
+            match part.split_once('=') {
+                Some((target, level)) => {
+                    let level = level
+                        .parse()
+                        .map_err(|_| format!("unknown log level {level:?}"))?;
+                    filter.targets.push((target.to_string(), level));
+                }
+                None => {
+                    filter.default = part
+                        .parse()
+                        .map_err(|_| format!("unknown log level {part:?}"))?;
+                }
+            }
+        }
+        // Longest first, so the first match is the most specific one
+        filter.targets.sort_by(|a, b| b.0.len().cmp(&a.0.len()));
+        Ok(filter)
+    }
+
+    pub fn level_for(&self, target: &str) -> LevelFilter {
+        self.targets
+            .iter()
+            .find(|(prefix, _)| {
+                target == prefix
+                    || target
+                        .strip_prefix(prefix.as_str())
+                        .is_some_and(|rest| rest.starts_with("::"))
+            })
+            .map_or(self.default, |(_, level)| *level)
+    }
+
+    // The most verbose level anything is logged at, for the log crate's global fast path
+    fn max_level(&self) -> LevelFilter {
+        self.targets
+            .iter()
+            .map(|(_, level)| *level)
+            .fold(self.default, LevelFilter::max)
+    }
+}
+
+pub struct LogConfig {
+    pub filter: LogFilter,
+    // Directory of the rotating log file, no file is written without one
+    pub dir: Option<PathBuf>,
+    // Echo every line to stderr as well
+    pub console: bool,
+}
+
+impl Default for LogConfig {
+    fn default() -> Self {
+        Self {
+            filter: LogFilter::default(),
+            dir: Some(default_log_dir()),
+            console: true,
+        }
+    }
+}
+
+// Appends to game.log and shifts it to game.1.log, game.2.log, ... when it gets too big
+struct RotatingFile {
+    dir: PathBuf,
+    file: File,
+    size: u64,
+}
+
+impl RotatingFile {
+    fn open(dir: &Path) -> io::Result<Self> {
+        fs::create_dir_all(dir)?;
+        let file = OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(dir.join(LOG_FILE))?;
+        let size = file.metadata()?.len();
+        Ok(Self {
+            dir: dir.to_path_buf(),
+            file,
+            size,
+        })
+    }
+
+    fn write_line(&mut self, line: &str) -> io::Result<()> {
+        if self.size >= MAX_FILE_SIZE {
+            self.rotate()?;
+        }
+        writeln!(self.file, "{line}")?;
+        self.size += line.len() as u64 + 1;
+        Ok(())
+    }
+
+    fn rotate(&mut self) -> io::Result<()> {
+        let path = |index: usize| match index {
+            0 => self.dir.join(LOG_FILE),
+            _ => self.dir.join(format!("game.{index}.log")),
+        };
+        // The oldest file falls off the end
+        let _ = fs::remove_file(path(KEPT_FILES));
+        for index in (0..KEPT_FILES).rev() {
+            if path(index).exists() {
+                fs::rename(path(index), path(index + 1))?;
+            }
+        }
+
+        self.file = File::create(path(0))?;
+        self.size = 0;
+        Ok(())
+    }
+}
+
+struct Mirror {
+    enabled: bool,
+    lines: Vec<String>,
+}
+
+// Warnings and errors copied aside for the game to show on screen, off until toggled
+static MIRROR: Mutex<Mirror> = Mutex::new(Mirror {
+    enabled: false,
+    lines: Vec::new(),
+});
+
+pub fn set_mirror_warnings(enabled: bool) {
+    let mut mirror = MIRROR.lock().unwrap();
+    mirror.enabled = enabled;
+    if !enabled {
+        mirror.lines.clear();
+    }
+}
+
+pub fn mirrors_warnings() -> bool {
+    MIRROR.lock().unwrap().enabled
+}
+
+// Mirrored lines since the last call, oldest first
+pub fn take_mirrored() -> Vec<String> {
+    std::mem::take(&mut MIRROR.lock().unwrap().lines)
+}
+
+// Every line logged lately, whatever the filter let into the file
+static TAIL: Mutex<VecDeque<String>> = Mutex::new(VecDeque::new());
+
+// The last lines logged, oldest first. Empty if the log is busy, this is called from the
+// panic hook where waiting could hang forever.
+pub fn tail() -> Vec<String> {
+    TAIL.try_lock()
+        .map(|tail| tail.iter().cloned().collect())
+        .unwrap_or_default()
+}
+
+pub fn register_commands(commands: &mut CommandRegistry) {
+    commands.register(
+        "log",
+        "on|off",
+        "show warnings from the log in the console",
+        command_log,
+    );
+    commands.complete_with("log", || vec!["on".to_string(), "off".to_string()]);
+}
+
+fn command_log(
+    _game: &mut Game,
+    _context: &mut PluginContext,
+    args: &[&str],
+) -> Result<String, String> {
+    let enabled = match args {
+        ["on"] => true,
+        ["off"] => false,
+        [] => mirrors_warnings(),
+        _ => return Err("usage: log on|off".to_string()),
+    };
+    set_mirror_warnings(enabled);
+    Ok(format!("log {}", if enabled { "on" } else { "off" }))
+}
+
+struct GameLogger {
+    filter: LogFilter,
+    file: Option<Mutex<RotatingFile>>,
+    console: bool,
+    started: Instant,
+}
+
+impl Log for GameLogger {
+    fn enabled(&self, metadata: &Metadata) -> bool {
+        metadata.level() <= self.filter.level_for(metadata.target())
+    }
+
+    fn log(&self, record: &Record) {
+        if !self.enabled(record.metadata()) {
+            return;
+        }
+
+        let line = format!(
+            "[{:>9.3}s {:<5} {}] {}",
+            self.started.elapsed().as_secs_f32(),
+            record.level(),
+            record.target(),
+            record.args()
+        );
+        if self.console {
+            // stderr goes nowhere in a browser, its developer console is the console there
+            #[cfg(target_arch = "wasm32")]
+            web_sys::console::log_1(&line.as_str().into());
+            #[cfg(not(target_arch = "wasm32"))]
+            eprintln!("{line}");
+        }
+        if let Some(file) = self.file.as_ref() {
+            // Nowhere left to report a failing log file, the console still has the line
+            let _ = file.lock().unwrap().write_line(&line);
+        }
+
+        if let Ok(mut tail) = TAIL.lock() {
+            if tail.len() >= TAIL_LINES {
+                tail.pop_front();
+            }
+            tail.push_back(line);
+        }
+
+        if record.level() <= Level::Warn {
+            let mut mirror = MIRROR.lock().unwrap();
+            if mirror.enabled && mirror.lines.len() < MAX_MIRRORED {
+                mirror
+                    .lines
+                    .push(format!("{}: {}", record.level(), record.args()));
+            }
+        }
+    }
+
+    fn flush(&self) {
+        if let Some(file) = self.file.as_ref() {
+            let _ = file.lock().unwrap().file.flush();
+        }
+    }
+}
+
+// Installs the game's logger for the rest of the process. A log file that can't be
+// opened is reported and logging carries on without it.
+pub fn init(config: LogConfig) {
+    let file = config
+        .dir
+        .as_deref()
+        .map(RotatingFile::open)
+        .and_then(|file| {
+            file.map_err(|err| eprintln!("Logging to the console only: {err}"))
+                .ok()
+        })
+        .map(Mutex::new);
+
+    let max_level = config.filter.max_level();
+    let logger = GameLogger {
+        filter: config.filter,
+        file,
+        console: config.console,
+        started: Instant::now(),
+    };
+    if log::set_boxed_logger(Box::new(logger)).is_ok() {
+        log::set_max_level(max_level);
+    }
+}
@@ -0,0 +1,269 @@
+use crate::{
+    error::GameError,
+    palette::{self, ColorRole},
+};
+use fyrox::{
+    asset::manager::ResourceManager,
+    core::{color::Color, instant::Instant, sstorage::ImmutableString},
+    material::{shader::SamplerFallback, Material, MaterialResource, PropertyValue},
+    resource::texture::{Texture, TextureResource},
+};
+use log::{info, warn};
+use serde::{Deserialize, Serialize};
+use std::{
+    fs,
+    path::{Path, PathBuf},
+    time::{Duration, SystemTime},
+};
+
+// Looked up relative to the working directory unless --materials points elsewhere
+pub const DEFAULT_MATERIALS_PATH: &str = "assets/materials.json";
+
+// How often the material file and the textures it uses are checked for changes
+const POLL_INTERVAL: Duration = Duration::from_millis(500);
+
+// Look of one kind of level geometry
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct MaterialDef {
+    pub color: [u8; 3],
+    // Diffuse texture, tinted by the color. Import options are read from the usual
+    // .options file next to it.
+    pub texture: Option<PathBuf>,
+}
+
+impl MaterialDef {
+    fn new(color: [u8; 3]) -> Self {
+        Self {
+            color,
+            texture: None,
+        }
+    }
+}
+
+impl Default for MaterialDef {
+    fn default() -> Self {
+        Self::new([255, 255, 255])
+    }
+}
+
+// The materials levels are built from, as written in the material file
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct MaterialSet {
+    pub ground: MaterialDef,
+    pub obstacle: MaterialDef,
+    pub goal: MaterialDef,
+}
+
+impl Default for MaterialSet {
+    fn default() -> Self {
+        Self {
+            ground: MaterialDef::new([100, 150, 100]),
+            obstacle: MaterialDef::new([200, 100, 50]),
+            goal: MaterialDef::new([255, 220, 40]),
+        }
+    }
+}
+
+// A texture in use and the file times it was loaded with
+struct WatchedTexture {
+    path: PathBuf,
+    resource: TextureResource,
+    modified: Option<SystemTime>,
+    options_modified: Option<SystemTime>,
+}
+
+// Shared materials for level geometry. Every level surface points at one of these,
+// so when the material file or a texture changes on disk the materials are updated
+// in place and the open scene picks the change up without being rebuilt.
+pub struct MaterialLibrary {
+    path: PathBuf,
+    modified: Option<SystemTime>,
+    last_poll: Instant,
+    ground: MaterialResource,
+    obstacle: MaterialResource,
+    goal: MaterialResource,
+    textures: Vec<WatchedTexture>,
+    // What was applied last, applied again when the palette changes
+    set: MaterialSet,
+}
+
+impl MaterialLibrary {
+    // A missing file just means the built-in looks, a broken one is reported and
+    // replaced by them as well
+    pub fn load(resource_manager: &ResourceManager, path: PathBuf) -> Result<Self, GameError> {
+        let set = if cfg!(any(target_arch = "wasm32", target_os = "android")) || path.exists() {
+            read_set(&path).unwrap_or_else(|err| {
+                warn!("Using the default materials: {err}");
+                MaterialSet::default()
+            })
+        } else {
+            MaterialSet::default()
+        };
+
+        let mut library = Self {
+            modified: modified_time(&path),
+            path,
+            last_poll: Instant::now(),
+            ground: new_material(resource_manager),
+            obstacle: new_material(resource_manager),
+            goal: new_material(resource_manager),
+            textures: Vec::new(),
+            set: MaterialSet::default(),
+        };
+        library.apply(resource_manager, set)?;
+        Ok(library)
+    }
+
+    pub fn ground(&self) -> MaterialResource {
+        self.ground.clone()
+    }
+
+    pub fn obstacle(&self) -> MaterialResource {
+        self.obstacle.clone()
+    }
+
+    pub fn goal(&self) -> MaterialResource {
+        self.goal.clone()
+    }
+
+    // Applies the current palette to the file's colors
+    pub fn refresh(&mut self, resource_manager: &ResourceManager) {
+        if let Err(err) = self.apply(resource_manager, self.set.clone()) {
+            warn!("Couldn't recolor the level materials: {err}");
+        }
+    }
+
+    // Picks up edits to the material file and its textures, call once per frame
+    pub fn poll(&mut self, resource_manager: &ResourceManager) {
+        if self.last_poll.elapsed() < POLL_INTERVAL {
+            return;
+        }
+        self.last_poll = Instant::now();
+
+        let modified = modified_time(&self.path);
+        if modified.is_some() && modified != self.modified {
+            self.modified = modified;
+            match read_set(&self.path) {
+                Ok(set) => match self.apply(resource_manager, set) {
+                    Ok(()) => info!("Reloaded {}", self.path.display()),
+                    Err(err) => warn!("Couldn't apply {}: {err}", self.path.display()),
+                },
+                // Keep the current look until the file is saved again
+                Err(err) => warn!("Ignoring material file: {err}"),
+            }
+        }
+
+        // Textures go back through the resource manager, which applies their import
+        // options again, and every material using them sees the new data
+        for texture in self.textures.iter_mut() {
+            let modified = modified_time(&texture.path);
+            let options_modified = modified_time(&options_path(&texture.path));
+            if modified != texture.modified || options_modified != texture.options_modified {
+                texture.modified = modified;
+                texture.options_modified = options_modified;
+                info!("Reloading {}", texture.path.display());
+                resource_manager
+                    .state()
+                    .reload_resource(texture.resource.clone().into_untyped());
+            }
+        }
+    }
+
+    fn apply(
+        &mut self,
+        resource_manager: &ResourceManager,
+        set: MaterialSet,
+    ) -> Result<(), GameError> {
+        let defs = [&set.ground, &set.obstacle, &set.goal];
+        let textures: Vec<_> = defs
+            .iter()
+            .map(|def| {
+                def.texture
+                    .as_deref()
+                    .map(|path| self.texture(resource_manager, path))
+            })
+            .collect();
+
+        let roles = [ColorRole::Ground, ColorRole::Obstacle, ColorRole::Goal];
+        for (((material, def), texture), role) in [&self.ground, &self.obstacle, &self.goal]
+            .into_iter()
+            .zip(defs)
+            .zip(textures)
+            .zip(roles)
+        {
+            let mut material = material.data_ref();
+            let [r, g, b] = def.color;
+            material.set_property(
+                &fyrox::material::DIFFUSE_COLOR,
+                PropertyValue::Color(palette::remap(role, Color::opaque(r, g, b))),
+            )?;
+            material.set_property(
+                &ImmutableString::new("diffuseTexture"),
+                PropertyValue::Sampler {
+                    value: texture,
+                    fallback: SamplerFallback::White,
+                },
+            )?;
+        }
+
+        // Stop watching textures nothing uses anymore
+        self.textures.retain(|texture| {
+            defs.iter()
+                .any(|def| def.texture.as_deref() == Some(texture.path.as_path()))
+        });
+        self.set = set;
+        Ok(())
+    }
+
+    // The texture at `path`, requested and watched the first time it is used
+    fn texture(&mut self, resource_manager: &ResourceManager, path: &Path) -> TextureResource {
+        if let Some(texture) = self.textures.iter().find(|texture| texture.path == path) {
+            return texture.resource.clone();
+        }
+        let resource = resource_manager.request::<Texture>(path);
+        self.textures.push(WatchedTexture {
+            path: path.to_path_buf(),
+            resource: resource.clone(),
+            modified: modified_time(path),
+            options_modified: modified_time(&options_path(path)),
+        });
+        resource
+    }
+}
+
+fn new_material(resource_manager: &ResourceManager) -> MaterialResource {
+    resource_manager
+        .state()
+        .containers_mut()
+        .materials
+        .add(Material::standard())
+}
+
+// The browser and Android builds have no files to watch and play with the materials
+// they were built with
+#[cfg(any(target_arch = "wasm32", target_os = "android"))]
+fn read_set(_path: &Path) -> Result<MaterialSet, String> {
+    serde_json::from_str(include_str!("../assets/materials.json"))
+        .map_err(|err| format!("built-in materials: {err}"))
+}
+
+#[cfg(not(any(target_arch = "wasm32", target_os = "android")))]
+fn read_set(path: &Path) -> Result<MaterialSet, String> {
+    fs::read_to_string(path)
+        .map_err(|err| err.to_string())
+        .and_then(|text| serde_json::from_str(&text).map_err(|err| err.to_string()))
+        .map_err(|err| format!("{}: {err}", path.display()))
+}
+
+fn modified_time(path: &Path) -> Option<SystemTime> {
+    fs::metadata(path).and_then(|meta| meta.modified()).ok()
+}
+
+// Where the resource manager looks for a texture's import options
+fn options_path(path: &Path) -> PathBuf {
+    let mut options = path.as_os_str().to_owned();
+    options.push(".options");
+    PathBuf::from(options)
+}
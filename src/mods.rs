@@ -0,0 +1,448 @@
+use crate::level::{find_builtin_level, LevelDefinition, Prefab};
+use log::{info, warn};
+use serde::Deserialize;
+use std::{
+    collections::BTreeMap,
+    fmt, fs,
+    path::{Path, PathBuf},
+    sync::OnceLock,
+};
+
+// Looked up relative to the working directory unless --mods points elsewhere
+pub const DEFAULT_MODS_DIR: &str = "mods";
+
+// Optional description at the root of a mod folder
+const MANIFEST_FILE: &str = "mod.json";
+
+// Largest pickup radius an item can have, about the size of a small level
+const MAX_ITEM_RADIUS: f32 = 10.0;
+
+#[derive(Default, Deserialize)]
+#[serde(default)]
+struct ModManifest {
+    name: Option<String>,
+    version: Option<String>,
+}
+
+// A mod that was found and merged, as listed in the menu
+#[derive(Clone, Debug)]
+pub struct ModInfo {
+    // Folder name, which also decides the load order
+    pub id: String,
+    pub name: String,
+    pub version: Option<String>,
+    pub levels: usize,
+    pub items: usize,
+    pub dialogue: usize,
+    pub scripts: usize,
+}
+
+impl fmt::Display for ModInfo {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match &self.version {
+            Some(version) => write!(f, "{} {version}", self.name),
+            None => f.write_str(&self.name),
+        }
+    }
+}
+
+// Something a mod defines that was already defined by the game or an earlier mod.
+// The later definition wins.
+#[derive(Clone, Debug)]
+pub struct ModConflict {
+    pub kind: &'static str,
+    pub id: String,
+    pub replaced: String,
+    pub by: String,
+}
+
+impl fmt::Display for ModConflict {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(
+            f,
+            "{} {:?} from {} replaced by {}",
+            self.kind, self.id, self.replaced, self.by
+        )
+    }
+}
+
+// Level as written in a mod's levels/*.json, same fields as the built-in ones
+#[derive(Deserialize)]
+struct LevelFile {
+    id: String,
+    name: String,
+    ground_size: f32,
+    #[serde(default)]
+    obstacles: Vec<[f32; 3]>,
+    spawn_points: Vec<(String, [f32; 3])>,
+    goal: [f32; 3],
+    par_time: f32,
+}
+
+impl LevelFile {
+    // Level definitions are static data, mods are loaded once per process so leaking is fine
+    fn into_definition(self) -> &'static LevelDefinition {
+        let spawn_points: Vec<_> = self
+            .spawn_points
+            .into_iter()
+            .map(|(name, position)| (&*Box::leak(name.into_boxed_str()), position))
+            .collect();
+        Box::leak(Box::new(LevelDefinition {
+            id: Box::leak(self.id.into_boxed_str()),
+            name: Box::leak(self.name.into_boxed_str()),
+            ground_size: self.ground_size,
+            obstacles: Box::leak(self.obstacles.into_boxed_slice()),
+            spawn_points: Box::leak(spawn_points.into_boxed_slice()),
+            goal: self.goal,
+            par_time: self.par_time,
+        }))
+    }
+}
+
+#[derive(Deserialize)]
+struct ItemFile {
+    prefab: String,
+    value: Option<u32>,
+    radius: Option<f32>,
+}
+
+// Something level scripts can spawn by name: one of the prefabs, optionally with
+// different pickup values
+#[derive(Clone, Debug)]
+pub struct ItemDefinition {
+    pub prefab: Prefab,
+    pub value: Option<u32>,
+    pub radius: Option<f32>,
+}
+
+impl ItemDefinition {
+    // The plain prefab, as level scripts spawn it without mods
+    pub fn base(prefab: Prefab) -> Self {
+        Self {
+            prefab,
+            value: None,
+            radius: None,
+        }
+    }
+}
+
+#[derive(Clone, Debug, Deserialize)]
+pub struct DialogueLine {
+    pub speaker: String,
+    pub text: String,
+}
+
+// Everything the installed mods add, merged in load order
+#[derive(Default)]
+pub struct ModRegistry {
+    mods: Vec<ModInfo>,
+    levels: BTreeMap<String, (&'static LevelDefinition, String)>,
+    items: BTreeMap<String, (ItemDefinition, String)>,
+    dialogue: BTreeMap<String, (Vec<DialogueLine>, String)>,
+    scripts: BTreeMap<String, (PathBuf, String)>,
+    lang_dirs: Vec<PathBuf>,
+    conflicts: Vec<ModConflict>,
+    // Files that couldn't be read, the rest of their mod is still used
+    errors: Vec<String>,
+}
+
+impl ModRegistry {
+    // Every folder in `dir` is a mod, loaded in name order:
+    //   mod.json              name and version (optional)
+    //   levels/*.json         level definitions
+    //   items.json            { "id": { "prefab": "coin", "value": 5, "radius": 1.0 } }
+    //   dialogue.json         { "id": [{ "speaker": "...", "text": "..." }] }
+    //   scripts/LEVEL.rhai    level script, replaces the game's own for that level
+    //   lang/CODE.json        strings per language, added to and replacing the game's
+    pub fn scan(dir: &Path) -> Self {
+        let mut registry = Self::default();
+        let Ok(entries) = fs::read_dir(dir) else {
+            return registry;
+        };
+
+        let mut folders: Vec<_> = entries
+            .filter_map(|entry| entry.ok())
+            .map(|entry| entry.path())
+            .filter(|path| path.is_dir())
+            .collect();
+        folders.sort();
+
+        for folder in folders {
+            registry.load_mod(&folder);
+        }
+        registry
+    }
+
+    fn load_mod(&mut self, folder: &Path) {
+        let id = folder
+            .file_name()
+            .map_or_else(String::new, |name| name.to_string_lossy().into_owned());
+        let manifest: ModManifest = match fs::read_to_string(folder.join(MANIFEST_FILE)) {
+            Ok(text) => serde_json::from_str(&text).unwrap_or_else(|err| {
+                self.errors
+                    .push(format!("{}: {err}", folder.join(MANIFEST_FILE).display()));
+                ModManifest::default()
+            }),
+            Err(_) => ModManifest::default(),
+        };
+        let mut info = ModInfo {
+            name: manifest.name.unwrap_or_else(|| id.clone()),
+            version: manifest.version,
+            id,
+            levels: 0,
+            items: 0,
+            dialogue: 0,
+            scripts: 0,
+        };
+
+        for path in files_with_extension(&folder.join("levels"), "json") {
+            if let Some(level) = self.read_json::<LevelFile>(&path) {
+                let level = level.into_definition();
+                if find_builtin_level(level.id).is_some() && !self.levels.contains_key(level.id) {
+                    self.conflicts.push(ModConflict {
+                        kind: "level",
+                        id: level.id.to_string(),
+                        replaced: "the base game".to_string(),
+                        by: info.id.clone(),
+                    });
+                }
+                insert(
+                    &mut self.levels,
+                    &mut self.conflicts,
+                    "level",
+                    level.id,
+                    level,
+                    &info.id,
+                );
+                info.levels += 1;
+            }
+        }
+
+        let items_path = folder.join("items.json");
+        if items_path.exists() {
+            let items = self
+                .read_json::<BTreeMap<String, ItemFile>>(&items_path)
+                .unwrap_or_default();
+            for (item_id, item) in items {
+                let Some(prefab) = Prefab::from_name(&item.prefab) else {
+                    self.errors.push(format!(
+                        "{}: item {item_id:?} uses unknown prefab {:?}",
+                        items_path.display(),
+                        item.prefab
+                    ));
+                    continue;
+                };
+                // Pickups are looked for within their radius every tick, a huge one
+                // would have the whole level searched
+                if let Some(radius) = item
+                    .radius
+                    .filter(|radius| !(0.0..=MAX_ITEM_RADIUS).contains(radius))
+                {
+                    self.errors.push(format!(
+                        "{}: item {item_id:?} has radius {radius}, it has to be between 0 and {MAX_ITEM_RADIUS}",
+                        items_path.display(),
+                    ));
+                    continue;
+                }
+                if Prefab::from_name(&item_id).is_some() && !self.items.contains_key(&item_id) {
+                    self.conflicts.push(ModConflict {
+                        kind: "item",
+                        id: item_id.clone(),
+                        replaced: "the base game".to_string(),
+                        by: info.id.clone(),
+                    });
+                }
+                let definition = ItemDefinition {
+                    prefab,
+                    value: item.value,
+                    radius: item.radius,
+                };
+                insert(
+                    &mut self.items,
+                    &mut self.conflicts,
+                    "item",
+                    &item_id,
+                    definition,
+                    &info.id,
+                );
+                info.items += 1;
+            }
+        }
+
+        let dialogue_path = folder.join("dialogue.json");
+        if dialogue_path.exists() {
+            let dialogue = self
+                .read_json::<BTreeMap<String, Vec<DialogueLine>>>(&dialogue_path)
+                .unwrap_or_default();
+            for (dialogue_id, lines) in dialogue {
+                insert(
+                    &mut self.dialogue,
+                    &mut self.conflicts,
+                    "dialogue",
+                    &dialogue_id,
+                    lines,
+                    &info.id,
+                );
+                info.dialogue += 1;
+            }
+        }
+
+        for path in files_with_extension(&folder.join("scripts"), "rhai") {
+            let Some(level_id) = path
+                .file_stem()
+                .map(|stem| stem.to_string_lossy().into_owned())
+            else {
+                continue;
+            };
+            insert(
+                &mut self.scripts,
+                &mut self.conflicts,
+                "script",
+                &level_id,
+                path,
+                &info.id,
+            );
+            info.scripts += 1;
+        }
+
+        // Strings are merged by the localization layer, which reads them itself
+        let lang_dir = folder.join("lang");
+        if lang_dir.is_dir() {
+            self.lang_dirs.push(lang_dir);
+        }
+
+        self.mods.push(info);
+    }
+
+    fn read_json<T: for<'de> Deserialize<'de>>(&mut self, path: &Path) -> Option<T> {
+        fs::read_to_string(path)
+            .map_err(|err| err.to_string())
+            .and_then(|text| serde_json::from_str(&text).map_err(|err| err.to_string()))
+            .map_err(|err| self.errors.push(format!("{}: {err}", path.display())))
+            .ok()
+    }
+
+    pub fn mods(&self) -> &[ModInfo] {
+        &self.mods
+    }
+
+    pub fn conflicts(&self) -> &[ModConflict] {
+        &self.conflicts
+    }
+
+    pub fn errors(&self) -> &[String] {
+        &self.errors
+    }
+
+    // Levels added by mods that aren't replacements of built-in ones, in id order
+    pub fn extra_levels(&self) -> impl Iterator<Item = &'static LevelDefinition> + '_ {
+        self.levels
+            .values()
+            .map(|(level, _)| *level)
+            .filter(|level| find_builtin_level(level.id).is_none())
+    }
+}
+
+// Adds a definition, noting which mod it replaced if the id was taken
+fn insert<T>(
+    map: &mut BTreeMap<String, (T, String)>,
+    conflicts: &mut Vec<ModConflict>,
+    kind: &'static str,
+    id: &str,
+    value: T,
+    mod_id: &str,
+) {
+    if let Some((_, previous)) = map.insert(id.to_string(), (value, mod_id.to_string())) {
+        conflicts.push(ModConflict {
+            kind,
+            id: id.to_string(),
+            replaced: previous,
+            by: mod_id.to_string(),
+        });
+    }
+}
+
+// Files in a directory with the given extension, in name order
+fn files_with_extension(dir: &Path, extension: &str) -> Vec<PathBuf> {
+    let mut files: Vec<_> = fs::read_dir(dir)
+        .into_iter()
+        .flatten()
+        .filter_map(|entry| entry.ok())
+        .map(|entry| entry.path())
+        .filter(|path| path.extension().is_some_and(|ext| ext == extension))
+        .collect();
+    files.sort();
+    files
+}
+
+// Mods are merged once at startup, before anything looks up game data
+static REGISTRY: OnceLock<ModRegistry> = OnceLock::new();
+
+// Makes the registry the one every lookup below uses and reports what was loaded.
+// Only the first call has any effect.
+pub fn install(registry: ModRegistry) {
+    for info in registry.mods() {
+        info!(
+            "Loaded mod {info} ({} levels, {} items, {} dialogues, {} scripts)",
+            info.levels, info.items, info.dialogue, info.scripts
+        );
+    }
+    for conflict in registry.conflicts() {
+        warn!("Mod conflict: {conflict}");
+    }
+    for error in registry.errors() {
+        warn!("Mod file skipped: {error}");
+    }
+    let _ = REGISTRY.set(registry);
+}
+
+pub fn installed() -> &'static [ModInfo] {
+    REGISTRY.get().map_or(&[], |registry| registry.mods())
+}
+
+pub fn level(id: &str) -> Option<&'static LevelDefinition> {
+    REGISTRY.get()?.levels.get(id).map(|(level, _)| *level)
+}
+
+pub fn extra_levels() -> Vec<&'static LevelDefinition> {
+    REGISTRY
+        .get()
+        .map(|registry| registry.extra_levels().collect())
+        .unwrap_or_default()
+}
+
+pub fn item(id: &str) -> Option<&'static ItemDefinition> {
+    REGISTRY.get()?.items.get(id).map(|(item, _)| item)
+}
+
+// Ids of every item mods add, for listing them
+pub fn item_ids() -> Vec<String> {
+    REGISTRY
+        .get()
+        .map(|registry| registry.items.keys().cloned().collect())
+        .unwrap_or_default()
+}
+
+pub fn dialogue(id: &str) -> Option<&'static [DialogueLine]> {
+    REGISTRY
+        .get()?
+        .dialogue
+        .get(id)
+        .map(|(lines, _)| lines.as_slice())
+}
+
+// Folders with string tables, in mod load order
+pub fn lang_dirs() -> Vec<PathBuf> {
+    REGISTRY
+        .get()
+        .map(|registry| registry.lang_dirs.clone())
+        .unwrap_or_default()
+}
+
+pub fn script(level_id: &str) -> Option<&'static Path> {
+    REGISTRY
+        .get()?
+        .scripts
+        .get(level_id)
+        .map(|(path, _)| path.as_path())
+}
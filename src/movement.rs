@@ -0,0 +1,138 @@
+use fyrox::core::algebra::Vector3;
+
+// Units per second
+pub const PLAYER_SPEED: f32 = 5.0;
+
+// A dash moves this many times faster than walking, for DASH_TIME seconds
+pub const DASH_SPEED_FACTOR: f32 = 3.0;
+pub const DASH_TIME: f32 = 0.2;
+// What dashing into an enemy does to it
+pub const DASH_DAMAGE: f32 = 15.0;
+// Stamina a dash costs, out of STAMINA_MAX
+pub const DASH_COST: f32 = 25.0;
+pub const STAMINA_MAX: f32 = 100.0;
+// Stamina regained per second while not dashing
+pub const STAMINA_REGEN: f32 = 20.0;
+
+// Movement keys held during one simulation step
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct MoveInput {
+    pub forward: bool,
+    pub backward: bool,
+    pub left: bool,
+    pub right: bool,
+}
+
+impl MoveInput {
+    pub fn to_bits(self) -> u8 {
+        (self.forward as u8)
+            | (self.backward as u8) << 1
+            | (self.left as u8) << 2
+            | (self.right as u8) << 3
+    }
+
+    pub fn from_bits(bits: u8) -> Self {
+        Self {
+            forward: bits & 1 != 0,
+            backward: bits & (1 << 1) != 0,
+            left: bits & (1 << 2) != 0,
+            right: bits & (1 << 3) != 0,
+        }
+    }
+}
+
+// Advances a player position by one step. Shared by local play, client-side
+// prediction and the server so all of them agree on where a player ends up.
+pub fn simulate_movement(position: Vector3<f32>, input: MoveInput, dt: f32) -> Vector3<f32> {
+    simulate_movement_at(position, input, dt, PLAYER_SPEED)
+}
+
+// Same as simulate_movement with a custom speed, for local tuning
+pub fn simulate_movement_at(
+    position: Vector3<f32>,
+    input: MoveInput,
+    dt: f32,
+    speed: f32,
+) -> Vector3<f32> {
+    let mut movement = Vector3::new(0.0, 0.0, 0.0);
+
+    // Calculate movement direction based on input
+    if input.forward {
+        movement.z -= 1.0;
+    }
+    if input.backward {
+        movement.z += 1.0;
+    }
+    if input.left {
+        movement.x -= 1.0;
+    }
+    if input.right {
+        movement.x += 1.0;
+    }
+
+    // Normalize movement vector if not zero
+    if movement.magnitude() > 0.0 {
+        position + movement.normalize() * speed * dt
+    } else {
+        position
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const DT: f32 = 0.5;
+
+    fn start() -> Vector3<f32> {
+        Vector3::new(1.0, 1.0, 1.0)
+    }
+
+    #[test]
+    fn no_keys_stay_in_place() {
+        assert_eq!(
+            simulate_movement(start(), MoveInput::default(), DT),
+            start()
+        );
+    }
+
+    #[test]
+    fn forward_moves_along_negative_z_at_player_speed() {
+        let input = MoveInput {
+            forward: true,
+            ..Default::default()
+        };
+        let moved = simulate_movement(start(), input, DT);
+        assert_eq!(moved, start() + Vector3::new(0.0, 0.0, -PLAYER_SPEED * DT));
+    }
+
+    #[test]
+    fn opposite_keys_cancel_out() {
+        let input = MoveInput {
+            forward: true,
+            backward: true,
+            left: true,
+            right: true,
+        };
+        assert_eq!(simulate_movement(start(), input, DT), start());
+    }
+
+    #[test]
+    fn diagonals_are_no_faster_than_straight_lines() {
+        let input = MoveInput {
+            forward: true,
+            right: true,
+            ..Default::default()
+        };
+        let moved = simulate_movement_at(start(), input, DT, 2.0);
+        assert!(((moved - start()).norm() - 2.0 * DT).abs() < 1e-5);
+        assert!(moved.x > start().x && moved.z < start().z);
+    }
+
+    #[test]
+    fn inputs_survive_the_trip_through_bits() {
+        for bits in 0..16 {
+            assert_eq!(MoveInput::from_bits(bits).to_bits(), bits);
+        }
+    }
+}
@@ -0,0 +1,116 @@
+use crate::net::{
+    discovery::broadcast_query,
+    protocol::{Message, SessionInfo, MAX_PACKET_SIZE},
+};
+use fyrox::core::instant::Instant;
+use std::{
+    io,
+    net::{SocketAddr, UdpSocket},
+    time::Duration,
+};
+
+// How often known servers are asked for a fresh summary
+const QUERY_INTERVAL: Duration = Duration::from_secs(2);
+
+// Sessions that stopped answering disappear from the list after this long
+const SESSION_TIMEOUT: Duration = Duration::from_secs(6);
+
+#[derive(Clone, Debug)]
+pub struct SessionEntry {
+    pub addr: SocketAddr,
+    pub info: SessionInfo,
+    // Found by LAN broadcast rather than a manually added address
+    pub lan: bool,
+    last_seen: Instant,
+}
+
+// Polls manually added server addresses plus the whole LAN and keeps the ones that answer
+pub struct SessionBrowser {
+    socket: UdpSocket,
+    targets: Vec<SocketAddr>,
+    sessions: Vec<SessionEntry>,
+    last_query: Option<Instant>,
+}
+
+impl SessionBrowser {
+    pub fn new() -> io::Result<Self> {
+        let socket = UdpSocket::bind(("0.0.0.0", 0))?;
+        socket.set_nonblocking(true)?;
+        socket.set_broadcast(true)?;
+
+        Ok(Self {
+            socket,
+            targets: Vec::new(),
+            sessions: Vec::new(),
+            last_query: None,
+        })
+    }
+
+    pub fn add_server(&mut self, addr: SocketAddr) {
+        if !self.targets.contains(&addr) {
+            self.targets.push(addr);
+            self.refresh();
+        }
+    }
+
+    // Ask every known server right away instead of waiting for the next interval
+    pub fn refresh(&mut self) {
+        self.last_query = None;
+    }
+
+    pub fn update(&mut self) {
+        if self
+            .last_query
+            .is_none_or(|sent| sent.elapsed() >= QUERY_INTERVAL)
+        {
+            let bytes = Message::QueryInfo.encode();
+            for addr in &self.targets {
+                let _ = self.socket.send_to(&bytes, addr);
+            }
+            broadcast_query(&self.socket);
+            self.last_query = Some(Instant::now());
+        }
+
+        let mut buf = [0u8; MAX_PACKET_SIZE];
+        loop {
+            let (len, addr) = match self.socket.recv_from(&mut buf) {
+                Ok(received) => received,
+                Err(err) if err.kind() == io::ErrorKind::WouldBlock => break,
+                Err(_) => continue,
+            };
+
+            if let Some(Message::Info(info)) = Message::decode(&buf[..len]) {
+                self.record(addr, info);
+            }
+        }
+
+        self.sessions
+            .retain(|session| session.last_seen.elapsed() < SESSION_TIMEOUT);
+    }
+
+    fn record(&mut self, addr: SocketAddr, info: SessionInfo) {
+        let last_seen = Instant::now();
+        let lan = !self.targets.contains(&addr);
+        match self
+            .sessions
+            .iter_mut()
+            .find(|session| session.addr == addr)
+        {
+            Some(session) => {
+                session.info = info;
+                session.lan = lan;
+                session.last_seen = last_seen;
+            }
+            None => self.sessions.push(SessionEntry {
+                addr,
+                info,
+                lan,
+                last_seen,
+            }),
+        }
+    }
+
+    pub fn sessions(&self) -> &[SessionEntry] {
+        &self.sessions
+    }
+}
@@ -0,0 +1,56 @@
+use crate::net::protocol::ChatLine;
+use std::collections::VecDeque;
+
+// Every chat datagram goes out on this many consecutive ticks, cheap insurance against loss
+pub const CHAT_REDUNDANCY: u8 = 3;
+
+// How many received line ids are remembered to filter out the repeats
+const SEEN_HISTORY: usize = 64;
+
+// Chat items waiting to be (re)sent
+pub struct ChatOutbox<T> {
+    pending: Vec<(T, u8)>,
+}
+
+impl<T> Default for ChatOutbox<T> {
+    fn default() -> Self {
+        Self {
+            pending: Vec::new(),
+        }
+    }
+}
+
+impl<T: Clone> ChatOutbox<T> {
+    pub fn push(&mut self, item: T) {
+        self.pending.push((item, CHAT_REDUNDANCY));
+    }
+
+    // Items to send on this tick
+    pub fn take_tick(&mut self) -> Vec<T> {
+        let items = self.pending.iter().map(|(item, _)| item.clone()).collect();
+        for (_, remaining) in self.pending.iter_mut() {
+            *remaining -= 1;
+        }
+        self.pending.retain(|(_, remaining)| *remaining > 0);
+        items
+    }
+}
+
+// Drops chat lines that were already delivered
+#[derive(Default)]
+pub struct ChatInbox {
+    seen: VecDeque<u32>,
+}
+
+impl ChatInbox {
+    pub fn accept(&mut self, line: &ChatLine) -> bool {
+        if self.seen.contains(&line.id) {
+            return false;
+        }
+        self.seen.push_back(line.id);
+        while self.seen.len() > SEEN_HISTORY {
+            self.seen.pop_front();
+        }
+        true
+    }
+}
@@ -0,0 +1,202 @@
+use crate::net::{
+    chat::{ChatInbox, ChatOutbox},
+    protocol::{
+        truncate_str, ChatLine, InputCommand, LobbyState, Message, PlayerId, PlayerTransform,
+        MAX_CHAT_LEN, MAX_PACKET_SIZE, PROTOCOL_VERSION,
+    },
+};
+use fyrox::core::instant::Instant;
+use std::{
+    io,
+    net::{SocketAddr, UdpSocket},
+    time::Duration,
+};
+
+// How often the join request is repeated until the server answers
+const HELLO_INTERVAL: Duration = Duration::from_millis(500);
+
+// The server is considered gone after this much silence
+const SERVER_TIMEOUT: Duration = Duration::from_secs(5);
+
+pub enum ClientEvent {
+    Connected(PlayerId),
+    Snapshot {
+        tick: u32,
+        ack: u32,
+        players: Vec<PlayerTransform>,
+    },
+    Lobby(LobbyState),
+    Chat(ChatLine),
+    Disconnected,
+}
+
+pub struct NetClient {
+    socket: UdpSocket,
+    server: SocketAddr,
+    name: String,
+    spectator: bool,
+    player_id: Option<PlayerId>,
+    tick_rate: u32,
+    last_hello: Option<Instant>,
+    last_heard: Instant,
+    disconnected: bool,
+    next_chat_sequence: u32,
+    chat_outbox: ChatOutbox<(u32, String)>,
+    chat_inbox: ChatInbox,
+}
+
+impl NetClient {
+    pub fn connect(server: SocketAddr, name: &str, spectator: bool) -> io::Result<Self> {
+        let socket = UdpSocket::bind(("0.0.0.0", 0))?;
+        socket.set_nonblocking(true)?;
+
+        Ok(Self {
+            socket,
+            server,
+            name: name.to_string(),
+            spectator,
+            player_id: None,
+            tick_rate: 0,
+            last_hello: None,
+            last_heard: Instant::now(),
+            disconnected: false,
+            next_chat_sequence: 1,
+            chat_outbox: Default::default(),
+            chat_inbox: Default::default(),
+        })
+    }
+
+    pub fn player_id(&self) -> Option<PlayerId> {
+        self.player_id
+    }
+
+    // Server tick rate, known once the server accepted us
+    pub fn tick_rate(&self) -> u32 {
+        self.tick_rate
+    }
+
+    pub fn poll(&mut self) -> Vec<ClientEvent> {
+        let mut events = Vec::new();
+
+        if self.disconnected {
+            return events;
+        }
+
+        if self.player_id.is_none()
+            && self
+                .last_hello
+                .is_none_or(|sent| sent.elapsed() >= HELLO_INTERVAL)
+        {
+            self.send(&Message::Hello {
+                version: PROTOCOL_VERSION,
+                name: self.name.clone(),
+                spectator: self.spectator,
+            });
+            self.last_hello = Some(Instant::now());
+        }
+
+        let mut buf = [0u8; MAX_PACKET_SIZE];
+        loop {
+            let (len, addr) = match self.socket.recv_from(&mut buf) {
+                Ok(received) => received,
+                Err(err) if err.kind() == io::ErrorKind::WouldBlock => break,
+                Err(_) => continue,
+            };
+
+            if addr != self.server {
+                continue;
+            }
+
+            let Some(message) = Message::decode(&buf[..len]) else {
+                continue;
+            };
+
+            self.last_heard = Instant::now();
+
+            match message {
+                Message::Welcome {
+                    player_id,
+                    tick_rate,
+                } => {
+                    if self.player_id.is_none() {
+                        self.player_id = Some(player_id);
+                        self.tick_rate = tick_rate;
+                        events.push(ClientEvent::Connected(player_id));
+                    }
+                }
+                Message::Snapshot { tick, ack, players } => {
+                    events.push(ClientEvent::Snapshot { tick, ack, players });
+                }
+                Message::Lobby(lobby) => events.push(ClientEvent::Lobby(lobby)),
+                Message::Chat(line) => {
+                    if self.chat_inbox.accept(&line) {
+                        events.push(ClientEvent::Chat(line));
+                    }
+                }
+                Message::Disconnect => {
+                    self.disconnected = true;
+                    events.push(ClientEvent::Disconnected);
+                    break;
+                }
+                Message::Hello { .. }
+                | Message::Inputs { .. }
+                | Message::SetReady { .. }
+                | Message::QueryInfo
+                | Message::Info(_)
+                | Message::ChatSend { .. } => {}
+            }
+        }
+
+        if !self.disconnected && self.last_heard.elapsed() >= SERVER_TIMEOUT {
+            self.disconnected = true;
+            events.push(ClientEvent::Disconnected);
+        }
+
+        events
+    }
+
+    pub fn send_inputs<'a>(&self, commands: impl Iterator<Item = &'a InputCommand>) {
+        if self.player_id.is_some() {
+            self.send(&Message::Inputs {
+                commands: commands.copied().collect(),
+            });
+        }
+    }
+
+    pub fn send_ready(&self, ready: bool) {
+        if self.player_id.is_some() {
+            self.send(&Message::SetReady { ready });
+        }
+    }
+
+    pub fn send_chat(&mut self, text: &str) {
+        let text = truncate_str(text.trim(), MAX_CHAT_LEN);
+        if text.is_empty() {
+            return;
+        }
+        self.chat_outbox
+            .push((self.next_chat_sequence, text.to_string()));
+        self.next_chat_sequence += 1;
+    }
+
+    pub fn flush_chat(&mut self) {
+        for (sequence, text) in self.chat_outbox.take_tick() {
+            self.send(&Message::ChatSend { sequence, text });
+        }
+    }
+
+    pub fn is_disconnected(&self) -> bool {
+        self.disconnected
+    }
+
+    pub fn disconnect(&mut self) {
+        if !self.disconnected {
+            self.send(&Message::Disconnect);
+            self.disconnected = true;
+        }
+    }
+
+    fn send(&self, message: &Message) {
+        let _ = self.socket.send_to(&message.encode(), self.server);
+    }
+}
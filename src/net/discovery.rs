@@ -0,0 +1,48 @@
+use crate::net::protocol::{Message, MAX_PACKET_SIZE};
+use std::{
+    io,
+    net::{Ipv4Addr, SocketAddr, UdpSocket},
+};
+
+// Well-known port hosts listen on for LAN session queries
+pub const DISCOVERY_PORT: u16 = 7778;
+
+// Listens for broadcast session queries on the discovery port. Only one host per
+// machine can own the port, any further hosts are simply not discoverable.
+pub struct DiscoveryResponder {
+    socket: UdpSocket,
+}
+
+impl DiscoveryResponder {
+    pub fn bind() -> io::Result<Self> {
+        let socket = UdpSocket::bind((Ipv4Addr::UNSPECIFIED, DISCOVERY_PORT))?;
+        socket.set_nonblocking(true)?;
+        Ok(Self { socket })
+    }
+
+    // Addresses that asked for a session summary since the last call
+    pub fn poll_queries(&self) -> Vec<SocketAddr> {
+        let mut queries = Vec::new();
+        let mut buf = [0u8; MAX_PACKET_SIZE];
+
+        loop {
+            match self.socket.recv_from(&mut buf) {
+                Ok((len, addr)) => {
+                    if let Some(Message::QueryInfo) = Message::decode(&buf[..len]) {
+                        queries.push(addr);
+                    }
+                }
+                Err(err) if err.kind() == io::ErrorKind::WouldBlock => break,
+                Err(_) => continue,
+            }
+        }
+
+        queries
+    }
+}
+
+// Asks every host on the local network to describe its session
+pub fn broadcast_query(socket: &UdpSocket) {
+    let bytes = Message::QueryInfo.encode();
+    let _ = socket.send_to(&bytes, (Ipv4Addr::BROADCAST, DISCOVERY_PORT));
+}
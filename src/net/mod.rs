@@ -0,0 +1,335 @@
+pub mod browser;
+pub mod chat;
+pub mod client;
+pub mod discovery;
+pub mod prediction;
+pub mod protocol;
+pub mod replication;
+pub mod server;
+
+use crate::{
+    movement::{simulate_movement, MoveInput},
+    net::{
+        client::{ClientEvent, NetClient},
+        prediction::Predictor,
+        protocol::{ChatLine, LobbyState, PlayerId, PlayerTransform, HOST_PLAYER_ID},
+        replication::{InterpolationSettings, RemoteEntities},
+        server::{NetServer, ServerEvent},
+    },
+};
+use fyrox::core::{
+    algebra::{UnitQuaternion, Vector3},
+    instant::Instant,
+};
+use std::{io, net::SocketAddr};
+
+pub const DEFAULT_PORT: u16 = 7777;
+pub const DEFAULT_TICK_RATE: u32 = 20;
+
+#[derive(Clone, Debug)]
+pub enum NetRole {
+    // Run a listen server and play on it
+    Host { port: u16 },
+    // Join someone else's server, either as a player or as a spectator
+    Client { server: SocketAddr, spectator: bool },
+}
+
+#[derive(Clone, Debug)]
+pub struct NetConfig {
+    pub role: NetRole,
+    pub player_name: String,
+    // Transform updates sent per second
+    pub tick_rate: u32,
+    pub interpolation: InterpolationSettings,
+}
+
+enum NetSession {
+    Host(NetServer),
+    Client {
+        client: NetClient,
+        spectator: bool,
+        // Created once the server has accepted us
+        predictor: Option<Predictor>,
+        // Latest lobby state received from the server
+        lobby: LobbyState,
+        ready: bool,
+    },
+}
+
+// Networking front-end used by the game. In the lobby it keeps membership and
+// readiness in sync, during a round the host sends snapshots and clients send their
+// inputs at a fixed tick rate, and everybody keeps interpolated transforms of the others.
+pub struct Network {
+    session: NetSession,
+    tick_rate: u32,
+    tick_accumulator: f32,
+    ticks: u32,
+    remote: RemoteEntities,
+    started: Instant,
+    // Chat lines received since the game last asked for them
+    chat: Vec<ChatLine>,
+}
+
+impl Network {
+    pub fn start(config: NetConfig) -> io::Result<Self> {
+        let session = match config.role {
+            NetRole::Host { port } => NetSession::Host(NetServer::bind(
+                port,
+                config.tick_rate,
+                &config.player_name,
+            )?),
+            NetRole::Client { server, spectator } => NetSession::Client {
+                client: NetClient::connect(server, &config.player_name, spectator)?,
+                spectator,
+                predictor: None,
+                lobby: Default::default(),
+                ready: false,
+            },
+        };
+
+        Ok(Self {
+            session,
+            tick_rate: config.tick_rate.max(1),
+            tick_accumulator: 0.0,
+            ticks: 0,
+            remote: RemoteEntities::new(config.interpolation),
+            started: Instant::now(),
+            chat: Vec::new(),
+        })
+    }
+
+    pub fn is_host(&self) -> bool {
+        matches!(self.session, NetSession::Host(_))
+    }
+
+    // Spectators receive world state but never own a player
+    pub fn is_spectator(&self) -> bool {
+        matches!(
+            self.session,
+            NetSession::Client {
+                spectator: true,
+                ..
+            }
+        )
+    }
+
+    pub fn local_player_id(&self) -> Option<PlayerId> {
+        match &self.session {
+            NetSession::Host(_) => Some(HOST_PLAYER_ID),
+            NetSession::Client { client, .. } => client.player_id(),
+        }
+    }
+
+    // True once the connection to the server is lost or was refused
+    pub fn is_disconnected(&self) -> bool {
+        match &self.session {
+            NetSession::Host(_) => false,
+            NetSession::Client { client, .. } => client.is_disconnected(),
+        }
+    }
+
+    pub fn lobby(&self) -> LobbyState {
+        match &self.session {
+            NetSession::Host(server) => server.lobby_state(),
+            NetSession::Client { lobby, .. } => lobby.clone(),
+        }
+    }
+
+    pub fn set_ready(&mut self, is_ready: bool) {
+        match &mut self.session {
+            NetSession::Host(server) => server.set_host_ready(is_ready),
+            NetSession::Client { client, ready, .. } => {
+                *ready = is_ready;
+                client.send_ready(is_ready);
+            }
+        }
+    }
+
+    // Only the host picks the level, clients silently ignore this
+    pub fn select_level(&mut self, level: &str) {
+        if let NetSession::Host(server) = &mut self.session {
+            server.set_level(level);
+        }
+    }
+
+    // Host only: begins the round once everybody is ready
+    pub fn start_game(&mut self) -> bool {
+        match &mut self.session {
+            NetSession::Host(server) => {
+                let started = server.start_game();
+                if started {
+                    server.broadcast_lobby();
+                }
+                started
+            }
+            NetSession::Client { .. } => false,
+        }
+    }
+
+    pub fn send_chat(&mut self, text: &str) {
+        match &mut self.session {
+            NetSession::Host(server) => {
+                // The host is the server, so its own line is final right away
+                if let Some(line) = server.host_chat(text) {
+                    self.chat.push(line);
+                }
+            }
+            NetSession::Client { client, .. } => client.send_chat(text),
+        }
+    }
+
+    pub fn take_chat(&mut self) -> Vec<ChatLine> {
+        std::mem::take(&mut self.chat)
+    }
+
+    pub fn set_interpolation(&mut self, settings: InterpolationSettings) {
+        self.remote.set_settings(settings);
+    }
+
+    // Moves the local player. Clients predict the result of their input right
+    // away and remember it for reconciliation, the host simply is authoritative.
+    pub fn predict(&mut self, input: MoveInput, dt: f32, position: Vector3<f32>) -> Vector3<f32> {
+        match &mut self.session {
+            NetSession::Client {
+                client, predictor, ..
+            } if client.player_id().is_some() => predictor
+                .get_or_insert_with(|| Predictor::new(position))
+                .predict(input, dt),
+            _ => simulate_movement(position, input, dt),
+        }
+    }
+
+    // `local` is the local player's transform, or None while there is no level loaded
+    pub fn update(&mut self, dt: f32, local: Option<(Vector3<f32>, UnitQuaternion<f32>)>) {
+        self.receive();
+
+        // Outgoing traffic runs at a fixed rate regardless of the frame rate
+        self.tick_accumulator += dt;
+        let tick_duration = 1.0 / self.tick_rate as f32;
+        if self.tick_accumulator >= tick_duration {
+            // Don't try to catch up after a long stall, a single fresh state is enough
+            self.tick_accumulator %= tick_duration;
+            self.ticks = self.ticks.wrapping_add(1);
+            self.send(local);
+        }
+    }
+
+    fn receive(&mut self) {
+        match &mut self.session {
+            NetSession::Host(server) => {
+                // Clients aren't tick-stamped, so the host buffers them on its own clock
+                let now = self.started.elapsed().as_secs_f32();
+                for event in server.poll() {
+                    match event {
+                        ServerEvent::PlayerJoined(_) => {}
+                        ServerEvent::Chat(line) => self.chat.push(line),
+                        ServerEvent::PlayerLeft(id) => self.remote.remove(id),
+                        ServerEvent::PlayerMoved(transform) => self.remote.push(
+                            transform.id,
+                            now,
+                            transform.position,
+                            transform.rotation,
+                        ),
+                    }
+                }
+            }
+            NetSession::Client {
+                client,
+                predictor,
+                lobby,
+                ..
+            } => {
+                for event in client.poll() {
+                    match event {
+                        ClientEvent::Connected(_) => {}
+                        ClientEvent::Lobby(state) => *lobby = state,
+                        ClientEvent::Chat(line) => self.chat.push(line),
+                        ClientEvent::Snapshot { tick, ack, players } => {
+                            let time = tick as f32 / client.tick_rate().max(1) as f32;
+                            let own_id = client.player_id();
+
+                            // Players missing from a full snapshot have left
+                            self.remote
+                                .retain(|id| players.iter().any(|player| player.id == id));
+
+                            for player in players {
+                                if Some(player.id) == own_id {
+                                    if let Some(predictor) = predictor.as_mut() {
+                                        predictor.reconcile(ack, player.position);
+                                    }
+                                } else {
+                                    self.remote.push(
+                                        player.id,
+                                        time,
+                                        player.position,
+                                        player.rotation,
+                                    );
+                                }
+                            }
+                        }
+                        ClientEvent::Disconnected => self.remote.retain(|_| false),
+                    }
+                }
+            }
+        }
+    }
+
+    fn send(&mut self, local: Option<(Vector3<f32>, UnitQuaternion<f32>)>) {
+        match &mut self.session {
+            NetSession::Host(server) => {
+                server.flush_chat();
+
+                let in_game = server.lobby_state().in_game;
+
+                // Once a second is enough to keep lobby info fresh during a round
+                if !in_game || self.ticks % self.tick_rate == 0 {
+                    server.broadcast_lobby();
+                }
+
+                if in_game {
+                    server.broadcast_snapshot(local.map(|(position, rotation)| PlayerTransform {
+                        id: HOST_PLAYER_ID,
+                        position,
+                        rotation,
+                    }));
+                }
+            }
+            NetSession::Client {
+                client,
+                predictor,
+                lobby,
+                ready,
+                ..
+            } => {
+                client.flush_chat();
+
+                match predictor.as_ref() {
+                    // Inputs are resent until acknowledged, so a lost datagram costs nothing
+                    Some(predictor) if lobby.in_game => {
+                        client.send_inputs(predictor.unacknowledged())
+                    }
+                    // Doubles as a keep-alive while nothing else is being sent
+                    _ => client.send_ready(*ready),
+                }
+            }
+        }
+    }
+
+    // Interpolated (or briefly extrapolated) transforms of all remote players
+    pub fn remote_transforms(&self) -> Vec<(PlayerId, Vector3<f32>, UnitQuaternion<f32>)> {
+        self.remote.sample_all()
+    }
+
+    pub fn shutdown(&mut self) {
+        match &mut self.session {
+            NetSession::Host(server) => server.shutdown(),
+            NetSession::Client { client, .. } => client.disconnect(),
+        }
+    }
+}
+
+impl Drop for Network {
+    fn drop(&mut self) {
+        self.shutdown();
+    }
+}
@@ -0,0 +1,95 @@
+use crate::{
+    movement::{simulate_movement, MoveInput},
+    net::protocol::InputCommand,
+};
+use fyrox::core::algebra::Vector3;
+use std::collections::VecDeque;
+
+// Upper bound on inputs waiting for the server, older ones are dropped
+const MAX_PENDING_INPUTS: usize = 128;
+
+// Corrections further than this are snapped instead of smoothed
+const SNAP_DISTANCE: f32 = 2.0;
+
+// How quickly a visual correction error fades out, per second
+const ERROR_DECAY_RATE: f32 = 10.0;
+
+// Runs the local player's movement ahead of the server and rewinds/replays
+// unacknowledged inputs whenever an authoritative position arrives
+pub struct Predictor {
+    next_sequence: u32,
+    pending: VecDeque<InputCommand>,
+    position: Vector3<f32>,
+    // Visual offset that hides small corrections, decays towards zero
+    error: Vector3<f32>,
+}
+
+impl Predictor {
+    pub fn new(position: Vector3<f32>) -> Self {
+        Self {
+            next_sequence: 1,
+            pending: Default::default(),
+            position,
+            error: Vector3::zeros(),
+        }
+    }
+
+    pub fn predict(&mut self, input: MoveInput, dt: f32) -> Vector3<f32> {
+        let command = InputCommand {
+            sequence: self.next_sequence,
+            input,
+            dt,
+        };
+        self.next_sequence = self.next_sequence.wrapping_add(1);
+
+        self.position = simulate_movement(self.position, input, dt);
+
+        self.pending.push_back(command);
+        while self.pending.len() > MAX_PENDING_INPUTS {
+            self.pending.pop_front();
+        }
+
+        self.error *= (-ERROR_DECAY_RATE * dt).exp();
+
+        self.render_position()
+    }
+
+    pub fn reconcile(&mut self, acknowledged: u32, server_position: Vector3<f32>) {
+        // Everything up to the ack is already included in the server position
+        while self
+            .pending
+            .front()
+            .is_some_and(|command| command.sequence <= acknowledged)
+        {
+            self.pending.pop_front();
+        }
+
+        let mut corrected = server_position;
+        for command in &self.pending {
+            corrected = simulate_movement(corrected, command.input, command.dt);
+        }
+
+        let previous_render = self.render_position();
+        self.position = corrected;
+
+        let error = previous_render - corrected;
+        self.error = if error.magnitude() > SNAP_DISTANCE {
+            Vector3::zeros()
+        } else {
+            error
+        };
+    }
+
+    // Inputs the server hasn't confirmed yet, oldest first
+    pub fn unacknowledged(&self) -> impl Iterator<Item = &InputCommand> {
+        self.pending.iter()
+    }
+
+    pub fn position(&self) -> Vector3<f32> {
+        self.position
+    }
+
+    pub fn render_position(&self) -> Vector3<f32> {
+        self.position + self.error
+    }
+}
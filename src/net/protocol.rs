@@ -0,0 +1,570 @@
+use crate::movement::MoveInput;
+use fyrox::core::algebra::{Quaternion, UnitQuaternion, Vector3};
+
+pub type PlayerId = u32;
+
+// The listen-server host always owns this id
+pub const HOST_PLAYER_ID: PlayerId = 0;
+
+// Keep datagrams below a typical MTU so they are never fragmented
+pub const MAX_PACKET_SIZE: usize = 1200;
+
+pub const PROTOCOL_VERSION: u16 = 5;
+
+// Longest player or session name sent over the wire, in bytes
+pub const MAX_NAME_LEN: usize = 32;
+
+// Longest chat line sent over the wire, in bytes
+pub const MAX_CHAT_LEN: usize = 160;
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct PlayerTransform {
+    pub id: PlayerId,
+    pub position: Vector3<f32>,
+    pub rotation: UnitQuaternion<f32>,
+}
+
+// One frame of local player input, tagged so the server can acknowledge it
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct InputCommand {
+    pub sequence: u32,
+    pub input: MoveInput,
+    pub dt: f32,
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct LobbyMember {
+    pub id: PlayerId,
+    pub name: String,
+    pub ready: bool,
+    // Spectators watch without a player of their own and don't hold up the start
+    pub spectator: bool,
+}
+
+// What everybody in a session agrees on before and during a round
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct LobbyState {
+    pub session_name: String,
+    pub level: String,
+    pub in_game: bool,
+    pub members: Vec<LobbyMember>,
+}
+
+impl LobbyState {
+    pub fn everyone_ready(&self) -> bool {
+        let mut players = self.members.iter().filter(|member| !member.spectator);
+        players.clone().next().is_some() && players.all(|member| member.ready)
+    }
+}
+
+// Summary answered to session browsers without joining
+#[derive(Clone, Debug, PartialEq)]
+pub struct SessionInfo {
+    pub session_name: String,
+    pub level: String,
+    pub players: u8,
+    pub max_players: u8,
+    pub in_game: bool,
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub enum Message {
+    // Client -> server: request to join
+    Hello {
+        version: u16,
+        name: String,
+        spectator: bool,
+    },
+    // Server -> client: join accepted
+    Welcome {
+        player_id: PlayerId,
+        tick_rate: u32,
+    },
+    // Client -> server: every input the server hasn't acknowledged yet, oldest first
+    Inputs {
+        commands: Vec<InputCommand>,
+    },
+    // Server -> client: every player's transform at a server tick, plus the
+    // sequence of the recipient's last applied input
+    Snapshot {
+        tick: u32,
+        ack: u32,
+        players: Vec<PlayerTransform>,
+    },
+    Disconnect,
+    // Client -> server: lobby readiness, repeated while in the lobby
+    SetReady {
+        ready: bool,
+    },
+    // Server -> client: lobby membership, level choice and whether the round started
+    Lobby(LobbyState),
+    // Anyone -> server: ask for a session summary
+    QueryInfo,
+    // Server -> anyone: answer to a query
+    Info(SessionInfo),
+    // Client -> server: a chat line typed by the sender, numbered so repeats can be dropped
+    ChatSend {
+        sequence: u32,
+        text: String,
+    },
+    // Server -> client: a chat line for everybody, numbered by the server
+    Chat(ChatLine),
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct ChatLine {
+    pub id: u32,
+    pub sender: String,
+    pub text: String,
+}
+
+const TAG_HELLO: u8 = 1;
+const TAG_WELCOME: u8 = 2;
+const TAG_INPUTS: u8 = 3;
+const TAG_SNAPSHOT: u8 = 4;
+const TAG_DISCONNECT: u8 = 5;
+const TAG_SET_READY: u8 = 6;
+const TAG_LOBBY: u8 = 7;
+const TAG_QUERY_INFO: u8 = 8;
+const TAG_INFO: u8 = 9;
+const TAG_CHAT_SEND: u8 = 10;
+const TAG_CHAT: u8 = 11;
+
+// Bytes used by one player entry in a snapshot
+const PLAYER_TRANSFORM_SIZE: usize = 4 + 3 * 4 + 4 * 4;
+
+// How many players fit into a single snapshot datagram
+pub const MAX_PLAYERS_PER_SNAPSHOT: usize = (MAX_PACKET_SIZE - 12) / PLAYER_TRANSFORM_SIZE;
+
+// Bytes used by one input command
+const INPUT_COMMAND_SIZE: usize = 4 + 1 + 4;
+
+// How many inputs fit into a single datagram
+pub const MAX_INPUTS_PER_PACKET: usize = (MAX_PACKET_SIZE - 4) / INPUT_COMMAND_SIZE;
+
+impl Message {
+    pub fn encode(&self) -> Vec<u8> {
+        let mut writer = Writer::default();
+
+        match self {
+            Message::Hello {
+                version,
+                name,
+                spectator,
+            } => {
+                writer.u8(TAG_HELLO);
+                writer.u16(*version);
+                writer.string(name);
+                writer.bool(*spectator);
+            }
+            Message::Welcome {
+                player_id,
+                tick_rate,
+            } => {
+                writer.u8(TAG_WELCOME);
+                writer.u32(*player_id);
+                writer.u32(*tick_rate);
+            }
+            Message::Inputs { commands } => {
+                writer.u8(TAG_INPUTS);
+                // Keep the newest inputs if there are too many to fit
+                let skip = commands.len().saturating_sub(MAX_INPUTS_PER_PACKET);
+                writer.u16((commands.len() - skip) as u16);
+                for command in &commands[skip..] {
+                    writer.u32(command.sequence);
+                    writer.u8(command.input.to_bits());
+                    writer.f32(command.dt);
+                }
+            }
+            Message::Snapshot { tick, ack, players } => {
+                writer.u8(TAG_SNAPSHOT);
+                writer.u32(*tick);
+                writer.u32(*ack);
+                let count = players.len().min(MAX_PLAYERS_PER_SNAPSHOT);
+                writer.u16(count as u16);
+                for player in &players[..count] {
+                    writer.u32(player.id);
+                    writer.vector3(&player.position);
+                    writer.rotation(&player.rotation);
+                }
+            }
+            Message::Disconnect => writer.u8(TAG_DISCONNECT),
+            Message::SetReady { ready } => {
+                writer.u8(TAG_SET_READY);
+                writer.bool(*ready);
+            }
+            Message::Lobby(lobby) => {
+                writer.u8(TAG_LOBBY);
+                writer.string(&lobby.session_name);
+                writer.string(&lobby.level);
+                writer.bool(lobby.in_game);
+                let count = lobby.members.len().min(u8::MAX as usize);
+                writer.u8(count as u8);
+                for member in &lobby.members[..count] {
+                    writer.u32(member.id);
+                    writer.string(&member.name);
+                    writer.bool(member.ready);
+                    writer.bool(member.spectator);
+                }
+            }
+            Message::QueryInfo => writer.u8(TAG_QUERY_INFO),
+            Message::Info(info) => {
+                writer.u8(TAG_INFO);
+                writer.string(&info.session_name);
+                writer.string(&info.level);
+                writer.u8(info.players);
+                writer.u8(info.max_players);
+                writer.bool(info.in_game);
+            }
+            Message::ChatSend { sequence, text } => {
+                writer.u8(TAG_CHAT_SEND);
+                writer.u32(*sequence);
+                writer.text(text, MAX_CHAT_LEN);
+            }
+            Message::Chat(line) => {
+                writer.u8(TAG_CHAT);
+                writer.u32(line.id);
+                writer.string(&line.sender);
+                writer.text(&line.text, MAX_CHAT_LEN);
+            }
+        }
+
+        writer.buf
+    }
+
+    pub fn decode(bytes: &[u8]) -> Option<Message> {
+        let mut reader = Reader { bytes, pos: 0 };
+
+        let message = match reader.u8()? {
+            TAG_HELLO => Message::Hello {
+                version: reader.u16()?,
+                name: reader.string()?,
+                spectator: reader.bool()?,
+            },
+            TAG_WELCOME => Message::Welcome {
+                player_id: reader.u32()?,
+                tick_rate: reader.u32()?,
+            },
+            TAG_INPUTS => {
+                let count = reader.u16()? as usize;
+                let mut commands = Vec::with_capacity(count.min(MAX_INPUTS_PER_PACKET));
+                for _ in 0..count {
+                    commands.push(InputCommand {
+                        sequence: reader.u32()?,
+                        input: MoveInput::from_bits(reader.u8()?),
+                        dt: reader.f32()?,
+                    });
+                }
+                Message::Inputs { commands }
+            }
+            TAG_SNAPSHOT => {
+                let tick = reader.u32()?;
+                let ack = reader.u32()?;
+                let count = reader.u16()? as usize;
+                let mut players = Vec::with_capacity(count.min(MAX_PLAYERS_PER_SNAPSHOT));
+                for _ in 0..count {
+                    players.push(PlayerTransform {
+                        id: reader.u32()?,
+                        position: reader.vector3()?,
+                        rotation: reader.rotation()?,
+                    });
+                }
+                Message::Snapshot { tick, ack, players }
+            }
+            TAG_DISCONNECT => Message::Disconnect,
+            TAG_SET_READY => Message::SetReady {
+                ready: reader.bool()?,
+            },
+            TAG_LOBBY => {
+                let session_name = reader.string()?;
+                let level = reader.string()?;
+                let in_game = reader.bool()?;
+                let count = reader.u8()? as usize;
+                let mut members = Vec::with_capacity(count);
+                for _ in 0..count {
+                    members.push(LobbyMember {
+                        id: reader.u32()?,
+                        name: reader.string()?,
+                        ready: reader.bool()?,
+                        spectator: reader.bool()?,
+                    });
+                }
+                Message::Lobby(LobbyState {
+                    session_name,
+                    level,
+                    in_game,
+                    members,
+                })
+            }
+            TAG_QUERY_INFO => Message::QueryInfo,
+            TAG_INFO => Message::Info(SessionInfo {
+                session_name: reader.string()?,
+                level: reader.string()?,
+                players: reader.u8()?,
+                max_players: reader.u8()?,
+                in_game: reader.bool()?,
+            }),
+            TAG_CHAT_SEND => Message::ChatSend {
+                sequence: reader.u32()?,
+                text: reader.string()?,
+            },
+            TAG_CHAT => Message::Chat(ChatLine {
+                id: reader.u32()?,
+                sender: reader.string()?,
+                text: reader.string()?,
+            }),
+            _ => return None,
+        };
+
+        Some(message)
+    }
+}
+
+// Cuts a name down to MAX_NAME_LEN bytes without splitting a character
+pub fn truncate_name(name: &str) -> &str {
+    truncate_str(name, MAX_NAME_LEN)
+}
+
+pub fn truncate_str(value: &str, max_len: usize) -> &str {
+    if value.len() <= max_len {
+        return value;
+    }
+    let mut end = max_len;
+    while !value.is_char_boundary(end) {
+        end -= 1;
+    }
+    &value[..end]
+}
+
+#[derive(Default)]
+struct Writer {
+    buf: Vec<u8>,
+}
+
+impl Writer {
+    fn u8(&mut self, value: u8) {
+        self.buf.push(value);
+    }
+
+    fn u16(&mut self, value: u16) {
+        self.buf.extend_from_slice(&value.to_le_bytes());
+    }
+
+    fn bool(&mut self, value: bool) {
+        self.u8(value as u8);
+    }
+
+    fn string(&mut self, value: &str) {
+        self.text(value, MAX_NAME_LEN);
+    }
+
+    // Length-prefixed string, max_len must fit into the u8 prefix
+    fn text(&mut self, value: &str, max_len: usize) {
+        let value = truncate_str(value, max_len.min(u8::MAX as usize));
+        self.u8(value.len() as u8);
+        self.buf.extend_from_slice(value.as_bytes());
+    }
+
+    fn u32(&mut self, value: u32) {
+        self.buf.extend_from_slice(&value.to_le_bytes());
+    }
+
+    fn f32(&mut self, value: f32) {
+        self.buf.extend_from_slice(&value.to_le_bytes());
+    }
+
+    fn vector3(&mut self, value: &Vector3<f32>) {
+        self.f32(value.x);
+        self.f32(value.y);
+        self.f32(value.z);
+    }
+
+    fn rotation(&mut self, value: &UnitQuaternion<f32>) {
+        let q = value.quaternion();
+        self.f32(q.i);
+        self.f32(q.j);
+        self.f32(q.k);
+        self.f32(q.w);
+    }
+}
+
+struct Reader<'a> {
+    bytes: &'a [u8],
+    pos: usize,
+}
+
+impl<'a> Reader<'a> {
+    fn take<const N: usize>(&mut self) -> Option<[u8; N]> {
+        let slice = self.bytes.get(self.pos..self.pos + N)?;
+        self.pos += N;
+        slice.try_into().ok()
+    }
+
+    fn u8(&mut self) -> Option<u8> {
+        self.take::<1>().map(|b| b[0])
+    }
+
+    fn u16(&mut self) -> Option<u16> {
+        self.take().map(u16::from_le_bytes)
+    }
+
+    fn bool(&mut self) -> Option<bool> {
+        self.u8().map(|value| value != 0)
+    }
+
+    fn string(&mut self) -> Option<String> {
+        let len = self.u8()? as usize;
+        let slice = self.bytes.get(self.pos..self.pos + len)?;
+        self.pos += len;
+        String::from_utf8(slice.to_vec()).ok()
+    }
+
+    fn u32(&mut self) -> Option<u32> {
+        self.take().map(u32::from_le_bytes)
+    }
+
+    fn f32(&mut self) -> Option<f32> {
+        let value = self.take().map(f32::from_le_bytes)?;
+        // Never let garbage from the wire poison the scene graph
+        value.is_finite().then_some(value)
+    }
+
+    fn vector3(&mut self) -> Option<Vector3<f32>> {
+        Some(Vector3::new(self.f32()?, self.f32()?, self.f32()?))
+    }
+
+    fn rotation(&mut self) -> Option<UnitQuaternion<f32>> {
+        let (i, j, k, w) = (self.f32()?, self.f32()?, self.f32()?, self.f32()?);
+        let quaternion = Quaternion::new(w, i, j, k);
+        if quaternion.norm_squared() <= f32::EPSILON {
+            return Some(UnitQuaternion::identity());
+        }
+        Some(UnitQuaternion::new_normalize(quaternion))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn messages() -> Vec<Message> {
+        vec![
+            Message::Hello {
+                version: PROTOCOL_VERSION,
+                name: "Ada".to_string(),
+                spectator: true,
+            },
+            Message::Welcome {
+                player_id: 3,
+                tick_rate: 60,
+            },
+            Message::Inputs {
+                commands: vec![InputCommand {
+                    sequence: 9,
+                    input: MoveInput {
+                        forward: true,
+                        right: true,
+                        ..Default::default()
+                    },
+                    dt: 1.0 / 60.0,
+                }],
+            },
+            Message::Snapshot {
+                tick: 100,
+                ack: 9,
+                players: vec![PlayerTransform {
+                    id: HOST_PLAYER_ID,
+                    position: Vector3::new(1.0, -2.0, 3.5),
+                    rotation: UnitQuaternion::identity(),
+                }],
+            },
+            Message::Disconnect,
+            Message::SetReady { ready: true },
+            Message::Lobby(LobbyState {
+                session_name: "Evening".to_string(),
+                level: "meadow".to_string(),
+                in_game: false,
+                members: vec![LobbyMember {
+                    id: 1,
+                    name: "Grace".to_string(),
+                    ready: true,
+                    spectator: false,
+                }],
+            }),
+            Message::QueryInfo,
+            Message::Info(SessionInfo {
+                session_name: "Evening".to_string(),
+                level: "meadow".to_string(),
+                players: 2,
+                max_players: 8,
+                in_game: true,
+            }),
+            Message::ChatSend {
+                sequence: 4,
+                text: "gg".to_string(),
+            },
+            Message::Chat(ChatLine {
+                id: 12,
+                sender: "Ada".to_string(),
+                text: "gg".to_string(),
+            }),
+        ]
+    }
+
+    #[test]
+    fn every_message_survives_the_round_trip() {
+        for message in messages() {
+            let bytes = message.encode();
+            assert!(bytes.len() <= MAX_PACKET_SIZE);
+            assert_eq!(Message::decode(&bytes), Some(message));
+        }
+    }
+
+    #[test]
+    fn truncated_messages_are_rejected() {
+        for message in messages() {
+            let bytes = message.encode();
+            for len in 0..bytes.len() {
+                assert_eq!(
+                    Message::decode(&bytes[..len]),
+                    None,
+                    "{message:?} cut to {len} bytes"
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn unknown_tags_and_non_finite_numbers_are_rejected() {
+        assert_eq!(Message::decode(&[0]), None);
+        assert_eq!(Message::decode(&[u8::MAX]), None);
+
+        let mut bytes = Message::Inputs {
+            commands: vec![InputCommand {
+                sequence: 1,
+                input: MoveInput::default(),
+                dt: 0.0,
+            }],
+        }
+        .encode();
+        let dt = bytes.len() - 4;
+        bytes[dt..].copy_from_slice(&f32::NAN.to_le_bytes());
+        assert_eq!(Message::decode(&bytes), None);
+    }
+
+    #[test]
+    fn long_names_are_cut_on_character_boundaries() {
+        let name = "é".repeat(MAX_NAME_LEN);
+        let bytes = Message::Hello {
+            version: PROTOCOL_VERSION,
+            name: name.clone(),
+            spectator: false,
+        }
+        .encode();
+        let Some(Message::Hello { name: sent, .. }) = Message::decode(&bytes) else {
+            panic!("hello didn't decode");
+        };
+        assert_eq!(sent, truncate_name(&name));
+        assert_eq!(sent.chars().count(), MAX_NAME_LEN / 2);
+    }
+}
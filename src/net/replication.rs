@@ -0,0 +1,230 @@
+use crate::net::protocol::PlayerId;
+use fyrox::core::{
+    algebra::{UnitQuaternion, Vector3},
+    instant::Instant,
+};
+use std::collections::{HashMap, VecDeque};
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum ConnectionQuality {
+    Lan,
+    Good,
+    Poor,
+}
+
+impl ConnectionQuality {
+    pub fn from_name(name: &str) -> Option<Self> {
+        match name.to_ascii_lowercase().as_str() {
+            "lan" => Some(Self::Lan),
+            "good" => Some(Self::Good),
+            "poor" => Some(Self::Poor),
+            _ => None,
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct InterpolationSettings {
+    // How far behind the newest known remote time entities are rendered, in seconds
+    pub delay: f32,
+    // Maximum number of snapshots kept per entity
+    pub buffer_size: usize,
+    // How long to keep extrapolating past the newest snapshot before freezing, in seconds
+    pub max_extrapolation: f32,
+}
+
+impl InterpolationSettings {
+    pub fn for_quality(quality: ConnectionQuality) -> Self {
+        match quality {
+            ConnectionQuality::Lan => Self {
+                delay: 0.05,
+                buffer_size: 8,
+                max_extrapolation: 0.1,
+            },
+            ConnectionQuality::Good => Self {
+                delay: 0.1,
+                buffer_size: 16,
+                max_extrapolation: 0.2,
+            },
+            ConnectionQuality::Poor => Self {
+                delay: 0.25,
+                buffer_size: 32,
+                max_extrapolation: 0.35,
+            },
+        }
+    }
+}
+
+impl Default for InterpolationSettings {
+    fn default() -> Self {
+        Self::for_quality(ConnectionQuality::Good)
+    }
+}
+
+#[derive(Clone, Copy, Debug)]
+struct Snapshot {
+    time: f32,
+    position: Vector3<f32>,
+    rotation: UnitQuaternion<f32>,
+}
+
+// Time-ordered transform history of a single remote entity
+#[derive(Default)]
+pub struct SnapshotBuffer {
+    snapshots: VecDeque<Snapshot>,
+}
+
+impl SnapshotBuffer {
+    pub fn push(
+        &mut self,
+        time: f32,
+        position: Vector3<f32>,
+        rotation: UnitQuaternion<f32>,
+        capacity: usize,
+    ) {
+        // Late or duplicated datagrams are useless once something newer arrived
+        if let Some(newest) = self.snapshots.back() {
+            if time <= newest.time {
+                return;
+            }
+        }
+
+        self.snapshots.push_back(Snapshot {
+            time,
+            position,
+            rotation,
+        });
+
+        while self.snapshots.len() > capacity.max(2) {
+            self.snapshots.pop_front();
+        }
+    }
+
+    pub fn sample(
+        &self,
+        time: f32,
+        max_extrapolation: f32,
+    ) -> Option<(Vector3<f32>, UnitQuaternion<f32>)> {
+        let oldest = self.snapshots.front()?;
+        let newest = self.snapshots.back()?;
+
+        if time <= oldest.time {
+            return Some((oldest.position, oldest.rotation));
+        }
+
+        if time >= newest.time {
+            // Ran out of data - keep the entity moving along its last known velocity for a bit
+            if self.snapshots.len() >= 2 {
+                let previous = &self.snapshots[self.snapshots.len() - 2];
+                let span = newest.time - previous.time;
+                if span > f32::EPSILON {
+                    let ahead = (time - newest.time).min(max_extrapolation);
+                    let velocity = (newest.position - previous.position) / span;
+                    return Some((newest.position + velocity * ahead, newest.rotation));
+                }
+            }
+            return Some((newest.position, newest.rotation));
+        }
+
+        for (a, b) in self.snapshots.iter().zip(self.snapshots.iter().skip(1)) {
+            if time >= a.time && time <= b.time {
+                let t = (time - a.time) / (b.time - a.time);
+                return Some((
+                    a.position.lerp(&b.position, t),
+                    a.rotation.slerp(&b.rotation, t),
+                ));
+            }
+        }
+
+        None
+    }
+}
+
+// Estimates the current remote time from the newest sample and the local clock
+#[derive(Default)]
+struct RemoteClock {
+    latest: f32,
+    received_at: Option<Instant>,
+}
+
+impl RemoteClock {
+    fn observe(&mut self, time: f32) {
+        if self.received_at.is_none() || time > self.latest {
+            self.latest = time;
+            self.received_at = Some(Instant::now());
+        }
+    }
+
+    fn now(&self) -> f32 {
+        match self.received_at {
+            Some(received_at) => self.latest + received_at.elapsed().as_secs_f32(),
+            None => 0.0,
+        }
+    }
+}
+
+// Buffers replicated transforms of every remote player and renders them slightly in the past
+#[derive(Default)]
+pub struct RemoteEntities {
+    settings: InterpolationSettings,
+    clock: RemoteClock,
+    buffers: HashMap<PlayerId, SnapshotBuffer>,
+}
+
+impl RemoteEntities {
+    pub fn new(settings: InterpolationSettings) -> Self {
+        Self {
+            settings,
+            ..Default::default()
+        }
+    }
+
+    pub fn settings(&self) -> InterpolationSettings {
+        self.settings
+    }
+
+    pub fn set_settings(&mut self, settings: InterpolationSettings) {
+        self.settings = settings;
+    }
+
+    pub fn push(
+        &mut self,
+        id: PlayerId,
+        time: f32,
+        position: Vector3<f32>,
+        rotation: UnitQuaternion<f32>,
+    ) {
+        self.clock.observe(time);
+        self.buffers.entry(id).or_default().push(
+            time,
+            position,
+            rotation,
+            self.settings.buffer_size,
+        );
+    }
+
+    pub fn remove(&mut self, id: PlayerId) {
+        self.buffers.remove(&id);
+    }
+
+    pub fn retain(&mut self, mut keep: impl FnMut(PlayerId) -> bool) {
+        self.buffers.retain(|id, _| keep(*id));
+    }
+
+    pub fn ids(&self) -> impl Iterator<Item = PlayerId> + '_ {
+        self.buffers.keys().copied()
+    }
+
+    pub fn sample_all(&self) -> Vec<(PlayerId, Vector3<f32>, UnitQuaternion<f32>)> {
+        let render_time = self.clock.now() - self.settings.delay;
+
+        self.buffers
+            .iter()
+            .filter_map(|(id, buffer)| {
+                buffer
+                    .sample(render_time, self.settings.max_extrapolation)
+                    .map(|(position, rotation)| (*id, position, rotation))
+            })
+            .collect()
+    }
+}
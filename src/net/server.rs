@@ -0,0 +1,523 @@
+use crate::{
+    level::{default_level, find_level},
+    movement::simulate_movement,
+    net::{
+        chat::ChatOutbox,
+        discovery::DiscoveryResponder,
+        protocol::{
+            truncate_name, truncate_str, ChatLine, InputCommand, LobbyMember, LobbyState, Message,
+            PlayerId, PlayerTransform, SessionInfo, HOST_PLAYER_ID, MAX_CHAT_LEN, MAX_PACKET_SIZE,
+            PROTOCOL_VERSION,
+        },
+    },
+};
+use fyrox::core::{algebra::Vector3, instant::Instant};
+use std::{
+    collections::HashMap,
+    io,
+    net::{SocketAddr, UdpSocket},
+    time::Duration,
+};
+
+// Clients that stay silent for this long are considered gone
+const CLIENT_TIMEOUT: Duration = Duration::from_secs(5);
+
+// Longest step a single input may cover, so a client can't fake large time deltas to speed up
+const MAX_INPUT_DT: f32 = 0.25;
+
+// Movement time a client may have saved up. Inputs can only spend what the server's clock
+// has given them, so many made-up commands in one packet don't add up to a speed hack.
+// A little slack covers packets that arrive bunched together.
+const MAX_INPUT_BUDGET: f32 = 0.5;
+
+pub const DEFAULT_MAX_PLAYERS: u8 = 8;
+
+pub enum ServerEvent {
+    PlayerJoined(PlayerId),
+    PlayerLeft(PlayerId),
+    PlayerMoved(PlayerTransform),
+    Chat(ChatLine),
+}
+
+struct RemoteClient {
+    name: String,
+    ready: bool,
+    spectator: bool,
+    transform: PlayerTransform,
+    // Sequence of the newest input applied to the authoritative transform
+    last_input: u32,
+    // Seconds of movement the client's inputs may still cover, refilled by the server's clock
+    input_budget: f32,
+    budget_refilled: Instant,
+    // Sequence of the newest chat line received from this client
+    last_chat: u32,
+    last_heard: Instant,
+}
+
+impl RemoteClient {
+    fn new(name: String, spectator: bool, transform: PlayerTransform) -> Self {
+        let now = Instant::now();
+        Self {
+            name,
+            ready: false,
+            spectator,
+            transform,
+            last_input: 0,
+            input_budget: 0.0,
+            budget_refilled: now,
+            last_chat: 0,
+            last_heard: now,
+        }
+    }
+
+    fn apply_inputs(&mut self, commands: &[InputCommand], now: Instant) -> bool {
+        let elapsed = now.duration_since(self.budget_refilled).as_secs_f32();
+        self.input_budget = (self.input_budget + elapsed).min(MAX_INPUT_BUDGET);
+        self.budget_refilled = now;
+
+        let mut moved = false;
+        for command in commands {
+            // Inputs are resent until acknowledged, skip the ones we already have
+            if command.sequence <= self.last_input {
+                continue;
+            }
+            self.last_input = command.sequence;
+            let dt = command.dt.clamp(0.0, MAX_INPUT_DT);
+            if dt.is_nan() || dt > self.input_budget {
+                // More time than has passed, the next snapshot corrects the client's prediction
+                continue;
+            }
+            self.input_budget -= dt;
+            self.transform.position = simulate_movement(self.transform.position, command.input, dt);
+            moved = true;
+        }
+        moved
+    }
+}
+
+pub struct NetServer {
+    socket: UdpSocket,
+    discovery: Option<DiscoveryResponder>,
+    clients: HashMap<SocketAddr, RemoteClient>,
+    next_player_id: PlayerId,
+    tick: u32,
+    tick_rate: u32,
+    session_name: String,
+    // None on a dedicated server, where nobody plays on the server itself
+    host_name: Option<String>,
+    host_ready: bool,
+    level: String,
+    in_game: bool,
+    max_players: u8,
+    next_chat_id: u32,
+    chat_outbox: ChatOutbox<ChatLine>,
+}
+
+impl NetServer {
+    // Listen server, the host plays on it as HOST_PLAYER_ID
+    pub fn bind(port: u16, tick_rate: u32, host_name: &str) -> io::Result<Self> {
+        let session_name = format!("{host_name}'s game");
+        Self::bind_with(port, tick_rate, &session_name, Some(host_name))
+    }
+
+    // Dedicated server without a local player
+    pub fn bind_dedicated(port: u16, tick_rate: u32, session_name: &str) -> io::Result<Self> {
+        Self::bind_with(port, tick_rate, session_name, None)
+    }
+
+    fn bind_with(
+        port: u16,
+        tick_rate: u32,
+        session_name: &str,
+        host_name: Option<&str>,
+    ) -> io::Result<Self> {
+        let socket = UdpSocket::bind(("0.0.0.0", port))?;
+        socket.set_nonblocking(true)?;
+
+        Ok(Self {
+            socket,
+            // Being invisible on the LAN is no reason to refuse hosting
+            discovery: DiscoveryResponder::bind().ok(),
+            clients: Default::default(),
+            next_player_id: HOST_PLAYER_ID + 1,
+            tick: 0,
+            tick_rate,
+            session_name: truncate_name(session_name).to_string(),
+            host_name: host_name.map(|name| truncate_name(name).to_string()),
+            host_ready: false,
+            level: default_level().id.to_string(),
+            in_game: false,
+            max_players: DEFAULT_MAX_PLAYERS,
+            next_chat_id: 1,
+            chat_outbox: Default::default(),
+        })
+    }
+
+    pub fn tick_rate(&self) -> u32 {
+        self.tick_rate
+    }
+
+    pub fn is_dedicated(&self) -> bool {
+        self.host_name.is_none()
+    }
+
+    pub fn level(&self) -> &str {
+        &self.level
+    }
+
+    pub fn in_game(&self) -> bool {
+        self.in_game
+    }
+
+    // Everybody in the session, the host included
+    pub fn player_count(&self) -> usize {
+        self.clients.len() + self.host_name.is_some() as usize
+    }
+
+    pub fn set_host_ready(&mut self, ready: bool) {
+        self.host_ready = ready;
+    }
+
+    pub fn set_level(&mut self, level: &str) {
+        // The level is locked once the round is running
+        if !self.in_game {
+            self.level = level.to_string();
+        }
+    }
+
+    pub fn lobby_state(&self) -> LobbyState {
+        let host = self.host_name.as_ref().map(|name| LobbyMember {
+            id: HOST_PLAYER_ID,
+            name: name.clone(),
+            ready: self.host_ready,
+            spectator: false,
+        });
+
+        let mut members: Vec<_> = host
+            .into_iter()
+            .chain(self.clients.values().map(|client| LobbyMember {
+                id: client.transform.id,
+                name: client.name.clone(),
+                ready: client.ready,
+                spectator: client.spectator,
+            }))
+            .collect();
+        members.sort_by_key(|member| member.id);
+
+        LobbyState {
+            session_name: self.session_name.clone(),
+            level: self.level.clone(),
+            in_game: self.in_game,
+            members,
+        }
+    }
+
+    // Starts the round if everybody is ready, moving every client to its spawn point
+    pub fn start_game(&mut self) -> bool {
+        if self.in_game || !self.lobby_state().everyone_ready() {
+            return false;
+        }
+
+        self.in_game = true;
+        for client in self.clients.values_mut() {
+            client.transform.position = spawn_position(&self.level, client.transform.id);
+        }
+        true
+    }
+
+    // Back to the lobby, everybody has to ready up again for the next round
+    pub fn end_round(&mut self) {
+        self.in_game = false;
+        self.host_ready = false;
+        for client in self.clients.values_mut() {
+            client.ready = false;
+        }
+    }
+
+    pub fn poll(&mut self) -> Vec<ServerEvent> {
+        let mut events = Vec::new();
+        let mut buf = [0u8; MAX_PACKET_SIZE];
+
+        loop {
+            let (len, addr) = match self.socket.recv_from(&mut buf) {
+                Ok(received) => received,
+                Err(err) if err.kind() == io::ErrorKind::WouldBlock => break,
+                // ICMP "port unreachable" from a vanished client shows up here on some platforms
+                Err(_) => continue,
+            };
+
+            let Some(message) = Message::decode(&buf[..len]) else {
+                continue;
+            };
+
+            self.handle_message(addr, message, &mut events);
+        }
+
+        // Answer LAN browsers from the game socket, so the reply carries the address to join
+        if let Some(discovery) = self.discovery.as_ref() {
+            let info = Message::Info(self.session_info());
+            for addr in discovery.poll_queries() {
+                self.send(addr, &info);
+            }
+        }
+
+        let now = Instant::now();
+        self.clients.retain(|_, client| {
+            let alive = now.duration_since(client.last_heard) < CLIENT_TIMEOUT;
+            if !alive {
+                events.push(ServerEvent::PlayerLeft(client.transform.id));
+            }
+            alive
+        });
+
+        events
+    }
+
+    fn handle_message(
+        &mut self,
+        addr: SocketAddr,
+        message: Message,
+        events: &mut Vec<ServerEvent>,
+    ) {
+        match message {
+            Message::Hello {
+                version,
+                name,
+                spectator,
+            } => {
+                let full = self.player_count() >= self.max_players as usize;
+                if version != PROTOCOL_VERSION || (full && !self.clients.contains_key(&addr)) {
+                    self.send(addr, &Message::Disconnect);
+                    return;
+                }
+
+                let id = match self.clients.get_mut(&addr) {
+                    // Our welcome got lost, the client is asking again
+                    Some(client) => {
+                        client.last_heard = Instant::now();
+                        client.transform.id
+                    }
+                    None => {
+                        let id = self.next_player_id;
+                        self.next_player_id += 1;
+                        let transform = PlayerTransform {
+                            id,
+                            position: spawn_position(&self.level, id),
+                            rotation: Default::default(),
+                        };
+                        self.clients
+                            .insert(addr, RemoteClient::new(name, spectator, transform));
+                        events.push(ServerEvent::PlayerJoined(id));
+                        id
+                    }
+                };
+
+                self.send(
+                    addr,
+                    &Message::Welcome {
+                        player_id: id,
+                        tick_rate: self.tick_rate,
+                    },
+                );
+            }
+            Message::Inputs { commands } => {
+                if let Some(client) = self.clients.get_mut(&addr) {
+                    client.last_heard = Instant::now();
+                    if self.in_game
+                        && !client.spectator
+                        && client.apply_inputs(&commands, Instant::now())
+                    {
+                        events.push(ServerEvent::PlayerMoved(client.transform.clone()));
+                    }
+                }
+            }
+            Message::SetReady { ready } => {
+                if let Some(client) = self.clients.get_mut(&addr) {
+                    client.ready = ready;
+                    client.last_heard = Instant::now();
+                }
+            }
+            Message::ChatSend { sequence, text } => {
+                let Some(client) = self.clients.get_mut(&addr) else {
+                    return;
+                };
+                client.last_heard = Instant::now();
+                if sequence <= client.last_chat {
+                    return;
+                }
+                client.last_chat = sequence;
+                let sender = client.name.clone();
+                if let Some(line) = self.post_chat(sender, &text) {
+                    events.push(ServerEvent::Chat(line));
+                }
+            }
+            Message::QueryInfo => self.send(addr, &Message::Info(self.session_info())),
+            Message::Disconnect => {
+                if let Some(client) = self.clients.remove(&addr) {
+                    events.push(ServerEvent::PlayerLeft(client.transform.id));
+                }
+            }
+            Message::Welcome { .. }
+            | Message::Snapshot { .. }
+            | Message::Lobby(_)
+            | Message::Info(_)
+            | Message::Chat(_) => {}
+        }
+    }
+
+    pub fn session_info(&self) -> SessionInfo {
+        SessionInfo {
+            session_name: self.session_name.clone(),
+            level: self.level.clone(),
+            players: self.player_count().min(u8::MAX as usize) as u8,
+            max_players: self.max_players,
+            in_game: self.in_game,
+        }
+    }
+
+    // Chat typed by the host, returned so the host can show it right away
+    pub fn host_chat(&mut self, text: &str) -> Option<ChatLine> {
+        let sender = self
+            .host_name
+            .clone()
+            .unwrap_or_else(|| "Server".to_string());
+        self.post_chat(sender, text)
+    }
+
+    fn post_chat(&mut self, sender: String, text: &str) -> Option<ChatLine> {
+        let text = truncate_str(text.trim(), MAX_CHAT_LEN);
+        if text.is_empty() {
+            return None;
+        }
+
+        let line = ChatLine {
+            id: self.next_chat_id,
+            sender,
+            text: text.to_string(),
+        };
+        self.next_chat_id += 1;
+        self.chat_outbox.push(line.clone());
+        Some(line)
+    }
+
+    pub fn flush_chat(&mut self) {
+        for line in self.chat_outbox.take_tick() {
+            let bytes = Message::Chat(line).encode();
+            for addr in self.clients.keys() {
+                let _ = self.socket.send_to(&bytes, addr);
+            }
+        }
+    }
+
+    pub fn broadcast_lobby(&mut self) {
+        let bytes = Message::Lobby(self.lobby_state()).encode();
+        for addr in self.clients.keys() {
+            let _ = self.socket.send_to(&bytes, addr);
+        }
+    }
+
+    // Sends every client the transforms of all players, including the host's own
+    pub fn broadcast_snapshot(&mut self, host: Option<PlayerTransform>) {
+        self.tick = self.tick.wrapping_add(1);
+
+        let players: Vec<_> = host
+            .into_iter()
+            .chain(
+                self.clients
+                    .values()
+                    .filter(|client| !client.spectator)
+                    .map(|client| client.transform.clone()),
+            )
+            .collect();
+
+        // Every client gets its own ack, so the snapshot is encoded per recipient
+        for (addr, client) in self.clients.iter() {
+            let bytes = Message::Snapshot {
+                tick: self.tick,
+                ack: client.last_input,
+                players: players.clone(),
+            }
+            .encode();
+            let _ = self.socket.send_to(&bytes, addr);
+        }
+    }
+
+    pub fn shutdown(&mut self) {
+        let bytes = Message::Disconnect.encode();
+        for addr in self.clients.keys() {
+            let _ = self.socket.send_to(&bytes, addr);
+        }
+        self.clients.clear();
+    }
+
+    fn send(&self, addr: SocketAddr, message: &Message) {
+        let _ = self.socket.send_to(&message.encode(), addr);
+    }
+}
+
+pub fn spawn_position(level: &str, id: PlayerId) -> Vector3<f32> {
+    find_level(level)
+        .unwrap_or_else(default_level)
+        .spawn_point(id as usize)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::movement::MoveInput;
+
+    fn client() -> RemoteClient {
+        let transform = PlayerTransform {
+            id: 2,
+            position: Vector3::default(),
+            rotation: Default::default(),
+        };
+        RemoteClient::new("Bob".to_string(), false, transform)
+    }
+
+    fn forward(count: u32, dt: f32) -> Vec<InputCommand> {
+        let input = MoveInput {
+            forward: true,
+            ..Default::default()
+        };
+        (1..=count)
+            .map(|sequence| InputCommand {
+                sequence,
+                input,
+                dt,
+            })
+            .collect()
+    }
+
+    #[test]
+    fn inputs_within_elapsed_time_move_the_player() {
+        let mut client = client();
+        let now = client.budget_refilled + Duration::from_millis(100);
+        assert!(client.apply_inputs(&forward(5, 0.02), now));
+        let expected = simulate_movement(Vector3::default(), forward(1, 0.0)[0].input, 0.1);
+        assert!((client.transform.position - expected).norm() < 1e-4);
+    }
+
+    #[test]
+    fn a_packet_of_made_up_inputs_moves_no_further_than_the_budget() {
+        let mut client = client();
+        let now = client.budget_refilled + Duration::from_secs(1);
+        client.apply_inputs(&forward(400, MAX_INPUT_DT), now);
+        let limit = simulate_movement(
+            Vector3::default(),
+            forward(1, 0.0)[0].input,
+            MAX_INPUT_BUDGET,
+        );
+        assert!(client.transform.position.norm() <= limit.norm() + 1e-4);
+        // Dropped inputs are still acknowledged, they aren't resent forever
+        assert_eq!(client.last_input, 400);
+    }
+
+    #[test]
+    fn nan_time_deltas_are_dropped() {
+        let mut client = client();
+        let now = client.budget_refilled + Duration::from_secs(1);
+        assert!(!client.apply_inputs(&forward(3, f32::NAN), now));
+        assert_eq!(client.transform.position, Vector3::default());
+    }
+}
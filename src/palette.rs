@@ -0,0 +1,220 @@
+use crate::{console::CommandRegistry, error::GameError, game::Game};
+use fyrox::{
+    asset::manager::ResourceManager,
+    core::color::Color,
+    material::{Material, MaterialResource, PropertyValue},
+    plugin::PluginContext,
+};
+use log::warn;
+use std::sync::{
+    atomic::{AtomicU8, Ordering},
+    Mutex,
+};
+
+// Which kind of color vision the palette is made for
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum ColorVision {
+    #[default]
+    Normal,
+    // Red-green, weak green cones, the most common
+    Deuteranopia,
+    // Red-green, weak red cones, reds look dark
+    Protanopia,
+    // Blue-yellow
+    Tritanopia,
+}
+
+impl ColorVision {
+    pub const ALL: [ColorVision; 4] = [
+        ColorVision::Normal,
+        ColorVision::Deuteranopia,
+        ColorVision::Protanopia,
+        ColorVision::Tritanopia,
+    ];
+
+    pub fn name(self) -> &'static str {
+        match self {
+            ColorVision::Normal => "normal",
+            ColorVision::Deuteranopia => "deuteranopia",
+            ColorVision::Protanopia => "protanopia",
+            ColorVision::Tritanopia => "tritanopia",
+        }
+    }
+
+    pub fn from_name(name: &str) -> Option<Self> {
+        Self::ALL.into_iter().find(|vision| vision.name() == name)
+    }
+
+    pub fn next(self) -> Self {
+        Self::ALL[(self as usize + 1) % Self::ALL.len()]
+    }
+}
+
+// Colors that tell the player something. Everything that draws one of these asks
+// the palette for it instead of using a literal.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum ColorRole {
+    Player,
+    RemotePlayer,
+    Ground,
+    Obstacle,
+    Goal,
+    Coin,
+    HealthPack,
+    Enemy,
+    // An enemy that is after the player
+    EnemyAlert,
+    Crate,
+}
+
+const ROLES: usize = 10;
+
+// In ColorRole order. The alternatives lean on the Okabe-Ito colors and keep
+// things that must not be confused apart in brightness as well as hue.
+const NORMAL: [[u8; 3]; ROLES] = [
+    [0, 100, 255],
+    [255, 80, 80],
+    [100, 150, 100],
+    [200, 100, 50],
+    [255, 220, 40],
+    [255, 200, 0],
+    [60, 220, 90],
+    [150, 60, 200],
+    [255, 60, 60],
+    [150, 100, 50],
+];
+const DEUTERANOPIA: [[u8; 3]; ROLES] = [
+    [0, 114, 178],
+    [204, 121, 167],
+    [125, 125, 115],
+    [90, 80, 70],
+    [240, 228, 66],
+    [230, 159, 0],
+    [86, 180, 233],
+    [213, 94, 0],
+    [255, 255, 255],
+    [160, 140, 110],
+];
+const PROTANOPIA: [[u8; 3]; ROLES] = [
+    [0, 114, 178],
+    [230, 170, 200],
+    [125, 125, 115],
+    [90, 80, 70],
+    [240, 228, 66],
+    [230, 159, 0],
+    [86, 180, 233],
+    [245, 130, 40],
+    [255, 255, 255],
+    [160, 140, 110],
+];
+const TRITANOPIA: [[u8; 3]; ROLES] = [
+    [0, 150, 170],
+    [220, 50, 32],
+    [115, 115, 115],
+    [80, 60, 60],
+    [255, 255, 255],
+    [255, 130, 160],
+    [120, 200, 110],
+    [170, 0, 60],
+    [255, 40, 40],
+    [150, 110, 90],
+];
+
+static VISION: AtomicU8 = AtomicU8::new(ColorVision::Normal as u8);
+
+// Materials handed out by `material`, recolored in place when the palette changes
+static MATERIALS: Mutex<Vec<(ColorRole, MaterialResource)>> = Mutex::new(Vec::new());
+
+pub fn vision() -> ColorVision {
+    ColorVision::ALL[VISION.load(Ordering::Relaxed) as usize]
+}
+
+// Recolors everything built from the palette's materials right away. Level
+// geometry follows once its MaterialLibrary is refreshed.
+pub fn set_vision(vision: ColorVision) {
+    VISION.store(vision as u8, Ordering::Relaxed);
+    for (role, material) in MATERIALS.lock().unwrap().iter() {
+        if let Err(err) = material.data_ref().set_property(
+            &fyrox::material::DIFFUSE_COLOR,
+            PropertyValue::Color(color(*role)),
+        ) {
+            warn!("Couldn't recolor the {role:?} material: {err:?}");
+        }
+    }
+}
+
+pub fn color(role: ColorRole) -> Color {
+    let table = match vision() {
+        ColorVision::Normal => &NORMAL,
+        ColorVision::Deuteranopia => &DEUTERANOPIA,
+        ColorVision::Protanopia => &PROTANOPIA,
+        ColorVision::Tritanopia => &TRITANOPIA,
+    };
+    let [r, g, b] = table[role as usize];
+    Color::opaque(r, g, b)
+}
+
+// For colors that come from data files: kept as they are with normal vision,
+// replaced by the palette's otherwise
+pub fn remap(role: ColorRole, normal: Color) -> Color {
+    match vision() {
+        ColorVision::Normal => normal,
+        _ => color(role),
+    }
+}
+
+// The shared material for everything drawn in `role`'s color
+pub fn material(
+    resource_manager: &ResourceManager,
+    role: ColorRole,
+) -> Result<MaterialResource, GameError> {
+    let mut materials = MATERIALS.lock().unwrap();
+    if let Some((_, material)) = materials.iter().find(|(existing, _)| *existing == role) {
+        return Ok(material.clone());
+    }
+
+    let mut material = Material::standard();
+    material.set_property(
+        &fyrox::material::DIFFUSE_COLOR,
+        PropertyValue::Color(color(role)),
+    )?;
+    let material = resource_manager
+        .state()
+        .containers_mut()
+        .materials
+        .add(material);
+    materials.push((role, material.clone()));
+    Ok(material)
+}
+
+pub fn register_commands(commands: &mut CommandRegistry) {
+    commands.register(
+        "palette",
+        "[NAME]",
+        "show or switch the color palette",
+        command_palette,
+    );
+    commands.complete_with("palette", || {
+        ColorVision::ALL
+            .iter()
+            .map(|vision| vision.name().to_string())
+            .collect()
+    });
+}
+
+fn command_palette(
+    game: &mut Game,
+    context: &mut PluginContext,
+    args: &[&str],
+) -> Result<String, String> {
+    match args {
+        [] => Ok(format!("palette {}", vision().name())),
+        [name] => {
+            let vision =
+                ColorVision::from_name(name).ok_or_else(|| format!("no palette {name:?}"))?;
+            game.set_color_vision(context, vision);
+            Ok(format!("palette {}", vision.name()))
+        }
+        _ => Err("usage: palette [NAME]".to_string()),
+    }
+}
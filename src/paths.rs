@@ -0,0 +1,30 @@
+use std::{env, path::PathBuf, sync::OnceLock};
+
+const APP_DIR: &str = "simple-fyrox-game";
+
+// Set by platforms that hand the app its directory instead of following a convention
+static APP_DATA_DIR: OnceLock<PathBuf> = OnceLock::new();
+
+// Takes the place of the conventional directory, only the first call counts
+pub fn set_data_dir(dir: PathBuf) {
+    let _ = APP_DATA_DIR.set(dir);
+}
+
+// Per-user directory for files the game writes, following each platform's convention
+pub fn data_dir() -> PathBuf {
+    if let Some(dir) = APP_DATA_DIR.get() {
+        return dir.clone();
+    }
+    let base = if cfg!(windows) {
+        env::var_os("APPDATA").map(PathBuf::from)
+    } else if cfg!(target_os = "macos") {
+        env::var_os("HOME").map(|home| PathBuf::from(home).join("Library/Application Support"))
+    } else {
+        env::var_os("XDG_DATA_HOME")
+            .map(PathBuf::from)
+            .or_else(|| env::var_os("HOME").map(|home| PathBuf::from(home).join(".local/share")))
+    };
+
+    // Fall back to the working directory rather than failing outright
+    base.unwrap_or_else(|| PathBuf::from(".")).join(APP_DIR)
+}
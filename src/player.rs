@@ -0,0 +1,114 @@
+use crate::{
+    components::SimTransform,
+    error::GameError,
+    game::Game,
+    handles::PlayerHandle,
+    palette::{self, ColorRole},
+    profiler::Phase,
+};
+use fyrox::{
+    asset::manager::ResourceManager,
+    core::{
+        algebra::Vector3, math::Matrix4, pool::Handle, reflect::prelude::*,
+        type_traits::prelude::*, visitor::prelude::*,
+    },
+    scene::{
+        base::BaseBuilder,
+        graph::Graph,
+        mesh::{
+            surface::{Surface, SurfaceBuilder, SurfaceData},
+            MeshBuilder, RenderPath,
+        },
+        node::Node,
+        transform::TransformBuilder,
+        Scene,
+    },
+    script::{Script, ScriptContext, ScriptTrait},
+};
+
+// Moves the node it is attached to from the local player's input. Online, the
+// movement goes through the network's client-side prediction. Nodes with a
+// SimTransform are moved there and left to render interpolation.
+#[derive(Visit, Reflect, Default, Debug, Clone, TypeUuidProvider, ComponentProvider)]
+#[type_uuid(id = "5b8f3a0e-2c41-4d6b-9e37-7f1a0c6d2b54")]
+#[visit(optional)]
+pub struct Player;
+
+impl ScriptTrait for Player {
+    fn on_update(&mut self, ctx: &mut ScriptContext) {
+        let game = ctx.plugins.get_mut::<Game>();
+        // Frozen for frame-stepping or a hitstop, the player only moves on the ticks
+        // that are let through
+        if !game.is_ticking() {
+            return;
+        }
+        let scope = game.profiler_mut().begin(Phase::Movement);
+        let dt = ctx.dt * game.time_scale();
+        move_player(game, &mut ctx.scene.graph, ctx.handle, dt);
+        game.profiler_mut().end(scope);
+    }
+}
+
+// One tick of player movement, also used to drive the player without the script runtime
+pub fn move_player(game: &mut Game, graph: &mut Graph, handle: Handle<Node>, dt: f32) {
+    let input = game.player_input();
+
+    let Some(node) = graph.try_get_mut(handle) else {
+        return;
+    };
+    let current_position = game
+        .components()
+        .get::<SimTransform>(handle)
+        .map_or(**node.local_transform().position(), |sim| sim.position);
+
+    let new_position = game.step_player(input, dt, current_position);
+    match game.components_mut().get_mut::<SimTransform>(handle) {
+        Some(sim) => sim.position = new_position,
+        None if new_position != current_position => {
+            node.local_transform_mut().set_position(new_position);
+        }
+        None => {}
+    }
+}
+
+pub fn create_player(
+    scene: &mut Scene,
+    resource_manager: &ResourceManager,
+    position: Vector3<f32>,
+) -> Result<PlayerHandle, GameError> {
+    // Create a simple colored cube for the player, bright blue unless the palette says otherwise
+    let surface = create_cube_surface(resource_manager, ColorRole::Player)?;
+
+    let player = create_actor(scene, surface, position);
+    scene.graph[player].add_script(Script::new(Player));
+    Ok(PlayerHandle::new(&scene.graph, player))
+}
+
+// Cubes of one role share the palette's material, so they all change color with it
+pub fn create_cube_surface(
+    resource_manager: &ResourceManager,
+    role: ColorRole,
+) -> Result<Surface, GameError> {
+    let material_handle = palette::material(resource_manager, role)?;
+
+    // Create cube geometry
+    let surface_data = SurfaceData::make_cube(Matrix4::identity());
+    Ok(SurfaceBuilder::new(surface_data)
+        .with_material(material_handle)
+        .build())
+}
+
+// Player-sized cube, used for the local player and remote ones
+pub fn create_actor(scene: &mut Scene, surface: Surface, position: Vector3<f32>) -> Handle<Node> {
+    MeshBuilder::new(
+        BaseBuilder::new().with_local_transform(
+            TransformBuilder::new()
+                .with_local_position(position)
+                .with_local_scale(Vector3::new(0.5, 1.0, 0.5))
+                .build(),
+        ),
+    )
+    .with_surfaces(vec![surface])
+    .with_render_path(RenderPath::Forward)
+    .build(&mut scene.graph)
+}
@@ -0,0 +1,53 @@
+use crate::{
+    components::ComponentStore,
+    events::{GameEvent, GameEvents},
+    presence::Presence,
+};
+use fyrox::{
+    asset::manager::ResourceManager,
+    gui::{BuildContext, UserInterface},
+    scene::Scene,
+};
+
+// The level as plugins see it during a hook
+pub struct LevelContext<'a> {
+    pub scene: &'a mut Scene,
+    pub components: &'a mut ComponentStore,
+    // Events published here are handled by the game and other plugins next tick
+    pub events: &'a mut GameEvents,
+    pub resource_manager: &'a ResourceManager,
+    // Seconds since the level started
+    pub level_time: f32,
+}
+
+// Extension point for other crates: new enemies, game modes or debug tools without
+// forking the game. Plugins are added with Game::add_plugin before the executor runs
+// and are called in the order they were added. Every hook is optional.
+pub trait GamePlugin: 'static {
+    fn name(&self) -> &str;
+
+    // Once, when the game starts
+    fn init(&mut self, _resource_manager: &ResourceManager) {}
+
+    // Once, to add widgets next to the game's own
+    fn build_ui(&mut self, _ctx: &mut BuildContext) {}
+
+    // After a level is built, before its first tick
+    fn level_started(&mut self, _ctx: &mut LevelContext) {}
+
+    // Every simulation tick while a level is running, after the gameplay systems
+    fn update(&mut self, _ctx: &mut LevelContext, _dt: f32) {}
+
+    // For each event of the tick, after the game has reacted to it
+    fn on_event(&mut self, _event: &GameEvent, _ctx: &mut LevelContext) {}
+
+    // Every frame, to refresh the widgets added in build_ui
+    fn update_ui(&mut self, _ui: &UserInterface) {}
+
+    // Every frame in every state, menus included, e.g. to talk to a launcher
+    fn update_frame(&mut self) {}
+
+    // When what the player is doing changes: the activity, the mode, the level or the
+    // number of players in the session
+    fn presence_changed(&mut self, _presence: &Presence) {}
+}
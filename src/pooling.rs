@@ -0,0 +1,188 @@
+use crate::{
+    error::GameError,
+    level::{create_prefab, create_prefab_surface, Prefab},
+};
+use fyrox::{
+    asset::manager::ResourceManager,
+    core::{algebra::Vector3, pool::Handle},
+    scene::{mesh::surface::Surface, node::Node, Scene},
+};
+use std::collections::{BTreeMap, HashMap};
+
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct PoolStats {
+    // Nodes built from scratch
+    pub created: u32,
+    // Nodes handed out again after being released
+    pub reused: u32,
+    pub released: u32,
+}
+
+impl PoolStats {
+    // Share of handed out nodes that didn't have to be built
+    pub fn reuse_rate(&self) -> f32 {
+        let acquired = self.created + self.reused;
+        if acquired == 0 {
+            0.0
+        } else {
+            self.reused as f32 / acquired as f32
+        }
+    }
+}
+
+// Recycles prefab nodes of the current scene. Released nodes are hidden instead of
+// removed and handed out again by the next acquire of the same prefab, and all
+// instances of a prefab share one surface, so spawning and despawning in a loop
+// doesn't keep building meshes and materials.
+#[derive(Default)]
+pub struct PrefabPool {
+    surfaces: HashMap<Prefab, Surface>,
+    free: HashMap<Prefab, Vec<Handle<Node>>>,
+    // Nodes currently handed out, so releasing tells pooled nodes from any other
+    live: BTreeMap<Handle<Node>, Prefab>,
+    stats: PoolStats,
+}
+
+impl PrefabPool {
+    pub fn acquire(
+        &mut self,
+        scene: &mut Scene,
+        resource_manager: &ResourceManager,
+        prefab: Prefab,
+        name: &str,
+        position: Vector3<f32>,
+    ) -> Result<Handle<Node>, GameError> {
+        let recycled = self
+            .free
+            .get_mut(&prefab)
+            .and_then(|free| free.pop())
+            .filter(|node| scene.graph.is_valid_handle(*node));
+
+        let node = match recycled {
+            Some(node) => {
+                let base = &mut scene.graph[node];
+                base.set_name(name);
+                base.set_visibility(true);
+                base.local_transform_mut().set_position(position);
+                self.stats.reused += 1;
+                node
+            }
+            None => {
+                let surface = match self.surfaces.get(&prefab) {
+                    Some(surface) => surface.clone(),
+                    None => {
+                        let surface = create_prefab_surface(resource_manager, prefab)?;
+                        self.surfaces.insert(prefab, surface.clone());
+                        surface
+                    }
+                };
+                self.stats.created += 1;
+                create_prefab(scene, surface, prefab, name, position)
+            }
+        };
+
+        self.live.insert(node, prefab);
+        Ok(node)
+    }
+
+    // Takes a node out of play. Nodes that didn't come from the pool are removed
+    // from the scene as usual.
+    pub fn release(&mut self, scene: &mut Scene, node: Handle<Node>) {
+        let Some(prefab) = self.live.remove(&node) else {
+            scene.graph.remove_node(node);
+            return;
+        };
+
+        if let Some(base) = scene.graph.try_get_mut(node) {
+            base.set_visibility(false);
+            self.free.entry(prefab).or_default().push(node);
+            self.stats.released += 1;
+        }
+    }
+
+    // Forgets the nodes of a scene that is going away, surfaces are kept for the next one
+    pub fn clear(&mut self) {
+        self.free.clear();
+        self.live.clear();
+    }
+
+    pub fn stats(&self) -> PoolStats {
+        self.stats
+    }
+
+    // Nodes handed out and not released yet
+    pub fn in_use(&self) -> usize {
+        self.live.len()
+    }
+
+    // Hidden nodes waiting to be handed out again
+    pub fn idle(&self) -> usize {
+        self.free.values().map(Vec::len).sum()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use fyrox::core::task::TaskPool;
+    use std::sync::Arc;
+
+    fn acquire(pool: &mut PrefabPool, scene: &mut Scene, prefab: Prefab) -> Handle<Node> {
+        let resource_manager = ResourceManager::new(Arc::new(TaskPool::new()));
+        pool.acquire(
+            scene,
+            &resource_manager,
+            prefab,
+            "pooled",
+            Vector3::default(),
+        )
+        .unwrap()
+    }
+
+    #[test]
+    fn released_nodes_are_handed_out_again() {
+        let mut pool = PrefabPool::default();
+        let mut scene = Scene::new();
+        let coin = acquire(&mut pool, &mut scene, Prefab::Coin);
+        pool.release(&mut scene, coin);
+        assert!(!scene.graph[coin].visibility());
+        assert_eq!((pool.in_use(), pool.idle()), (0, 1));
+
+        assert_eq!(acquire(&mut pool, &mut scene, Prefab::Coin), coin);
+        assert!(scene.graph[coin].visibility());
+        assert_eq!(
+            pool.stats(),
+            PoolStats {
+                created: 1,
+                reused: 1,
+                released: 1,
+            }
+        );
+        assert_eq!(pool.stats().reuse_rate(), 0.5);
+    }
+
+    #[test]
+    fn nodes_are_only_reused_for_the_same_prefab() {
+        let mut pool = PrefabPool::default();
+        let mut scene = Scene::new();
+        let coin = acquire(&mut pool, &mut scene, Prefab::Coin);
+        pool.release(&mut scene, coin);
+
+        assert_ne!(acquire(&mut pool, &mut scene, Prefab::Crate), coin);
+        assert_eq!(pool.stats().created, 2);
+        assert_eq!(pool.idle(), 1);
+    }
+
+    #[test]
+    fn cleared_pools_build_new_nodes() {
+        let mut pool = PrefabPool::default();
+        let mut scene = Scene::new();
+        let coin = acquire(&mut pool, &mut scene, Prefab::Coin);
+        pool.release(&mut scene, coin);
+        pool.clear();
+
+        assert_eq!((pool.in_use(), pool.idle()), (0, 0));
+        acquire(&mut pool, &mut scene, Prefab::Coin);
+        assert_eq!(pool.stats().reused, 0);
+    }
+}
@@ -0,0 +1,109 @@
+use fyrox::core::instant::Instant;
+
+// What the player is doing, as far as friends lists and status displays care
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Activity {
+    Loading,
+    MainMenu,
+    Lobby,
+    Playing,
+    // On the results screen after reaching the goal
+    Finished,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum GameMode {
+    Solo,
+    Host,
+    Client,
+    Spectator,
+}
+
+impl GameMode {
+    pub fn name(self) -> &'static str {
+        match self {
+            GameMode::Solo => "solo",
+            GameMode::Host => "hosting",
+            GameMode::Client => "online",
+            GameMode::Spectator => "spectating",
+        }
+    }
+}
+
+// The game's state as shown to others through Steam or Discord. The game works it out
+// every frame and tells plugins when it changes.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Presence {
+    pub activity: Activity,
+    pub mode: GameMode,
+    // Name of the level being played or picked in the lobby
+    pub level: Option<String>,
+    // Everyone in the session, spectators included, 1 offline
+    pub players: usize,
+    // When the activity or the level last changed, for "elapsed" timers
+    pub since: Instant,
+}
+
+impl Default for Presence {
+    fn default() -> Self {
+        Self {
+            activity: Activity::Loading,
+            mode: GameMode::Solo,
+            level: None,
+            players: 1,
+            since: Instant::now(),
+        }
+    }
+}
+
+impl Presence {
+    // Takes on a new state, returns whether anything changed. The timer only starts
+    // over for a new activity or level, not when someone joins.
+    pub fn update(
+        &mut self,
+        activity: Activity,
+        mode: GameMode,
+        level: Option<String>,
+        players: usize,
+    ) -> bool {
+        if activity != self.activity || level != self.level {
+            self.since = Instant::now();
+        } else if mode == self.mode && players == self.players {
+            return false;
+        }
+        self.activity = activity;
+        self.mode = mode;
+        self.level = level;
+        self.players = players;
+        true
+    }
+
+    // One line for status texts, e.g. "Playing Meadow (hosting, 3 players)"
+    pub fn describe(&self) -> String {
+        match self.session() {
+            Some(session) => format!("{} ({session})", self.doing()),
+            None => self.doing(),
+        }
+    }
+
+    // The activity with its level, e.g. "Playing Meadow"
+    pub fn doing(&self) -> String {
+        let level = self.level.as_deref().unwrap_or("a level");
+        match self.activity {
+            Activity::Loading => "Starting up".to_string(),
+            Activity::MainMenu => "In the main menu".to_string(),
+            Activity::Lobby => format!("In a lobby for {level}"),
+            Activity::Playing => format!("Playing {level}"),
+            Activity::Finished => format!("Finished {level}"),
+        }
+    }
+
+    // The kind of session, e.g. "hosting, 3 players", nothing for a solo game or the menus
+    pub fn session(&self) -> Option<String> {
+        match (self.activity, self.mode, self.players) {
+            (Activity::Loading | Activity::MainMenu, ..) | (_, GameMode::Solo, _) => None,
+            (_, mode, 1) => Some(mode.name().to_string()),
+            (_, mode, players) => Some(format!("{}, {players} players", mode.name())),
+        }
+    }
+}
@@ -0,0 +1,130 @@
+use fyrox::core::instant::Instant;
+use std::{collections::VecDeque, time::Duration};
+
+// Frames kept for the rolling averages, about two seconds at 60 fps
+const HISTORY_FRAMES: usize = 120;
+
+// The parts of a frame that are timed. Movement and camera are measured inside
+// their node scripts, everything else inside the game plugin.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Phase {
+    Input,
+    Network,
+    Movement,
+    Camera,
+    // The parallel gameplay systems: enemy AI, pickups and triggers
+    Ai,
+    // Event handling, level scripts and game plugins
+    Events,
+    Ui,
+}
+
+impl Phase {
+    pub const ALL: [Phase; 7] = [
+        Phase::Input,
+        Phase::Network,
+        Phase::Movement,
+        Phase::Camera,
+        Phase::Ai,
+        Phase::Events,
+        Phase::Ui,
+    ];
+
+    pub fn name(self) -> &'static str {
+        match self {
+            Phase::Input => "input",
+            Phase::Network => "network",
+            Phase::Movement => "movement",
+            Phase::Camera => "camera",
+            Phase::Ai => "ai",
+            Phase::Events => "events",
+            Phase::Ui => "ui",
+        }
+    }
+}
+
+// A running timer, handed back to Profiler::end when the phase is over. It holds no
+// borrow, so the code in between can use the game freely.
+#[must_use]
+pub struct Scope {
+    phase: Phase,
+    started: Instant,
+}
+
+// Time spent in each phase, per frame
+pub struct Profiler {
+    current: [Duration; Phase::ALL.len()],
+    history: VecDeque<[Duration; Phase::ALL.len()]>,
+    frame_started: Instant,
+    frame_times: VecDeque<Duration>,
+}
+
+impl Default for Profiler {
+    fn default() -> Self {
+        Self {
+            current: Default::default(),
+            history: VecDeque::with_capacity(HISTORY_FRAMES),
+            frame_started: Instant::now(),
+            frame_times: VecDeque::with_capacity(HISTORY_FRAMES),
+        }
+    }
+}
+
+impl Profiler {
+    pub fn begin(&self, phase: Phase) -> Scope {
+        Scope {
+            phase,
+            started: Instant::now(),
+        }
+    }
+
+    // A phase can be entered several times a frame, the times add up
+    pub fn end(&mut self, scope: Scope) {
+        self.current[scope.phase as usize] += scope.started.elapsed();
+    }
+
+    // Closes the frame that was being measured and starts the next one
+    pub fn end_frame(&mut self) {
+        if self.history.len() == HISTORY_FRAMES {
+            self.history.pop_front();
+            self.frame_times.pop_front();
+        }
+        self.history.push_back(std::mem::take(&mut self.current));
+        self.frame_times.push_back(self.frame_started.elapsed());
+        self.frame_started = Instant::now();
+    }
+
+    // Milliseconds spent in `phase` during the last complete frame
+    pub fn last_ms(&self, phase: Phase) -> f32 {
+        self.history
+            .back()
+            .map_or(0.0, |frame| as_ms(frame[phase as usize]))
+    }
+
+    // Milliseconds spent in `phase` per frame, averaged over the recent frames
+    pub fn average_ms(&self, phase: Phase) -> f32 {
+        average(self.history.iter().map(|frame| frame[phase as usize]))
+    }
+
+    // Whole frame, from one update to the next, averaged like the phases
+    pub fn average_frame_ms(&self) -> f32 {
+        average(self.frame_times.iter().copied())
+    }
+
+    // Largest frame time in the history, what a hitch looks like
+    pub fn worst_frame_ms(&self) -> f32 {
+        self.frame_times.iter().copied().max().map_or(0.0, as_ms)
+    }
+}
+
+fn as_ms(duration: Duration) -> f32 {
+    duration.as_secs_f32() * 1000.0
+}
+
+fn average(durations: impl ExactSizeIterator<Item = Duration>) -> f32 {
+    let count = durations.len();
+    if count == 0 {
+        return 0.0;
+    }
+    as_ms(durations.sum()) / count as f32
+}
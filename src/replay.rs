@@ -0,0 +1,215 @@
+use crate::{level::LevelConfig, movement::MoveInput};
+use serde::{Deserialize, Serialize};
+use std::{fs, path::Path};
+
+// Bumped whenever an older replay would play back differently
+pub const REPLAY_VERSION: u32 = 1;
+
+// Set in a frame's input next to the movement keys when a dash was asked for
+const DASH_BIT: u8 = 1 << 4;
+
+// The local player's input for each tick of a solo run, enough to play the run again
+// from the same level and seed. Written by --record-replay, played back by the demo.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct Replay {
+    pub version: u32,
+    pub level: String,
+    pub spawn_index: usize,
+    // What the level script was seeded with
+    pub script_seed: u64,
+    pub frames: Vec<ReplayFrame>,
+}
+
+// Input bits and for how many ticks in a row they were held, runs of the same input
+// are stored once
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ReplayFrame(pub u8, pub u32);
+
+impl Replay {
+    pub fn load(path: &Path) -> Result<Self, String> {
+        fs::read_to_string(path)
+            .map_err(|err| err.to_string())
+            .and_then(|text| Self::parse(&text))
+            .map_err(|err| format!("{}: {err}", path.display()))
+    }
+
+    pub fn parse(text: &str) -> Result<Self, String> {
+        let replay: Self = serde_json::from_str(text).map_err(|err| err.to_string())?;
+        if replay.version != REPLAY_VERSION {
+            return Err(format!("replay version {} isn't supported", replay.version));
+        }
+        Ok(replay)
+    }
+
+    pub fn save(&self, path: &Path) -> Result<(), String> {
+        if let Some(dir) = path.parent() {
+            fs::create_dir_all(dir).map_err(|err| err.to_string())?;
+        }
+        serde_json::to_vec_pretty(self)
+            .map_err(|err| err.to_string())
+            .and_then(|data| fs::write(path, data).map_err(|err| err.to_string()))
+            .map_err(|err| format!("{}: {err}", path.display()))
+    }
+
+    // Starts the level the way it was when the replay was recorded
+    pub fn level_config(&self) -> LevelConfig {
+        LevelConfig {
+            level_id: self.level.clone(),
+            spawn_index: self.spawn_index,
+            script_seed: Some(self.script_seed),
+            ..Default::default()
+        }
+    }
+
+    pub fn ticks(&self) -> u64 {
+        self.frames.iter().map(|frame| frame.1 as u64).sum()
+    }
+}
+
+// Collects a run's input tick by tick
+#[derive(Clone, Debug)]
+pub struct ReplayRecorder {
+    replay: Replay,
+}
+
+impl ReplayRecorder {
+    pub fn new(level: &str, spawn_index: usize, script_seed: u64) -> Self {
+        Self {
+            replay: Replay {
+                version: REPLAY_VERSION,
+                level: level.to_string(),
+                spawn_index,
+                script_seed,
+                frames: Vec::new(),
+            },
+        }
+    }
+
+    pub fn record(&mut self, input: MoveInput, dash: bool) {
+        let bits = input.to_bits() | if dash { DASH_BIT } else { 0 };
+        match self.replay.frames.last_mut() {
+            Some(frame) if frame.0 == bits => frame.1 += 1,
+            _ => self.replay.frames.push(ReplayFrame(bits, 1)),
+        }
+    }
+
+    pub fn replay(&self) -> &Replay {
+        &self.replay
+    }
+
+    pub fn finish(self) -> Replay {
+        self.replay
+    }
+}
+
+// Hands out a replay's input one tick at a time
+#[derive(Clone, Debug)]
+pub struct ReplayPlayer {
+    frames: Vec<ReplayFrame>,
+    next_frame: usize,
+    // Ticks of the current frame already played
+    played: u32,
+    ticks_left: u64,
+}
+
+impl ReplayPlayer {
+    pub fn new(replay: &Replay) -> Self {
+        Self {
+            frames: replay.frames.clone(),
+            next_frame: 0,
+            played: 0,
+            ticks_left: replay.ticks(),
+        }
+    }
+
+    // Keys held and whether to dash in the coming tick, None once the replay is over
+    pub fn next_tick(&mut self) -> Option<(MoveInput, bool)> {
+        while self
+            .frames
+            .get(self.next_frame)
+            .is_some_and(|frame| self.played >= frame.1)
+        {
+            self.next_frame += 1;
+            self.played = 0;
+        }
+        let ReplayFrame(bits, _) = *self.frames.get(self.next_frame)?;
+        self.played += 1;
+        self.ticks_left -= 1;
+        Some((MoveInput::from_bits(bits & !DASH_BIT), bits & DASH_BIT != 0))
+    }
+
+    pub fn is_finished(&self) -> bool {
+        self.ticks_left == 0
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn forward() -> MoveInput {
+        MoveInput {
+            forward: true,
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn held_input_is_stored_once_per_run() {
+        let mut recorder = ReplayRecorder::new("meadow", 0, 7);
+        for _ in 0..3 {
+            recorder.record(forward(), false);
+        }
+        recorder.record(forward(), true);
+        recorder.record(MoveInput::default(), false);
+
+        let replay = recorder.finish();
+        assert_eq!(
+            replay.frames,
+            vec![
+                ReplayFrame(1, 3),
+                ReplayFrame(1 | DASH_BIT, 1),
+                ReplayFrame(0, 1)
+            ]
+        );
+        assert_eq!(replay.ticks(), 5);
+    }
+
+    #[test]
+    fn a_player_hands_back_what_was_recorded() {
+        let ticks = [
+            (forward(), false),
+            (forward(), true),
+            (forward(), false),
+            (MoveInput::default(), false),
+        ];
+        let mut recorder = ReplayRecorder::new("meadow", 0, 7);
+        for (input, dash) in ticks {
+            recorder.record(input, dash);
+        }
+
+        let mut player = ReplayPlayer::new(recorder.replay());
+        for tick in ticks {
+            assert!(!player.is_finished());
+            assert_eq!(player.next_tick(), Some(tick));
+        }
+        assert!(player.is_finished());
+        assert_eq!(player.next_tick(), None);
+    }
+
+    #[test]
+    fn replays_survive_the_trip_through_json() {
+        let mut recorder = ReplayRecorder::new("meadow", 1, u64::MAX);
+        recorder.record(forward(), true);
+        let replay = recorder.finish();
+
+        let text = serde_json::to_string(&replay).unwrap();
+        assert_eq!(Replay::parse(&text), Ok(replay));
+    }
+
+    #[test]
+    fn other_versions_are_refused() {
+        let text = r#"{"version":0,"level":"meadow","spawn_index":0,"script_seed":0,"frames":[]}"#;
+        assert!(Replay::parse(text).is_err());
+    }
+}
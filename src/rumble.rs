@@ -0,0 +1,236 @@
+use crate::{components::PickupKind, console::CommandRegistry, events::GameEvent, game::Game};
+use fyrox::{
+    core::{instant::Instant, pool::Handle},
+    plugin::PluginContext,
+    scene::node::Node,
+};
+use gilrs::{
+    ff::{BaseEffect, BaseEffectType, Effect, EffectBuilder, Envelope, Replay, Ticks},
+    Gilrs,
+};
+use log::{info, warn};
+use std::{
+    sync::atomic::{AtomicBool, Ordering},
+    time::Duration,
+};
+
+// Damage that rumbles as hard as a hit can
+const FULL_RUMBLE_DAMAGE: f32 = 40.0;
+
+// Master switch, off for players who don't want their controller shaking at all
+static ENABLED: AtomicBool = AtomicBool::new(true);
+
+pub fn enabled() -> bool {
+    ENABLED.load(Ordering::Relaxed)
+}
+
+pub fn set_enabled(enabled: bool) {
+    ENABLED.store(enabled, Ordering::Relaxed);
+}
+
+// How one kind of rumble plays out: the strong motor is the heavy, low one, the weak
+// motor the light buzz. Levels go from 0 to 1.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct RumbleCurve {
+    pub strong: f32,
+    pub weak: f32,
+    // Ramp up to the full level
+    pub attack_ms: u32,
+    // Whole length, the ramps included
+    pub length_ms: u32,
+    // Ramp down at the end
+    pub fade_ms: u32,
+}
+
+impl RumbleCurve {
+    // A heavy thud that dies off slowly
+    pub const DAMAGE: RumbleCurve = RumbleCurve {
+        strong: 0.9,
+        weak: 0.5,
+        attack_ms: 0,
+        length_ms: 300,
+        fade_ms: 200,
+    };
+    // A short light tick
+    pub const COIN: RumbleCurve = RumbleCurve {
+        strong: 0.0,
+        weak: 0.35,
+        attack_ms: 0,
+        length_ms: 60,
+        fade_ms: 30,
+    };
+    // A quick rising whoosh, over when the dash is
+    pub const DASH: RumbleCurve = RumbleCurve {
+        strong: 0.2,
+        weak: 0.6,
+        attack_ms: 60,
+        length_ms: 200,
+        fade_ms: 60,
+    };
+    // A soft swell
+    pub const HEALTH: RumbleCurve = RumbleCurve {
+        strong: 0.25,
+        weak: 0.4,
+        attack_ms: 80,
+        length_ms: 250,
+        fade_ms: 120,
+    };
+
+    // The curve with both motors scaled by `intensity`
+    pub fn scaled(self, intensity: f32) -> Self {
+        let intensity = intensity.clamp(0.0, 1.0);
+        Self {
+            strong: self.strong * intensity,
+            weak: self.weak * intensity,
+            ..self
+        }
+    }
+
+    fn effect(&self, magnitude: f32, kind: fn(u16) -> BaseEffectType) -> BaseEffect {
+        BaseEffect {
+            kind: kind((magnitude.clamp(0.0, 1.0) * u16::MAX as f32) as u16),
+            scheduling: Replay {
+                play_for: Ticks::from_ms(self.length_ms),
+                ..Default::default()
+            },
+            envelope: Envelope {
+                attack_length: Ticks::from_ms(self.attack_ms),
+                attack_level: 0.0,
+                fade_length: Ticks::from_ms(self.fade_ms),
+                fade_level: 0.0,
+            },
+        }
+    }
+}
+
+// The local player's rumble for a gameplay event, if it gets any
+pub fn curve_for(event: &GameEvent, player: Handle<Node>) -> Option<RumbleCurve> {
+    match *event {
+        GameEvent::DamageDealt { target, amount, .. } if target == player => {
+            Some(RumbleCurve::DAMAGE.scaled(0.4 + amount / FULL_RUMBLE_DAMAGE))
+        }
+        GameEvent::PickupCollected {
+            collector, kind, ..
+        } if collector == player => Some(match kind {
+            PickupKind::Coin => RumbleCurve::COIN,
+            PickupKind::Health => RumbleCurve::HEALTH,
+        }),
+        GameEvent::Dashed { player: dasher } if dasher == player => Some(RumbleCurve::DASH),
+        _ => None,
+    }
+}
+
+// Vibration on every connected gamepad that supports it
+pub struct Rumble {
+    gilrs: Gilrs,
+    // Effects stop when dropped, so they are kept until they have played out
+    playing: Vec<(Effect, Instant)>,
+}
+
+impl Rumble {
+    pub fn new() -> Result<Self, String> {
+        let gilrs = Gilrs::new().map_err(|err| err.to_string())?;
+        for (_, gamepad) in gilrs.gamepads() {
+            info!(
+                "Gamepad {}{}",
+                gamepad.name(),
+                if gamepad.is_ff_supported() {
+                    ""
+                } else {
+                    " (no rumble)"
+                }
+            );
+        }
+        Ok(Self {
+            gilrs,
+            playing: Vec::new(),
+        })
+    }
+
+    // Called every frame: keeps the gamepad list current and lets finished effects go
+    pub fn update(&mut self) {
+        while self.gilrs.next_event().is_some() {}
+        let now = Instant::now();
+        self.playing.retain(|(_, end)| *end > now);
+    }
+
+    pub fn play(&mut self, curve: RumbleCurve) {
+        if !enabled() {
+            return;
+        }
+        let gamepads: Vec<_> = self
+            .gilrs
+            .gamepads()
+            .filter(|(_, gamepad)| gamepad.is_ff_supported())
+            .map(|(id, _)| id)
+            .collect();
+        if gamepads.is_empty() {
+            return;
+        }
+
+        let effect = EffectBuilder::new()
+            .add_effect(
+                curve.effect(curve.strong, |magnitude| BaseEffectType::Strong {
+                    magnitude,
+                }),
+            )
+            .add_effect(curve.effect(curve.weak, |magnitude| BaseEffectType::Weak { magnitude }))
+            .gamepads(&gamepads)
+            .finish(&mut self.gilrs)
+            .and_then(|effect| effect.play().map(|_| effect));
+        match effect {
+            Ok(effect) => {
+                let end = Instant::now() + Duration::from_millis(curve.length_ms as u64);
+                self.playing.push((effect, end));
+            }
+            Err(err) => warn!("Couldn't rumble: {err}"),
+        }
+    }
+
+    pub fn stop(&mut self) {
+        self.playing.clear();
+    }
+}
+
+pub fn register_commands(commands: &mut CommandRegistry) {
+    commands.register(
+        "rumble",
+        "[on|off]",
+        "show or switch gamepad rumble",
+        command_rumble,
+    );
+    commands.complete_with("rumble", || vec!["on".to_string(), "off".to_string()]);
+}
+
+fn command_rumble(
+    game: &mut Game,
+    context: &mut PluginContext,
+    args: &[&str],
+) -> Result<String, String> {
+    match args {
+        [] => {}
+        ["on"] => game.set_rumble(context, true),
+        ["off"] => game.set_rumble(context, false),
+        _ => return Err("usage: rumble [on|off]".to_string()),
+    }
+    Ok(format!("rumble {}", if enabled() { "on" } else { "off" }))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn only_the_local_players_dashes_rumble() {
+        let player = Handle::new(1, 1);
+        let other = Handle::new(2, 1);
+        assert_eq!(
+            curve_for(&GameEvent::Dashed { player }, player),
+            Some(RumbleCurve::DASH)
+        );
+        assert_eq!(
+            curve_for(&GameEvent::Dashed { player: other }, player),
+            None
+        );
+    }
+}
@@ -0,0 +1,121 @@
+use hmac::{Hmac, Mac};
+use serde::{de::DeserializeOwned, Deserialize, Serialize};
+use serde_json::value::RawValue;
+use sha2::Sha256;
+use std::{fmt, fs, io, path::Path};
+
+// Mixed into every checksum. It ships with the game, so checksums catch damaged files and
+// hand edits, not someone who read this file. The leaderboard service still checks what
+// it is sent.
+const SAVE_KEY: &[u8] = b"simple-fyrox-game/saves/v1";
+
+// What is written to disk: the contents exactly as they were signed, and their checksum
+#[derive(Serialize, Deserialize)]
+struct SignedFile<'a> {
+    checksum: String,
+    #[serde(borrow)]
+    data: &'a RawValue,
+}
+
+#[derive(Debug)]
+pub enum SaveError {
+    Io(io::Error),
+    // The checksum is missing or doesn't match, the file was damaged or modified
+    Tampered,
+    // Signed by the game but not what this version expects
+    Invalid(serde_json::Error),
+}
+
+impl SaveError {
+    pub fn is_missing(&self) -> bool {
+        matches!(self, SaveError::Io(err) if err.kind() == io::ErrorKind::NotFound)
+    }
+}
+
+impl fmt::Display for SaveError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            SaveError::Io(err) => write!(f, "{err}"),
+            SaveError::Tampered => write!(f, "the file is corrupted or was modified"),
+            SaveError::Invalid(err) => write!(f, "unexpected contents: {err}"),
+        }
+    }
+}
+
+impl std::error::Error for SaveError {}
+
+// Writes a save or score file along with a checksum of its contents
+pub fn write<T: Serialize + ?Sized>(path: &Path, value: &T) -> io::Result<()> {
+    if let Some(dir) = path.parent() {
+        fs::create_dir_all(dir)?;
+    }
+    let data = RawValue::from_string(serde_json::to_string(value)?)?;
+    let file = SignedFile {
+        checksum: checksum(data.get()),
+        data: &data,
+    };
+    fs::write(path, serde_json::to_vec_pretty(&file)?)
+}
+
+// Reads a file written by write(), only if its checksum still matches. Files from before
+// there were checksums count as modified.
+pub fn read<T: DeserializeOwned>(path: &Path) -> Result<T, SaveError> {
+    match read_flagged(path)? {
+        (value, true) => Ok(value),
+        (_, false) => Err(SaveError::Tampered),
+    }
+}
+
+// Like read(), but also hands out contents whose checksum doesn't match, along with
+// whether it did. For files still worth showing when they can't be trusted.
+pub fn read_flagged<T: DeserializeOwned>(path: &Path) -> Result<(T, bool), SaveError> {
+    let text = fs::read_to_string(path).map_err(SaveError::Io)?;
+    let file: SignedFile = serde_json::from_str(&text).map_err(|_| SaveError::Tampered)?;
+    let trusted = verify(file.data.get(), &file.checksum);
+    match serde_json::from_str(file.data.get()) {
+        Ok(value) => Ok((value, trusted)),
+        Err(err) if trusted => Err(SaveError::Invalid(err)),
+        Err(_) => Err(SaveError::Tampered),
+    }
+}
+
+// Moves a file that failed its check out of the way, so it is neither used nor overwritten.
+// It stays next to the original with ".rejected" added, for anyone who wants to look.
+pub fn reject(path: &Path) {
+    let mut rejected = path.as_os_str().to_owned();
+    rejected.push(".rejected");
+    let _ = fs::rename(path, rejected);
+}
+
+fn mac(data: &str) -> Hmac<Sha256> {
+    let mut mac = Hmac::<Sha256>::new_from_slice(SAVE_KEY).expect("HMAC takes keys of any size");
+    mac.update(data.as_bytes());
+    mac
+}
+
+fn checksum(data: &str) -> String {
+    mac(data)
+        .finalize()
+        .into_bytes()
+        .iter()
+        .map(|byte| format!("{byte:02x}"))
+        .collect()
+}
+
+fn verify(data: &str, checksum: &str) -> bool {
+    let Some(bytes) = decode_hex(checksum) else {
+        return false;
+    };
+    // Compared in constant time, like any other MAC
+    mac(data).verify_slice(&bytes).is_ok()
+}
+
+fn decode_hex(text: &str) -> Option<Vec<u8>> {
+    if text.len() % 2 != 0 {
+        return None;
+    }
+    (0..text.len())
+        .step_by(2)
+        .map(|index| u8::from_str_radix(text.get(index..index + 2)?, 16).ok())
+        .collect()
+}
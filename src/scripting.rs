@@ -0,0 +1,222 @@
+use crate::{determinism::Rng, localization, mods};
+use fyrox::core::algebra::Vector3;
+use log::{info, warn};
+use rhai::{
+    module_resolvers::DummyModuleResolver, Array, Dynamic, Engine, FuncArgs, Scope, AST, FLOAT,
+};
+use std::{
+    fs,
+    path::{Path, PathBuf},
+    sync::{Arc, Mutex},
+};
+
+// Level scripts live next to the other assets, one per level id
+const SCRIPT_DIR: &str = "assets/scripts";
+
+// Upper bound on the work a single hook may do, so a stuck loop can't freeze the game
+const MAX_OPERATIONS: u64 = 100_000;
+
+// A mod's script for the level takes the place of the game's own
+pub fn script_path(level_id: &str) -> PathBuf {
+    mods::script(level_id).map_or_else(
+        || Path::new(SCRIPT_DIR).join(format!("{level_id}.rhai")),
+        Path::to_path_buf,
+    )
+}
+
+// Something a script asked for. Scripts never touch the scene directly, the
+// game applies these after each hook returns.
+#[derive(Clone, Debug)]
+pub enum ScriptCommand {
+    SpawnPrefab {
+        prefab: String,
+        name: String,
+        position: Vector3<f32>,
+    },
+    MoveNode {
+        name: String,
+        position: Vector3<f32>,
+    },
+    PlaySound {
+        path: String,
+    },
+    SetEnemySpeed {
+        name: String,
+        speed: f32,
+    },
+    ShowDialogue {
+        id: String,
+    },
+}
+
+// What scripts can query about the game, refreshed before hooks run
+#[derive(Clone, Debug, Default)]
+pub struct ScriptView {
+    pub player_position: Option<Vector3<f32>>,
+    pub player_health: Option<f32>,
+    // Seconds since the level started
+    pub level_time: f32,
+}
+
+struct Shared {
+    view: ScriptView,
+    commands: Vec<ScriptCommand>,
+    // Scripts get their own generator, seeded by the game so deterministic runs stay that way
+    rng: Rng,
+}
+
+// A compiled rhai script with the level's event handlers. Every handler is
+// optional: on_start(), on_tick(dt), on_pickup(kind, value), on_trigger(kind),
+// on_damage(amount) and on_enemy_died().
+pub struct LevelScript {
+    engine: Engine,
+    ast: AST,
+    shared: Arc<Mutex<Shared>>,
+}
+
+impl LevelScript {
+    pub fn load(path: &Path, seed: u64) -> Result<Self, String> {
+        let source =
+            fs::read_to_string(path).map_err(|err| format!("{}: {err}", path.display()))?;
+        let shared = Arc::new(Mutex::new(Shared {
+            view: ScriptView::default(),
+            commands: Vec::new(),
+            rng: Rng::new(seed),
+        }));
+        let engine = create_engine(&shared);
+        let ast = engine
+            .compile(source)
+            .map_err(|err| format!("{}: {err}", path.display()))?;
+
+        Ok(Self {
+            engine,
+            ast,
+            shared,
+        })
+    }
+
+    pub fn set_view(&self, view: ScriptView) {
+        self.shared.lock().unwrap().view = view;
+    }
+
+    // Runs a handler if the script defines it. Errors are reported and the
+    // script stays loaded, so one broken handler doesn't take down the others.
+    pub fn call(&self, name: &str, args: impl FuncArgs) {
+        let mut values = Vec::new();
+        args.parse(&mut values);
+        let defined = self
+            .ast
+            .iter_functions()
+            .any(|function| function.name == name && function.params.len() == values.len());
+        if !defined {
+            return;
+        }
+
+        if let Err(err) = self
+            .engine
+            .call_fn::<Dynamic>(&mut Scope::new(), &self.ast, name, values)
+        {
+            warn!("Level script error in {name}: {err}");
+        }
+    }
+
+    // Commands queued by the handlers run since the last call
+    pub fn take_commands(&self) -> Vec<ScriptCommand> {
+        std::mem::take(&mut self.shared.lock().unwrap().commands)
+    }
+}
+
+fn create_engine(shared: &Arc<Mutex<Shared>>) -> Engine {
+    let mut engine = Engine::new();
+
+    // Scripts get the functions below and nothing else: no imports, no file access
+    engine.set_module_resolver(DummyModuleResolver::new());
+    engine.set_max_operations(MAX_OPERATIONS);
+    engine.set_max_call_levels(32);
+    engine.on_print(|text| info!(target: "level_script", "{text}"));
+
+    let push = {
+        let shared = shared.clone();
+        move |command: ScriptCommand| shared.lock().unwrap().commands.push(command)
+    };
+
+    let spawn = push.clone();
+    engine.register_fn(
+        "spawn_prefab",
+        move |prefab: &str, name: &str, x: FLOAT, y: FLOAT, z: FLOAT| {
+            spawn(ScriptCommand::SpawnPrefab {
+                prefab: prefab.to_string(),
+                name: name.to_string(),
+                position: to_vector(x, y, z),
+            })
+        },
+    );
+    let move_node = push.clone();
+    engine.register_fn(
+        "move_node",
+        move |name: &str, x: FLOAT, y: FLOAT, z: FLOAT| {
+            move_node(ScriptCommand::MoveNode {
+                name: name.to_string(),
+                position: to_vector(x, y, z),
+            })
+        },
+    );
+    let play_sound = push.clone();
+    engine.register_fn("play_sound", move |path: &str| {
+        play_sound(ScriptCommand::PlaySound {
+            path: path.to_string(),
+        })
+    });
+    let set_enemy_speed = push.clone();
+    engine.register_fn("set_enemy_speed", move |name: &str, speed: FLOAT| {
+        set_enemy_speed(ScriptCommand::SetEnemySpeed {
+            name: name.to_string(),
+            speed: speed as f32,
+        })
+    });
+    engine.register_fn("show_dialogue", move |id: &str| {
+        push(ScriptCommand::ShowDialogue { id: id.to_string() })
+    });
+    // Text in the player's language, for hints and tutorials a script prints or shows
+    engine.register_fn("tr", |key: &str| localization::tr(key));
+
+    // Queries return () when there is nothing to report, e.g. while spectating
+    let view = shared.clone();
+    engine.register_fn("player_position", move || -> Dynamic {
+        match view.lock().unwrap().view.player_position {
+            Some(position) => Dynamic::from_array(
+                position
+                    .iter()
+                    .map(|value| Dynamic::from_float(*value as FLOAT))
+                    .collect::<Array>(),
+            ),
+            None => Dynamic::UNIT,
+        }
+    });
+    let view = shared.clone();
+    engine.register_fn("player_health", move || -> Dynamic {
+        match view.lock().unwrap().view.player_health {
+            Some(health) => Dynamic::from_float(health as FLOAT),
+            None => Dynamic::UNIT,
+        }
+    });
+    let view = shared.clone();
+    engine.register_fn("level_time", move || -> FLOAT {
+        view.lock().unwrap().view.level_time as FLOAT
+    });
+
+    let rng = shared.clone();
+    engine.register_fn("random", move || -> FLOAT {
+        rng.lock().unwrap().rng.next_f32() as FLOAT
+    });
+    let rng = shared.clone();
+    engine.register_fn("random_range", move |min: FLOAT, max: FLOAT| -> FLOAT {
+        rng.lock().unwrap().rng.range(min as f32, max as f32) as FLOAT
+    });
+
+    engine
+}
+
+fn to_vector(x: FLOAT, y: FLOAT, z: FLOAT) -> Vector3<f32> {
+    Vector3::new(x as f32, y as f32, z as f32)
+}
@@ -0,0 +1,166 @@
+use fyrox::{
+    core::{algebra::Vector3, pool::Handle},
+    scene::node::Node,
+};
+use std::collections::HashMap;
+
+// Edge length of a grid cell in world units, about the size of the things queried for
+pub const DEFAULT_CELL_SIZE: f32 = 4.0;
+
+// Uniform grid over the ground plane (x/z), each entity filed under the cell its
+// position falls into. Range queries only visit the cells the range overlaps, so
+// their cost depends on how crowded the area is rather than on the entity count.
+// Ranges are cut down to the cells that hold anything, so a huge radius costs no
+// more than looking at every entity once.
+pub struct SpatialGrid {
+    cell_size: f32,
+    cells: HashMap<(i32, i32), Vec<(Handle<Node>, Vector3<f32>)>>,
+    // Smallest and largest occupied cell on each axis, none while the grid is empty
+    bounds: Option<((i32, i32), (i32, i32))>,
+}
+
+impl Default for SpatialGrid {
+    fn default() -> Self {
+        Self::new(DEFAULT_CELL_SIZE)
+    }
+}
+
+impl SpatialGrid {
+    pub fn new(cell_size: f32) -> Self {
+        Self {
+            cell_size: cell_size.max(f32::EPSILON),
+            cells: HashMap::new(),
+            bounds: None,
+        }
+    }
+
+    pub fn insert(&mut self, entity: Handle<Node>, position: Vector3<f32>) {
+        let (x, z) = self.cell(position.x, position.z);
+        self.cells
+            .entry((x, z))
+            .or_default()
+            .push((entity, position));
+        self.bounds = Some(match self.bounds {
+            Some(((min_x, min_z), (max_x, max_z))) => {
+                ((min_x.min(x), min_z.min(z)), (max_x.max(x), max_z.max(z)))
+            }
+            None => ((x, z), (x, z)),
+        });
+    }
+
+    pub fn clear(&mut self) {
+        self.cells.clear();
+        self.bounds = None;
+    }
+
+    // Entities within `radius` of `center` on the ground plane, in handle order so
+    // callers see the same order every run
+    pub fn query(&self, center: Vector3<f32>, radius: f32) -> Vec<Handle<Node>> {
+        let Some(((low_x, low_z), (high_x, high_z))) = self.bounds else {
+            return Vec::new();
+        };
+        let (min_x, min_z) = self.cell(center.x - radius, center.z - radius);
+        let (max_x, max_z) = self.cell(center.x + radius, center.z + radius);
+        let (min_x, min_z) = (min_x.max(low_x), min_z.max(low_z));
+        let (max_x, max_z) = (max_x.min(high_x), max_z.min(high_z));
+
+        let in_range = |(_, position): &&(Handle<Node>, Vector3<f32>)| {
+            let offset = Vector3::new(position.x - center.x, 0.0, position.z - center.z);
+            offset.norm() <= radius
+        };
+        let mut found = Vec::new();
+        let range_cells =
+            (max_x as i64 - min_x as i64 + 1).max(0) * (max_z as i64 - min_z as i64 + 1).max(0);
+        if range_cells > self.cells.len() as i64 {
+            // Fewer occupied cells than the range covers, cheaper to look at all of them
+            for (&(x, z), cell) in &self.cells {
+                if (min_x..=max_x).contains(&x) && (min_z..=max_z).contains(&z) {
+                    found.extend(cell.iter().filter(in_range).map(|(entity, _)| *entity));
+                }
+            }
+        } else {
+            for x in min_x..=max_x {
+                for z in min_z..=max_z {
+                    if let Some(cell) = self.cells.get(&(x, z)) {
+                        found.extend(cell.iter().filter(in_range).map(|(entity, _)| *entity));
+                    }
+                }
+            }
+        }
+        found.sort();
+        found
+    }
+
+    fn cell(&self, x: f32, z: f32) -> (i32, i32) {
+        (
+            (x / self.cell_size).floor() as i32,
+            (z / self.cell_size).floor() as i32,
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn entity(index: u32) -> Handle<Node> {
+        Handle::new(index, 1)
+    }
+
+    #[test]
+    fn a_huge_radius_finds_everything_without_visiting_empty_cells() {
+        let mut grid = SpatialGrid::default();
+        grid.insert(entity(2), Vector3::new(-30.0, 0.0, 12.0));
+        grid.insert(entity(1), Vector3::new(50.0, 0.0, -7.0));
+
+        assert_eq!(
+            grid.query(Vector3::default(), 1e30),
+            vec![entity(1), entity(2)]
+        );
+        assert_eq!(grid.query(Vector3::default(), f32::INFINITY).len(), 2);
+    }
+
+    #[test]
+    fn ranges_reach_across_cell_edges() {
+        let mut grid = SpatialGrid::default();
+        // Either side of the edge at x = 4 and of the one at 0, where floor() matters
+        grid.insert(entity(1), Vector3::new(3.9, 0.0, 1.0));
+        grid.insert(entity(2), Vector3::new(4.1, 0.0, 1.0));
+        grid.insert(entity(3), Vector3::new(-0.1, 0.0, -0.1));
+        grid.insert(entity(4), Vector3::new(0.1, 0.0, 0.1));
+
+        assert_eq!(
+            grid.query(Vector3::new(4.0, 0.0, 1.0), 0.2),
+            vec![entity(1), entity(2)]
+        );
+        assert_eq!(
+            grid.query(Vector3::default(), 0.2),
+            vec![entity(3), entity(4)]
+        );
+    }
+
+    #[test]
+    fn the_radius_is_measured_on_the_ground_and_includes_its_edge() {
+        let mut grid = SpatialGrid::default();
+        grid.insert(entity(1), Vector3::new(1.0, 0.0, 0.0));
+        // High up but right above the center
+        grid.insert(entity(2), Vector3::new(0.0, 50.0, 0.0));
+        // In the same cell, just too far
+        grid.insert(entity(3), Vector3::new(1.01, 0.0, 0.0));
+
+        assert_eq!(
+            grid.query(Vector3::default(), 1.0),
+            vec![entity(1), entity(2)]
+        );
+    }
+
+    #[test]
+    fn an_empty_grid_finds_nothing() {
+        let mut grid = SpatialGrid::default();
+        assert!(grid.query(Vector3::default(), 1e5).is_empty());
+
+        grid.insert(entity(1), Vector3::default());
+        grid.clear();
+        assert!(grid.query(Vector3::default(), 1e5).is_empty());
+    }
+}
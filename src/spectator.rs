@@ -0,0 +1,173 @@
+use crate::{handles::CameraHandle, net::protocol::PlayerId};
+use fyrox::{
+    core::{
+        algebra::{UnitQuaternion, Vector3},
+        pool::Handle,
+    },
+    scene::{node::Node, Scene},
+};
+use std::collections::BTreeMap;
+
+// Free-fly speed in units per second
+const FLY_SPEED: f32 = 10.0;
+
+// Radians of rotation per pixel of mouse movement
+const MOUSE_SENSITIVITY: f32 = 0.003;
+
+// Keeps the free-fly camera from flipping over
+const MAX_PITCH: f32 = 1.5;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum SpectatorMode {
+    FreeFly,
+    Follow(PlayerId),
+}
+
+// Controls for one frame of spectating
+#[derive(Default)]
+pub struct SpectatorInput {
+    pub forward: bool,
+    pub backward: bool,
+    pub left: bool,
+    pub right: bool,
+    pub up: bool,
+    pub down: bool,
+    // Mouse movement in pixels since the last frame
+    pub mouse_delta: (f32, f32),
+}
+
+// Camera of a client that watches without owning a player
+pub struct SpectatorCamera {
+    mode: SpectatorMode,
+    position: Vector3<f32>,
+    yaw: f32,
+    pitch: f32,
+}
+
+impl SpectatorCamera {
+    pub fn new(position: Vector3<f32>) -> Self {
+        Self {
+            mode: SpectatorMode::FreeFly,
+            position,
+            yaw: 0.0,
+            pitch: -0.4,
+        }
+    }
+
+    // Free-fly camera that starts out looking along `direction`
+    pub fn looking(position: Vector3<f32>, direction: Vector3<f32>) -> Self {
+        let direction = direction
+            .try_normalize(f32::EPSILON)
+            .unwrap_or(-Vector3::z());
+        Self {
+            mode: SpectatorMode::FreeFly,
+            position,
+            yaw: direction.x.atan2(-direction.z),
+            pitch: direction.y.asin().clamp(-MAX_PITCH, MAX_PITCH),
+        }
+    }
+
+    pub fn mode(&self) -> SpectatorMode {
+        self.mode
+    }
+
+    // Free-fly -> first player -> next player ... -> last player -> free-fly
+    pub fn cycle_target(&mut self, players: &[PlayerId]) {
+        let mut players = players.to_vec();
+        players.sort_unstable();
+
+        self.mode = match self.mode {
+            SpectatorMode::FreeFly => players
+                .first()
+                .map_or(SpectatorMode::FreeFly, |id| SpectatorMode::Follow(*id)),
+            SpectatorMode::Follow(current) => players
+                .iter()
+                .find(|id| **id > current)
+                .map_or(SpectatorMode::FreeFly, |id| SpectatorMode::Follow(*id)),
+        };
+    }
+
+    pub fn update(
+        &mut self,
+        scene: &mut Scene,
+        camera: CameraHandle,
+        players: &BTreeMap<PlayerId, Handle<Node>>,
+        input: &SpectatorInput,
+        dt: f32,
+    ) {
+        // The followed player left, fall back to flying around
+        let target = match self.mode {
+            SpectatorMode::Follow(id) => {
+                let target = players
+                    .get(&id)
+                    .and_then(|handle| scene.graph.try_get(*handle))
+                    .map(|node| **node.local_transform().position());
+                if target.is_none() {
+                    self.mode = SpectatorMode::FreeFly;
+                }
+                target
+            }
+            SpectatorMode::FreeFly => None,
+        };
+
+        let (position, look_direction) = match target {
+            Some(target) => {
+                // Same chase view a player gets of their own cube
+                let target_position = target + Vector3::new(0.0, 3.0, 5.0);
+                self.position = self.position.lerp(&target_position, dt * 2.0);
+                (self.position, (target - self.position).normalize())
+            }
+            None => {
+                self.fly(input, dt);
+                (self.position, self.look_direction())
+            }
+        };
+
+        if let Some(camera_node) = camera.get_mut(&mut scene.graph) {
+            let transform = camera_node.local_transform_mut();
+            transform.set_position(position);
+            transform.set_rotation(UnitQuaternion::look_at_rh(&look_direction, &Vector3::y()));
+        }
+    }
+
+    fn fly(&mut self, input: &SpectatorInput, dt: f32) {
+        self.yaw += input.mouse_delta.0 * MOUSE_SENSITIVITY;
+        self.pitch =
+            (self.pitch - input.mouse_delta.1 * MOUSE_SENSITIVITY).clamp(-MAX_PITCH, MAX_PITCH);
+
+        // Forward is -Z at zero yaw, matching player movement
+        let right = Vector3::new(self.yaw.cos(), 0.0, self.yaw.sin());
+
+        let mut movement = Vector3::new(0.0, 0.0, 0.0);
+        if input.forward {
+            movement += self.look_direction();
+        }
+        if input.backward {
+            movement -= self.look_direction();
+        }
+        if input.left {
+            movement -= right;
+        }
+        if input.right {
+            movement += right;
+        }
+        if input.up {
+            movement.y += 1.0;
+        }
+        if input.down {
+            movement.y -= 1.0;
+        }
+
+        if movement.magnitude() > 0.0 {
+            self.position += movement.normalize() * FLY_SPEED * dt;
+        }
+    }
+
+    fn look_direction(&self) -> Vector3<f32> {
+        Vector3::new(
+            self.yaw.sin() * self.pitch.cos(),
+            self.pitch.sin(),
+            -self.yaw.cos() * self.pitch.cos(),
+        )
+    }
+}
@@ -0,0 +1,114 @@
+use crate::{components::ComponentStore, pooling::PrefabPool};
+use fyrox::{
+    asset::manager::ResourceManager,
+    core::instant::Instant,
+    renderer::Renderer,
+    resource::texture::Texture,
+    scene::{
+        sound::{Sound, Status},
+        Scene,
+    },
+};
+use std::time::Duration;
+
+// Summing up texture memory walks every loaded resource, too slow for every frame
+const TEXTURE_SCAN_INTERVAL: Duration = Duration::from_secs(1);
+
+// What the renderer did for the last frame
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct RenderStats {
+    pub draw_calls: usize,
+    pub triangles: usize,
+    // Textures uploaded to the GPU
+    pub gpu_textures: usize,
+    // Pixel data of every loaded texture, what the GPU copies take up give or take mipmaps
+    pub texture_bytes: usize,
+}
+
+// What the game is keeping alive
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct GameStats {
+    pub scene_nodes: usize,
+    // Nodes with gameplay components
+    pub entities: usize,
+    pub pooled_in_use: usize,
+    pub pooled_idle: usize,
+    pub active_sounds: usize,
+}
+
+// Counts for the debug overlay, only gathered while it is shown. Numbers that keep
+// growing while nothing happens in game point at a leak, many draw calls for few
+// triangles at broken batching.
+#[derive(Default)]
+pub struct StatsCollector {
+    render: RenderStats,
+    game: GameStats,
+    last_texture_scan: Option<Instant>,
+}
+
+impl StatsCollector {
+    pub fn render(&self) -> RenderStats {
+        self.render
+    }
+
+    pub fn game(&self) -> GameStats {
+        self.game
+    }
+
+    pub fn collect_render(&mut self, renderer: &Renderer, resource_manager: &ResourceManager) {
+        let statistics = renderer.get_statistics();
+        self.render.draw_calls = statistics.geometry.draw_calls;
+        self.render.triangles = statistics.geometry.triangles_rendered;
+        self.render.gpu_textures = statistics.texture_cache_size;
+
+        if self
+            .last_texture_scan
+            .is_none_or(|scan| scan.elapsed() >= TEXTURE_SCAN_INTERVAL)
+        {
+            self.last_texture_scan = Some(Instant::now());
+            self.render.texture_bytes = texture_bytes(resource_manager);
+        }
+    }
+
+    pub fn collect_game(
+        &mut self,
+        scene: Option<&Scene>,
+        components: &ComponentStore,
+        pool: &PrefabPool,
+    ) {
+        self.game = GameStats {
+            scene_nodes: scene.map_or(0, |scene| scene.graph.node_count() as usize),
+            entities: components.entity_count(),
+            pooled_in_use: pool.in_use(),
+            pooled_idle: pool.idle(),
+            active_sounds: scene.map_or(0, |scene| {
+                scene
+                    .graph
+                    .linear_iter()
+                    .filter_map(|node| node.query_component_ref::<Sound>())
+                    .filter(|sound| sound.status() == Status::Playing)
+                    .count()
+            }),
+        };
+    }
+}
+
+fn texture_bytes(resource_manager: &ResourceManager) -> usize {
+    resource_manager
+        .state()
+        .resources()
+        .into_iter()
+        .filter_map(|resource| resource.try_cast::<Texture>())
+        .filter(|texture| texture.is_ok())
+        .map(|texture| texture.data_ref().data().len())
+        .sum()
+}
+
+// Byte count with a readable unit
+pub fn format_bytes(bytes: usize) -> String {
+    match bytes {
+        0..=1023 => format!("{bytes} B"),
+        1024..=1_048_575 => format!("{:.1} KB", bytes as f32 / 1024.0),
+        _ => format!("{:.1} MB", bytes as f32 / (1024.0 * 1024.0)),
+    }
+}
@@ -0,0 +1,51 @@
+// Steam integration, only built with `--features steam`. Without Steam running (or
+// the game started outside it) the plugin is simply not added.
+//
+// Achievements and Steam Cloud have nothing to mirror yet: the game keeps no
+// achievements of its own and has no save data besides the display settings. They
+// belong here once it does, next to the rich presence.
+use crate::{plugins::GamePlugin, presence::Presence};
+use log::{info, warn};
+use steamworks::{Client, ClientManager, SingleClient};
+
+// Valve's Spacewar test app until the game has an app id of its own. Outside Steam
+// the id is also read from steam_appid.txt in the working directory.
+pub const STEAM_APP_ID: u32 = 480;
+
+pub struct SteamPlugin {
+    client: Client<ClientManager>,
+    // Runs Steam's callbacks, has to stay on the thread that made it
+    single: SingleClient<ClientManager>,
+}
+
+impl SteamPlugin {
+    pub fn init() -> Option<Self> {
+        match Client::init_app(STEAM_APP_ID) {
+            Ok((client, single)) => {
+                info!("Steam: signed in as {}", client.friends().name());
+                Some(Self { client, single })
+            }
+            Err(err) => {
+                warn!("Playing without Steam: {err}");
+                None
+            }
+        }
+    }
+}
+
+impl GamePlugin for SteamPlugin {
+    fn name(&self) -> &str {
+        "steam"
+    }
+
+    fn update_frame(&mut self) {
+        self.single.run_callbacks();
+    }
+
+    // Shown under the player's name in the friends list
+    fn presence_changed(&mut self, presence: &Presence) {
+        self.client
+            .friends()
+            .set_rich_presence("status", Some(&presence.describe()));
+    }
+}
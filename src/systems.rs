@@ -0,0 +1,292 @@
+use crate::{
+    components::{
+        Component, ComponentStore, ContactDamage, EnemyAi, EnemyState, Interactable, Pickup,
+        SimTransform, Trigger, TriggerKind,
+    },
+    debug_draw::{self, DebugCategory},
+    palette::{self, ColorRole},
+    spatial::SpatialGrid,
+};
+use fyrox::{
+    core::{algebra::Vector3, pool::Handle},
+    scene::{node::Node, Scene},
+};
+use std::collections::{BTreeMap, BTreeSet};
+
+// Enemies closer to home than this have made it back
+const HOME_RADIUS: f32 = 0.1;
+
+// Entities with one kind of component, filed by position
+#[derive(Default)]
+struct Layer {
+    grid: SpatialGrid,
+    // Largest radius of anything in the layer, how far a query has to look to find
+    // everything that could be touching a point
+    reach: f32,
+}
+
+impl Layer {
+    fn near(&self, point: Vector3<f32>) -> Vec<Handle<Node>> {
+        self.grid.query(point, self.reach)
+    }
+}
+
+// Positions the gameplay systems need, copied out of the scene once per tick. The
+// scene graph can't be shared between threads, this can.
+#[derive(Default)]
+pub struct WorldSnapshot {
+    pub player_position: Option<Vector3<f32>>,
+    positions: BTreeMap<Handle<Node>, Vector3<f32>>,
+    triggers: Layer,
+    pickups: Layer,
+    enemies: Layer,
+    contacts: Layer,
+    interactables: Layer,
+}
+
+impl WorldSnapshot {
+    pub fn capture(
+        scene: &Scene,
+        components: &ComponentStore,
+        player_position: Option<Vector3<f32>>,
+    ) -> Self {
+        let mut snapshot = Self {
+            player_position,
+            ..Default::default()
+        };
+        snapshot.triggers = snapshot.add::<Trigger>(scene, components, |trigger| trigger.radius);
+        snapshot.pickups = snapshot.add::<Pickup>(scene, components, |pickup| pickup.radius);
+        snapshot.enemies = snapshot.add::<EnemyAi>(scene, components, |ai| ai.aggro_radius);
+        snapshot.contacts =
+            snapshot.add::<ContactDamage>(scene, components, |contact| contact.radius);
+        snapshot.interactables =
+            snapshot.add::<Interactable>(scene, components, |interactable| interactable.radius);
+        snapshot
+    }
+
+    // Simulated position where there is one, otherwise wherever the node is
+    fn add<T: Component>(
+        &mut self,
+        scene: &Scene,
+        components: &ComponentStore,
+        radius: impl Fn(&T) -> f32,
+    ) -> Layer {
+        let mut layer = Layer::default();
+        for (entity, component) in components.iter::<T>() {
+            let position = components
+                .get::<SimTransform>(entity)
+                .map(|sim| sim.position)
+                .or_else(|| {
+                    scene
+                        .graph
+                        .try_get(entity)
+                        .map(|node| **node.local_transform().position())
+                });
+            if let Some(position) = position {
+                self.positions.insert(entity, position);
+                layer.grid.insert(entity, position);
+                layer.reach = layer.reach.max(radius(component));
+            }
+        }
+        layer
+    }
+
+    pub fn position(&self, entity: Handle<Node>) -> Option<Vector3<f32>> {
+        self.positions.get(&entity).copied()
+    }
+}
+
+#[derive(Clone, Debug)]
+pub struct EnemyStep {
+    pub enemy: Handle<Node>,
+    pub state: EnemyState,
+    pub position: Vector3<f32>,
+}
+
+// What the read-only systems decided this tick, applied by the game afterwards
+#[derive(Default)]
+pub struct SystemOutput {
+    pub entered_triggers: Vec<(Handle<Node>, TriggerKind)>,
+    pub collected_pickups: Vec<(Handle<Node>, Pickup)>,
+    pub enemy_steps: Vec<EnemyStep>,
+    // Things touching the local player that are ready to hurt it, with the damage they do
+    pub contact_hits: Vec<(Handle<Node>, f32)>,
+    // The closest thing the local player is near enough to use
+    pub interactable: Option<Handle<Node>>,
+}
+
+// Runs the systems that only read game state side by side on the job pool. Returns
+// once all of them are done, which is the sync point before the scene is mutated.
+// Each system visits entities in handle order, so the result doesn't depend on
+// which thread finishes first.
+pub fn run_parallel(
+    snapshot: &WorldSnapshot,
+    components: &ComponentStore,
+    dt: f32,
+) -> SystemOutput {
+    let mut output = SystemOutput::default();
+    let SystemOutput {
+        entered_triggers,
+        collected_pickups,
+        enemy_steps,
+        contact_hits,
+        interactable,
+    } = &mut output;
+
+    rayon::scope(|scope| {
+        scope.spawn(|_| *entered_triggers = find_entered_triggers(snapshot, components));
+        scope.spawn(|_| *collected_pickups = find_collected_pickups(snapshot, components));
+        scope.spawn(|_| *enemy_steps = think_enemies(snapshot, components, dt));
+        scope.spawn(|_| *contact_hits = find_contact_hits(snapshot, components));
+        scope.spawn(|_| *interactable = find_interactable(snapshot, components));
+    });
+
+    output
+}
+
+// Triggers the local player is standing in that haven't used up their one shot
+fn find_entered_triggers(
+    snapshot: &WorldSnapshot,
+    components: &ComponentStore,
+) -> Vec<(Handle<Node>, TriggerKind)> {
+    let Some(player_position) = snapshot.player_position else {
+        return Vec::new();
+    };
+
+    snapshot
+        .triggers
+        .near(player_position)
+        .into_iter()
+        .filter_map(|entity| Some((entity, components.get::<Trigger>(entity)?)))
+        .filter(|(_, trigger)| !(trigger.once && trigger.fired))
+        .filter(|(entity, trigger)| {
+            snapshot
+                .position(*entity)
+                .is_some_and(|center| trigger.contains(center, player_position))
+        })
+        .map(|(entity, trigger)| (entity, trigger.kind))
+        .collect()
+}
+
+// Pickups the local player touches
+fn find_collected_pickups(
+    snapshot: &WorldSnapshot,
+    components: &ComponentStore,
+) -> Vec<(Handle<Node>, Pickup)> {
+    let Some(player_position) = snapshot.player_position else {
+        return Vec::new();
+    };
+
+    snapshot
+        .pickups
+        .near(player_position)
+        .into_iter()
+        .filter_map(|entity| Some((entity, components.get::<Pickup>(entity)?)))
+        .filter(|(entity, pickup)| {
+            snapshot
+                .position(*entity)
+                .is_some_and(|position| (position - player_position).norm() <= pickup.radius)
+        })
+        .map(|(entity, pickup)| (entity, pickup.clone()))
+        .collect()
+}
+
+// Damage dealers touching the local player whose cooldown has run out
+fn find_contact_hits(
+    snapshot: &WorldSnapshot,
+    components: &ComponentStore,
+) -> Vec<(Handle<Node>, f32)> {
+    let Some(player_position) = snapshot.player_position else {
+        return Vec::new();
+    };
+
+    snapshot
+        .contacts
+        .near(player_position)
+        .into_iter()
+        .filter_map(|entity| Some((entity, components.get::<ContactDamage>(entity)?)))
+        .filter(|(_, contact)| contact.cooldown <= 0.0)
+        .filter(|(entity, contact)| {
+            snapshot.position(*entity).is_some_and(|position| {
+                horizontal(player_position - position).norm() <= contact.radius
+            })
+        })
+        .map(|(entity, contact)| (entity, contact.amount))
+        .collect()
+}
+
+// The closest interactable whose radius the local player is in, the first in handle
+// order when two are equally close
+fn find_interactable(
+    snapshot: &WorldSnapshot,
+    components: &ComponentStore,
+) -> Option<Handle<Node>> {
+    let player_position = snapshot.player_position?;
+
+    snapshot
+        .interactables
+        .near(player_position)
+        .into_iter()
+        .filter_map(|entity| {
+            let interactable = components.get::<Interactable>(entity)?;
+            let distance = horizontal(player_position - snapshot.position(entity)?).norm();
+            (distance <= interactable.radius).then_some((entity, distance))
+        })
+        .min_by(|(_, a), (_, b)| a.total_cmp(b))
+        .map(|(entity, _)| entity)
+}
+
+// Enemy perception: chase a player within aggro range, otherwise walk back home
+fn think_enemies(snapshot: &WorldSnapshot, components: &ComponentStore, dt: f32) -> Vec<EnemyStep> {
+    // Only enemies around the player can possibly see it
+    let in_range: BTreeSet<_> = snapshot
+        .player_position
+        .map(|player| snapshot.enemies.near(player))
+        .unwrap_or_default()
+        .into_iter()
+        .collect();
+
+    components
+        .iter::<EnemyAi>()
+        .filter_map(|(entity, ai)| {
+            let position = snapshot.position(entity)?;
+            let chase = snapshot.player_position.filter(|player| {
+                in_range.contains(&entity)
+                    && horizontal(*player - position).norm() <= ai.aggro_radius
+            });
+
+            let (state, target) = match chase {
+                Some(player) => (EnemyState::Chasing, player),
+                None if horizontal(ai.home - position).norm() > HOME_RADIUS => {
+                    (EnemyState::Returning, ai.home)
+                }
+                None => (EnemyState::Idle, position),
+            };
+            if state != EnemyState::Idle {
+                let color = match state {
+                    EnemyState::Chasing => palette::color(ColorRole::EnemyAlert),
+                    _ => palette::color(ColorRole::Enemy),
+                };
+                debug_draw::arrow(DebugCategory::AiPaths, position, target, color);
+            }
+
+            let offset = horizontal(target - position);
+            let step = ai.speed * dt;
+            let position = if offset.norm() > step {
+                position + offset.normalize() * step
+            } else {
+                position + offset
+            };
+
+            Some(EnemyStep {
+                enemy: entity,
+                state,
+                position,
+            })
+        })
+        .collect()
+}
+
+fn horizontal(vector: Vector3<f32>) -> Vector3<f32> {
+    Vector3::new(vector.x, 0.0, vector.z)
+}
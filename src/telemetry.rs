@@ -0,0 +1,214 @@
+use crate::{console::CommandRegistry, game::Game, paths::data_dir};
+use fyrox::{core::instant::Instant, plugin::PluginContext};
+use log::{info, warn};
+use serde::{Deserialize, Serialize};
+use std::{
+    collections::BTreeMap,
+    fs, io,
+    sync::atomic::{AtomicBool, Ordering},
+    time::Duration,
+};
+
+// File inside the data directory holding the player's choice
+const CONSENT_FILE: &str = "telemetry.json";
+
+// The report goes out while the game closes, which shouldn't hang on a dead endpoint
+const REQUEST_TIMEOUT: Duration = Duration::from_secs(3);
+
+// Frames further apart than this are a stall (loading, a breakpoint), not a frame rate
+const MAX_FRAME_TIME: f32 = 1.0;
+
+// Off until the player turns it on, nothing is sent without their say
+static ENABLED: AtomicBool = AtomicBool::new(false);
+
+pub fn enabled() -> bool {
+    ENABLED.load(Ordering::Relaxed)
+}
+
+// Also remembered for the next start
+pub fn set_enabled(enabled: bool) {
+    ENABLED.store(enabled, Ordering::Relaxed);
+    if let Err(err) = save_consent(enabled) {
+        warn!("Couldn't save the telemetry choice: {err}");
+    }
+}
+
+#[derive(Default, Serialize, Deserialize)]
+#[serde(default)]
+struct Consent {
+    enabled: bool,
+}
+
+// Picks up what the player chose in an earlier run
+pub fn load_consent() {
+    let consent: Consent = fs::read_to_string(data_dir().join(CONSENT_FILE))
+        .ok()
+        .and_then(|text| serde_json::from_str(&text).ok())
+        .unwrap_or_default();
+    ENABLED.store(consent.enabled, Ordering::Relaxed);
+}
+
+fn save_consent(enabled: bool) -> io::Result<()> {
+    let dir = data_dir();
+    fs::create_dir_all(&dir)?;
+    fs::write(
+        dir.join(CONSENT_FILE),
+        serde_json::to_vec_pretty(&Consent { enabled })?,
+    )
+}
+
+#[derive(Clone, Debug, Default, PartialEq, Eq, Serialize)]
+pub struct LevelStats {
+    pub started: u32,
+    pub completed: u32,
+    pub deaths: u32,
+}
+
+// Everything a session sends. Nothing in it says who played or on what machine.
+#[derive(Clone, Debug, PartialEq, Serialize)]
+pub struct SessionReport {
+    pub version: &'static str,
+    pub platform: &'static str,
+    pub minutes_played: u32,
+    // By level id
+    pub levels: BTreeMap<String, LevelStats>,
+    // Average frame rate while a level was on screen, in broad buckets
+    pub fps_bucket: &'static str,
+}
+
+// Anonymous stats for tuning difficulty and performance: how often levels are started,
+// finished and died in, and how fast the game runs. They are collected either way but
+// only posted, once when the game closes, if the player opted in and an endpoint is set.
+pub struct Telemetry {
+    // Base URL of the telemetry service, reports go to its /sessions
+    endpoint: Option<String>,
+    started: Instant,
+    levels: BTreeMap<String, LevelStats>,
+    frames: u32,
+    frame_time: f32,
+    last_frame: Option<Instant>,
+}
+
+impl Default for Telemetry {
+    fn default() -> Self {
+        Self::new(None)
+    }
+}
+
+impl Telemetry {
+    pub fn new(endpoint: Option<String>) -> Self {
+        Self {
+            endpoint,
+            started: Instant::now(),
+            levels: BTreeMap::new(),
+            frames: 0,
+            frame_time: 0.0,
+            last_frame: None,
+        }
+    }
+
+    pub fn endpoint(&self) -> Option<&str> {
+        self.endpoint.as_deref()
+    }
+
+    pub fn level_started(&mut self, level: &str) {
+        self.level(level).started += 1;
+    }
+
+    pub fn level_completed(&mut self, level: &str) {
+        self.level(level).completed += 1;
+    }
+
+    pub fn player_died(&mut self, level: &str) {
+        self.level(level).deaths += 1;
+    }
+
+    fn level(&mut self, level: &str) -> &mut LevelStats {
+        self.levels.entry(level.to_string()).or_default()
+    }
+
+    // Called for every drawn frame, `playing` says whether a level was on screen
+    pub fn record_frame(&mut self, playing: bool) {
+        let now = Instant::now();
+        if let (true, Some(last)) = (playing, self.last_frame) {
+            let frame_time = (now - last).as_secs_f32();
+            if frame_time < MAX_FRAME_TIME {
+                self.frames += 1;
+                self.frame_time += frame_time;
+            }
+        }
+        self.last_frame = Some(now);
+    }
+
+    pub fn report(&self) -> SessionReport {
+        let fps = (self.frame_time > 0.0).then(|| self.frames as f32 / self.frame_time);
+        SessionReport {
+            version: env!("CARGO_PKG_VERSION"),
+            platform: std::env::consts::OS,
+            minutes_played: (self.started.elapsed().as_secs() / 60) as u32,
+            levels: self.levels.clone(),
+            fps_bucket: fps_bucket(fps),
+        }
+    }
+
+    // Posts the session's report if the player agreed, called once when the game closes.
+    // A report that doesn't get through is dropped, it isn't worth keeping around.
+    pub fn send(&self) {
+        let Some(endpoint) = self.endpoint.as_deref().filter(|_| enabled()) else {
+            return;
+        };
+        let url = format!("{}/sessions", endpoint.trim_end_matches('/'));
+        let agent = ureq::AgentBuilder::new().timeout(REQUEST_TIMEOUT).build();
+        match agent.post(&url).send_json(self.report()) {
+            Ok(_) => info!("Sent anonymous session stats"),
+            Err(err) => warn!("Session stats not sent: {err}"),
+        }
+    }
+}
+
+fn fps_bucket(fps: Option<f32>) -> &'static str {
+    match fps {
+        None => "none",
+        Some(fps) if fps < 30.0 => "under 30",
+        Some(fps) if fps < 60.0 => "30-59",
+        Some(fps) if fps < 120.0 => "60-119",
+        Some(_) => "120+",
+    }
+}
+
+pub fn register_commands(commands: &mut CommandRegistry) {
+    commands.register(
+        "telemetry",
+        "[on|off|show]",
+        "show or switch anonymous session stats, show prints what would be sent",
+        command_telemetry,
+    );
+    commands.complete_with("telemetry", || {
+        vec!["on".to_string(), "off".to_string(), "show".to_string()]
+    });
+}
+
+fn command_telemetry(
+    game: &mut Game,
+    context: &mut PluginContext,
+    args: &[&str],
+) -> Result<String, String> {
+    match args {
+        [] => {}
+        ["on"] => game.set_telemetry(context, true),
+        ["off"] => game.set_telemetry(context, false),
+        ["show"] => {
+            return serde_json::to_string_pretty(&game.telemetry().report())
+                .map_err(|err| err.to_string())
+        }
+        _ => return Err("usage: telemetry [on|off|show]".to_string()),
+    }
+    Ok(format!(
+        "telemetry {}, {}",
+        if enabled() { "on" } else { "off" },
+        match game.telemetry().endpoint() {
+            Some(endpoint) => format!("reports go to {endpoint}"),
+            None => "no endpoint set".to_string(),
+        }
+    ))
+}
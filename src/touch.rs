@@ -0,0 +1,123 @@
+use crate::{input::InputState, movement::MoveInput};
+use fyrox::{
+    core::algebra::Vector2,
+    event::{Touch, TouchPhase},
+    gui::{
+        message::{ButtonState, MouseButton, OsEvent},
+        UserInterface,
+    },
+};
+
+// How far, in the UI's pixels, a finger moves the stick all the way over
+const STICK_RADIUS: f32 = 60.0;
+// Part of the stick's travel that doesn't move the player, so a resting thumb stands still
+const DEAD_ZONE: f32 = 0.3;
+
+// Fingers on a touch screen. While a level is played the left half of the screen is a
+// floating stick that walks the player, the right half looks around like the mouse.
+// Anywhere else a tap is a click, the UI only knows the mouse.
+#[derive(Default)]
+pub struct TouchControls {
+    // Finger on the stick, where it came down and where it is now
+    stick: Option<(u64, Vector2<f32>, Vector2<f32>)>,
+    // Finger looking around, where it was last
+    look: Option<(u64, Vector2<f32>)>,
+    // Whether anything was touched yet, a touch screen can come with a mouse
+    used: bool,
+}
+
+impl TouchControls {
+    pub fn is_used(&self) -> bool {
+        self.used
+    }
+
+    // `playing` says whether the fingers steer the game or tap the UI. The scale factor
+    // turns the screen's physical pixels into the UI's, the stick grows along with it.
+    pub fn handle_touch(
+        &mut self,
+        touch: &Touch,
+        playing: bool,
+        screen_width: f32,
+        scale_factor: f32,
+        input: &mut InputState,
+        ui: &mut UserInterface,
+    ) {
+        self.used = true;
+        let position = Vector2::new(touch.location.x as f32, touch.location.y as f32);
+
+        if !playing {
+            self.release(input);
+            tap(ui, position, touch.phase);
+            return;
+        }
+
+        match touch.phase {
+            TouchPhase::Started if position.x < screen_width / 2.0 => {
+                if self.stick.is_none() {
+                    self.stick = Some((touch.id, position, position));
+                }
+            }
+            TouchPhase::Started => {
+                if self.look.is_none() {
+                    self.look = Some((touch.id, position));
+                }
+            }
+            TouchPhase::Moved => {
+                if let Some((_, origin, current)) =
+                    self.stick.as_mut().filter(|(id, ..)| *id == touch.id)
+                {
+                    *current = position;
+                    let tilt = (*current - *origin) / (STICK_RADIUS * scale_factor);
+                    input.set_move_input(stick_input(tilt));
+                } else if let Some((_, last)) = self.look.as_mut().filter(|(id, _)| *id == touch.id)
+                {
+                    let delta = (position - *last) / scale_factor;
+                    *last = position;
+                    input.mouse_delta.x += delta.x;
+                    input.mouse_delta.y += delta.y;
+                }
+            }
+            TouchPhase::Ended | TouchPhase::Cancelled => {
+                if self.stick.is_some_and(|(id, ..)| id == touch.id) {
+                    self.stick = None;
+                    input.set_move_input(MoveInput::default());
+                }
+                if self.look.is_some_and(|(id, _)| id == touch.id) {
+                    self.look = None;
+                }
+            }
+        }
+    }
+
+    // Lets go of the stick, e.g. when a menu opens or the app goes to the background
+    pub fn release(&mut self, input: &mut InputState) {
+        if self.stick.take().is_some() {
+            input.set_move_input(MoveInput::default());
+        }
+        self.look = None;
+    }
+}
+
+// The keys a stick tilted by `tilt` (1 is all the way over, y down) holds
+fn stick_input(tilt: Vector2<f32>) -> MoveInput {
+    MoveInput {
+        forward: tilt.y < -DEAD_ZONE,
+        backward: tilt.y > DEAD_ZONE,
+        left: tilt.x < -DEAD_ZONE,
+        right: tilt.x > DEAD_ZONE,
+    }
+}
+
+// A finger on the UI, passed on as the left mouse button at the same spot
+fn tap(ui: &mut UserInterface, position: Vector2<f32>, phase: TouchPhase) {
+    ui.process_os_event(&OsEvent::CursorMoved { position });
+    let state = match phase {
+        TouchPhase::Started => ButtonState::Pressed,
+        TouchPhase::Ended | TouchPhase::Cancelled => ButtonState::Released,
+        TouchPhase::Moved => return,
+    };
+    ui.process_os_event(&OsEvent::MouseInput {
+        button: MouseButton::Left,
+        state,
+    });
+}
@@ -0,0 +1,84 @@
+use crate::{camera::CameraController, movement::PLAYER_SPEED};
+use fyrox::core::instant::Instant;
+use log::warn;
+use serde::{Deserialize, Serialize};
+use std::{
+    fs,
+    path::PathBuf,
+    time::{Duration, SystemTime},
+};
+
+// How often the tuning file is checked for changes
+const POLL_INTERVAL: Duration = Duration::from_millis(500);
+
+// Gameplay numbers that can be changed while the game is running
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct Tuning {
+    // Units per second, only used offline so online play stays in sync with the server
+    pub player_speed: f32,
+    // Camera position relative to the player (behind and above)
+    pub camera_offset: [f32; 3],
+    pub camera_smoothing: f32,
+}
+
+impl Default for Tuning {
+    fn default() -> Self {
+        let camera = CameraController::default();
+        Self {
+            player_speed: PLAYER_SPEED,
+            camera_offset: camera.offset.into(),
+            camera_smoothing: camera.smoothing,
+        }
+    }
+}
+
+// Watches a JSON tuning file and hands out its contents whenever it changes, so
+// tweaks apply on save without restarting or reloading the level
+pub struct TuningWatcher {
+    path: PathBuf,
+    modified: Option<SystemTime>,
+    last_poll: Instant,
+}
+
+impl TuningWatcher {
+    pub fn new(path: PathBuf) -> Self {
+        // Start from a file with every value in it, so there is something to edit
+        if !path.exists() {
+            if let Ok(json) = serde_json::to_string_pretty(&Tuning::default()) {
+                if let Err(err) = fs::write(&path, json) {
+                    warn!("Couldn't create {}: {err}", path.display());
+                }
+            }
+        }
+
+        Self {
+            path,
+            modified: None,
+            last_poll: Instant::now() - POLL_INTERVAL,
+        }
+    }
+
+    // New tuning if the file changed since the last call. A file that doesn't
+    // parse is reported once and ignored until it is saved again.
+    pub fn poll(&mut self) -> Option<Result<Tuning, String>> {
+        if self.last_poll.elapsed() < POLL_INTERVAL {
+            return None;
+        }
+        self.last_poll = Instant::now();
+
+        let modified = fs::metadata(&self.path)
+            .and_then(|meta| meta.modified())
+            .ok();
+        if modified.is_none() || modified == self.modified {
+            return None;
+        }
+        self.modified = modified;
+
+        Some(
+            fs::read_to_string(&self.path)
+                .map_err(|err| err.to_string())
+                .and_then(|text| serde_json::from_str(&text).map_err(|err| err.to_string())),
+        )
+    }
+}
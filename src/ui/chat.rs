@@ -0,0 +1,187 @@
+use fyrox::{
+    core::{color::Color, instant::Instant, pool::Handle},
+    gui::{
+        brush::Brush,
+        message::{MessageDirection, UiMessage},
+        stack_panel::StackPanelBuilder,
+        text::{TextBuilder, TextMessage},
+        text_box::TextBoxBuilder,
+        widget::{WidgetBuilder, WidgetMessage},
+        BuildContext, HorizontalAlignment, Thickness, UiNode, UserInterface, VerticalAlignment,
+    },
+};
+use std::collections::VecDeque;
+
+// Number of chat lines shown in the overlay
+const VISIBLE_LINES: usize = 6;
+
+// Seconds a line stays fully visible before it starts fading
+const LINE_LIFETIME: f32 = 8.0;
+
+// Seconds it takes a line to fade out completely
+const FADE_DURATION: f32 = 2.0;
+
+struct ChatEntry {
+    text: String,
+    received: Instant,
+}
+
+// Chat overlay in the bottom left corner: fading recent lines plus an input box
+#[derive(Default)]
+pub struct ChatBox {
+    input: Handle<UiNode>,
+    line_slots: Vec<Handle<UiNode>>,
+    lines: VecDeque<ChatEntry>,
+    draft: String,
+    open: bool,
+    // What is currently displayed, so unchanged lines don't spam the UI with messages
+    lines_dirty: bool,
+    shown_alpha: Vec<u8>,
+}
+
+impl ChatBox {
+    pub fn new(ctx: &mut BuildContext) -> Self {
+        let line_slots: Vec<_> = (0..VISIBLE_LINES)
+            .map(|_| TextBuilder::new(WidgetBuilder::new()).build(ctx))
+            .collect();
+
+        let input = TextBoxBuilder::new(
+            WidgetBuilder::new()
+                .with_width(400.0)
+                .with_height(26.0)
+                .with_visibility(false),
+        )
+        .build(ctx);
+
+        let mut children = line_slots.clone();
+        children.push(input);
+
+        StackPanelBuilder::new(
+            WidgetBuilder::new()
+                .with_horizontal_alignment(HorizontalAlignment::Left)
+                .with_vertical_alignment(VerticalAlignment::Bottom)
+                .with_margin(Thickness::uniform(10.0))
+                .with_children(children),
+        )
+        .build(ctx);
+
+        Self {
+            input,
+            line_slots,
+            lines: VecDeque::new(),
+            draft: String::new(),
+            open: false,
+            lines_dirty: false,
+            shown_alpha: vec![0; VISIBLE_LINES],
+        }
+    }
+
+    pub fn is_open(&self) -> bool {
+        self.open
+    }
+
+    pub fn open(&mut self, ui: &UserInterface) {
+        self.open = true;
+        self.draft.clear();
+        ui.send_message(TextMessage::text(
+            self.input,
+            MessageDirection::ToWidget,
+            String::new(),
+        ));
+        ui.send_message(WidgetMessage::visibility(
+            self.input,
+            MessageDirection::ToWidget,
+            true,
+        ));
+        ui.send_message(WidgetMessage::focus(self.input, MessageDirection::ToWidget));
+    }
+
+    pub fn close(&mut self, ui: &UserInterface) {
+        self.open = false;
+        self.draft.clear();
+        ui.send_message(WidgetMessage::unfocus(
+            self.input,
+            MessageDirection::ToWidget,
+        ));
+        ui.send_message(WidgetMessage::visibility(
+            self.input,
+            MessageDirection::ToWidget,
+            false,
+        ));
+    }
+
+    // Closes the box and returns what was typed, if anything
+    pub fn submit(&mut self, ui: &UserInterface) -> Option<String> {
+        let text = self.draft.trim().to_string();
+        self.close(ui);
+        (!text.is_empty()).then_some(text)
+    }
+
+    pub fn handle_ui_message(&mut self, message: &UiMessage) {
+        if let Some(TextMessage::Text(text)) = message.data() {
+            if message.destination() == self.input
+                && message.direction() == MessageDirection::FromWidget
+            {
+                self.draft = text.clone();
+            }
+        }
+    }
+
+    pub fn push_line(&mut self, sender: &str, text: &str) {
+        self.lines.push_back(ChatEntry {
+            text: format!("{sender}: {text}"),
+            received: Instant::now(),
+        });
+        while self.lines.len() > VISIBLE_LINES {
+            self.lines.pop_front();
+        }
+        self.lines_dirty = true;
+    }
+
+    pub fn clear(&mut self, ui: &UserInterface) {
+        self.lines.clear();
+        self.lines_dirty = true;
+        if self.open {
+            self.close(ui);
+        }
+    }
+
+    pub fn update(&mut self, ui: &UserInterface) {
+        // Newest line goes into the bottom slot
+        let first_slot = VISIBLE_LINES - self.lines.len();
+
+        for (index, slot) in self.line_slots.iter().enumerate() {
+            let entry = index
+                .checked_sub(first_slot)
+                .and_then(|line| self.lines.get(line));
+
+            if self.lines_dirty {
+                let text = entry.map_or(String::new(), |entry| entry.text.clone());
+                ui.send_message(TextMessage::text(*slot, MessageDirection::ToWidget, text));
+            }
+
+            // Everything stays readable while the player is typing
+            let alpha = match entry {
+                Some(_) if self.open => 255,
+                Some(entry) => line_alpha(entry.received.elapsed().as_secs_f32()),
+                None => 0,
+            };
+
+            if self.shown_alpha[index] != alpha {
+                self.shown_alpha[index] = alpha;
+                ui.send_message(WidgetMessage::foreground(
+                    *slot,
+                    MessageDirection::ToWidget,
+                    Brush::Solid(Color::from_rgba(255, 255, 255, alpha)),
+                ));
+            }
+        }
+
+        self.lines_dirty = false;
+    }
+}
+
+fn line_alpha(age: f32) -> u8 {
+    let fade = ((age - LINE_LIFETIME) / FADE_DURATION).clamp(0.0, 1.0);
+    ((1.0 - fade) * 255.0) as u8
+}
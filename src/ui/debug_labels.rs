@@ -0,0 +1,70 @@
+use crate::debug_draw::Label;
+use fyrox::{
+    core::{algebra::Vector2, pool::Handle},
+    gui::{
+        brush::Brush,
+        canvas::CanvasBuilder,
+        message::MessageDirection,
+        text::{TextBuilder, TextMessage},
+        widget::{WidgetBuilder, WidgetMessage},
+        BuildContext, UiNode, UserInterface,
+    },
+};
+
+// Labels shown at once, the rest of a frame's labels are left out
+const LABEL_SLOTS: usize = 32;
+
+// Screen-space text for the debug labels drawn in the world
+#[derive(Default)]
+pub struct DebugLabels {
+    slots: Vec<Handle<UiNode>>,
+    // Slots showing something last frame, so hidden ones aren't hidden again every frame
+    shown: usize,
+}
+
+impl DebugLabels {
+    pub fn new(ctx: &mut BuildContext) -> Self {
+        let slots: Vec<_> = (0..LABEL_SLOTS)
+            .map(|_| TextBuilder::new(WidgetBuilder::new().with_visibility(false)).build(ctx))
+            .collect();
+        CanvasBuilder::new(WidgetBuilder::new().with_children(slots.iter().copied())).build(ctx);
+
+        Self { slots, shown: 0 }
+    }
+
+    // `labels` are already projected into screen coordinates
+    pub fn update(&mut self, ui: &UserInterface, labels: &[(Vector2<f32>, &Label)]) {
+        for (slot, (position, label)) in self.slots.iter().zip(labels) {
+            ui.send_message(TextMessage::text(
+                *slot,
+                MessageDirection::ToWidget,
+                label.text.clone(),
+            ));
+            ui.send_message(WidgetMessage::foreground(
+                *slot,
+                MessageDirection::ToWidget,
+                Brush::Solid(label.color),
+            ));
+            ui.send_message(WidgetMessage::desired_position(
+                *slot,
+                MessageDirection::ToWidget,
+                *position,
+            ));
+            ui.send_message(WidgetMessage::visibility(
+                *slot,
+                MessageDirection::ToWidget,
+                true,
+            ));
+        }
+
+        let used = labels.len().min(LABEL_SLOTS);
+        for slot in self.slots.iter().take(self.shown).skip(used) {
+            ui.send_message(WidgetMessage::visibility(
+                *slot,
+                MessageDirection::ToWidget,
+                false,
+            ));
+        }
+        self.shown = used;
+    }
+}
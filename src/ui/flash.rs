@@ -0,0 +1,81 @@
+use fyrox::{
+    core::{algebra::Vector2, color::Color, pool::Handle},
+    gui::{
+        border::BorderBuilder,
+        brush::Brush,
+        message::MessageDirection,
+        widget::{WidgetBuilder, WidgetMessage},
+        BuildContext, UiNode, UserInterface,
+    },
+};
+
+// Tint of the damage flash, its opacity comes from the ScreenFeedback
+const FLASH_COLOR: Color = Color::opaque(220, 30, 30);
+
+// A colored layer over the whole screen that lets every click through
+#[derive(Default)]
+pub struct ScreenFlash {
+    layer: Handle<UiNode>,
+    // What is shown, so nothing is sent while the flash is off
+    shown_alpha: u8,
+    size: Vector2<f32>,
+}
+
+impl ScreenFlash {
+    pub fn new(ctx: &mut BuildContext) -> Self {
+        let layer = BorderBuilder::new(
+            WidgetBuilder::new()
+                .with_visibility(false)
+                .with_hit_test_visibility(false)
+                .with_background(Brush::Solid(Color::TRANSPARENT)),
+        )
+        .build(ctx);
+
+        Self {
+            layer,
+            shown_alpha: 0,
+            size: Vector2::default(),
+        }
+    }
+
+    // `size` is the screen in the UI's logical pixels
+    pub fn update(&mut self, ui: &UserInterface, opacity: f32, size: Vector2<f32>) {
+        let alpha = (opacity.clamp(0.0, 1.0) * 255.0) as u8;
+        if alpha == self.shown_alpha {
+            return;
+        }
+        if self.shown_alpha == 0 || alpha == 0 {
+            ui.send_message(WidgetMessage::visibility(
+                self.layer,
+                MessageDirection::ToWidget,
+                alpha > 0,
+            ));
+        }
+        self.shown_alpha = alpha;
+
+        // Follows the window, the layer isn't in a container that would stretch it
+        if size != self.size {
+            self.size = size;
+            ui.send_message(WidgetMessage::width(
+                self.layer,
+                MessageDirection::ToWidget,
+                size.x,
+            ));
+            ui.send_message(WidgetMessage::height(
+                self.layer,
+                MessageDirection::ToWidget,
+                size.y,
+            ));
+        }
+        ui.send_message(WidgetMessage::background(
+            self.layer,
+            MessageDirection::ToWidget,
+            Brush::Solid(Color::from_rgba(
+                FLASH_COLOR.r,
+                FLASH_COLOR.g,
+                FLASH_COLOR.b,
+                alpha,
+            )),
+        ));
+    }
+}
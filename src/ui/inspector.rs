@@ -0,0 +1,538 @@
+use crate::components::{
+    ComponentStore, ContactDamage, EnemyAi, EnemyState, Health, Interactable, Pickup, SimTransform,
+    Stamina, Trigger,
+};
+use fyrox::{
+    core::{
+        algebra::{Vector2, Vector3},
+        color::Color,
+        pool::Handle,
+    },
+    gui::{
+        border::BorderBuilder,
+        brush::Brush,
+        button::{ButtonBuilder, ButtonMessage},
+        grid::{Column, GridBuilder, Row},
+        message::{MessageDirection, UiMessage},
+        numeric::{NumericUpDownBuilder, NumericUpDownMessage},
+        stack_panel::StackPanelBuilder,
+        text::{TextBuilder, TextMessage},
+        widget::{WidgetBuilder, WidgetMessage},
+        BuildContext, HorizontalAlignment, Orientation, Thickness, UiNode, UserInterface,
+        VerticalAlignment,
+    },
+    scene::{camera::Camera, mesh::Mesh, node::Node, Scene},
+};
+
+// Nodes listed per page
+const LIST_SLOTS: usize = 12;
+
+// Values are refreshed a few times a second, often enough to watch them change
+const REFRESH_INTERVAL: u32 = 10;
+
+// A value of the selected entity that can be typed in
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum InspectorField {
+    PositionX,
+    PositionY,
+    PositionZ,
+    Health,
+    AiSpeed,
+    AiAggroRadius,
+}
+
+impl InspectorField {
+    const ALL: [InspectorField; 6] = [
+        InspectorField::PositionX,
+        InspectorField::PositionY,
+        InspectorField::PositionZ,
+        InspectorField::Health,
+        InspectorField::AiSpeed,
+        InspectorField::AiAggroRadius,
+    ];
+
+    fn label(self) -> &'static str {
+        match self {
+            InspectorField::PositionX => "position x",
+            InspectorField::PositionY => "position y",
+            InspectorField::PositionZ => "position z",
+            InspectorField::Health => "health",
+            InspectorField::AiSpeed => "ai speed",
+            InspectorField::AiAggroRadius => "ai aggro radius",
+        }
+    }
+}
+
+// Changes asked for in the inspector, applied by the game
+#[derive(Clone, Debug, PartialEq)]
+pub enum InspectorEdit {
+    Set {
+        entity: Handle<Node>,
+        field: InspectorField,
+        value: f32,
+    },
+    NextAiState(Handle<Node>),
+}
+
+struct ListSlot {
+    button: Handle<UiNode>,
+    text: Handle<UiNode>,
+    entity: Handle<Node>,
+}
+
+struct FieldEditor {
+    field: InspectorField,
+    row: Handle<UiNode>,
+    editor: Handle<UiNode>,
+    // Last value sent to the widget, so its echo isn't mistaken for an edit
+    shown: Option<f32>,
+}
+
+// Runtime inspector on the left side, toggled with F4: every node of the level with
+// the components it has, and the values of the selected one, live and editable.
+// Right-clicking in the world selects what is under the cursor.
+#[derive(Default)]
+pub struct Inspector {
+    panel: Handle<UiNode>,
+    page_text: Handle<UiNode>,
+    previous_button: Handle<UiNode>,
+    next_button: Handle<UiNode>,
+    slots: Vec<ListSlot>,
+    details: Handle<UiNode>,
+    fields: Vec<FieldEditor>,
+    ai_state_button: Handle<UiNode>,
+    page: usize,
+    selected: Handle<Node>,
+    visible: bool,
+    frames_until_refresh: u32,
+}
+
+impl Inspector {
+    pub fn new(ctx: &mut BuildContext) -> Self {
+        let title = TextBuilder::new(WidgetBuilder::new().with_margin(Thickness::uniform(2.0)))
+            .with_text("Inspector - right-click to pick")
+            .build(ctx);
+
+        let previous_button = make_button(ctx, "<", 30.0);
+        let page_text = TextBuilder::new(
+            WidgetBuilder::new()
+                .with_width(120.0)
+                .with_vertical_alignment(VerticalAlignment::Center),
+        )
+        .build(ctx);
+        let next_button = make_button(ctx, ">", 30.0);
+        let pager = StackPanelBuilder::new(WidgetBuilder::new().with_children([
+            previous_button,
+            page_text,
+            next_button,
+        ]))
+        .with_orientation(Orientation::Horizontal)
+        .build(ctx);
+
+        let slots: Vec<_> = (0..LIST_SLOTS)
+            .map(|_| {
+                let text = TextBuilder::new(WidgetBuilder::new()).build(ctx);
+                let button = ButtonBuilder::new(
+                    WidgetBuilder::new()
+                        .with_height(22.0)
+                        .with_margin(Thickness::uniform(1.0))
+                        .with_visibility(false),
+                )
+                .with_content(text)
+                .build(ctx);
+                ListSlot {
+                    button,
+                    text,
+                    entity: Handle::NONE,
+                }
+            })
+            .collect();
+
+        let details =
+            TextBuilder::new(WidgetBuilder::new().with_margin(Thickness::uniform(4.0))).build(ctx);
+
+        let fields: Vec<_> = InspectorField::ALL
+            .into_iter()
+            .map(|field| {
+                let label = TextBuilder::new(
+                    WidgetBuilder::new()
+                        .on_column(0)
+                        .with_vertical_alignment(VerticalAlignment::Center),
+                )
+                .with_text(field.label())
+                .build(ctx);
+                let editor = NumericUpDownBuilder::<f32>::new(WidgetBuilder::new().on_column(1))
+                    .with_step(0.1)
+                    .with_precision(2)
+                    .build(ctx);
+                let row = GridBuilder::new(
+                    WidgetBuilder::new()
+                        .with_height(24.0)
+                        .with_visibility(false)
+                        .with_children([label, editor]),
+                )
+                .add_column(Column::strict(130.0))
+                .add_column(Column::stretch())
+                .add_row(Row::stretch())
+                .build(ctx);
+                FieldEditor {
+                    field,
+                    row,
+                    editor,
+                    shown: None,
+                }
+            })
+            .collect();
+        let ai_state_button = make_button(ctx, "Next AI state", 300.0);
+
+        let mut children = vec![title, pager];
+        children.extend(slots.iter().map(|slot| slot.button));
+        children.push(details);
+        children.extend(fields.iter().map(|field| field.row));
+        children.push(ai_state_button);
+
+        let panel = BorderBuilder::new(
+            WidgetBuilder::new()
+                .with_visibility(false)
+                .with_width(320.0)
+                .with_horizontal_alignment(HorizontalAlignment::Left)
+                .with_vertical_alignment(VerticalAlignment::Top)
+                .with_margin(Thickness::uniform(10.0))
+                .with_background(Brush::Solid(Color::from_rgba(0, 0, 0, 180)))
+                .with_child(
+                    StackPanelBuilder::new(
+                        WidgetBuilder::new()
+                            .with_margin(Thickness::uniform(6.0))
+                            .with_children(children),
+                    )
+                    .build(ctx),
+                ),
+        )
+        .build(ctx);
+
+        Self {
+            panel,
+            page_text,
+            previous_button,
+            next_button,
+            slots,
+            details,
+            fields,
+            ai_state_button,
+            page: 0,
+            selected: Handle::NONE,
+            visible: false,
+            frames_until_refresh: 0,
+        }
+    }
+
+    pub fn is_visible(&self) -> bool {
+        self.visible
+    }
+
+    pub fn toggle(&mut self, ui: &UserInterface) {
+        self.visible = !self.visible;
+        self.frames_until_refresh = 0;
+        ui.send_message(WidgetMessage::visibility(
+            self.panel,
+            MessageDirection::ToWidget,
+            self.visible,
+        ));
+    }
+
+    pub fn select(&mut self, entity: Handle<Node>) {
+        self.selected = entity;
+        self.frames_until_refresh = 0;
+        for field in self.fields.iter_mut() {
+            field.shown = None;
+        }
+    }
+
+    // The level was unloaded, nothing in the old list exists anymore
+    pub fn clear(&mut self) {
+        self.select(Handle::NONE);
+        self.page = 0;
+    }
+
+    pub fn handle_ui_message(&mut self, message: &UiMessage) -> Option<InspectorEdit> {
+        if let Some(NumericUpDownMessage::<f32>::Value(value)) = message.data() {
+            if message.direction() != MessageDirection::FromWidget {
+                return None;
+            }
+            let editor = self
+                .fields
+                .iter_mut()
+                .find(|field| field.editor == message.destination())?;
+            if editor.shown == Some(*value) || self.selected.is_none() {
+                return None;
+            }
+            editor.shown = Some(*value);
+            return Some(InspectorEdit::Set {
+                entity: self.selected,
+                field: editor.field,
+                value: *value,
+            });
+        }
+
+        if let Some(ButtonMessage::Click) = message.data() {
+            let button = message.destination();
+            if button == self.previous_button {
+                self.page = self.page.saturating_sub(1);
+                self.frames_until_refresh = 0;
+            } else if button == self.next_button {
+                self.page += 1;
+                self.frames_until_refresh = 0;
+            } else if button == self.ai_state_button && self.selected.is_some() {
+                return Some(InspectorEdit::NextAiState(self.selected));
+            } else if let Some(slot) = self.slots.iter().find(|slot| slot.button == button) {
+                let entity = slot.entity;
+                self.select(entity);
+            }
+        }
+        None
+    }
+
+    pub fn update(
+        &mut self,
+        ui: &UserInterface,
+        scene: Option<&Scene>,
+        components: &ComponentStore,
+    ) {
+        if !self.visible {
+            return;
+        }
+        if self.frames_until_refresh > 0 {
+            self.frames_until_refresh -= 1;
+            return;
+        }
+        self.frames_until_refresh = REFRESH_INTERVAL;
+
+        let nodes: Vec<_> = scene.map_or_else(Vec::new, |scene| {
+            scene
+                .graph
+                .pair_iter()
+                .filter(|(handle, _)| *handle != scene.graph.get_root())
+                .map(|(handle, node)| (handle, entry_text(handle, node, components)))
+                .collect()
+        });
+        let pages = nodes.len().div_ceil(LIST_SLOTS).max(1);
+        self.page = self.page.min(pages - 1);
+        ui.send_message(TextMessage::text(
+            self.page_text,
+            MessageDirection::ToWidget,
+            format!("{} nodes, {}/{pages}", nodes.len(), self.page + 1),
+        ));
+
+        let mut page_nodes = nodes.into_iter().skip(self.page * LIST_SLOTS);
+        for slot in self.slots.iter_mut() {
+            let entry = page_nodes.next();
+            slot.entity = entry.as_ref().map_or(Handle::NONE, |(handle, _)| *handle);
+            ui.send_message(WidgetMessage::visibility(
+                slot.button,
+                MessageDirection::ToWidget,
+                entry.is_some(),
+            ));
+            if let Some((handle, text)) = entry {
+                let marker = if handle == self.selected { "> " } else { "" };
+                ui.send_message(TextMessage::text(
+                    slot.text,
+                    MessageDirection::ToWidget,
+                    format!("{marker}{text}"),
+                ));
+            }
+        }
+
+        let selected = scene.and_then(|scene| scene.graph.try_get(self.selected));
+        let details = match selected {
+            Some(node) => details_text(self.selected, node, components),
+            None => "Nothing selected".to_string(),
+        };
+        ui.send_message(TextMessage::text(
+            self.details,
+            MessageDirection::ToWidget,
+            details,
+        ));
+
+        for field in self.fields.iter_mut() {
+            let value =
+                selected.and_then(|node| field_value(field.field, self.selected, node, components));
+            ui.send_message(WidgetMessage::visibility(
+                field.row,
+                MessageDirection::ToWidget,
+                value.is_some(),
+            ));
+            // Leave the box alone while it's being typed in
+            let focus = ui.keyboard_focus();
+            let editing = focus == field.editor
+                || ui
+                    .try_get(field.editor)
+                    .is_some_and(|editor| editor.has_descendant(focus, ui));
+            if let Some(value) = value.filter(|_| !editing) {
+                if field.shown != Some(value) {
+                    field.shown = Some(value);
+                    ui.send_message(NumericUpDownMessage::value(
+                        field.editor,
+                        MessageDirection::ToWidget,
+                        value,
+                    ));
+                }
+            }
+        }
+        ui.send_message(WidgetMessage::visibility(
+            self.ai_state_button,
+            MessageDirection::ToWidget,
+            components.get::<EnemyAi>(self.selected).is_some(),
+        ));
+    }
+}
+
+// The mesh under the cursor, nearest to the camera. `cursor` and `screen_size` are
+// in window pixels.
+pub fn pick(
+    scene: &Scene,
+    camera: Handle<Node>,
+    cursor: Vector2<f32>,
+    screen_size: Vector2<f32>,
+) -> Option<Handle<Node>> {
+    let camera = scene
+        .graph
+        .try_get(camera)?
+        .query_component_ref::<Camera>()?;
+    let ray = camera.make_ray(cursor, screen_size);
+
+    scene
+        .graph
+        .pair_iter()
+        .filter(|(_, node)| node.query_component_ref::<Mesh>().is_some())
+        .filter_map(|(handle, node)| {
+            let bounds = node.world_bounding_box();
+            let [hit, _] = ray.box_intersection(&bounds.min, &bounds.max)?;
+            Some((handle, (hit - ray.origin).norm()))
+        })
+        .min_by(|(_, a), (_, b)| a.total_cmp(b))
+        .map(|(handle, _)| handle)
+}
+
+fn entry_text(handle: Handle<Node>, node: &Node, components: &ComponentStore) -> String {
+    let mut text = format!("{} #{}", display_name(node), handle.index());
+    let tags = [
+        (components.get::<SimTransform>(handle).is_some(), "sim"),
+        (components.get::<Health>(handle).is_some(), "health"),
+        (components.get::<Stamina>(handle).is_some(), "stamina"),
+        (components.get::<EnemyAi>(handle).is_some(), "ai"),
+        (components.get::<Pickup>(handle).is_some(), "pickup"),
+        (components.get::<Trigger>(handle).is_some(), "trigger"),
+        (components.get::<ContactDamage>(handle).is_some(), "contact"),
+        (components.get::<Interactable>(handle).is_some(), "interact"),
+    ];
+    for (_, tag) in tags.into_iter().filter(|(present, _)| *present) {
+        text.push(' ');
+        text.push_str(tag);
+    }
+    text
+}
+
+fn details_text(handle: Handle<Node>, node: &Node, components: &ComponentStore) -> String {
+    let position = entity_position(handle, node, components);
+    let mut lines = vec![
+        format!("{} #{}", display_name(node), handle.index()),
+        format!(
+            "position {:.2} {:.2} {:.2}",
+            position.x, position.y, position.z
+        ),
+    ];
+    if let Some(health) = components.get::<Health>(handle) {
+        lines.push(format!("health {:.0}/{:.0}", health.current, health.max));
+    }
+    if let Some(stamina) = components.get::<Stamina>(handle) {
+        lines.push(format!("stamina {:.0}/{:.0}", stamina.current, stamina.max));
+    }
+    if let Some(ai) = components.get::<EnemyAi>(handle) {
+        lines.push(format!(
+            "ai {:?}, home {:.1} {:.1} {:.1}",
+            ai.state, ai.home.x, ai.home.y, ai.home.z
+        ));
+    }
+    if let Some(pickup) = components.get::<Pickup>(handle) {
+        lines.push(format!(
+            "pickup {:?} worth {} within {:.1}",
+            pickup.kind, pickup.value, pickup.radius
+        ));
+    }
+    if let Some(trigger) = components.get::<Trigger>(handle) {
+        lines.push(format!(
+            "trigger {:?} within {:.1}{}",
+            trigger.kind,
+            trigger.radius,
+            if trigger.fired { ", fired" } else { "" }
+        ));
+    }
+    if let Some(contact) = components.get::<ContactDamage>(handle) {
+        lines.push(format!(
+            "contact {:.0} damage within {:.1}, every {:.1}s",
+            contact.amount, contact.radius, contact.interval
+        ));
+    }
+    if let Some(interactable) = components.get::<Interactable>(handle) {
+        lines.push(format!(
+            "interactable {:?} within {:.1}",
+            interactable.prompt, interactable.radius
+        ));
+    }
+    lines.join("\n")
+}
+
+fn field_value(
+    field: InspectorField,
+    handle: Handle<Node>,
+    node: &Node,
+    components: &ComponentStore,
+) -> Option<f32> {
+    let position = entity_position(handle, node, components);
+    match field {
+        InspectorField::PositionX => Some(position.x),
+        InspectorField::PositionY => Some(position.y),
+        InspectorField::PositionZ => Some(position.z),
+        InspectorField::Health => components
+            .get::<Health>(handle)
+            .map(|health| health.current),
+        InspectorField::AiSpeed => components.get::<EnemyAi>(handle).map(|ai| ai.speed),
+        InspectorField::AiAggroRadius => {
+            components.get::<EnemyAi>(handle).map(|ai| ai.aggro_radius)
+        }
+    }
+}
+
+// Simulated position where there is one, the node's own otherwise
+fn entity_position(handle: Handle<Node>, node: &Node, components: &ComponentStore) -> Vector3<f32> {
+    components
+        .get::<SimTransform>(handle)
+        .map_or(**node.local_transform().position(), |sim| sim.position)
+}
+
+fn display_name(node: &Node) -> &str {
+    match node.name() {
+        "" => "(unnamed)",
+        name => name,
+    }
+}
+
+// The state an enemy is put in by the inspector's state button
+pub fn next_ai_state(state: EnemyState) -> EnemyState {
+    match state {
+        EnemyState::Idle => EnemyState::Chasing,
+        EnemyState::Chasing => EnemyState::Returning,
+        EnemyState::Returning => EnemyState::Idle,
+    }
+}
+
+fn make_button(ctx: &mut BuildContext, text: &str, width: f32) -> Handle<UiNode> {
+    ButtonBuilder::new(
+        WidgetBuilder::new()
+            .with_width(width)
+            .with_height(24.0)
+            .with_margin(Thickness::uniform(2.0)),
+    )
+    .with_text(text)
+    .build(ctx)
+}
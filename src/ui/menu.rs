@@ -0,0 +1,1171 @@
+use crate::{
+    assets::{MissingAsset, PreloadProgress},
+    comfort,
+    leaderboard::{format_time, LeaderboardEntry, LeaderboardEvent, RankingScope},
+    level::find_level,
+    localization::{tr, tr_or, tr_with, LANGUAGE_NAME_KEY},
+    mods::ModInfo,
+    net::{
+        browser::SessionEntry,
+        protocol::{LobbyState, PlayerId},
+    },
+    palette, rumble, telemetry,
+    ui::scale::{MAX_UI_SCALE, MIN_UI_SCALE, UI_SCALE_STEP},
+};
+use fyrox::{
+    core::pool::Handle,
+    gui::{
+        button::{ButtonBuilder, ButtonMessage},
+        message::{MessageDirection, UiMessage},
+        scroll_bar::{ScrollBarBuilder, ScrollBarMessage},
+        stack_panel::StackPanelBuilder,
+        text::{TextBuilder, TextMessage},
+        text_box::TextBoxBuilder,
+        widget::{WidgetBuilder, WidgetMessage},
+        BuildContext, HorizontalAlignment, Orientation, Thickness, UiNode, UserInterface,
+        VerticalAlignment,
+    },
+};
+use std::net::SocketAddr;
+
+// Number of rows the session browser can show at once
+const SESSION_SLOTS: usize = 8;
+
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum MenuScreen {
+    Loading,
+    #[default]
+    Main,
+    Browser,
+    Lobby,
+    Results,
+    // The last run crashed, shown instead of the main screen
+    Crashed,
+    // Only a banner over the attract mode's demo
+    Demo,
+    Hidden,
+}
+
+#[derive(Clone, Debug)]
+pub enum MenuAction {
+    PlaySolo,
+    HostSession,
+    OpenBrowser,
+    Quit,
+    AddServer(String),
+    RefreshSessions,
+    ToggleSpectate,
+    CyclePalette,
+    CycleComfort,
+    ToggleRumble,
+    ToggleTelemetry,
+    CycleMonitor,
+    SetUiScale(f32),
+    CycleLanguage,
+    JoinSession { addr: SocketAddr, spectator: bool },
+    BackToMain,
+    ToggleReady,
+    CycleLevel(isize),
+    StartGame,
+    LeaveLobby,
+    CloseResults,
+    RestoreCrash,
+    DismissCrash,
+}
+
+// What happens to the time of a finished run
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum ResultsSubmit {
+    // No leaderboard service is set up
+    NotConfigured,
+    Submitting,
+    // Cheats were used, the rankings are still shown
+    Withheld,
+}
+
+// Text straight from the string table, sent again when the language changes
+struct Label {
+    text: Handle<UiNode>,
+    key: &'static str,
+}
+
+struct SessionSlot {
+    button: Handle<UiNode>,
+    text: Handle<UiNode>,
+    addr: Option<SocketAddr>,
+}
+
+#[derive(Default)]
+pub struct MainMenu {
+    screen: MenuScreen,
+    loading_panel: Handle<UiNode>,
+    main_panel: Handle<UiNode>,
+    browser_panel: Handle<UiNode>,
+    lobby_panel: Handle<UiNode>,
+    results_panel: Handle<UiNode>,
+    crash_panel: Handle<UiNode>,
+    demo_panel: Handle<UiNode>,
+
+    play_button: Handle<UiNode>,
+    host_button: Handle<UiNode>,
+    join_button: Handle<UiNode>,
+    quit_button: Handle<UiNode>,
+    palette_button: Handle<UiNode>,
+    palette_text: Handle<UiNode>,
+    comfort_button: Handle<UiNode>,
+    comfort_text: Handle<UiNode>,
+    rumble_button: Handle<UiNode>,
+    rumble_text: Handle<UiNode>,
+    telemetry_button: Handle<UiNode>,
+    telemetry_text: Handle<UiNode>,
+    monitor_button: Handle<UiNode>,
+    monitor_text: Handle<UiNode>,
+    // Shown on the monitor button, there is no window to ask before the menu is built
+    monitor_name: Option<String>,
+    ui_scale_text: Handle<UiNode>,
+    ui_scale_slider: Handle<UiNode>,
+    ui_scale: f32,
+    language_button: Handle<UiNode>,
+    language_text: Handle<UiNode>,
+    // Problems worth telling the player about, e.g. missing assets or a level that failed to load
+    notice: Handle<UiNode>,
+    mods_text: Handle<UiNode>,
+    // Kept to show them again in another language
+    missing_assets: Vec<String>,
+    mod_names: Vec<String>,
+    error: Option<String>,
+    labels: Vec<Label>,
+
+    loading_text: Handle<UiNode>,
+
+    address_box: Handle<UiNode>,
+    address: String,
+    add_button: Handle<UiNode>,
+    refresh_button: Handle<UiNode>,
+    browser_back_button: Handle<UiNode>,
+    spectate_button: Handle<UiNode>,
+    spectate_text: Handle<UiNode>,
+    join_as_spectator: bool,
+    no_sessions_text: Handle<UiNode>,
+    session_slots: Vec<SessionSlot>,
+
+    lobby_title: Handle<UiNode>,
+    lobby_members: Handle<UiNode>,
+    level_text: Handle<UiNode>,
+    previous_level_button: Handle<UiNode>,
+    next_level_button: Handle<UiNode>,
+    ready_button: Handle<UiNode>,
+    ready_text: Handle<UiNode>,
+    start_button: Handle<UiNode>,
+    leave_button: Handle<UiNode>,
+
+    results_title: Handle<UiNode>,
+    results_time: Handle<UiNode>,
+    leaderboard_status: Handle<UiNode>,
+    global_ranking: Handle<UiNode>,
+    friends_ranking: Handle<UiNode>,
+    continue_button: Handle<UiNode>,
+    // Level and player the results screen is showing, late replies for other runs are ignored
+    results_run: Option<(String, String)>,
+
+    crash_text: Handle<UiNode>,
+    restore_button: Handle<UiNode>,
+    discard_button: Handle<UiNode>,
+    // Panic message, report path, the level that can be restored (if any) and whether
+    // the crash's save failed its checksum
+    crash: Option<(String, String, Option<String>, bool)>,
+
+    // What is currently displayed, so unchanged state doesn't spam the UI with messages
+    shown_sessions: Option<Vec<(SocketAddr, String)>>,
+    shown_lobby: Option<(LobbyState, bool)>,
+}
+
+impl MainMenu {
+    pub fn new(ctx: &mut BuildContext) -> Self {
+        let mut labels = Vec::new();
+
+        // Loading screen, shown until the asset preload is done
+        let loading_text = make_label(ctx, &mut labels, "menu.loading");
+        let loading_panel = make_panel(
+            ctx,
+            vec![make_label(ctx, &mut labels, "menu.title"), loading_text],
+            true,
+        );
+
+        // Main screen
+        let play_button = make_button(ctx, &mut labels, "menu.play");
+        let host_button = make_button(ctx, &mut labels, "menu.host");
+        let join_button = make_button(ctx, &mut labels, "menu.join");
+        let palette_text = make_text(ctx, &palette_label());
+        let palette_button = make_content_button(ctx, palette_text);
+        let comfort_text = make_text(ctx, &comfort_label());
+        let comfort_button = make_content_button(ctx, comfort_text);
+        let rumble_text = make_text(ctx, &rumble_label());
+        let rumble_button = make_content_button(ctx, rumble_text);
+        let telemetry_text = make_text(ctx, &telemetry_label());
+        let telemetry_button = make_content_button(ctx, telemetry_text);
+        let monitor_text = make_text(ctx, &monitor_label(None));
+        let monitor_button = make_content_button(ctx, monitor_text);
+        let ui_scale_text = make_text(ctx, &ui_scale_label(1.0));
+        let ui_scale_slider = ScrollBarBuilder::new(
+            WidgetBuilder::new()
+                .with_width(240.0)
+                .with_height(20.0)
+                .with_margin(Thickness::uniform(4.0)),
+        )
+        .with_orientation(Orientation::Horizontal)
+        .with_min(MIN_UI_SCALE)
+        .with_max(MAX_UI_SCALE)
+        .with_step(UI_SCALE_STEP)
+        .with_value(1.0)
+        .build(ctx);
+        let language_text = make_text(ctx, &language_label());
+        let language_button = make_content_button(ctx, language_text);
+        let quit_button = make_button(ctx, &mut labels, "menu.quit");
+        let notice = make_text(ctx, "");
+        let mods_text = make_text(ctx, "");
+        let main_panel = make_panel(
+            ctx,
+            vec![
+                make_label(ctx, &mut labels, "menu.title"),
+                play_button,
+                host_button,
+                join_button,
+                palette_button,
+                comfort_button,
+                rumble_button,
+                telemetry_button,
+                monitor_button,
+                ui_scale_text,
+                ui_scale_slider,
+                language_button,
+                quit_button,
+                notice,
+                mods_text,
+            ],
+            false,
+        );
+
+        // Session browser
+        let address_box = TextBoxBuilder::new(
+            WidgetBuilder::new()
+                .with_width(240.0)
+                .with_height(28.0)
+                .with_margin(Thickness::uniform(4.0)),
+        )
+        .with_text("127.0.0.1:7777")
+        .build(ctx);
+        let add_button = make_button(ctx, &mut labels, "browser.add_server");
+        let refresh_button = make_button(ctx, &mut labels, "browser.refresh");
+        let browser_back_button = make_button(ctx, &mut labels, "browser.back");
+        let spectate_text = make_text(ctx, &tr("browser.join_as_player"));
+        let spectate_button = ButtonBuilder::new(
+            WidgetBuilder::new()
+                .with_width(240.0)
+                .with_height(36.0)
+                .with_margin(Thickness::uniform(4.0)),
+        )
+        .with_content(spectate_text)
+        .build(ctx);
+        let no_sessions_text = make_label(ctx, &mut labels, "browser.no_sessions");
+
+        let session_slots: Vec<_> = (0..SESSION_SLOTS)
+            .map(|_| {
+                let text = make_text(ctx, "");
+                let button = ButtonBuilder::new(
+                    WidgetBuilder::new()
+                        .with_width(360.0)
+                        .with_height(32.0)
+                        .with_margin(Thickness::uniform(2.0))
+                        .with_visibility(false),
+                )
+                .with_content(text)
+                .build(ctx);
+                SessionSlot {
+                    button,
+                    text,
+                    addr: None,
+                }
+            })
+            .collect();
+
+        let mut browser_children = vec![
+            make_label(ctx, &mut labels, "browser.sessions"),
+            no_sessions_text,
+        ];
+        browser_children.extend(session_slots.iter().map(|slot| slot.button));
+        browser_children.extend([
+            spectate_button,
+            address_box,
+            add_button,
+            refresh_button,
+            browser_back_button,
+        ]);
+        let browser_panel = make_panel(ctx, browser_children, false);
+
+        // Lobby
+        let lobby_title = make_text(ctx, &tr("lobby.connecting"));
+        let lobby_members = make_text(ctx, "");
+        let previous_level_button = make_small_button(ctx, "<");
+        let level_text = make_text(ctx, "");
+        let next_level_button = make_small_button(ctx, ">");
+        let level_row = StackPanelBuilder::new(
+            WidgetBuilder::new()
+                .with_horizontal_alignment(HorizontalAlignment::Center)
+                .with_children([previous_level_button, level_text, next_level_button]),
+        )
+        .with_orientation(Orientation::Horizontal)
+        .build(ctx);
+        let ready_text = make_text(ctx, &tr("lobby.ready"));
+        let ready_button = ButtonBuilder::new(
+            WidgetBuilder::new()
+                .with_width(240.0)
+                .with_height(36.0)
+                .with_margin(Thickness::uniform(4.0)),
+        )
+        .with_content(ready_text)
+        .build(ctx);
+        let start_button = make_button(ctx, &mut labels, "lobby.start");
+        let leave_button = make_button(ctx, &mut labels, "lobby.leave");
+        let lobby_panel = make_panel(
+            ctx,
+            vec![
+                lobby_title,
+                lobby_members,
+                level_row,
+                ready_button,
+                start_button,
+                leave_button,
+            ],
+            false,
+        );
+
+        // Results
+        let results_title = make_text(ctx, "");
+        let results_time = make_text(ctx, "");
+        let leaderboard_status = make_text(ctx, "");
+        let global_ranking = make_text(ctx, "");
+        let friends_ranking = make_text(ctx, "");
+        let continue_button = make_button(ctx, &mut labels, "results.continue");
+        let results_panel = make_panel(
+            ctx,
+            vec![
+                results_title,
+                results_time,
+                leaderboard_status,
+                make_label(ctx, &mut labels, "results.global"),
+                global_ranking,
+                make_label(ctx, &mut labels, "results.friends"),
+                friends_ranking,
+                continue_button,
+            ],
+            false,
+        );
+
+        // Crash notice
+        let crash_text = make_text(ctx, "");
+        let restore_button = make_button(ctx, &mut labels, "crash.restore");
+        let discard_button = make_button(ctx, &mut labels, "crash.discard");
+        let crash_panel = make_panel(
+            ctx,
+            vec![
+                make_label(ctx, &mut labels, "crash.title"),
+                crash_text,
+                restore_button,
+                discard_button,
+            ],
+            false,
+        );
+
+        // Demo banner, kept to the bottom so the level can be seen
+        let demo_panel = StackPanelBuilder::new(
+            WidgetBuilder::new()
+                .with_visibility(false)
+                .with_horizontal_alignment(HorizontalAlignment::Center)
+                .with_vertical_alignment(VerticalAlignment::Bottom)
+                .with_margin(Thickness::uniform(24.0))
+                .with_children([
+                    make_label(ctx, &mut labels, "demo.title"),
+                    make_label(ctx, &mut labels, "demo.hint"),
+                ]),
+        )
+        .build(ctx);
+
+        Self {
+            screen: MenuScreen::Loading,
+            loading_panel,
+            main_panel,
+            browser_panel,
+            lobby_panel,
+            results_panel,
+            crash_panel,
+            demo_panel,
+            play_button,
+            host_button,
+            join_button,
+            quit_button,
+            palette_button,
+            palette_text,
+            comfort_button,
+            comfort_text,
+            rumble_button,
+            rumble_text,
+            telemetry_button,
+            telemetry_text,
+            monitor_button,
+            monitor_text,
+            monitor_name: None,
+            ui_scale_text,
+            ui_scale_slider,
+            ui_scale: 1.0,
+            language_button,
+            language_text,
+            notice,
+            mods_text,
+            missing_assets: Vec::new(),
+            mod_names: Vec::new(),
+            error: None,
+            labels,
+            loading_text,
+            address_box,
+            address: "127.0.0.1:7777".to_string(),
+            add_button,
+            refresh_button,
+            browser_back_button,
+            spectate_button,
+            spectate_text,
+            join_as_spectator: false,
+            no_sessions_text,
+            session_slots,
+            lobby_title,
+            lobby_members,
+            level_text,
+            previous_level_button,
+            next_level_button,
+            ready_button,
+            ready_text,
+            start_button,
+            leave_button,
+            results_title,
+            results_time,
+            leaderboard_status,
+            global_ranking,
+            friends_ranking,
+            continue_button,
+            results_run: None,
+            crash_text,
+            restore_button,
+            discard_button,
+            crash: None,
+            shown_sessions: None,
+            shown_lobby: None,
+        }
+    }
+
+    pub fn screen(&self) -> MenuScreen {
+        self.screen
+    }
+
+    pub fn set_screen(&mut self, ui: &UserInterface, screen: MenuScreen) {
+        self.screen = screen;
+
+        for (panel, visible) in [
+            (self.loading_panel, screen == MenuScreen::Loading),
+            (self.main_panel, screen == MenuScreen::Main),
+            (self.browser_panel, screen == MenuScreen::Browser),
+            (self.lobby_panel, screen == MenuScreen::Lobby),
+            (self.results_panel, screen == MenuScreen::Results),
+            (self.crash_panel, screen == MenuScreen::Crashed),
+            (self.demo_panel, screen == MenuScreen::Demo),
+        ] {
+            ui.send_message(WidgetMessage::visibility(
+                panel,
+                MessageDirection::ToWidget,
+                visible,
+            ));
+        }
+
+        // Force a refresh next time the screen is shown
+        self.shown_sessions = None;
+        self.shown_lobby = None;
+    }
+
+    pub fn handle_ui_message(&mut self, message: &UiMessage) -> Option<MenuAction> {
+        if let Some(TextMessage::Text(text)) = message.data() {
+            if message.destination() == self.address_box
+                && message.direction() == MessageDirection::FromWidget
+            {
+                self.address = text.clone();
+            }
+            return None;
+        }
+
+        if let Some(ScrollBarMessage::Value(value)) = message.data() {
+            if message.destination() == self.ui_scale_slider
+                && message.direction() == MessageDirection::FromWidget
+            {
+                return Some(MenuAction::SetUiScale(*value));
+            }
+            return None;
+        }
+
+        if let Some(ButtonMessage::Click) = message.data() {
+            let button = message.destination();
+
+            let action = if button == self.play_button {
+                MenuAction::PlaySolo
+            } else if button == self.host_button {
+                MenuAction::HostSession
+            } else if button == self.join_button {
+                MenuAction::OpenBrowser
+            } else if button == self.quit_button {
+                MenuAction::Quit
+            } else if button == self.palette_button {
+                MenuAction::CyclePalette
+            } else if button == self.comfort_button {
+                MenuAction::CycleComfort
+            } else if button == self.rumble_button {
+                MenuAction::ToggleRumble
+            } else if button == self.telemetry_button {
+                MenuAction::ToggleTelemetry
+            } else if button == self.monitor_button {
+                MenuAction::CycleMonitor
+            } else if button == self.language_button {
+                MenuAction::CycleLanguage
+            } else if button == self.add_button {
+                MenuAction::AddServer(self.address.clone())
+            } else if button == self.refresh_button {
+                MenuAction::RefreshSessions
+            } else if button == self.browser_back_button {
+                MenuAction::BackToMain
+            } else if button == self.spectate_button {
+                MenuAction::ToggleSpectate
+            } else if button == self.previous_level_button {
+                MenuAction::CycleLevel(-1)
+            } else if button == self.next_level_button {
+                MenuAction::CycleLevel(1)
+            } else if button == self.ready_button {
+                MenuAction::ToggleReady
+            } else if button == self.start_button {
+                MenuAction::StartGame
+            } else if button == self.leave_button {
+                MenuAction::LeaveLobby
+            } else if button == self.continue_button {
+                MenuAction::CloseResults
+            } else if button == self.restore_button {
+                MenuAction::RestoreCrash
+            } else if button == self.discard_button {
+                MenuAction::DismissCrash
+            } else {
+                let slot = self
+                    .session_slots
+                    .iter()
+                    .find(|slot| slot.button == button)?;
+                MenuAction::JoinSession {
+                    addr: slot.addr?,
+                    spectator: self.join_as_spectator,
+                }
+            };
+
+            return Some(action);
+        }
+
+        None
+    }
+
+    pub fn set_join_as_spectator(&mut self, ui: &UserInterface, spectator: bool) {
+        self.join_as_spectator = spectator;
+        let key = if spectator {
+            "browser.join_as_spectator"
+        } else {
+            "browser.join_as_player"
+        };
+        ui.send_message(TextMessage::text(
+            self.spectate_text,
+            MessageDirection::ToWidget,
+            tr(key),
+        ));
+    }
+
+    pub fn refresh_palette(&self, ui: &UserInterface) {
+        ui.send_message(TextMessage::text(
+            self.palette_text,
+            MessageDirection::ToWidget,
+            palette_label(),
+        ));
+    }
+
+    pub fn refresh_comfort(&self, ui: &UserInterface) {
+        ui.send_message(TextMessage::text(
+            self.comfort_text,
+            MessageDirection::ToWidget,
+            comfort_label(),
+        ));
+    }
+
+    pub fn refresh_rumble(&self, ui: &UserInterface) {
+        ui.send_message(TextMessage::text(
+            self.rumble_text,
+            MessageDirection::ToWidget,
+            rumble_label(),
+        ));
+    }
+
+    pub fn refresh_telemetry(&self, ui: &UserInterface) {
+        ui.send_message(TextMessage::text(
+            self.telemetry_text,
+            MessageDirection::ToWidget,
+            telemetry_label(),
+        ));
+    }
+
+    pub fn set_monitor(&mut self, ui: &UserInterface, name: Option<String>) {
+        ui.send_message(TextMessage::text(
+            self.monitor_text,
+            MessageDirection::ToWidget,
+            monitor_label(name.as_deref()),
+        ));
+        self.monitor_name = name;
+    }
+
+    // Snaps the slider to the scale in use, dragging it ends up between the steps
+    pub fn set_ui_scale(&mut self, ui: &UserInterface, scale: f32) {
+        self.ui_scale = scale;
+        ui.send_message(TextMessage::text(
+            self.ui_scale_text,
+            MessageDirection::ToWidget,
+            ui_scale_label(scale),
+        ));
+        ui.send_message(ScrollBarMessage::value(
+            self.ui_scale_slider,
+            MessageDirection::ToWidget,
+            scale,
+        ));
+    }
+
+    // Shows everything in the language that was just switched to. Lists that are
+    // refreshed anyway are only marked as stale.
+    pub fn refresh_language(&mut self, ui: &UserInterface) {
+        for label in &self.labels {
+            ui.send_message(TextMessage::text(
+                label.text,
+                MessageDirection::ToWidget,
+                tr(label.key),
+            ));
+        }
+        ui.send_message(TextMessage::text(
+            self.language_text,
+            MessageDirection::ToWidget,
+            language_label(),
+        ));
+        self.refresh_palette(ui);
+        self.refresh_comfort(ui);
+        self.refresh_rumble(ui);
+        self.refresh_telemetry(ui);
+        self.set_monitor(ui, self.monitor_name.clone());
+        self.set_ui_scale(ui, self.ui_scale);
+        self.set_join_as_spectator(ui, self.join_as_spectator);
+        self.show_notice(ui);
+        self.show_mod_names(ui);
+        self.show_crash_text(ui);
+        self.shown_sessions = None;
+        self.shown_lobby = None;
+    }
+
+    pub fn join_as_spectator(&self) -> bool {
+        self.join_as_spectator
+    }
+
+    pub fn update_sessions(&mut self, ui: &UserInterface, sessions: &[SessionEntry]) {
+        let rows: Vec<_> = sessions
+            .iter()
+            .take(SESSION_SLOTS)
+            .map(|session| {
+                let info = &session.info;
+                let mut label = tr_with(
+                    "browser.session",
+                    &[
+                        ("name", &info.session_name),
+                        ("level", &level_name(&info.level)),
+                        ("players", &info.players),
+                        ("max_players", &info.max_players),
+                    ],
+                );
+                if session.lan {
+                    label = format!("{} {label}", tr("browser.lan"));
+                }
+                if info.in_game {
+                    label = format!("{label} {}", tr("browser.in_game"));
+                }
+                (session.addr, label)
+            })
+            .collect();
+
+        if self.shown_sessions.as_ref() == Some(&rows) {
+            return;
+        }
+
+        for (index, slot) in self.session_slots.iter_mut().enumerate() {
+            let row = rows.get(index);
+            slot.addr = row.map(|(addr, _)| *addr);
+            if let Some((_, label)) = row {
+                ui.send_message(TextMessage::text(
+                    slot.text,
+                    MessageDirection::ToWidget,
+                    label.clone(),
+                ));
+            }
+            ui.send_message(WidgetMessage::visibility(
+                slot.button,
+                MessageDirection::ToWidget,
+                row.is_some(),
+            ));
+        }
+
+        ui.send_message(WidgetMessage::visibility(
+            self.no_sessions_text,
+            MessageDirection::ToWidget,
+            rows.is_empty(),
+        ));
+
+        self.shown_sessions = Some(rows);
+    }
+
+    pub fn update_lobby(
+        &mut self,
+        ui: &UserInterface,
+        lobby: &LobbyState,
+        is_host: bool,
+        local_id: Option<PlayerId>,
+    ) {
+        if self
+            .shown_lobby
+            .as_ref()
+            .is_some_and(|(shown, host)| shown == lobby && *host == is_host)
+        {
+            return;
+        }
+
+        let title = if lobby.session_name.is_empty() {
+            tr("lobby.connecting")
+        } else {
+            lobby.session_name.clone()
+        };
+
+        let members = lobby
+            .members
+            .iter()
+            .map(|member| {
+                let name = if Some(member.id) == local_id {
+                    format!("{} {}", member.name, tr("lobby.you"))
+                } else {
+                    member.name.clone()
+                };
+                let status = match (member.spectator, member.ready) {
+                    (true, _) => "lobby.spectating",
+                    (false, true) => "lobby.member_ready",
+                    (false, false) => "lobby.member_not_ready",
+                };
+                format!("{name} - {}", tr(status))
+            })
+            .collect::<Vec<_>>()
+            .join("\n");
+
+        let local_ready = lobby
+            .members
+            .iter()
+            .any(|member| Some(member.id) == local_id && member.ready);
+
+        let local_spectator = lobby
+            .members
+            .iter()
+            .any(|member| Some(member.id) == local_id && member.spectator);
+
+        for (text, value) in [
+            (self.lobby_title, title),
+            (self.lobby_members, members),
+            (
+                self.level_text,
+                tr_with("lobby.level", &[("level", &level_name(&lobby.level))]),
+            ),
+            (
+                self.ready_text,
+                tr(if local_ready {
+                    "lobby.not_ready"
+                } else {
+                    "lobby.ready"
+                }),
+            ),
+        ] {
+            ui.send_message(TextMessage::text(text, MessageDirection::ToWidget, value));
+        }
+
+        // Spectators have nothing to get ready
+        ui.send_message(WidgetMessage::visibility(
+            self.ready_button,
+            MessageDirection::ToWidget,
+            !local_spectator,
+        ));
+
+        // Only the host picks the level and starts the round
+        for button in [self.previous_level_button, self.next_level_button] {
+            ui.send_message(WidgetMessage::enabled(
+                button,
+                MessageDirection::ToWidget,
+                is_host,
+            ));
+        }
+        ui.send_message(WidgetMessage::visibility(
+            self.start_button,
+            MessageDirection::ToWidget,
+            is_host,
+        ));
+        ui.send_message(WidgetMessage::enabled(
+            self.start_button,
+            MessageDirection::ToWidget,
+            lobby.everyone_ready(),
+        ));
+
+        self.shown_lobby = Some((lobby.clone(), is_host));
+    }
+
+    pub fn set_loading_progress(&self, ui: &UserInterface, progress: PreloadProgress) {
+        ui.send_message(TextMessage::text(
+            self.loading_text,
+            MessageDirection::ToWidget,
+            tr_with(
+                "menu.loading_assets",
+                &[("done", &progress.done), ("total", &progress.total)],
+            ),
+        ));
+    }
+
+    // Lists assets that failed to preload under the main menu buttons
+    pub fn show_missing_assets(&mut self, ui: &UserInterface, missing: &[MissingAsset]) {
+        self.missing_assets = missing.iter().map(|asset| asset.to_string()).collect();
+        self.show_notice(ui);
+    }
+
+    pub fn show_mods(&mut self, ui: &UserInterface, mods: &[ModInfo]) {
+        self.mod_names = mods.iter().map(|info| info.to_string()).collect();
+        self.show_mod_names(ui);
+    }
+
+    // Shown in place of the missing assets list
+    pub fn show_error(&mut self, ui: &UserInterface, text: &str) {
+        self.error = Some(text.to_string());
+        self.show_notice(ui);
+    }
+
+    fn show_notice(&self, ui: &UserInterface) {
+        let text = match &self.error {
+            Some(error) => error.clone(),
+            None if self.missing_assets.is_empty() => String::new(),
+            None => {
+                let count = self.missing_assets.len();
+                let mut lines = vec![tr_with("menu.missing_assets", &[("count", &count)])];
+                lines.extend(self.missing_assets.iter().cloned());
+                lines.join("\n")
+            }
+        };
+        ui.send_message(TextMessage::text(
+            self.notice,
+            MessageDirection::ToWidget,
+            text,
+        ));
+    }
+
+    fn show_mod_names(&self, ui: &UserInterface) {
+        let text = if self.mod_names.is_empty() {
+            String::new()
+        } else {
+            tr_with("menu.mods", &[("mods", &self.mod_names.join(", "))])
+        };
+        ui.send_message(TextMessage::text(
+            self.mods_text,
+            MessageDirection::ToWidget,
+            text,
+        ));
+    }
+
+    // Switches to the crash notice. Restoring is only offered with a level to go back to
+    // and a save that passed its checksum.
+    pub fn show_crash(
+        &mut self,
+        ui: &UserInterface,
+        message: &str,
+        report: &str,
+        level: Option<&str>,
+        modified: bool,
+    ) {
+        self.crash = Some((
+            message.to_string(),
+            report.to_string(),
+            level.map(str::to_string),
+            modified,
+        ));
+        self.show_crash_text(ui);
+        ui.send_message(WidgetMessage::visibility(
+            self.restore_button,
+            MessageDirection::ToWidget,
+            level.is_some(),
+        ));
+        self.set_screen(ui, MenuScreen::Crashed);
+    }
+
+    fn show_crash_text(&self, ui: &UserInterface) {
+        let Some((message, report, level, modified)) = self.crash.as_ref() else {
+            return;
+        };
+        let mut lines = vec![
+            tr_with("crash.message", &[("message", message)]),
+            tr_with("crash.report", &[("path", report)]),
+        ];
+        if *modified {
+            lines.push(tr("crash.modified"));
+        } else if let Some(level) = level {
+            lines.push(tr_with("crash.saved", &[("level", &level_name(level))]));
+        }
+        ui.send_message(TextMessage::text(
+            self.crash_text,
+            MessageDirection::ToWidget,
+            lines.join("\n"),
+        ));
+    }
+
+    // Switches to the results screen for a finished run, rankings arrive later
+    pub fn show_results(
+        &mut self,
+        ui: &UserInterface,
+        level: &str,
+        player: &str,
+        time_ms: u64,
+        score: u32,
+        submit: ResultsSubmit,
+    ) {
+        self.results_run = Some((level.to_string(), player.to_string()));
+
+        let status = tr(match submit {
+            ResultsSubmit::NotConfigured => "results.not_configured",
+            ResultsSubmit::Submitting => "results.submitting",
+            ResultsSubmit::Withheld => "results.tainted",
+        });
+        let ranking = if submit != ResultsSubmit::NotConfigured {
+            tr("results.loading")
+        } else {
+            String::new()
+        };
+
+        for (text, value) in [
+            (
+                self.results_title,
+                tr_with("results.complete", &[("level", &level_name(level))]),
+            ),
+            (
+                self.results_time,
+                tr_with(
+                    "results.time",
+                    &[("time", &format_time(time_ms)), ("score", &score)],
+                ),
+            ),
+            (self.leaderboard_status, status),
+            (self.global_ranking, ranking.clone()),
+            (self.friends_ranking, ranking),
+        ] {
+            ui.send_message(TextMessage::text(text, MessageDirection::ToWidget, value));
+        }
+
+        self.set_screen(ui, MenuScreen::Results);
+    }
+
+    pub fn handle_leaderboard_event(&mut self, ui: &UserInterface, event: LeaderboardEvent) {
+        // Not about the run on screen, it goes with the other problems on the main screen
+        if let LeaderboardEvent::QueueRejected = event {
+            self.show_error(ui, &tr("menu.scores_rejected"));
+            return;
+        }
+
+        let Some((shown_level, player)) = self.results_run.as_ref() else {
+            return;
+        };
+
+        let (target, text) = match event {
+            LeaderboardEvent::Submitted => (self.leaderboard_status, tr("results.submitted")),
+            LeaderboardEvent::Queued => (self.leaderboard_status, tr("results.queued")),
+            LeaderboardEvent::Rejected => (self.leaderboard_status, tr("results.rejected")),
+            LeaderboardEvent::Rankings {
+                level,
+                scope,
+                entries,
+            } if &level == shown_level => {
+                (self.ranking_text(scope), format_rankings(&entries, player))
+            }
+            LeaderboardEvent::RankingsUnavailable { level, scope } if &level == shown_level => {
+                (self.ranking_text(scope), tr("results.unavailable"))
+            }
+            _ => return,
+        };
+
+        ui.send_message(TextMessage::text(target, MessageDirection::ToWidget, text));
+    }
+
+    fn ranking_text(&self, scope: RankingScope) -> Handle<UiNode> {
+        match scope {
+            RankingScope::Global => self.global_ranking,
+            RankingScope::Friends => self.friends_ranking,
+        }
+    }
+}
+
+fn format_rankings(entries: &[LeaderboardEntry], player: &str) -> String {
+    if entries.is_empty() {
+        return tr("results.no_times");
+    }
+
+    entries
+        .iter()
+        .map(|entry| {
+            let name = if entry.player == player {
+                format!("{} {}", entry.player, tr("lobby.you"))
+            } else {
+                entry.player.clone()
+            };
+            format!(
+                "{}. {name} - {} ({})",
+                entry.rank,
+                format_time(entry.time_ms),
+                entry.score
+            )
+        })
+        .collect::<Vec<_>>()
+        .join("\n")
+}
+
+// Levels from mods keep the name they came with unless a string table has one
+fn level_name(id: &str) -> String {
+    let name = find_level(id).map_or(id, |level| level.name);
+    tr_or(&format!("level.{id}"), name)
+}
+
+fn palette_label() -> String {
+    let name = tr(&format!("palette.{}", palette::vision().name()));
+    tr_with("menu.palette", &[("palette", &name)])
+}
+
+// One level for all effects, "custom" once they were set apart from the console
+fn comfort_label() -> String {
+    let level = match comfort::settings().common_level() {
+        Some(level) => tr(&format!("comfort.{}", level.name())),
+        None => tr("comfort.custom"),
+    };
+    tr_with("menu.comfort", &[("level", &level)])
+}
+
+fn rumble_label() -> String {
+    tr(if rumble::enabled() {
+        "menu.rumble_on"
+    } else {
+        "menu.rumble_off"
+    })
+}
+
+fn telemetry_label() -> String {
+    tr(if telemetry::enabled() {
+        "menu.telemetry_on"
+    } else {
+        "menu.telemetry_off"
+    })
+}
+
+fn monitor_label(name: Option<&str>) -> String {
+    let name = name.map_or_else(|| tr("menu.monitor_unknown"), str::to_string);
+    tr_with("menu.monitor", &[("monitor", &name)])
+}
+
+fn ui_scale_label(scale: f32) -> String {
+    tr_with("menu.ui_scale", &[("percent", &(scale * 100.0).round())])
+}
+
+fn language_label() -> String {
+    tr_with("menu.language", &[("language", &tr(LANGUAGE_NAME_KEY))])
+}
+
+fn make_text(ctx: &mut BuildContext, text: &str) -> Handle<UiNode> {
+    TextBuilder::new(
+        WidgetBuilder::new()
+            .with_margin(Thickness::uniform(4.0))
+            .with_horizontal_alignment(HorizontalAlignment::Center),
+    )
+    .with_text(text)
+    .build(ctx)
+}
+
+fn make_label(
+    ctx: &mut BuildContext,
+    labels: &mut Vec<Label>,
+    key: &'static str,
+) -> Handle<UiNode> {
+    let text = make_text(ctx, &tr(key));
+    labels.push(Label { text, key });
+    text
+}
+
+fn make_button(
+    ctx: &mut BuildContext,
+    labels: &mut Vec<Label>,
+    key: &'static str,
+) -> Handle<UiNode> {
+    let text = TextBuilder::new(
+        WidgetBuilder::new()
+            .with_horizontal_alignment(HorizontalAlignment::Center)
+            .with_vertical_alignment(VerticalAlignment::Center),
+    )
+    .with_text(tr(key))
+    .build(ctx);
+    labels.push(Label { text, key });
+    ButtonBuilder::new(
+        WidgetBuilder::new()
+            .with_width(240.0)
+            .with_height(36.0)
+            .with_margin(Thickness::uniform(4.0)),
+    )
+    .with_content(text)
+    .build(ctx)
+}
+
+fn make_content_button(ctx: &mut BuildContext, content: Handle<UiNode>) -> Handle<UiNode> {
+    ButtonBuilder::new(
+        WidgetBuilder::new()
+            .with_width(240.0)
+            .with_height(36.0)
+            .with_margin(Thickness::uniform(4.0)),
+    )
+    .with_content(content)
+    .build(ctx)
+}
+
+fn make_small_button(ctx: &mut BuildContext, text: &str) -> Handle<UiNode> {
+    ButtonBuilder::new(
+        WidgetBuilder::new()
+            .with_width(36.0)
+            .with_height(36.0)
+            .with_margin(Thickness::uniform(4.0)),
+    )
+    .with_text(text)
+    .build(ctx)
+}
+
+fn make_panel(
+    ctx: &mut BuildContext,
+    children: Vec<Handle<UiNode>>,
+    visible: bool,
+) -> Handle<UiNode> {
+    StackPanelBuilder::new(
+        WidgetBuilder::new()
+            .with_visibility(visible)
+            .with_horizontal_alignment(HorizontalAlignment::Center)
+            .with_vertical_alignment(VerticalAlignment::Center)
+            .with_children(children),
+    )
+    .build(ctx)
+}
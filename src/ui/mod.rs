@@ -0,0 +1,8 @@
+pub mod chat;
+pub mod debug_labels;
+pub mod flash;
+pub mod inspector;
+pub mod menu;
+pub mod profiler;
+pub mod prompt;
+pub mod scale;
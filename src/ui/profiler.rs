@@ -0,0 +1,198 @@
+use crate::{
+    profiler::{Phase, Profiler},
+    stats::{format_bytes, StatsCollector},
+};
+use fyrox::{
+    core::{color::Color, pool::Handle},
+    gui::{
+        border::BorderBuilder,
+        brush::Brush,
+        grid::{Column, GridBuilder, Row},
+        message::MessageDirection,
+        stack_panel::StackPanelBuilder,
+        text::{TextBuilder, TextMessage},
+        widget::{WidgetBuilder, WidgetMessage},
+        BuildContext, HorizontalAlignment, Thickness, UiNode, UserInterface, VerticalAlignment,
+    },
+};
+
+// Bar length per millisecond, a 60 fps frame budget fills the whole bar
+const BAR_WIDTH_PER_MS: f32 = 12.0;
+const MAX_BAR_WIDTH: f32 = 200.0;
+
+// Numbers change every frame, redrawing them only every few keeps them readable
+const TEXT_INTERVAL: u32 = 15;
+
+struct PhaseRow {
+    phase: Phase,
+    label: Handle<UiNode>,
+    bar: Handle<UiNode>,
+}
+
+// Per-phase timings in the top right corner, toggled with F3: a bar for the last
+// frame and the rolling average next to it, then renderer and game counts
+#[derive(Default)]
+pub struct ProfilerOverlay {
+    panel: Handle<UiNode>,
+    header: Handle<UiNode>,
+    rows: Vec<PhaseRow>,
+    stats: Handle<UiNode>,
+    visible: bool,
+    frames_until_text: u32,
+}
+
+impl ProfilerOverlay {
+    pub fn new(ctx: &mut BuildContext) -> Self {
+        let header = TextBuilder::new(WidgetBuilder::new()).build(ctx);
+
+        let mut rows = Vec::new();
+        let mut cells = Vec::new();
+        for (index, phase) in Phase::ALL.into_iter().enumerate() {
+            let label =
+                TextBuilder::new(WidgetBuilder::new().on_row(index).on_column(0)).build(ctx);
+            let bar = BorderBuilder::new(
+                WidgetBuilder::new()
+                    .on_row(index)
+                    .on_column(1)
+                    .with_width(0.0)
+                    .with_height(10.0)
+                    .with_horizontal_alignment(HorizontalAlignment::Left)
+                    .with_background(Brush::Solid(phase_color(phase))),
+            )
+            .build(ctx);
+            cells.extend([label, bar]);
+            rows.push(PhaseRow { phase, label, bar });
+        }
+        let table = GridBuilder::new(WidgetBuilder::new().with_children(cells))
+            .add_column(Column::strict(170.0))
+            .add_column(Column::strict(MAX_BAR_WIDTH))
+            .add_rows(Phase::ALL.iter().map(|_| Row::strict(16.0)).collect())
+            .build(ctx);
+        let stats =
+            TextBuilder::new(WidgetBuilder::new().with_margin(Thickness::top(6.0))).build(ctx);
+
+        let panel = BorderBuilder::new(
+            WidgetBuilder::new()
+                .with_visibility(false)
+                .with_horizontal_alignment(HorizontalAlignment::Right)
+                .with_vertical_alignment(VerticalAlignment::Top)
+                .with_margin(Thickness::uniform(10.0))
+                .with_background(Brush::Solid(Color::from_rgba(0, 0, 0, 160)))
+                .with_child(
+                    StackPanelBuilder::new(
+                        WidgetBuilder::new()
+                            .with_margin(Thickness::uniform(6.0))
+                            .with_child(header)
+                            .with_child(table)
+                            .with_child(stats),
+                    )
+                    .build(ctx),
+                ),
+        )
+        .build(ctx);
+
+        Self {
+            panel,
+            header,
+            rows,
+            stats,
+            visible: false,
+            frames_until_text: 0,
+        }
+    }
+
+    pub fn toggle(&mut self, ui: &UserInterface) {
+        self.visible = !self.visible;
+        self.frames_until_text = 0;
+        ui.send_message(WidgetMessage::visibility(
+            self.panel,
+            MessageDirection::ToWidget,
+            self.visible,
+        ));
+    }
+
+    pub fn is_visible(&self) -> bool {
+        self.visible
+    }
+
+    pub fn update(&mut self, ui: &UserInterface, profiler: &Profiler, stats: &StatsCollector) {
+        if !self.visible {
+            return;
+        }
+
+        for row in &self.rows {
+            let width = (profiler.last_ms(row.phase) * BAR_WIDTH_PER_MS).min(MAX_BAR_WIDTH);
+            ui.send_message(WidgetMessage::width(
+                row.bar,
+                MessageDirection::ToWidget,
+                width,
+            ));
+        }
+
+        if self.frames_until_text > 0 {
+            self.frames_until_text -= 1;
+            return;
+        }
+        self.frames_until_text = TEXT_INTERVAL;
+
+        let frame = profiler.average_frame_ms();
+        ui.send_message(TextMessage::text(
+            self.header,
+            MessageDirection::ToWidget,
+            format!(
+                "frame {frame:.1} ms ({:.0} fps), worst {:.1} ms",
+                1000.0 / frame.max(0.001),
+                profiler.worst_frame_ms()
+            ),
+        ));
+        for row in &self.rows {
+            ui.send_message(TextMessage::text(
+                row.label,
+                MessageDirection::ToWidget,
+                format!(
+                    "{:<9}{:>6.2} avg {:>5.2}",
+                    row.phase.name(),
+                    profiler.last_ms(row.phase),
+                    profiler.average_ms(row.phase)
+                ),
+            ));
+        }
+
+        let (render, game) = (stats.render(), stats.game());
+        let text = [
+            format!(
+                "draw calls {}, triangles {}",
+                render.draw_calls, render.triangles
+            ),
+            format!(
+                "textures {} on gpu, {} loaded",
+                render.gpu_textures,
+                format_bytes(render.texture_bytes)
+            ),
+            format!("nodes {}, entities {}", game.scene_nodes, game.entities),
+            format!(
+                "pooled {} in use, {} idle",
+                game.pooled_in_use, game.pooled_idle
+            ),
+            format!("sounds playing {}", game.active_sounds),
+        ]
+        .join("\n");
+        ui.send_message(TextMessage::text(
+            self.stats,
+            MessageDirection::ToWidget,
+            text,
+        ));
+    }
+}
+
+fn phase_color(phase: Phase) -> Color {
+    match phase {
+        Phase::Input => Color::opaque(200, 200, 200),
+        Phase::Network => Color::opaque(80, 160, 255),
+        Phase::Movement => Color::opaque(80, 220, 120),
+        Phase::Camera => Color::opaque(40, 200, 200),
+        Phase::Ai => Color::opaque(220, 80, 200),
+        Phase::Events => Color::opaque(255, 200, 40),
+        Phase::Ui => Color::opaque(255, 120, 60),
+    }
+}
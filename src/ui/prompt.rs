@@ -0,0 +1,53 @@
+use crate::localization::{tr, tr_with};
+use fyrox::{
+    core::pool::Handle,
+    gui::{
+        message::MessageDirection,
+        text::{TextBuilder, TextMessage},
+        widget::{WidgetBuilder, WidgetMessage},
+        BuildContext, HorizontalAlignment, Thickness, UiNode, UserInterface, VerticalAlignment,
+    },
+};
+
+// "F: Open crate" above the bottom of the screen while the player can use something
+#[derive(Default)]
+pub struct InteractPrompt {
+    text: Handle<UiNode>,
+    // String table key of the prompt on screen, so nothing is sent while it stays
+    shown: Option<String>,
+}
+
+impl InteractPrompt {
+    pub fn new(ctx: &mut BuildContext) -> Self {
+        let text = TextBuilder::new(
+            WidgetBuilder::new()
+                .with_visibility(false)
+                .with_hit_test_visibility(false)
+                .with_horizontal_alignment(HorizontalAlignment::Center)
+                .with_vertical_alignment(VerticalAlignment::Bottom)
+                .with_margin(Thickness::uniform(96.0)),
+        )
+        .build(ctx);
+
+        Self { text, shown: None }
+    }
+
+    pub fn update(&mut self, ui: &UserInterface, prompt: Option<&str>) {
+        if self.shown.as_deref() == prompt {
+            return;
+        }
+        self.shown = prompt.map(str::to_string);
+        if let Some(key) = prompt {
+            ui.send_message(TextMessage::text(
+                self.text,
+                MessageDirection::ToWidget,
+                tr_with("prompt.interact", &[("action", &tr(key))]),
+            ));
+        }
+        ui.send_message(WidgetMessage::visibility(
+            self.text,
+            MessageDirection::ToWidget,
+            prompt.is_some(),
+        ));
+    }
+}
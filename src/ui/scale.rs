@@ -0,0 +1,79 @@
+use fyrox::{
+    core::algebra::{Matrix3, Vector2},
+    gui::{message::MessageDirection, widget::WidgetMessage, UserInterface},
+};
+
+pub const MIN_UI_SCALE: f32 = 0.5;
+pub const MAX_UI_SCALE: f32 = 2.0;
+// The manual scale snaps to these steps, so the slider lands on round numbers
+pub const UI_SCALE_STEP: f32 = 0.25;
+
+// How big the UI is drawn. Widgets are laid out in logical pixels, the whole tree is
+// scaled by the display's scale factor (2 on most high-DPI screens) times the
+// player's own UI scale.
+#[derive(Debug)]
+pub struct UiScale {
+    dpi: f32,
+    manual: f32,
+    // What the UI root has been told, so nothing is sent while it stays the same
+    applied: f32,
+}
+
+impl Default for UiScale {
+    fn default() -> Self {
+        Self::new(1.0)
+    }
+}
+
+impl UiScale {
+    pub fn new(manual: f32) -> Self {
+        Self {
+            dpi: 1.0,
+            manual: snap(manual),
+            applied: 1.0,
+        }
+    }
+
+    pub fn factor(&self) -> f32 {
+        self.dpi * self.manual
+    }
+
+    pub fn manual(&self) -> f32 {
+        self.manual
+    }
+
+    // From the window, when it is created and whenever it reports ScaleFactorChanged
+    pub fn set_dpi(&mut self, scale_factor: f64) {
+        self.dpi = scale_factor as f32;
+    }
+
+    // Returns the scale actually used
+    pub fn set_manual(&mut self, scale: f32) -> f32 {
+        self.manual = snap(scale);
+        self.manual
+    }
+
+    // Called every frame
+    pub fn update(&mut self, ui: &UserInterface) {
+        let factor = self.factor();
+        if factor == self.applied {
+            return;
+        }
+        self.applied = factor;
+        ui.send_message(WidgetMessage::layout_transform(
+            ui.root(),
+            MessageDirection::ToWidget,
+            Matrix3::new_scaling(factor),
+        ));
+    }
+
+    // Window pixels in the UI's logical ones, for widgets placed at screen positions
+    pub fn to_ui(&self, pixels: Vector2<f32>) -> Vector2<f32> {
+        pixels / self.applied
+    }
+}
+
+fn snap(scale: f32) -> f32 {
+    let scale = if scale.is_finite() { scale } else { 1.0 };
+    ((scale / UI_SCALE_STEP).round() * UI_SCALE_STEP).clamp(MIN_UI_SCALE, MAX_UI_SCALE)
+}
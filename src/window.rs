@@ -0,0 +1,138 @@
+use fyrox::{
+    core::color::Color,
+    event_loop::{ControlFlow, EventLoopWindowTarget},
+    window::{CursorGrabMode, Icon, Window},
+};
+use log::{info, warn};
+use std::time::Duration;
+
+const ICON_SIZE: u32 = 32;
+
+// How long the event loop sleeps between frames while the window can't be seen. It
+// still wakes now and then, a frame is drawn each time and network traffic is handled.
+const HIDDEN_FRAME_TIME: Duration = Duration::from_millis(250);
+
+// The player's blue cube, drawn here so the icon needs no image file or decoder
+pub fn window_icon() -> Option<Icon> {
+    let face = Color::opaque(0, 100, 255);
+    let top = Color::opaque(90, 160, 255);
+    let edge = Color::opaque(0, 40, 110);
+
+    let mut rgba = Vec::with_capacity((ICON_SIZE * ICON_SIZE * 4) as usize);
+    for y in 0..ICON_SIZE {
+        for x in 0..ICON_SIZE {
+            let color = match (x, y) {
+                (2..=29, 2..=29) if x <= 3 || x >= 28 || y <= 3 || y >= 28 => edge,
+                (2..=29, 4..=11) => top,
+                (2..=29, 2..=29) => face,
+                _ => Color::TRANSPARENT,
+            };
+            rgba.extend_from_slice(&[color.r, color.g, color.b, color.a]);
+        }
+    }
+    Icon::from_rgba(rgba, ICON_SIZE, ICON_SIZE)
+        .map_err(|err| warn!("No window icon: {err}"))
+        .ok()
+}
+
+// Keeps the cursor inside the window and out of sight while the mouse steers the
+// camera, and hands it back whenever something needs pointing at or the window
+// isn't in front
+pub struct CursorGrab {
+    focused: bool,
+    grabbed: bool,
+}
+
+impl Default for CursorGrab {
+    fn default() -> Self {
+        Self {
+            focused: true,
+            grabbed: false,
+        }
+    }
+}
+
+impl CursorGrab {
+    pub fn set_focused(&mut self, focused: bool) {
+        self.focused = focused;
+    }
+
+    // The window is gone, a new one starts out with a free cursor
+    pub fn window_lost(&mut self) {
+        self.grabbed = false;
+    }
+
+    // Called every frame with whether the game wants the mouse to itself
+    pub fn update(&mut self, window: &Window, wanted: bool) {
+        let grab = wanted && self.focused;
+        if grab == self.grabbed {
+            return;
+        }
+        self.grabbed = grab;
+
+        if grab {
+            // Confined is what most platforms offer, macOS only knows Locked
+            if let Err(err) = window
+                .set_cursor_grab(CursorGrabMode::Confined)
+                .or_else(|_| window.set_cursor_grab(CursorGrabMode::Locked))
+            {
+                warn!("Couldn't grab the cursor: {err}");
+            }
+        } else if let Err(err) = window.set_cursor_grab(CursorGrabMode::None) {
+            warn!("Couldn't release the cursor: {err}");
+        }
+        window.set_cursor_visible(!grab);
+    }
+}
+
+// Whether any of the window can be seen. The event loop polls flat out to keep frames
+// coming, which is wasted on a minimized window or one that is fully covered, so it
+// is told to wait between frames until the window is back.
+#[derive(Default)]
+pub struct WindowVisibility {
+    // Reported by the platform for a covered window, or a browser tab in the background
+    occluded: bool,
+    minimized: bool,
+    // The event loop was told to wait, it has to be set back to polling
+    throttled: bool,
+}
+
+impl WindowVisibility {
+    pub fn set_occluded(&mut self, occluded: bool) {
+        self.occluded = occluded;
+    }
+
+    pub fn is_hidden(&self) -> bool {
+        self.occluded || self.minimized
+    }
+
+    // Called every frame. Not every platform can say whether a window is minimized,
+    // some report it as resized to nothing instead.
+    pub fn update(&mut self, window: &Window) {
+        let size = window.inner_size();
+        self.minimized =
+            window.is_minimized().unwrap_or(false) || size.width == 0 || size.height == 0;
+    }
+
+    // Slows the event loop down while hidden and lets it run at full speed again after.
+    // Called after update, the executor asks for a redraw once the plugins are done.
+    pub fn throttle(&mut self, window_target: &EventLoopWindowTarget<()>) {
+        let hidden = self.is_hidden();
+        if hidden {
+            window_target.set_control_flow(ControlFlow::wait_duration(HIDDEN_FRAME_TIME));
+        } else if self.throttled {
+            window_target.set_control_flow(ControlFlow::Poll);
+        }
+        if hidden != self.throttled {
+            info!(
+                "Window {}",
+                if hidden {
+                    "hidden, drawing less often"
+                } else {
+                    "visible again"
+                }
+            );
+        }
+        self.throttled = hidden;
+    }
+}
@@ -0,0 +1,20 @@
+// Shared by the integration tests, not every test file uses every helper
+#![allow(dead_code)]
+
+use simple_fyrox_game::{game::LaunchOptions, headless::HeadlessSim, level::LevelConfig};
+
+pub fn meadow_config() -> LevelConfig {
+    LevelConfig {
+        level_id: "meadow".to_string(),
+        ..Default::default()
+    }
+}
+
+pub fn meadow() -> HeadlessSim {
+    meadow_with(LaunchOptions::default())
+}
+
+// The meadow launched as if from the command line with `options`
+pub fn meadow_with(options: LaunchOptions) -> HeadlessSim {
+    HeadlessSim::with_options(options, meadow_config()).unwrap()
+}
@@ -0,0 +1,207 @@
+// Plays the meadow without a window through HeadlessSim::run. The level script puts
+// coins on the diagonal from the center spawn to the goal and a health pack off to the
+// north-west, see assets/scripts/meadow.rhai.
+
+mod common;
+
+use common::meadow;
+use fyrox::{core::algebra::Vector3, keyboard::KeyCode};
+use simple_fyrox_game::{
+    cheats::Cheat,
+    headless::HeadlessSim,
+    input_script::InputScript,
+    level::Prefab,
+    movement::{MoveInput, PLAYER_SPEED},
+    ui::inspector::{InspectorEdit, InspectorField},
+};
+
+fn keys(forward: bool, left: bool, right: bool) -> MoveInput {
+    MoveInput {
+        forward,
+        left,
+        right,
+        ..Default::default()
+    }
+}
+
+// An enemy half a unit from the spawn, close enough to touch the player right away
+fn spawn_enemy(sim: &mut HeadlessSim) {
+    let start = sim.player_position().unwrap();
+    sim.spawn(Prefab::Enemy, "Enemy", start + Vector3::new(0.5, -0.5, 0.0))
+        .unwrap();
+}
+
+#[test]
+fn a_second_forward_moves_the_player_by_its_speed() {
+    let mut sim = meadow();
+    let start = sim.player_position().unwrap();
+    sim.run(60, keys(true, false, false));
+
+    let moved = sim.player_position().unwrap() - start;
+    assert!((moved.z + PLAYER_SPEED).abs() < 1e-3, "moved {moved:?}");
+    assert!(moved.x.abs() < 1e-6 && moved.y.abs() < 1e-6);
+    assert_eq!(sim.ticks(), 60);
+}
+
+#[test]
+fn standing_still_changes_nothing() {
+    let mut sim = meadow();
+    let start = sim.player_position().unwrap();
+    sim.run(120, MoveInput::default());
+
+    assert_eq!(sim.player_position(), Some(start));
+    assert_eq!(sim.player_health(), Some(100.0));
+    assert_eq!(sim.coins_collected(), 0);
+    assert!(!sim.level_finished());
+}
+
+#[test]
+fn walking_to_the_goal_collects_the_coins_on_the_way() {
+    let mut sim = meadow();
+    sim.run(45, keys(true, false, true));
+    assert_eq!(sim.coins_collected(), 1);
+
+    // The goal is about 11.3 units away, a little over two seconds
+    sim.run(90, keys(true, false, true));
+    assert_eq!(sim.coins_collected(), 3);
+    assert!(sim.level_finished());
+
+    // A finished run stands still on the goal
+    let finish = sim.player_position().unwrap();
+    sim.run(30, keys(true, false, true));
+    assert_eq!(sim.player_position(), Some(finish));
+}
+
+#[test]
+fn an_enemy_touching_the_player_hurts_it_once_a_second() {
+    let mut sim = meadow();
+    spawn_enemy(&mut sim);
+
+    sim.run(30, MoveInput::default());
+    assert_eq!(sim.player_health(), Some(90.0));
+
+    // Hits at about 1 and 2 seconds, the enemy stays on top of the player
+    sim.run(100, MoveInput::default());
+    assert_eq!(sim.player_health(), Some(70.0));
+}
+
+#[test]
+fn god_mode_keeps_the_player_unhurt() {
+    let mut sim = meadow();
+    sim.game_mut().set_cheat(Cheat::GodMode, true).unwrap();
+    spawn_enemy(&mut sim);
+
+    sim.run(130, MoveInput::default());
+    assert_eq!(sim.player_health(), Some(100.0));
+}
+
+#[test]
+fn a_health_pack_heals_what_an_enemy_took() {
+    let mut sim = meadow();
+    spawn_enemy(&mut sim);
+    sim.run(1, MoveInput::default());
+    assert_eq!(sim.player_health(), Some(90.0));
+
+    // Away from the enemy, which is slower, and over the pack at (-6, -6)
+    sim.run(110, keys(true, true, false));
+    assert_eq!(sim.player_health(), Some(100.0));
+    assert_eq!(sim.coins_collected(), 0);
+}
+
+// Straight to the goal over the coins, see walking_to_the_goal_collects_the_coins_on_the_way
+fn finish(sim: &mut HeadlessSim) {
+    sim.run(135, keys(true, false, true));
+    assert!(sim.level_finished());
+}
+
+#[test]
+fn a_plain_run_goes_to_the_leaderboard() {
+    let mut sim = meadow();
+    finish(&mut sim);
+    assert!(!sim.game().run_withheld());
+}
+
+#[test]
+fn a_run_with_a_spawned_item_is_withheld() {
+    let mut sim = meadow();
+    sim.spawn_item("health", 2.0).unwrap();
+    finish(&mut sim);
+    assert!(sim.game().run_withheld());
+}
+
+#[test]
+fn a_run_with_health_edited_in_the_inspector_is_withheld() {
+    let mut sim = meadow();
+    let player = sim.game().player().handle();
+    sim.edit(InspectorEdit::Set {
+        entity: player,
+        field: InspectorField::Health,
+        value: 50.0,
+    });
+    assert_eq!(sim.player_health(), Some(50.0));
+    finish(&mut sim);
+    assert!(sim.game().run_withheld());
+}
+
+#[test]
+fn a_run_moved_onto_the_goal_in_the_inspector_is_withheld() {
+    let mut sim = meadow();
+    let player = sim.game().player().handle();
+    for (field, value) in [
+        (InspectorField::PositionX, 8.0),
+        (InspectorField::PositionZ, -8.0),
+    ] {
+        sim.edit(InspectorEdit::Set {
+            entity: player,
+            field,
+            value,
+        });
+    }
+    sim.run(1, MoveInput::default());
+    assert!(sim.level_finished());
+    assert!(sim.game().run_withheld());
+}
+
+#[test]
+fn f_opens_a_crate_next_to_the_player_for_its_coin() {
+    let mut sim = meadow();
+    let start = sim.player_position().unwrap();
+    sim.spawn(Prefab::Crate, "Crate", start + Vector3::new(1.0, -0.5, 0.0))
+        .unwrap();
+    sim.run(1, MoveInput::default());
+    assert_eq!(sim.coins_collected(), 0);
+
+    sim.play(
+        &InputScript::new()
+            .press(KeyCode::KeyF)
+            .release(KeyCode::KeyF),
+    );
+    sim.run(2, MoveInput::default());
+    assert_eq!(sim.coins_collected(), 1);
+
+    // Opened is gone, another press does nothing
+    sim.play(
+        &InputScript::new()
+            .press(KeyCode::KeyF)
+            .release(KeyCode::KeyF),
+    );
+    sim.run(2, MoveInput::default());
+    assert_eq!(sim.coins_collected(), 1);
+}
+
+#[test]
+fn f_away_from_any_crate_opens_nothing_later() {
+    let mut sim = meadow();
+    let start = sim.player_position().unwrap();
+    sim.play(
+        &InputScript::new()
+            .press(KeyCode::KeyF)
+            .release(KeyCode::KeyF),
+    );
+    sim.run(1, MoveInput::default());
+
+    sim.spawn(Prefab::Crate, "Crate", start + Vector3::new(1.0, -0.5, 0.0))
+        .unwrap();
+    sim.run(2, MoveInput::default());
+    assert_eq!(sim.coins_collected(), 0);
+}
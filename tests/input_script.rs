@@ -0,0 +1,147 @@
+// Scripted keyboard and mouse input, played into a HeadlessSim through the same handling as
+// window events, and handed out tick by tick by a ScriptPlayer
+
+mod common;
+
+use common::{meadow, meadow_config};
+use fyrox::{core::algebra::UnitQuaternion, keyboard::KeyCode, scene::camera::Camera};
+use simple_fyrox_game::{
+    cheats::Cheat,
+    components::Stamina,
+    headless::HeadlessSim,
+    input_script::{InputScript, InputStep, ScriptPlayer},
+    level::LevelConfig,
+};
+
+#[test]
+fn holding_w_for_two_seconds_moves_ten_units() {
+    let mut sim = meadow();
+    let start = sim.player_position().unwrap();
+    sim.play(&InputScript::new().hold(KeyCode::KeyW, 2.0));
+
+    // 2 seconds at 5 units per second, straight ahead
+    let moved = sim.player_position().unwrap() - start;
+    assert!((moved.norm() - 10.0).abs() < 0.2, "moved {moved:?}");
+    assert!(moved.z < 0.0);
+    assert_eq!(sim.ticks(), 120);
+}
+
+#[test]
+fn a_released_key_stops_the_player() {
+    let mut sim = meadow();
+    let start = sim.player_position().unwrap();
+    sim.play(&InputScript::new().hold(KeyCode::KeyW, 1.0).wait(1.0));
+
+    let moved = (sim.player_position().unwrap() - start).norm();
+    assert!((moved - 5.0).abs() < 0.1, "moved {moved}");
+}
+
+fn stamina(sim: &HeadlessSim) -> f32 {
+    let player = sim.game().player().handle();
+    sim.game()
+        .components()
+        .get::<Stamina>(player)
+        .unwrap()
+        .current
+}
+
+#[test]
+fn space_dashes_ahead_for_a_quarter_of_the_stamina() {
+    let mut sim = meadow();
+    let start = sim.player_position().unwrap();
+    sim.play(
+        &InputScript::new()
+            .press(KeyCode::KeyW)
+            .press(KeyCode::Space)
+            .release(KeyCode::Space)
+            .wait_ticks(1),
+    );
+    assert_eq!(stamina(&sim), 75.0);
+    sim.play(&InputScript::new().wait(1.0).release(KeyCode::KeyW));
+
+    // A fifth of a second at three times the speed, the rest walking
+    let moved = (sim.player_position().unwrap() - start).norm();
+    assert!((moved - 7.0).abs() < 0.25, "moved {moved}");
+}
+
+#[test]
+fn infinite_stamina_dashes_for_free() {
+    let mut sim = meadow();
+    sim.game_mut()
+        .set_cheat(Cheat::InfiniteStamina, true)
+        .unwrap();
+    // More dashes than a full bar pays for, each one over before the next
+    let dash = InputScript::new()
+        .press(KeyCode::KeyD)
+        .press(KeyCode::Space)
+        .release(KeyCode::Space)
+        .wait(0.25);
+    for _ in 0..6 {
+        sim.play(&dash);
+    }
+    assert_eq!(stamina(&sim), 100.0);
+}
+
+#[test]
+fn f10_toggles_the_frozen_simulation() {
+    let mut sim = meadow();
+    assert!(!sim.game().frame_step().is_frozen());
+
+    sim.play(&InputScript::new().press(KeyCode::F10).release(KeyCode::F10));
+    assert!(sim.game().frame_step().is_frozen());
+
+    sim.play(&InputScript::new().press(KeyCode::F10).release(KeyCode::F10));
+    assert!(!sim.game().frame_step().is_frozen());
+}
+
+fn camera_rotation(sim: &HeadlessSim) -> UnitQuaternion<f32> {
+    let (_, camera) = sim
+        .scene()
+        .graph
+        .pair_iter()
+        .find(|(_, node)| node.cast::<Camera>().is_some())
+        .unwrap();
+    **camera.local_transform().rotation()
+}
+
+#[test]
+fn mouse_motion_turns_a_spectators_camera() {
+    let mut sim = HeadlessSim::new(LevelConfig {
+        spectator: true,
+        ..meadow_config()
+    })
+    .unwrap();
+    sim.play(&InputScript::new().wait_ticks(1));
+    let start = camera_rotation(&sim);
+
+    sim.play(&InputScript::new().mouse_motion(40.0, 0.0).wait_ticks(1));
+    let turned = camera_rotation(&sim);
+    assert!(turned.angle_to(&start) > 0.01);
+
+    // Motion is used up by the tick it arrived before
+    sim.play(&InputScript::new().wait_ticks(1));
+    assert!(camera_rotation(&sim).angle_to(&turned) < 1e-6);
+}
+
+#[test]
+fn a_script_player_hands_out_steps_on_their_ticks() {
+    let script = InputScript::new()
+        .hold(KeyCode::KeyW, 2.0)
+        .press(KeyCode::KeyD)
+        .wait_ticks(1);
+    let mut player = ScriptPlayer::new(&script);
+
+    assert_eq!(player.next_tick(), vec![InputStep::Press(KeyCode::KeyW)]);
+    for _ in 1..120 {
+        assert!(player.next_tick().is_empty());
+    }
+    assert_eq!(
+        player.next_tick(),
+        vec![
+            InputStep::Release(KeyCode::KeyW),
+            InputStep::Press(KeyCode::KeyD)
+        ]
+    );
+    assert!(player.is_finished());
+    assert_eq!(script.ticks(), 121);
+}
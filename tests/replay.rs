@@ -0,0 +1,77 @@
+// Records a solo run with --record-replay and plays it back into a fresh HeadlessSim,
+// the way the menu's demo plays the bundled replay
+
+mod common;
+
+use common::meadow_with;
+use fyrox::keyboard::KeyCode;
+use simple_fyrox_game::{
+    cheats::Cheat, demo::DEMO_REPLAY_PATH, game::LaunchOptions, headless::HeadlessSim,
+    input_script::InputScript, replay::Replay,
+};
+use std::path::{Path, PathBuf};
+
+fn replay_path(name: &str) -> PathBuf {
+    std::env::temp_dir().join(format!("replay-{}-{name}.json", std::process::id()))
+}
+
+fn recording(path: &Path) -> HeadlessSim {
+    meadow_with(LaunchOptions {
+        record_replay: Some(path.to_path_buf()),
+        ..Default::default()
+    })
+}
+
+// Diagonally to the goal over the coins, with a dash on the way
+fn run_to_the_goal() -> InputScript {
+    InputScript::new()
+        .press(KeyCode::KeyW)
+        .press(KeyCode::KeyD)
+        .wait(0.5)
+        .press(KeyCode::Space)
+        .release(KeyCode::Space)
+        .wait(2.5)
+}
+
+#[test]
+fn a_finished_run_plays_back_the_same() {
+    let path = replay_path("goal");
+    let mut recorded = recording(&path);
+    recorded.play(&run_to_the_goal());
+    assert!(recorded.level_finished());
+
+    let replay = Replay::load(&path).unwrap();
+    std::fs::remove_file(&path).unwrap();
+    assert_eq!(replay.level, "meadow");
+
+    let mut played = HeadlessSim::new(replay.level_config()).unwrap();
+    played.play_replay(&replay);
+    assert!(played.level_finished());
+    assert_eq!(played.coins_collected(), recorded.coins_collected());
+    assert_eq!(played.player_position(), recorded.player_position());
+    assert_eq!(played.ticks(), replay.ticks());
+}
+
+#[test]
+fn cheating_stops_the_recording() {
+    let path = replay_path("cheat");
+    let mut recorded = recording(&path);
+    recorded.play(&InputScript::new().wait(0.5));
+    recorded.game_mut().set_cheat(Cheat::GodMode, true).unwrap();
+    recorded.play(&run_to_the_goal());
+
+    assert!(recorded.level_finished());
+    assert!(!path.exists());
+}
+
+#[test]
+fn the_bundled_demo_plays_to_its_end() {
+    let replay = Replay::load(Path::new(DEMO_REPLAY_PATH)).unwrap();
+    let mut sim = HeadlessSim::new(replay.level_config()).unwrap();
+    let start = sim.player_position().unwrap();
+    sim.play_replay(&replay);
+
+    assert_eq!(sim.ticks(), replay.ticks());
+    assert_ne!(sim.player_position(), Some(start));
+    assert_eq!(sim.player_health(), Some(100.0));
+}